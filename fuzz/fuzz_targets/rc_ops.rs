@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use shared_slice::fuzz::{run_rc, Op};
+
+fuzz_target!(|ops: Vec<Op>| run_rc(&ops));
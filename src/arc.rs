@@ -1,12 +1,74 @@
 //! A thread-safe reference-counted slice type.
 
-use core::prelude::*;
-
-use core::{cmp, fmt, ops};
+use core::{cmp, fmt, hint, mem, ops, ptr, slice, str};
+use core::any::{Any, TypeId};
+use core::borrow::Borrow;
+use core::cell::UnsafeCell;
+use core::ffi::{c_char, CStr, FromBytesWithNulError};
 use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{self, AtomicUsize, Ordering};
 
-use alloc::arc::{Arc, Weak};
 use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::string::{FromUtf16Error, String};
+
+use cdc;
+use checks;
+use identity;
+use inner;
+use matrix::StridedArcSlice;
+use range::{self, SliceError};
+use search;
+
+use alloc::collections::BinaryHeap;
+use alloc::collections::binary_heap::PeekMut;
+use alloc::vec::{self, Vec};
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "rand")]
+use rand::seq::SliceRandom;
+
+#[cfg(feature = "digest")]
+use digest::{Digest, Output};
+
+#[cfg(feature = "xxhash-rust")]
+use xxhash_rust::xxh3::xxh3_64;
+
+#[cfg(feature = "encoding_rs")]
+use alloc::borrow::Cow;
+#[cfg(feature = "encoding_rs")]
+use encoding_rs::Encoding;
+
+#[cfg(feature = "unicode-segmentation")]
+use unicode_segmentation::{Graphemes, UWordBounds, UnicodeSegmentation, UnicodeWords};
+
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "std")]
+use std::{fs, io, panic, path, thread};
+#[cfg(all(feature = "flate2", feature = "std"))]
+use flate2::read::MultiGzDecoder;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+pub use cell::{ArcSliceCell, RcuSlice};
+pub use small::SmallArcSlice;
+#[cfg(feature = "std")]
+pub use interner::Interner;
+#[cfg(feature = "std")]
+pub use path::{ArcOsStr, ArcPath};
+#[cfg(feature = "std")]
+pub use recycle::SlicePool;
 
 
 /// A reference-counted slice type.
@@ -22,7 +84,7 @@ use alloc::boxed::Box;
 /// ```rust
 /// use shared_slice::arc::ArcSlice;
 ///
-/// let x = ArcSlice::new(Box::new(["foo", "bar", "baz"]));
+/// let x = ArcSlice::from(["foo", "bar", "baz"]);
 /// println!("{:?}", x); // ["foo", "bar", "baz"]
 /// println!("{:?}", x.slice(1, 3)); // ["bar", "baz"]
 /// ```
@@ -42,7 +104,8 @@ use alloc::boxed::Box;
 /// ```
 pub struct ArcSlice<T> {
     data: *const [T],
-    counts: Arc<Box<[T]>>,
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
 }
 
 unsafe impl<T: Send + Sync> Send for ArcSlice<T> {}
@@ -55,27 +118,393 @@ unsafe impl<T: Send + Sync> Sync for ArcSlice<T> {}
 /// being deallocated.
 pub struct WeakSlice<T> {
     data: *const [T],
-    counts: Weak<Box<[T]>>,
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
 }
 unsafe impl<T: Send + Sync> Send for WeakSlice<T> {}
 unsafe impl<T: Send + Sync> Sync for WeakSlice<T> {}
 
+/// A reference-counted reference to a single element of a `ArcSlice`.
+///
+/// This keeps the whole allocation alive, like a `ArcSlice`, but
+/// dereferences to a `&T`. It is created by `ArcSlice::get_ref`.
+pub struct ArcRef<T> {
+    slice: ArcSlice<T>,
+}
+
+/// A reference-counted reference to a value derived from the
+/// elements of a `ArcSlice`, such as a field of one of them.
+///
+/// This keeps the whole allocation alive, like a `ArcSlice`, but
+/// dereferences to a `&U`. It is created by `ArcSlice::map_ref`.
+pub struct ArcView<U: ?Sized> {
+    data: *const U,
+    header: NonNull<Header>,
+    // `release_strong` for the element type
+    release: unsafe fn(NonNull<Header>),
+}
+// the elements are `Send + Sync`, as required by `map_ref`
+unsafe impl<U: ?Sized + Sync> Send for ArcView<U> {}
+unsafe impl<U: ?Sized + Sync> Sync for ArcView<U> {}
+
+/// A compact reference-counted slice type.
+///
+/// This behaves like `ArcSlice`, but stores the position of the view
+/// in its allocation as 32-bit integers, making it two words in size
+/// rather than three. It can only point to elements whose indices
+/// fit in a `u32`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{CompactArcSlice, ArcSlice};
+///
+/// let x = ArcSlice::new(Box::new([1, 2, 3, 4]));
+/// let y = CompactArcSlice::new(x.slice(1, 3)).unwrap();
+/// assert_eq!(&*y, [2, 3]);
+///
+/// let z: ArcSlice<_> = y.into();
+/// assert_eq!(&*z, [2, 3]);
+/// ```
+pub struct CompactArcSlice<T> {
+    header: NonNull<Header>,
+    offset: u32,
+    len: u32,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send + Sync> Send for CompactArcSlice<T> {}
+unsafe impl<T: Send + Sync> Sync for CompactArcSlice<T> {}
+
+/// A type-erased reference-counted slice.
+///
+/// This holds an `ArcSlice<T>` whose element type `T` is only known
+/// at run time, so slices of many element types can be stored
+/// together, such as in a single `HashMap`. It can be converted back
+/// to an `ArcSlice<T>` with `downcast`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{AnyArcSlice, ArcSlice};
+///
+/// let slices = vec![AnyArcSlice::new(ArcSlice::new(Box::new([1u8, 2]))),
+///                   AnyArcSlice::new(ArcSlice::new(Box::new(["a", "b", "c"])))];
+///
+/// assert_eq!(slices[0].downcast_ref::<u8>(), Some(&[1, 2][..]));
+/// assert!(slices[1].downcast_ref::<u8>().is_none());
+///
+/// let strings = slices[1].clone().downcast::<&str>().unwrap();
+/// assert_eq!(&*strings, ["a", "b", "c"]);
+/// ```
+pub struct AnyArcSlice {
+    data: *const (),
+    len: usize,
+    type_id: TypeId,
+    header: NonNull<Header>,
+    // `release_strong` for the element type
+    release: unsafe fn(NonNull<Header>),
+}
+
+unsafe impl Send for AnyArcSlice {}
+unsafe impl Sync for AnyArcSlice {}
+
+/// The header of an `ArcSlice` allocation, which is followed by the
+/// elements.
+struct Header {
+    strong: AtomicUsize,
+    // the number of `WeakSlice`s, plus one for all the `ArcSlice`s
+    // together while there are any, or `usize::MAX` while
+    // `is_unique` is checking the strong count
+    weak: AtomicUsize,
+    len: usize,
+}
+
+impl Header {
+    fn new(len: usize) -> Header {
+        Header { strong: AtomicUsize::new(1), weak: AtomicUsize::new(1), len }
+    }
+
+    fn strong(&self) -> usize {
+        self.strong.load(Ordering::Acquire)
+    }
+
+    fn weak(&self) -> usize {
+        self.weak.load(Ordering::Acquire)
+    }
+
+    fn inc_strong(&self) {
+        // a new reference can only be made from an existing one, so
+        // there is nothing to synchronise with
+        if self.strong.fetch_add(1, Ordering::Relaxed) > inner::MAX_REFCOUNT {
+            inner::count_overflow()
+        }
+    }
+
+    fn inc_weak(&self) {
+        let mut weak = self.weak.load(Ordering::Relaxed);
+        loop {
+            if weak == usize::MAX {
+                hint::spin_loop();
+                weak = self.weak.load(Ordering::Relaxed);
+                continue
+            }
+            if weak > inner::MAX_REFCOUNT {
+                inner::count_overflow()
+            }
+            match self.weak.compare_exchange_weak(weak, weak + 1, Ordering::Acquire,
+                                                  Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(old) => weak = old,
+            }
+        }
+    }
+
+    /// Add a strong reference, unless the elements have already been
+    /// dropped. Returns whether it was added.
+    fn upgrade(&self) -> bool {
+        let mut strong = self.strong.load(Ordering::Relaxed);
+        while strong != 0 {
+            if strong > inner::MAX_REFCOUNT {
+                inner::count_overflow()
+            }
+            match self.strong.compare_exchange_weak(strong, strong + 1, Ordering::Acquire,
+                                                    Ordering::Relaxed) {
+                Ok(_) => return true,
+                Err(old) => strong = old,
+            }
+        }
+        false
+    }
+
+    /// Whether there is exactly one strong reference, and no weak
+    /// ones.
+    fn is_unique(&self) -> bool {
+        // like `Arc::get_mut`, lock the weak count so the only strong
+        // reference can't be downgraded (and that weak reference
+        // upgraded) between the two checks
+        if self.weak.compare_exchange(1, usize::MAX, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return false
+        }
+        let unique = self.strong.load(Ordering::Acquire) == 1;
+        self.weak.store(1, Ordering::Release);
+        unique
+    }
+}
+
+/// The header of slices that don't own an allocation: empty ones, and
+/// those from `from_static`. It is never dereferenced, and they
+/// aren't reference counted.
+const fn static_header() -> NonNull<Header> {
+    NonNull::dangling()
+}
+
+/// The header of `WeakSlice`s that never upgrade, which also isn't
+/// dereferenced or reference counted.
+const fn dead_header() -> NonNull<Header> {
+    unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(usize::MAX)) }
+}
+
+/// Whether `header` is the header of a real allocation, with
+/// reference counts.
+fn is_counted(header: NonNull<Header>) -> bool {
+    header != static_header() && header != dead_header()
+}
+
+/// Add a strong reference to the allocation `header`, if it is
+/// counted.
+fn retain(header: NonNull<Header>) {
+    if is_counted(header) {
+        unsafe { header.as_ref() }.inc_strong();
+    }
+}
+
+/// All of the elements of the allocation `header`.
+unsafe fn whole<'a, T>(header: NonNull<Header>) -> &'a [T] {
+    &*ptr::slice_from_raw_parts(inner::elems::<Header, T>(header), header.as_ref().len)
+}
+
+/// Release a strong reference to the allocation `header`, dropping
+/// the elements if it was the last one.
+unsafe fn release_strong<T>(header: NonNull<Header>) {
+    if !is_counted(header) {
+        return
+    }
+    let h = header.as_ref();
+    if h.strong.fetch_sub(1, Ordering::Release) != 1 {
+        return
+    }
+    // every other use of the elements happens before they are dropped
+    atomic::fence(Ordering::Acquire);
+    inner::drop_elems::<Header, T>(header, h.len);
+    release_weak::<T>(header);
+}
+
+/// Release a weak reference to the allocation `header` (or the one
+/// held by all the strong references together), freeing it if it was
+/// the last one.
+unsafe fn release_weak<T>(header: NonNull<Header>) {
+    if !is_counted(header) {
+        return
+    }
+    let h = header.as_ref();
+    if h.weak.fetch_sub(1, Ordering::Release) != 1 {
+        return
+    }
+    atomic::fence(Ordering::Acquire);
+    let len = h.len;
+    inner::free::<Header, T>(header, len);
+}
+
 impl<T> ArcSlice<T> {
     /// Construct a new `ArcSlice` containing the elements of `slice`.
     ///
-    /// This reuses the allocation of `slice`.
+    /// The elements are moved into a new allocation, after the
+    /// reference counts.
     pub fn new(slice: Box<[T]>) -> ArcSlice<T> {
+        if slice.is_empty() {
+            return ArcSlice::default()
+        }
+        let len = slice.len();
+        ArcSlice::from_header(inner::from_box::<Header, T>(Header::new(len), slice), len)
+    }
+
+    /// Construct a new `ArcSlice` pointing to the elements of the
+    /// static `slice`, without allocating.
+    ///
+    /// This isn't reference counted, so cloning and dropping it (and
+    /// the `ArcSlice`s sliced from it) costs nothing, and APIs taking
+    /// `ArcSlice`s can be passed baked-in data directly. As it doesn't
+    /// own an allocation, it is never unique: `get_mut` and
+    /// `try_unwrap` fail, and `make_mut` copies the elements into a
+    /// new allocation. Similarly, `full_slice` can't undo slicing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// static TABLE: [u8; 4] = [1, 2, 4, 8];
+    /// const POWERS: ArcSlice<u8> = ArcSlice::from_static(&TABLE);
+    ///
+    /// let x = POWERS.slice_from(2);
+    /// assert_eq!(&*x, [4, 8]);
+    /// ```
+    pub const fn from_static(slice: &'static [T]) -> ArcSlice<T> {
+        ArcSlice {
+            data: slice,
+            header: static_header(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Construct a new empty `ArcSlice`, without allocating.
+    ///
+    /// This is the same as `ArcSlice::default()`, but can be used in
+    /// constants.
+    pub const fn empty() -> ArcSlice<T> {
+        ArcSlice {
+            data: &[],
+            header: static_header(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of elements in `self`.
+    ///
+    /// This is the same as the slice method, but can be used in
+    /// constants.
+    pub const fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether `self` contains no elements.
+    ///
+    /// This is the same as the slice method, but can be used in
+    /// constants.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Construct a new `ArcSlice` containing `f(i)` for each index `i`
+    /// from 0 up to `len`, in order.
+    ///
+    /// The elements are written straight into the new allocation. If
+    /// `f` panics, the elements already written are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let squares = ArcSlice::from_fn(5, |i| i * i);
+    /// assert_eq!(&*squares, [0, 1, 4, 9, 16]);
+    /// ```
+    pub fn from_fn<F: FnMut(usize) -> T>(len: usize, f: F) -> ArcSlice<T> {
+        if len == 0 {
+            return ArcSlice::default()
+        }
+        let header = inner::from_fn::<Header, T, F>(Header::new(len), len, f);
+        ArcSlice::from_header(header, len)
+    }
+
+    /// Construct a new `ArcSlice` with space for `len` elements, which
+    /// are left uninitialized, like `Arc::new_uninit_slice`.
+    ///
+    /// The new `ArcSlice` is unique, so the elements can be written
+    /// through `get_mut`, and then `assume_init` used to convert it to
+    /// a `ArcSlice<T>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut x = ArcSlice::<u32>::new_uninit(3);
+    /// for (i, elem) in x.get_mut().unwrap().iter_mut().enumerate() {
+    ///     elem.write(i as u32 * 10);
+    /// }
+    /// let x = unsafe { x.assume_init() };
+    /// assert_eq!(&*x, [0, 10, 20]);
+    /// ```
+    pub fn new_uninit(len: usize) -> ArcSlice<MaybeUninit<T>> {
+        if len == 0 {
+            return ArcSlice::default()
+        }
+        let header = inner::allocate::<Header, MaybeUninit<T>>(Header::new(len), len);
+        ArcSlice::from_header(header, len)
+    }
+
+    /// Construct a new `ArcSlice` with space for `len` elements, whose
+    /// bytes are all set to zero, like `Arc::new_zeroed_slice`.
+    ///
+    /// This is `new_uninit`, for types where zero bytes may be a valid
+    /// value, such as integers.
+    pub fn new_zeroed(len: usize) -> ArcSlice<MaybeUninit<T>> {
+        let x = ArcSlice::<T>::new_uninit(len);
+        unsafe { ptr::write_bytes(x.data as *mut MaybeUninit<T>, 0, len) }
+        x
+    }
+
+    /// Construct a new `ArcSlice` pointing to all `len` elements of
+    /// the new allocation `header`.
+    fn from_header(header: NonNull<Header>, len: usize) -> ArcSlice<T> {
         ArcSlice {
-            data: &*slice,
-            counts: Arc::new(slice),
+            data: ptr::slice_from_raw_parts(inner::elems::<Header, T>(header), len),
+            header,
+            _marker: PhantomData,
         }
     }
 
     /// Downgrade self into a weak slice.
     pub fn downgrade(&self) -> WeakSlice<T> {
+        if is_counted(self.header) {
+            self.header().inc_weak();
+        }
         WeakSlice {
             data: self.data,
-            counts: self.counts.downgrade()
+            header: self.header,
+            _marker: PhantomData,
         }
     }
 
@@ -121,206 +550,5840 @@ impl<T> ArcSlice<T> {
         let hi = self.len();
         self.slice(lo, hi)
     }
-}
 
-impl<T> Clone for ArcSlice<T> {
-    fn clone(&self) -> ArcSlice<T> {
-        ArcSlice {
-            data: self.data,
-            counts: self.counts.clone()
-        }
+    /// Construct a new `ArcSlice` that only points to the elements in
+    /// `range`, such as `2..`, `..=5` or `..`.
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications. Use `.clone()` if it is necessary to refer to
+    /// `self` after calling this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, or if either
+    /// are out of bounds, like indexing a slice.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3, 4, 5]);
+    /// assert_eq!(&*x.clone().slice_range(2..), [3, 4, 5]);
+    /// assert_eq!(&*x.slice_range(1..=2), [2, 3]);
+    /// ```
+    pub fn slice_range<R: ops::RangeBounds<usize>>(mut self, range: R) -> ArcSlice<T> {
+        self.data = &self[range::bounds(&range)];
+        self
     }
-}
 
-impl<T> ops::Deref for ArcSlice<T> {
-    type Target = [T];
-    fn deref<'a>(&'a self) -> &'a [T] {
-        unsafe {&*self.data}
+    /// Construct a new `ArcSlice` that only points to the elements in
+    /// `range`, or return an error if it is out of bounds.
+    ///
+    /// This is `slice_range` for indices that may be invalid, such as
+    /// lengths read from untrusted input.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from(*b"\x03abcdef");
+    /// let n = x[0] as usize;
+    /// assert_eq!(&*x.clone().try_slice(1..1 + n).unwrap(), b"abc");
+    /// assert!(x.try_slice(1..1 + 3 * n).is_err());
+    /// ```
+    pub fn try_slice<R: ops::RangeBounds<usize>>(mut self, range: R) -> Result<ArcSlice<T>, SliceError> {
+        self.data = range::get(&self, range)?;
+        Ok(self)
     }
-}
 
-impl<T> AsRef<[T]> for ArcSlice<T> {
-    fn as_ref(&self) -> &[T] { &**self }
-}
+    /// Construct a new `ArcSlice` pointing to the elements of `self` in
+    /// `range`, or `None` if it is out of bounds.
+    ///
+    /// This is like `<[T]>::get`, but the result shares ownership of
+    /// the elements.
+    pub fn get_owned<R: ops::RangeBounds<usize>>(&self, range: R) -> Option<ArcSlice<T>> {
+        let data: *const [T] = range::get(self, range).ok()?;
+        let mut slice = self.clone();
+        slice.data = data;
+        Some(slice)
+    }
 
-impl<T: PartialEq> PartialEq for ArcSlice<T> {
-    fn eq(&self, other: &ArcSlice<T>) -> bool { **self == **other }
-    fn ne(&self, other: &ArcSlice<T>) -> bool { **self != **other }
-}
-impl<T: Eq> Eq for ArcSlice<T> {}
+    /// Divide `self` into two `ArcSlice`s at index `mid`, the first
+    /// pointing to the elements before it and the second to those
+    /// from it onwards.
+    ///
+    /// This only increments the reference count once, for the second
+    /// half.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let (left, right) = ArcSlice::from([1, 2, 3, 4, 5]).split_at(2);
+    /// assert_eq!(&*left, [1, 2]);
+    /// assert_eq!(&*right, [3, 4, 5]);
+    /// ```
+    pub fn split_at(mut self, mid: usize) -> (ArcSlice<T>, ArcSlice<T>) {
+        let other = self.split_off(mid);
+        (self, other)
+    }
 
-impl<T: PartialOrd> PartialOrd for ArcSlice<T> {
-    fn partial_cmp(&self, other: &ArcSlice<T>) -> Option<cmp::Ordering> {
-        (**self).partial_cmp(&**other)
+    /// Shorten `self` to the elements before index `at`, returning a
+    /// new `ArcSlice` pointing to the rest.
+    ///
+    /// This is like `Vec::split_off`, but doesn't copy the elements:
+    /// both halves share the original allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut buf = ArcSlice::from(*b"headbody");
+    /// let body = buf.split_off(4);
+    /// assert_eq!(&*buf, b"head");
+    /// assert_eq!(&*body, b"body");
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> ArcSlice<T> {
+        let rest: *const [T] = &self[at..];
+        let mut other = self.clone();
+        other.data = rest;
+        self.data = &self[..at];
+        other
     }
-    fn lt(&self, other: &ArcSlice<T>) -> bool { **self < **other }
-    fn le(&self, other: &ArcSlice<T>) -> bool { **self <= **other }
-    fn gt(&self, other: &ArcSlice<T>) -> bool { **self > **other }
-    fn ge(&self, other: &ArcSlice<T>) -> bool { **self >= **other }
-}
-impl<T: Ord> Ord for ArcSlice<T> {
-    fn cmp(&self, other: &ArcSlice<T>) -> cmp::Ordering { (**self).cmp(&**other) }
-}
 
-impl<T: Hash> Hash for ArcSlice<T> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        Hash::hash(&**self, state)
+    /// Shorten `self` to the elements from index `at` onwards,
+    /// returning a new `ArcSlice` pointing to the ones before it.
+    ///
+    /// This is the reverse of `split_off`, and is like
+    /// `bytes::Bytes::split_to`: it peels frames off the front of a
+    /// buffer without copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut buf = ArcSlice::from(*b"\x03abc\x02de");
+    /// let mut frames = vec![];
+    /// while !buf.is_empty() {
+    ///     let len = buf[0] as usize;
+    ///     buf.advance(1);
+    ///     frames.push(buf.split_to(len));
+    /// }
+    /// assert_eq!(frames, [ArcSlice::from(*b"abc"), ArcSlice::from(*b"de")]);
+    /// ```
+    pub fn split_to(&mut self, at: usize) -> ArcSlice<T> {
+        let rest = self.split_off(at);
+        mem::replace(self, rest)
     }
-}
 
-impl<T: fmt::Debug> fmt::Debug for ArcSlice<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
+    /// Drop the first `n` elements from the view of `self`, in place.
+    ///
+    /// This is like `self.slice_from(n)`, but doesn't need `self` to
+    /// be moved out and reassigned, and doesn't touch the reference
+    /// counts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut buf = ArcSlice::from(*b"GET /index.html");
+    /// buf.advance(4);
+    /// assert_eq!(&*buf, b"/index.html");
+    /// ```
+    pub fn advance(&mut self, n: usize) {
+        assert!(n <= self.len(), "ArcSlice::advance: {} out of bounds for length {}", n, self.len());
+        self.data = &self[n..];
     }
-}
 
-impl<T> WeakSlice<T> {
-    /// Attempt to upgrade `self` to a strongly-counted `ArcSlice`.
+    /// Shorten the view of `self` to its first `len` elements, in
+    /// place. This has no effect if `len >= self.len()`, like
+    /// `Vec::truncate`.
     ///
-    /// Returns `None` if this is not possible (the data has already
-    /// been freed).
-    pub fn upgrade(&self) -> Option<ArcSlice<T>> {
-        self.counts.upgrade().map(|counts| {
-            ArcSlice {
-                data: self.data,
-                counts: counts
-            }
-        })
+    /// The elements are still owned by the allocation, so aren't
+    /// dropped until it is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut line = ArcSlice::from(*b"hello\r\n");
+    /// line.truncate(5);
+    /// assert_eq!(&*line, b"hello");
+    /// line.truncate(10);
+    /// assert_eq!(&*line, b"hello");
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.data = &self[..len];
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{ArcSlice, WeakSlice};
-    use std::sync::{Arc, Mutex};
-    use std::cell::Cell;
-    use std::cmp::Ordering;
-    #[test]
-    fn clone() {
-        let x = ArcSlice::new(Box::new([Cell::new(false)]));
-        let y = x.clone();
+    /// Divide `self` into `ArcSlice`s of `size` elements each (except
+    /// possibly the last), like `<[T]>::chunks`.
+    ///
+    /// Each chunk shares ownership of the elements, so they can be
+    /// moved elsewhere independently.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3, 4, 5]);
+    /// let chunks: Vec<_> = x.chunks_owned(2).collect();
+    /// assert_eq!(chunks, [ArcSlice::from([1, 2]), ArcSlice::from([3, 4]), ArcSlice::from([5])]);
+    /// ```
+    pub fn chunks_owned(self, size: usize) -> ChunksOwned<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        ChunksOwned { rest: self, size }
+    }
 
-        assert_eq!(x[0].get(), false);
-        assert_eq!(y[0].get(), false);
+    /// Divide `self` into `ArcSlice`s of exactly `size` elements each,
+    /// like `<[T]>::chunks_exact`.
+    ///
+    /// The `len() % size` elements at the end that don't make up a
+    /// whole chunk are not yielded, but can be retrieved with the
+    /// iterator's `remainder` method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let chunks = ArcSlice::from([1, 2, 3, 4, 5]).chunks_exact_owned(2);
+    /// assert_eq!(&**chunks.remainder(), [5]);
+    /// let chunks: Vec<_> = chunks.collect();
+    /// assert_eq!(chunks, [ArcSlice::from([1, 2]), ArcSlice::from([3, 4])]);
+    /// ```
+    pub fn chunks_exact_owned(mut self, size: usize) -> ChunksExactOwned<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        let len = self.len();
+        let rem = self.split_off(len - len % size);
+        ChunksExactOwned { rest: self, rem, size }
+    }
 
-        x[0].set(true);
-        assert_eq!(x[0].get(), true);
-        assert_eq!(y[0].get(), true);
+    /// Divide `self` into `ArcSlice`s of `size` elements each, starting
+    /// at the end, like `<[T]>::rchunks`. The last chunk yielded
+    /// (which holds the start of `self`) may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3, 4, 5]);
+    /// let chunks: Vec<_> = x.rchunks_owned(2).collect();
+    /// assert_eq!(chunks, [ArcSlice::from([4, 5]), ArcSlice::from([2, 3]), ArcSlice::from([1])]);
+    /// ```
+    pub fn rchunks_owned(self, size: usize) -> RChunksOwned<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        RChunksOwned { rest: self, size }
+    }
+
+    /// Construct a `StridedArcSlice` pointing to every `step`th
+    /// element of `self`, starting with the first, like
+    /// `Iterator::step_by`.
+    ///
+    /// This shares the allocation of `self`, so can be used to pick
+    /// out a column of a row-major matrix without copying it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3, 4, 5]);
+    /// let odd = x.step_by_owned(2);
+    /// assert_eq!(odd.iter().cloned().collect::<Vec<_>>(), [1, 3, 5]);
+    /// ```
+    pub fn step_by_owned(self, step: usize) -> StridedArcSlice<T> {
+        StridedArcSlice::new(self, step)
+    }
+
+    /// Construct `ArcSlice`s pointing to each contiguous window of
+    /// `size` elements of `self`, like `<[T]>::windows`. The windows
+    /// overlap, and there are none if `self` is shorter than `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3, 4]);
+    /// let windows: Vec<_> = x.windows_owned(3).collect();
+    /// assert_eq!(windows, [ArcSlice::from([1, 2, 3]), ArcSlice::from([2, 3, 4])]);
+    /// ```
+    pub fn windows_owned(self, size: usize) -> WindowsOwned<T> {
+        assert!(size != 0, "window size must be non-zero");
+        WindowsOwned { rest: self, size }
+    }
+
+    /// Divide `self` into `ArcSlice`s pointing to the runs of elements
+    /// separated by elements that match `pred`, like `<[T]>::split`.
+    /// The matching elements are not included in any part.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 0, 2, 3, 0, 0, 4]);
+    /// let parts: Vec<_> = x.split_owned(|&n| n == 0).map(|p| p.to_vec()).collect();
+    /// assert_eq!(parts, [vec![1], vec![2, 3], vec![], vec![4]]);
+    /// ```
+    pub fn split_owned<F: FnMut(&T) -> bool>(self, pred: F) -> SplitOwned<T, F> {
+        SplitOwned { rest: Some(self), pred }
+    }
+
+    /// Divide `self` into at most `n` `ArcSlice`s pointing to the runs
+    /// of elements separated by elements that match `pred`, like
+    /// `<[T]>::splitn`. The last part contains the remainder of
+    /// `self`, including any further matching elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let line = ArcSlice::from(*b"GET /index.html HTTP/1.1");
+    /// let parts: Vec<_> = line.splitn_owned(2, |&b| b == b' ').collect();
+    /// assert_eq!(&*parts[0], b"GET");
+    /// assert_eq!(&*parts[1], b"/index.html HTTP/1.1");
+    /// ```
+    pub fn splitn_owned<F: FnMut(&T) -> bool>(self, n: usize, pred: F) -> SplitNOwned<T, F> {
+        SplitNOwned { inner: self.split_owned(pred), n }
+    }
+
+    /// Construct a new `ArcSlice` that only points to elements at
+    /// indices `lo` (inclusive) through `hi` (exclusive), without
+    /// checking the bounds.
+    ///
+    /// This is `slice` for code that has already validated the
+    /// indices.
+    ///
+    /// # Safety
+    ///
+    /// `lo <= hi` and `hi <= self.len()` must hold.
+    pub unsafe fn slice_unchecked(mut self, lo: usize, hi: usize) -> ArcSlice<T> {
+        self.data = self.get_unchecked(lo..hi);
+        self
+    }
+
+    /// Construct a new `ArcRef` pointing to the element at index
+    /// `i`, or `None` if `i` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::new(Box::new(["foo", "bar", "baz"]));
+    /// let bar = x.get_ref(1).unwrap();
+    /// drop(x);
+    /// assert_eq!(*bar, "bar");
+    /// ```
+    pub fn get_ref(&self, i: usize) -> Option<ArcRef<T>> {
+        if i < self.len() {
+            Some(ArcRef { slice: self.clone().slice(i, i + 1) })
+        } else {
+            None
+        }
+    }
+
+    /// Convert `self` into a `ArcRef` pointing to the element at index
+    /// `i`, or `None` if `i` is out of bounds.
+    ///
+    /// This is `get_ref` without the reference-count modifications,
+    /// for when `self` isn't needed afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let table = ArcSlice::from([("alice", 3), ("bob", 5)]);
+    /// let bob = table.clone().into_ref(1).unwrap();
+    /// drop(table);
+    /// assert_eq!(bob.1, 5);
+    /// ```
+    pub fn into_ref(self, i: usize) -> Option<ArcRef<T>> {
+        if i < self.len() {
+            Some(ArcRef { slice: self.slice(i, i + 1) })
+        } else {
+            None
+        }
+    }
+
+    /// Convert `self` into a `ArcView` pointing to a value derived
+    /// from its elements by `f`.
+    ///
+    /// This allows keeping a reference into the elements, such as a
+    /// field of one of them or a `str` validated from bytes, without
+    /// a lifetime tied to a borrow of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let bytes = ArcSlice::from(*b"name=value");
+    /// let value = bytes.map_ref(|b| std::str::from_utf8(&b[5..]).unwrap());
+    /// assert_eq!(&*value, "value");
+    /// ```
+    pub fn map_ref<U: ?Sized, F>(self, f: F) -> ArcView<U>
+        where F: FnOnce(&[T]) -> &U,
+              T: Send + Sync
+    {
+        let data: *const U = f(&self);
+        let (_, header) = self.into_parts();
+        ArcView { data, header, release: release_strong::<T> }
+    }
+
+    /// Construct a new `ArcRef` pointing to the element at index
+    /// `i`, without checking the bounds.
+    ///
+    /// # Safety
+    ///
+    /// `i < self.len()` must hold.
+    pub unsafe fn get_ref_unchecked(&self, i: usize) -> ArcRef<T> {
+        ArcRef { slice: self.clone().slice_unchecked(i, i + 1) }
+    }
+
+    /// Divide `self` into its first element and the rest, or return
+    /// `None` if it is empty, like `<[T]>::split_first`.
+    ///
+    /// Both parts keep the allocation alive, so they can be stored or
+    /// sent elsewhere, unlike the borrowed parts from `split_first`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// fn sum(x: ArcSlice<i32>) -> i32 {
+    ///     match x.split_first_owned() {
+    ///         Some((head, tail)) => *head + sum(tail),
+    ///         None => 0,
+    ///     }
+    /// }
+    /// assert_eq!(sum(ArcSlice::from([1, 2, 3])), 6);
+    /// ```
+    pub fn split_first_owned(self) -> Option<(ArcRef<T>, ArcSlice<T>)> {
+        if self.is_empty() {
+            return None
+        }
+        let (first, rest) = self.split_at(1);
+        Some((ArcRef { slice: first }, rest))
+    }
+
+    /// Divide `self` into its last element and the rest, or return
+    /// `None` if it is empty, like `<[T]>::split_last`.
+    pub fn split_last_owned(self) -> Option<(ArcRef<T>, ArcSlice<T>)> {
+        let len = self.len();
+        if len == 0 {
+            return None
+        }
+        let (rest, last) = self.split_at(len - 1);
+        Some((ArcRef { slice: last }, rest))
+    }
+
+    /// Replace `self` with an empty `ArcSlice`, returning the previous
+    /// value.
+    ///
+    /// This is like `mem::take`, and allows a `ArcSlice` to be moved
+    /// out of a field that can't be left uninitialized. The empty
+    /// `ArcSlice` left behind doesn't allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut x = ArcSlice::new(Box::new([1, 2, 3]));
+    /// let y = x.take();
+    /// assert_eq!(&*x, []);
+    /// assert_eq!(&*y, [1, 2, 3]);
+    /// ```
+    pub fn take(&mut self) -> ArcSlice<T> {
+        mem::take(self)
+    }
+
+    /// Get the elements viewed by `self` back as a `Box<[T]>`, if
+    /// `self` is the only `ArcSlice` pointing into its allocation, or
+    /// return `self` unchanged otherwise.
+    ///
+    /// Like `Arc::try_unwrap`, this succeeds even if there are
+    /// `WeakSlice`s, which will then fail to upgrade. The elements
+    /// are moved (not cloned) into a new allocation, and any elements
+    /// of the allocation outside the view are dropped. An empty slice
+    /// that doesn't point into an allocation, like `ArcSlice::empty()`,
+    /// always succeeds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::new(Box::new([1, 2, 3]));
+    /// let y = x.clone();
+    /// let x = x.try_unwrap().unwrap_err();
+    /// drop(y);
+    /// assert_eq!(&*x.slice_from(1).try_unwrap().unwrap(), [2, 3]);
+    /// ```
+    pub fn try_unwrap(self) -> Result<Box<[T]>, ArcSlice<T>> {
+        let (lo, len) = (self.offset(), self.len());
+        if !is_counted(self.header) {
+            return if len == 0 { Ok(Box::new([])) } else { Err(self) }
+        }
+        if self.header().strong.compare_exchange(1, 0, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return Err(self)
+        }
+        let (_, header) = self.into_parts();
+        unsafe {
+            let total = header.as_ref().len;
+            let elems = inner::take_elems::<Header, T>(header, total, lo, len);
+            release_weak::<T>(header);
+            Ok(elems)
+        }
+    }
+
+    /// Split `self` into its parts, without running its destructor.
+    fn into_parts(self) -> (*const [T], NonNull<Header>) {
+        let this = mem::ManuallyDrop::new(self);
+        (this.data, this.header)
+    }
+
+    /// The header of the allocation that `self` points into.
+    fn header(&self) -> &Header {
+        unsafe { self.header.as_ref() }
+    }
+
+    /// Check the internal invariants of `self`, if enabled.
+    #[inline]
+    fn check(&self) {
+        if checks::ENABLED && is_counted(self.header) {
+            checks::view(self.data, unsafe { whole(self.header) }, self.header().strong());
+        }
+    }
+
+    /// The index of the first element of `self` in the whole
+    /// allocation it points into.
+    ///
+    /// For zero-sized `T`, this is always 0, as the position of a
+    /// view can't be recovered from its address.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::new(Box::new([1, 2, 3, 4])).slice(1, 3);
+    /// assert_eq!(x.offset(), 1);
+    /// assert_eq!(&*x.full_slice(), [1, 2, 3, 4]);
+    /// ```
+    pub fn offset(&self) -> usize {
+        match ::core::mem::size_of::<T>() {
+            0 => 0,
+            _ if !is_counted(self.header) => 0,
+            size => (self.data as *const T as usize - inner::elems::<Header, T>(self.header) as usize) / size
+        }
+    }
+
+    /// Construct a new `ArcSlice` pointing to every element of the
+    /// allocation that `self` points into, undoing any slicing.
+    pub fn full_slice(&self) -> ArcSlice<T> {
+        let mut full = self.clone();
+        if is_counted(self.header) {
+            full.data = unsafe { whole(self.header) };
+        }
+        full
+    }
+
+    /// Whether `self` and `other` are the same view of the same
+    /// elements: the same elements of the same allocation, rather than
+    /// just equal ones.
+    ///
+    /// This is a cheap identity check, like `Arc::ptr_eq`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3]);
+    /// assert!(x.ptr_eq(&x.clone()));
+    /// assert!(!x.ptr_eq(&x.clone().slice_to(2)));
+    /// assert!(!x.ptr_eq(&ArcSlice::from([1, 2, 3])));
+    /// ```
+    pub fn ptr_eq(&self, other: &ArcSlice<T>) -> bool {
+        self.header == other.header && ptr::eq(self.data, other.data)
+    }
+
+    /// Whether `self` and `other` point into the same allocation,
+    /// possibly viewing different elements of it.
+    ///
+    /// Slices that don't own an allocation, like empty ones, only
+    /// count as sharing one if they are `ptr_eq`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3]);
+    /// assert!(x.same_allocation(&x.clone().slice_from(2)));
+    /// assert!(!x.same_allocation(&ArcSlice::from([1, 2, 3])));
+    /// ```
+    pub fn same_allocation(&self, other: &ArcSlice<T>) -> bool {
+        if is_counted(self.header) {
+            self.header == other.header
+        } else {
+            self.ptr_eq(other)
+        }
+    }
+
+    /// The number of `ArcSlice`s pointing into the allocation that
+    /// `self` points into, including `self`, like `Arc::strong_count`.
+    ///
+    /// Each subslice counts separately, so this helps find long-lived
+    /// views that keep a large allocation alive. Slices that don't own
+    /// an allocation, like empty ones, aren't counted, and this
+    /// returns 1 for them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3]);
+    /// let y = x.clone().slice_from(1);
+    /// let w = y.downgrade();
+    /// assert_eq!(x.strong_count(), 2);
+    /// assert_eq!(x.weak_count(), 1);
+    /// ```
+    pub fn strong_count(&self) -> usize {
+        if is_counted(self.header) { self.header().strong() } else { 1 }
+    }
+
+    /// The number of `WeakSlice`s pointing into the allocation that
+    /// `self` points into, like `Arc::weak_count`.
+    ///
+    /// Slices that don't own an allocation, like empty ones, aren't
+    /// counted, and this returns 0 for them.
+    pub fn weak_count(&self) -> usize {
+        if !is_counted(self.header) {
+            return 0
+        }
+        match self.header().weak() {
+            // `is_unique` is checking, so there were none
+            usize::MAX => 0,
+            // one is held by the strong references together
+            n => n - 1,
+        }
+    }
+
+    /// The whole allocation that `self` points into, along with an
+    /// address that identifies it uniquely while it is alive.
+    #[cfg(all(feature = "serde", feature = "std"))]
+    pub(crate) fn allocation(&self) -> (&[T], usize) {
+        if is_counted(self.header) {
+            (unsafe { whole(self.header) }, self.header.as_ptr() as usize)
+        } else {
+            (self, self.data as *const T as usize)
+        }
+    }
+
+    /// Whether `self` is the only `ArcSlice` or `WeakSlice` pointing
+    /// into its allocation, so `get_mut` will succeed.
+    ///
+    /// Slices that don't point into an allocation, like
+    /// `ArcSlice::empty()` and those from `from_static`, aren't unique,
+    /// though `get_mut` still succeeds for empty ones.
+    ///
+    /// This allows choosing between modifying a slice in place and
+    /// copying it first, as `make_mut` does. Another thread may clone
+    /// or drop other handles to the allocation concurrently, so a
+    /// `false` result may be out of date by the time it is used (but a
+    /// `true` one can't be, since no other handle exists to do so).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3]);
+    /// assert!(x.is_unique());
+    /// let w = x.downgrade();
+    /// assert!(!x.is_unique());
+    /// drop(w);
+    /// assert!(x.is_unique());
+    /// ```
+    pub fn is_unique(&self) -> bool {
+        is_counted(self.header) && self.header().is_unique()
+    }
+
+    /// Get mutable access to the elements viewed by `self`, if there
+    /// are no other `ArcSlice`s or `WeakSlice`s pointing into the same
+    /// allocation, or if `self` is empty and doesn't point into an
+    /// allocation.
+    ///
+    /// This is like `Arc::get_mut`, and allows a buffer to be filled
+    /// in place before it is shared.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut x = ArcSlice::new(Box::new([0; 4])).slice_from(1);
+    /// x.get_mut().unwrap()[0] = 1;
+    ///
+    /// let y = x.clone();
+    /// assert!(x.get_mut().is_none());
+    /// drop(y);
+    /// assert_eq!(&*x, [1, 0, 0]);
+    /// ```
+    pub fn get_mut(&mut self) -> Option<&mut [T]> {
+        if self.is_unique() {
+            Some(unsafe { &mut *(self.data as *mut [T]) })
+        } else if self.is_empty() && !is_counted(self.header) {
+            // there are no elements for anything else to see
+            Some(&mut [])
+        } else {
+            None
+        }
+    }
+
+    /// Convert `self` into a `ArcSliceMut`, if it is the only handle to
+    /// its allocation (like `is_unique`) and points to all of it, or
+    /// return it unchanged otherwise.
+    ///
+    /// This allows a buffer to be reused for the next round of filling
+    /// in and sharing, once everything it was shared with is done with
+    /// it, rather than allocating a new one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([1, 2, 3]);
+    /// let y = x.clone().slice_from(1);
+    /// let x = x.try_into_mut().unwrap_err();
+    /// let y = y.try_into_mut().unwrap_err();
+    /// drop(y);
+    ///
+    /// let mut x = x.try_into_mut().unwrap();
+    /// x[0] = 4;
+    /// assert_eq!(&*x.freeze(), [4, 2, 3]);
+    /// ```
+    pub fn try_into_mut(self) -> Result<ArcSliceMut<T>, ArcSlice<T>> {
+        if self.is_unique() && self.offset() == 0 && self.len() == self.header().len {
+            Ok(ArcSliceMut::from_unique(self))
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<T> ArcSlice<MaybeUninit<T>> {
+    /// Convert `self` into a `ArcSlice<T>`, pointing to the same
+    /// elements.
+    ///
+    /// # Safety
+    ///
+    /// All of the elements of the allocation must be initialized, not
+    /// just the ones `self` points to, since they are dropped as `T`s
+    /// when the last `ArcSlice<T>` pointing to them is.
+    pub unsafe fn assume_init(self) -> ArcSlice<T> {
+        let (data, header) = self.into_parts();
+        ArcSlice {
+            data: data as *const [T],
+            header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone> ArcSlice<T> {
+    /// Construct a new `ArcSlice` containing clones of the elements of
+    /// `slice`.
+    ///
+    /// The elements are cloned straight into the new allocation. With
+    /// the `unstable` feature, they are copied all at once when
+    /// `T: Copy`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from_slice(b"header: value");
+    /// assert_eq!(&*x.slice_to(6), b"header");
+    /// ```
+    pub fn from_slice(slice: &[T]) -> ArcSlice<T> {
+        if slice.is_empty() {
+            return ArcSlice::default()
+        }
+        let header = inner::from_slice::<Header, T>(Header::new(slice.len()), slice);
+        ArcSlice::from_header(header, slice.len())
+    }
+
+    /// Construct a new `ArcSlice` containing `n` clones of `value`, like
+    /// `vec![value; n]`.
+    ///
+    /// The elements are written straight into the new allocation.
+    /// With the `unstable` feature, this is a single `memset` for
+    /// `u8`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let zeros = ArcSlice::repeat(0u8, 4096);
+    /// assert!(zeros.iter().all(|&b| b == 0));
+    /// assert_eq!(&*ArcSlice::repeat("ab", 2), ["ab", "ab"]);
+    /// ```
+    pub fn repeat(value: T, n: usize) -> ArcSlice<T> {
+        if n == 0 {
+            return ArcSlice::default()
+        }
+        let header = inner::from_elem::<Header, T>(Header::new(n), value, n);
+        ArcSlice::from_header(header, n)
+    }
+
+    /// Construct a new `ArcSlice` containing clones of the elements of
+    /// each of `parts` in turn, like `<[T]>::concat`.
+    ///
+    /// The elements are cloned straight into a single new allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total length overflows `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let body = ArcSlice::from(*b"hello");
+    /// let message = ArcSlice::concat([&b"len=5;"[..], &body]);
+    /// assert_eq!(&*message, b"len=5;hello");
+    /// ```
+    pub fn concat<I>(parts: I) -> ArcSlice<T>
+        where I: IntoIterator, I::Item: AsRef<[T]>
+    {
+        let parts: Vec<I::Item> = parts.into_iter().collect();
+        let len = parts.iter()
+            .try_fold(0usize, |len, part| len.checked_add(part.as_ref().len()))
+            .expect("ArcSlice::concat: total length overflows usize");
+        if len == 0 {
+            return ArcSlice::default()
+        }
+        let header = inner::from_parts::<Header, T, I::Item>(Header::new(len), &parts, len);
+        ArcSlice::from_header(header, len)
+    }
+
+    /// Construct a new `ArcSlice` containing clones of the elements of
+    /// each of `parts` in turn, with the elements of `sep` between
+    /// each, like `<[T]>::join`.
+    ///
+    /// The elements are cloned straight into a single new allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let parts = [ArcSlice::from(*b"a"), ArcSlice::from(*b"bc")];
+    /// assert_eq!(&*ArcSlice::join(&parts, b", "), b"a, bc");
+    /// ```
+    pub fn join(parts: &[ArcSlice<T>], sep: &[T]) -> ArcSlice<T> {
+        let mut pieces = Vec::with_capacity(2 * parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                pieces.push(sep);
+            }
+            pieces.push(&**part);
+        }
+        ArcSlice::concat(pieces)
+    }
+
+    /// Construct a new `ArcSlice` containing clones of the elements of
+    /// each of the slices in `nested` in turn, like `<[[T]]>::concat`.
+    ///
+    /// The elements are cloned straight into a single new allocation.
+    ///
+    /// To avoid copying, the slices can instead be collected into a
+    /// `SegmentedSlice`, which behaves like their concatenation:
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    /// use shared_slice::segmented::SegmentedSlice;
+    ///
+    /// let batches = ArcSlice::from([ArcSlice::from([1, 2]), ArcSlice::from([3])]);
+    /// let all: SegmentedSlice<_> = batches.iter().cloned().collect();
+    /// assert_eq!(all.iter().sum::<i32>(), 6);
+    /// ```
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let batches = ArcSlice::from([ArcSlice::from([1, 2]), ArcSlice::from([3])]);
+    /// assert_eq!(&*ArcSlice::flatten(batches), [1, 2, 3]);
+    /// ```
+    pub fn flatten(nested: ArcSlice<ArcSlice<T>>) -> ArcSlice<T> {
+        ArcSlice::concat(&*nested)
+    }
+
+    /// Get mutable access to the elements viewed by `self`, first
+    /// copying them into a new allocation if any other `ArcSlice` or
+    /// `WeakSlice` points into the current one.
+    ///
+    /// This is like `Arc::make_mut`, for copy-on-write: the other
+    /// handles are left pointing to the original elements. Only the
+    /// elements viewed by `self` are copied.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let mut x = ArcSlice::new(Box::new([1, 2, 3]));
+    /// let y = x.clone();
+    /// x.make_mut()[0] = 10;
+    /// assert_eq!(&*x, [10, 2, 3]);
+    /// assert_eq!(&*y, [1, 2, 3]);
+    /// ```
+    pub fn make_mut(&mut self) -> &mut [T] {
+        if !self.is_unique() {
+            *self = ArcSlice::from_slice(self);
+        }
+        unsafe { &mut *(self.data as *mut [T]) }
+    }
+
+    /// Construct an iterator over clones of the elements of `self`.
+    ///
+    /// Unlike `self.iter().cloned()`, this holds its own reference to
+    /// the elements rather than borrowing `self`, so it can outlive
+    /// `self`, or be moved elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// fn evens(x: &ArcSlice<u32>) -> impl Iterator<Item = u32> + 'static {
+    ///     x.iter_cloned().filter(|n| n % 2 == 0)
+    /// }
+    /// let it = evens(&ArcSlice::from([1, 2, 3, 4]));
+    /// assert_eq!(it.collect::<Vec<_>>(), [2, 4]);
+    /// ```
+    pub fn iter_cloned(&self) -> IterCloned<T> {
+        IterCloned { rest: self.clone() }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<T: Clone> ArcSlice<T> {
+    /// Construct a `ArcSlice` containing the elements of `self` in a
+    /// random order.
+    ///
+    /// If `self` is the only reference to its allocation, the
+    /// elements are shuffled in place, otherwise they are copied into
+    /// a new allocation and the original is left untouched.
+    ///
+    /// This requires the `rand` feature.
+    pub fn shuffled<R: Rng + ?Sized>(mut self, rng: &mut R) -> ArcSlice<T> {
+        if let Some(data) = self.get_mut() {
+            data.shuffle(rng);
+            return self
+        }
+
+        let mut copy = ArcSlice::from_slice(&self);
+        copy.get_mut().unwrap().shuffle(rng);
+        copy
+    }
+
+    /// Construct a new `ArcSlice` containing `k` elements of `self`
+    /// chosen at random, without replacement.
+    ///
+    /// If `k` is greater than `self.len()`, all the elements are
+    /// chosen. The order of the result is not specified.
+    ///
+    /// This requires the `rand` feature.
+    pub fn choose_owned<R: Rng + ?Sized>(&self, rng: &mut R, k: usize) -> ArcSlice<T> {
+        let mut builder = ArcSliceBuilder::new();
+        builder.extend(self.choose_multiple(rng, k).cloned());
+        builder.build()
+    }
+}
+
+#[cfg(feature = "digest")]
+impl ArcSlice<u8> {
+    /// Compute the digest of the bytes viewed by `self` with the hash
+    /// function `D`.
+    ///
+    /// This requires the `digest` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate shared_slice; extern crate sha2;
+    /// use shared_slice::arc::ArcSlice;
+    /// use sha2::{Digest, Sha256};
+    ///
+    /// # fn main() {
+    /// let x = ArcSlice::new(Box::new(*b"hello world"));
+    /// assert_eq!(x.hash_with::<Sha256>(), Sha256::digest(b"hello world"));
+    /// # }
+    /// ```
+    pub fn hash_with<D: Digest>(&self) -> Output<D> {
+        D::digest(&**self)
+    }
+}
+
+#[cfg(feature = "xxhash-rust")]
+impl ArcSlice<u8> {
+    /// Compute a fast, non-cryptographic 64-bit fingerprint of the
+    /// bytes viewed by `self`, with XXH3.
+    ///
+    /// This is stable across runs and platforms, so can be used as a
+    /// key for content-addressed caches, but is not resistant to
+    /// deliberate collisions.
+    ///
+    /// This requires the `xxhash-rust` feature.
+    pub fn fingerprint64(&self) -> u64 {
+        xxh3_64(self)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl ArcSlice<u8> {
+    /// Find the leftmost-first match of `re` in `self`, returning it
+    /// as a `ArcSlice` pointing into the same allocation.
+    ///
+    /// This requires the `regex` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate shared_slice; extern crate regex;
+    /// use shared_slice::arc::ArcSlice;
+    /// use regex::bytes::Regex;
+    ///
+    /// # fn main() {
+    /// let re = Regex::new("[0-9]+").unwrap();
+    /// let x = ArcSlice::new(Box::new(*b"abc 123 def"));
+    /// assert_eq!(&*x.find_owned(&re).unwrap(), b"123");
+    /// # }
+    /// ```
+    pub fn find_owned(&self, re: &Regex) -> Option<ArcSlice<u8>> {
+        re.find(self).map(|m| self.clone().slice(m.start(), m.end()))
+    }
+
+    /// Create an iterator over the successive non-overlapping matches
+    /// of `re` in `self`, yielding each as a `ArcSlice` pointing into
+    /// the same allocation.
+    ///
+    /// This requires the `regex` feature.
+    pub fn find_iter_owned<'r>(&self, re: &'r Regex) -> FindOwned<'r> {
+        FindOwned {
+            re,
+            haystack: self.clone(),
+            pos: 0,
+            last_end: None,
+        }
+    }
+
+    /// Match `re` against `self`, returning the capture groups (with
+    /// the whole match at index 0) as `ArcSlice`s pointing into the
+    /// same allocation.
+    ///
+    /// Groups that did not participate in the match are `None`.
+    ///
+    /// This requires the `regex` feature.
+    pub fn captures_owned(&self, re: &Regex) -> Option<Vec<Option<ArcSlice<u8>>>> {
+        re.captures(self).map(|caps| {
+            caps.iter()
+                .map(|m| m.map(|m| self.clone().slice(m.start(), m.end())))
+                .collect()
+        })
+    }
+}
+
+/// An iterator over the matches of a regular expression in a
+/// `ArcSlice<u8>`.
+///
+/// This is created by `ArcSlice::find_iter_owned`, and requires the
+/// `regex` feature.
+#[cfg(feature = "regex")]
+pub struct FindOwned<'r> {
+    re: &'r Regex,
+    haystack: ArcSlice<u8>,
+    pos: usize,
+    last_end: Option<usize>,
+}
+
+#[cfg(feature = "regex")]
+impl<'r> Iterator for FindOwned<'r> {
+    type Item = ArcSlice<u8>;
+
+    fn next(&mut self) -> Option<ArcSlice<u8>> {
+        loop {
+            if self.pos > self.haystack.len() {
+                return None
+            }
+            let m = self.re.find_at(&self.haystack, self.pos)?;
+            if m.start() == m.end() {
+                // step over empty matches, so that we always make
+                // progress, and skip those directly after the
+                // previous match.
+                self.pos = m.end() + 1;
+                if Some(m.end()) == self.last_end {
+                    continue
+                }
+            } else {
+                self.pos = m.end();
+            }
+            self.last_end = Some(m.end());
+            return Some(self.haystack.clone().slice(m.start(), m.end()))
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl<'r> FusedIterator for FindOwned<'r> {}
+
+#[cfg(feature = "std")]
+impl ArcSlice<u8> {
+    /// Construct a new `ArcSlice` containing the contents of the file
+    /// at `path`.
+    ///
+    /// This requires the `std` feature.
+    pub fn from_file<P: AsRef<path::Path>>(path: P) -> io::Result<ArcSlice<u8>> {
+        let mut file = fs::File::open(path)?;
+        // the length is only a guess, since the file may change while
+        // it is being read
+        let size = file.metadata().map_or(0, |m| m.len() as usize);
+        let mut builder = ArcSliceBuilder::with_capacity(size);
+        builder.read_to_end_from(&mut file)?;
+        Ok(builder.build())
+    }
+
+    /// View `self` as an `IoSlice`, for writing it along with other
+    /// buffers with `Write::write_vectored` without copying them into
+    /// one contiguous buffer first.
+    ///
+    /// This requires the `std` feature.
+    pub fn as_io_slice(&self) -> io::IoSlice<'_> {
+        io::IoSlice::new(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Send + Sync> ArcSlice<T> {
+    /// Call `f` on each chunk of `chunk_size` elements of `self` (the
+    /// last may be shorter), distributing the chunks across
+    /// `nthreads` threads.
+    ///
+    /// This requires the `std` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nthreads` or `chunk_size` is zero, or if `f`
+    /// panics.
+    pub fn par_for_each_chunks<F>(&self, nthreads: usize, chunk_size: usize, f: F)
+        where F: Fn(ArcSlice<T>) + Sync
+    {
+        self.par_map_reduce(nthreads, chunk_size, f, |(), ()| ());
+    }
+
+    /// Call `map` on each chunk of `chunk_size` elements of `self`
+    /// (the last may be shorter), distributing the chunks across
+    /// `nthreads` threads, and then combine the results with
+    /// `reduce`.
+    ///
+    /// The results are combined in the order of the chunks they came
+    /// from, so `reduce` only needs to be associative, not
+    /// commutative. Returns `None` if `self` is empty.
+    ///
+    /// This requires the `std` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nthreads` or `chunk_size` is zero, or if `map`
+    /// panics.
+    ///
+    /// # Examples
+    ///
+    /// Alice's sum from the crate documentation:
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let numbers = ArcSlice::new((0..10_000u64).collect::<Vec<_>>().into_boxed_slice());
+    ///
+    /// let sum = numbers.par_map_reduce(10, 1_000,
+    ///                                  |chunk| chunk.iter().fold(0, |a, &b| a + b),
+    ///                                  |a, b| a + b);
+    /// assert_eq!(sum, Some(49_995_000));
+    /// ```
+    pub fn par_map_reduce<R, M, F>(&self, nthreads: usize, chunk_size: usize,
+                                   map: M, reduce: F) -> Option<R>
+        where R: Send, M: Fn(ArcSlice<T>) -> R + Sync, F: Fn(R, R) -> R
+    {
+        assert!(nthreads > 0, "par_map_reduce: nthreads must be non-zero");
+        assert!(chunk_size > 0, "par_map_reduce: chunk_size must be non-zero");
+
+        let len = self.len();
+        let nchunks = len.div_ceil(chunk_size);
+        // workers take chunks one at a time, so that slow chunks don't
+        // hold up the others.
+        let next = AtomicUsize::new(0);
+        let (map, next) = (&map, &next);
+
+        let mut results = thread::scope(|scope| {
+            let workers = (0..cmp::min(nthreads, nchunks)).map(|_| {
+                scope.spawn(move || {
+                    let mut out = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= nchunks { break }
+
+                        let lo = i * chunk_size;
+                        let hi = cmp::min(len, lo + chunk_size);
+                        out.push((i, map(self.clone().slice(lo, hi))));
+                    }
+                    out
+                })
+            }).collect::<Vec<_>>();
+
+            workers.into_iter().flat_map(|w| {
+                match w.join() {
+                    Ok(out) => out,
+                    Err(e) => panic::resume_unwind(e),
+                }
+            }).collect::<Vec<_>>()
+        });
+
+        results.sort_by_key(|&(i, _)| i);
+        results.into_iter().map(|(_, r)| r).reduce(reduce)
+    }
+}
+
+#[cfg(all(feature = "flate2", feature = "std"))]
+impl ArcSlice<u8> {
+    /// Construct a new `ArcSlice` containing the decompression of the
+    /// gzip data `input`.
+    ///
+    /// The output is decompressed straight into its allocation, which
+    /// is sized up front with the uncompressed size recorded at the
+    /// end of `input` (within the limits of compression, to avoid huge
+    /// allocations for corrupt data), so that it is allocated once when
+    /// that size is accurate. If
+    /// `input` has several gzip members, they are decompressed one
+    /// after the other.
+    ///
+    /// This requires the `flate2` and `std` features.
+    pub fn decompress_gzip(input: &[u8]) -> io::Result<ArcSlice<u8>> {
+        // deflate cannot compress by more than a factor of 1032
+        let size = match *input {
+            [.., a, b, c, d] => u32::from_le_bytes([a, b, c, d]) as usize,
+            _ => 0,
+        };
+        let mut out = ArcSliceBuilder::with_capacity(cmp::min(size, input.len().saturating_mul(1032)));
+        out.read_to_end_from(MultiGzDecoder::new(input))?;
+        Ok(out.build())
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+impl ArcSlice<u8> {
+    /// Decode the text in `self` from `encoding` into a `ArcStr`,
+    /// along with whether there were any malformed sequences, which
+    /// are replaced with U+FFFD.
+    ///
+    /// A byte order mark for `encoding` at the start is removed. When
+    /// the bytes are already valid UTF-8 that decodes to itself (such
+    /// as ASCII text in an ASCII-compatible encoding), the result
+    /// points into `self`'s allocation, rather than copying it.
+    ///
+    /// This requires the `encoding_rs` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate shared_slice; extern crate encoding_rs;
+    /// use shared_slice::arc::ArcSlice;
+    /// use encoding_rs::WINDOWS_1252;
+    ///
+    /// # fn main() {
+    /// let (text, had_errors) = ArcSlice::from(*b"caf\xe9").decode(WINDOWS_1252);
+    /// assert_eq!((&*text, had_errors), ("café", false));
+    ///
+    /// let ascii = ArcSlice::from(*b"plain");
+    /// assert_eq!(ascii.decode(WINDOWS_1252).0.as_ptr(), ascii.as_ptr());
+    /// # }
+    /// ```
+    pub fn decode(&self, encoding: &'static Encoding) -> (ArcStr, bool) {
+        let (text, had_errors) = encoding.decode_with_bom_removal(self);
+        let text = match text {
+            Cow::Borrowed(s) => {
+                // a suffix of `self`, after any byte order mark
+                let lo = s.as_ptr() as usize - self.as_ptr() as usize;
+                unsafe { ArcStr::from_utf8_unchecked(self.clone().slice(lo, lo + s.len())) }
+            }
+            Cow::Owned(s) => ArcStr::from(s),
+        };
+        (text, had_errors)
+    }
+}
+
+#[cfg(all(feature = "zstd", feature = "std"))]
+impl ArcSlice<u8> {
+    /// Construct a new `ArcSlice` containing the decompression of the
+    /// zstd data `input`.
+    ///
+    /// The output is decompressed straight into its allocation. If
+    /// `input` declares its uncompressed size, that is allocated up
+    /// front with that size, up to 64 MiB (so that corrupt data can't
+    /// force a huge allocation). If `input` has several
+    /// frames, they are decompressed one after the other.
+    ///
+    /// This requires the `zstd` and `std` features.
+    pub fn decompress_zstd(input: &[u8]) -> io::Result<ArcSlice<u8>> {
+        const MAX_PREALLOCATE: u64 = 64 << 20;
+        let size = match zstd::zstd_safe::get_frame_content_size(input) {
+            Ok(Some(size)) => cmp::min(size, MAX_PREALLOCATE) as usize,
+            _ => 0,
+        };
+        let mut out = ArcSliceBuilder::with_capacity(size);
+        out.read_to_end_from(zstd::stream::read::Decoder::with_buffer(input)?)?;
+        Ok(out.build())
+    }
+}
+
+impl ArcSlice<u8> {
+    /// Whether `needle` occurs in `self`.
+    ///
+    /// This uses SIMD with the `memchr` feature.
+    pub fn contains_subslice(&self, needle: &[u8]) -> bool {
+        self.find_subslice(needle).is_some()
+    }
+
+    /// The index of the first occurrence of `needle` in `self`, or
+    /// `None` if there is none.
+    ///
+    /// This uses SIMD with the `memchr` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::new(Box::new(*b"key: value\r\n"));
+    /// assert_eq!(x.find_subslice(b"\r\n"), Some(10));
+    /// assert!(!x.contains_subslice(b"\n\n"));
+    /// ```
+    pub fn find_subslice(&self, needle: &[u8]) -> Option<usize> {
+        search::find(self, needle)
+    }
+
+    /// Iterate over the parts of `self` separated by the byte
+    /// `delim`, as `ArcSlice`s pointing into the same allocation.
+    ///
+    /// Like `<[u8]>::split`, this yields an empty slice for each pair
+    /// of adjacent delimiters, and at the start or end if `self`
+    /// starts or ends with `delim`. This uses SIMD with the `memchr`
+    /// feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::new(Box::new(*b"a,bc,,d"));
+    /// let parts: Vec<_> = x.split_byte_owned(b',').collect();
+    /// assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(), [&b"a"[..], b"bc", b"", b"d"]);
+    /// ```
+    pub fn split_byte_owned(self, delim: u8) -> SplitByteOwned {
+        SplitByteOwned { rest: Some(self), delim }
+    }
+
+    /// Split `self` into content-defined chunks, sharing the same
+    /// allocation.
+    ///
+    /// The boundaries between chunks are chosen by a rolling hash of
+    /// the bytes before them (as in FastCDC), so editing the data only
+    /// changes the chunks near the edit. This allows deduplicating
+    /// storage of similar data. Each chunk has length between
+    /// `min_size` and `max_size`, except possibly the last, and they
+    /// are usually around `avg_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < min_size <= avg_size <= max_size`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let data: Vec<u8> = (0..10_000u32).map(|i| (i * i >> 3) as u8).collect();
+    /// let x = ArcSlice::new(data.into_boxed_slice());
+    ///
+    /// let chunks: Vec<_> = x.clone().cdc_chunks(256, 1024, 4096).collect();
+    /// assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), x.len());
+    /// ```
+    pub fn cdc_chunks(self, min_size: usize, avg_size: usize, max_size: usize) -> CdcChunks {
+        cdc::check_sizes(min_size, avg_size, max_size);
+        CdcChunks {
+            rest: self,
+            sizes: (min_size, avg_size, max_size),
+        }
+    }
+}
+
+impl<T: Ord + Clone> ArcSlice<T> {
+    /// Construct a new `ArcSlice` containing the elements of all of the
+    /// sorted `slices`, in sorted order.
+    ///
+    /// Equal elements are ordered by the position of their slice in
+    /// `slices`. The result is built in a single allocation; use
+    /// `MergeSorted` to produce the elements lazily instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let runs = vec![ArcSlice::new(Box::new([1, 4, 7])),
+    ///                 ArcSlice::new(Box::new([2, 3, 8]))];
+    /// let merged = ArcSlice::merge_sorted(runs);
+    /// assert_eq!(&*merged, [1, 2, 3, 4, 7, 8]);
+    /// ```
+    pub fn merge_sorted<I>(slices: I) -> ArcSlice<T>
+        where I: IntoIterator<Item = ArcSlice<T>>
+    {
+        let iter = MergeSorted::new(slices);
+        let mut builder = ArcSliceBuilder::with_capacity(iter.len());
+        for x in iter {
+            builder.push(x);
+        }
+        builder.build()
+    }
+}
+
+impl<T> Clone for ArcSlice<T> {
+    fn clone(&self) -> ArcSlice<T> {
+        retain(self.header);
+        ArcSlice {
+            data: self.data,
+            header: self.header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// An empty `ArcSlice`, which doesn't allocate.
+impl<T> Default for ArcSlice<T> {
+    fn default() -> ArcSlice<T> {
+        ArcSlice::empty()
+    }
+}
+
+impl<T> From<Vec<T>> for ArcSlice<T> {
+    fn from(v: Vec<T>) -> ArcSlice<T> {
+        ArcSlice::new(v.into_boxed_slice())
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for ArcSlice<T> {
+    fn from(array: [T; N]) -> ArcSlice<T> {
+        if N == 0 {
+            return ArcSlice::default()
+        }
+        ArcSlice::from_header(inner::from_array::<Header, T, N>(Header::new(N), array), N)
+    }
+}
+
+impl<'a, T: Clone> From<&'a [T]> for ArcSlice<T> {
+    fn from(slice: &'a [T]) -> ArcSlice<T> {
+        ArcSlice::from_slice(slice)
+    }
+}
+
+/// Consume a `ArcSlice`, yielding its elements by value.
+///
+/// If this is the only `ArcSlice` pointing to the allocation, the
+/// elements are moved out of it and it is freed, otherwise they are
+/// cloned as needed.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcSlice;
+///
+/// let x = ArcSlice::from([String::from("a"), String::from("b")]);
+/// let y = x.clone();
+/// let cloned: Vec<String> = x.into_iter().collect();
+/// let moved: Vec<String> = y.into_iter().collect();
+/// assert_eq!(cloned, moved);
+/// ```
+impl<T: Clone> IntoIterator for ArcSlice<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        match self.try_unwrap() {
+            Ok(elems) => IntoIter { elems: Elems::Owned(elems.into_vec().into_iter()) },
+            Err(rest) => IntoIter { elems: Elems::Shared(IterCloned { rest }) },
+        }
+    }
+}
+
+impl<T> Drop for ArcSlice<T> {
+    fn drop(&mut self) {
+        unsafe { release_strong::<T>(self.header) }
+    }
+}
+
+impl<T> ops::Deref for ArcSlice<T> {
+    type Target = [T];
+    fn deref<'a>(&'a self) -> &'a [T] {
+        self.check();
+        unsafe {&*self.data}
+    }
+}
+
+impl<T> AsRef<[T]> for ArcSlice<T> {
+    fn as_ref(&self) -> &[T] { &**self }
+}
+
+impl<T: PartialEq> PartialEq for ArcSlice<T> {
+    fn eq(&self, other: &ArcSlice<T>) -> bool { identity::slice_eq(self, other) }
+}
+impl<T: Eq> Eq for ArcSlice<T> {}
+
+impl<T: PartialOrd> PartialOrd for ArcSlice<T> {
+    fn partial_cmp(&self, other: &ArcSlice<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+    fn lt(&self, other: &ArcSlice<T>) -> bool { **self < **other }
+    fn le(&self, other: &ArcSlice<T>) -> bool { **self <= **other }
+    fn gt(&self, other: &ArcSlice<T>) -> bool { **self > **other }
+    fn ge(&self, other: &ArcSlice<T>) -> bool { **self >= **other }
+}
+impl<T: Ord> Ord for ArcSlice<T> {
+    fn cmp(&self, other: &ArcSlice<T>) -> cmp::Ordering { identity::slice_cmp(self, other) }
+}
+
+impl<T: Hash> Hash for ArcSlice<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArcSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// An iterator merging several sorted `ArcSlice`s, yielding clones
+/// of their elements in sorted order.
+///
+/// Equal elements are yielded in the order of the position of their
+/// slice in the input.
+pub struct MergeSorted<T> {
+    heap: BinaryHeap<MergeHead<T>>,
+    remaining: usize,
+}
+
+// A non-empty slice being merged, ordered so that the `BinaryHeap`
+// yields the smallest first element (then the earliest input) first.
+struct MergeHead<T> {
+    slice: ArcSlice<T>,
+    index: usize,
+}
+
+impl<T: Ord> MergeSorted<T> {
+    /// Construct a new `MergeSorted` over the sorted `slices`.
+    pub fn new<I>(slices: I) -> MergeSorted<T>
+        where I: IntoIterator<Item = ArcSlice<T>>
+    {
+        let heap: BinaryHeap<_> = slices.into_iter()
+            .enumerate()
+            .filter(|(_, slice)| !slice.is_empty())
+            .map(|(index, slice)| MergeHead { slice, index })
+            .collect();
+        let remaining = heap.iter().map(|head| head.slice.len()).sum();
+        MergeSorted { heap, remaining }
+    }
+}
+
+impl<T: Ord + Clone> Iterator for MergeSorted<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        let mut head = self.heap.peek_mut()?;
+        let x = head.slice[0].clone();
+        if head.slice.len() == 1 {
+            PeekMut::pop(head);
+        } else {
+            let rest: *const [T] = &head.slice[1..];
+            head.slice.data = rest;
+        }
+        self.remaining -= 1;
+        Some(x)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Ord + Clone> ExactSizeIterator for MergeSorted<T> {}
+
+impl<T: Ord + Clone> FusedIterator for MergeSorted<T> {}
+
+impl<T: Ord> PartialEq for MergeHead<T> {
+    fn eq(&self, other: &MergeHead<T>) -> bool { self.cmp(other) == cmp::Ordering::Equal }
+}
+impl<T: Ord> Eq for MergeHead<T> {}
+
+impl<T: Ord> PartialOrd for MergeHead<T> {
+    fn partial_cmp(&self, other: &MergeHead<T>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: Ord> Ord for MergeHead<T> {
+    fn cmp(&self, other: &MergeHead<T>) -> cmp::Ordering {
+        other.slice[0].cmp(&self.slice[0]).then(other.index.cmp(&self.index))
+    }
+}
+
+/// A cursor for decoding binary data from a `ArcSlice<u8>` without
+/// copying.
+///
+/// Each `take_...` method consumes bytes from the front of the
+/// remaining data, returning them as a `ArcSlice` pointing into the
+/// same allocation, or returns `None` and leaves the cursor unchanged
+/// if there is not enough data.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{FrameCursor, ArcSlice};
+///
+/// // a length-prefixed payload, followed by a line of text
+/// let data = ArcSlice::new(Box::new(*b"\x05helloworld\n"));
+/// let mut cursor = FrameCursor::new(data);
+///
+/// let len = cursor.take_bytes(1).unwrap()[0] as usize;
+/// let payload = cursor.take_bytes(len).unwrap();
+/// assert_eq!(&*payload, b"hello");
+///
+/// assert_eq!(cursor.peek(3), Some(&b"wor"[..]));
+/// assert_eq!(&*cursor.take_until(b'\n').unwrap(), b"world");
+/// assert!(cursor.is_empty());
+/// ```
+pub struct FrameCursor {
+    rest: ArcSlice<u8>,
+}
+
+impl FrameCursor {
+    /// Construct a new `FrameCursor` at the start of `data`.
+    pub fn new(data: ArcSlice<u8>) -> FrameCursor {
+        FrameCursor { rest: data }
+    }
+
+    /// The data that has not been consumed yet.
+    pub fn remaining(&self) -> &[u8] {
+        &self.rest
+    }
+
+    /// Construct a new `ArcSlice` pointing to the data that has not been
+    /// consumed yet.
+    pub fn remaining_owned(&self) -> ArcSlice<u8> {
+        self.rest.clone()
+    }
+
+    /// Whether all the data has been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    /// The next `n` bytes, without consuming them, or `None` if fewer
+    /// than `n` remain.
+    pub fn peek(&self, n: usize) -> Option<&[u8]> {
+        self.rest.get(..n)
+    }
+
+    /// Consume the next `n` bytes, or return `None` if fewer than `n`
+    /// remain.
+    pub fn take_bytes(&mut self, n: usize) -> Option<ArcSlice<u8>> {
+        if n > self.rest.len() {
+            return None
+        }
+        let taken = self.rest.clone().slice_to(n);
+        let rest: *const [u8] = &self.rest[n..];
+        self.rest.data = rest;
+        Some(taken)
+    }
+
+    /// Consume the bytes up to and including the next occurrence of
+    /// `delim`, returning those before it, or return `None` if `delim`
+    /// does not occur.
+    pub fn take_until(&mut self, delim: u8) -> Option<ArcSlice<u8>> {
+        let i = search::find_byte(delim, &self.rest)?;
+        let taken = self.take_bytes(i);
+        let rest: *const [u8] = &self.rest[1..];
+        self.rest.data = rest;
+        taken
+    }
+}
+
+impl Clone for FrameCursor {
+    fn clone(&self) -> FrameCursor {
+        FrameCursor { rest: self.rest.clone() }
+    }
+}
+
+impl fmt::Debug for FrameCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrameCursor").field("remaining", &self.rest.len()).finish()
+    }
+}
+
+/// An iterator over the parts of a `ArcSlice<u8>` separated by a byte.
+///
+/// This is created by `ArcSlice::split_byte_owned`.
+pub struct SplitByteOwned {
+    rest: Option<ArcSlice<u8>>,
+    delim: u8,
+}
+
+impl Iterator for SplitByteOwned {
+    type Item = ArcSlice<u8>;
+    fn next(&mut self) -> Option<ArcSlice<u8>> {
+        let rest = self.rest.as_mut()?;
+        match search::find_byte(self.delim, rest) {
+            Some(i) => {
+                let part = rest.clone().slice_to(i);
+                let after: *const [u8] = &rest[i + 1..];
+                rest.data = after;
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.rest {
+            Some(ref rest) => (1, Some(rest.len() + 1)),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl DoubleEndedIterator for SplitByteOwned {
+    fn next_back(&mut self) -> Option<ArcSlice<u8>> {
+        let rest = self.rest.as_mut()?;
+        match search::rfind_byte(self.delim, rest) {
+            Some(i) => {
+                let part = rest.clone().slice_from(i + 1);
+                let before: *const [u8] = &rest[..i];
+                rest.data = before;
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+}
+
+impl FusedIterator for SplitByteOwned {}
+
+/// An iterator over the content-defined chunks of a `ArcSlice<u8>`.
+///
+/// This is created by `ArcSlice::cdc_chunks`.
+pub struct CdcChunks {
+    rest: ArcSlice<u8>,
+    sizes: (usize, usize, usize),
+}
+
+impl Iterator for CdcChunks {
+    type Item = ArcSlice<u8>;
+    fn next(&mut self) -> Option<ArcSlice<u8>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let (min, avg, max) = self.sizes;
+        let cut = cdc::cut_point(&self.rest, min, avg, max);
+        let chunk = self.rest.clone().slice_to(cut);
+        let rest: *const [u8] = &self.rest[cut..];
+        self.rest.data = rest;
+        Some(chunk)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len();
+        (n.div_ceil(self.sizes.2), Some(n.div_ceil(self.sizes.0)))
+    }
+}
+
+impl FusedIterator for CdcChunks {}
+
+/// An iterator over `ArcSlice`s pointing to chunks of a `ArcSlice`.
+///
+/// This is created by `ArcSlice::chunks_owned`.
+pub struct ChunksOwned<T> {
+    rest: ArcSlice<T>,
+    size: usize,
+}
+
+impl<T> Iterator for ChunksOwned<T> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let n = cmp::min(self.size, self.rest.len());
+        let rest = self.rest.split_off(n);
+        Some(mem::replace(&mut self.rest, rest))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len().div_ceil(self.size);
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for ChunksOwned<T> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        let len = self.rest.len();
+        if len == 0 {
+            return None
+        }
+        let n = match len % self.size {
+            0 => self.size,
+            n => n,
+        };
+        Some(self.rest.split_off(len - n))
+    }
+}
+
+impl<T> ExactSizeIterator for ChunksOwned<T> {}
+impl<T> FusedIterator for ChunksOwned<T> {}
+
+/// An iterator over `ArcSlice`s pointing to chunks of exactly the same
+/// length of a `ArcSlice`.
+///
+/// This is created by `ArcSlice::chunks_exact_owned`.
+pub struct ChunksExactOwned<T> {
+    rest: ArcSlice<T>,
+    rem: ArcSlice<T>,
+    size: usize,
+}
+
+impl<T> ChunksExactOwned<T> {
+    /// The elements at the end that don't make up a whole chunk, and
+    /// so are never yielded.
+    pub fn remainder(&self) -> &ArcSlice<T> {
+        &self.rem
+    }
+}
+
+impl<T> Iterator for ChunksExactOwned<T> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = self.rest.split_off(self.size);
+        Some(mem::replace(&mut self.rest, rest))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len() / self.size;
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for ChunksExactOwned<T> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        let len = self.rest.len();
+        if len == 0 {
+            return None
+        }
+        Some(self.rest.split_off(len - self.size))
+    }
+}
+
+impl<T> ExactSizeIterator for ChunksExactOwned<T> {}
+impl<T> FusedIterator for ChunksExactOwned<T> {}
+
+/// An iterator over `ArcSlice`s pointing to chunks of a `ArcSlice`,
+/// starting at the end.
+///
+/// This is created by `ArcSlice::rchunks_owned`.
+pub struct RChunksOwned<T> {
+    rest: ArcSlice<T>,
+    size: usize,
+}
+
+impl<T> Iterator for RChunksOwned<T> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        let len = self.rest.len();
+        if len == 0 {
+            return None
+        }
+        Some(self.rest.split_off(len - cmp::min(self.size, len)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len().div_ceil(self.size);
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for RChunksOwned<T> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let n = match self.rest.len() % self.size {
+            0 => self.size,
+            n => n,
+        };
+        let rest = self.rest.split_off(n);
+        Some(mem::replace(&mut self.rest, rest))
+    }
+}
+
+impl<T> ExactSizeIterator for RChunksOwned<T> {}
+impl<T> FusedIterator for RChunksOwned<T> {}
+
+/// An iterator over `ArcSlice`s pointing to overlapping windows of a
+/// `ArcSlice`.
+///
+/// This is created by `ArcSlice::windows_owned`.
+pub struct WindowsOwned<T> {
+    // the elements covered by the windows not yet yielded
+    rest: ArcSlice<T>,
+    size: usize,
+}
+
+impl<T> Iterator for WindowsOwned<T> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        if self.rest.len() < self.size {
+            return None
+        }
+        let window = self.rest.clone().slice_to(self.size);
+        self.rest.data = &self.rest[1..];
+        Some(window)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.rest.len() + 1).saturating_sub(self.size);
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for WindowsOwned<T> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        let len = self.rest.len();
+        if len < self.size {
+            return None
+        }
+        let window = self.rest.clone().slice_from(len - self.size);
+        self.rest.data = &self.rest[..len - 1];
+        Some(window)
+    }
+}
+
+impl<T> ExactSizeIterator for WindowsOwned<T> {}
+impl<T> FusedIterator for WindowsOwned<T> {}
+
+/// An iterator over `ArcSlice`s pointing to the parts of a `ArcSlice`
+/// separated by elements that match a predicate.
+///
+/// This is created by `ArcSlice::split_owned`.
+pub struct SplitOwned<T, F> {
+    rest: Option<ArcSlice<T>>,
+    pred: F,
+}
+
+impl<T, F: FnMut(&T) -> bool> Iterator for SplitOwned<T, F> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        let rest = self.rest.as_mut()?;
+        match rest.iter().position(&mut self.pred) {
+            Some(i) => {
+                let part = rest.clone().slice_to(i);
+                rest.data = &rest[i + 1..];
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.rest {
+            Some(ref rest) => (1, Some(rest.len() + 1)),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> DoubleEndedIterator for SplitOwned<T, F> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        let rest = self.rest.as_mut()?;
+        match rest.iter().rposition(&mut self.pred) {
+            Some(i) => {
+                let part = rest.clone().slice_from(i + 1);
+                rest.data = &rest[..i];
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> FusedIterator for SplitOwned<T, F> {}
+
+/// An iterator over at most a certain number of `ArcSlice`s pointing to
+/// the parts of a `ArcSlice` separated by elements that match a
+/// predicate.
+///
+/// This is created by `ArcSlice::splitn_owned`.
+pub struct SplitNOwned<T, F> {
+    inner: SplitOwned<T, F>,
+    n: usize,
+}
+
+impl<T, F: FnMut(&T) -> bool> Iterator for SplitNOwned<T, F> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        match self.n {
+            0 => None,
+            1 => {
+                self.n = 0;
+                self.inner.rest.take()
+            }
+            _ => {
+                self.n -= 1;
+                self.inner.next()
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.inner.size_hint();
+        (cmp::min(lo, self.n), Some(hi.map_or(self.n, |hi| cmp::min(hi, self.n))))
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> FusedIterator for SplitNOwned<T, F> {}
+
+/// An iterator that yields the elements of a `ArcSlice` by value.
+///
+/// This is created by `ArcSlice::into_iter`.
+pub struct IntoIter<T> {
+    elems: Elems<T>,
+}
+
+enum Elems<T> {
+    // moved out of a unique allocation
+    Owned(vec::IntoIter<T>),
+    // still shared, so cloned
+    Shared(IterCloned<T>),
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        match self.elems {
+            Elems::Owned(ref mut it) => it.next(),
+            Elems::Shared(ref mut it) => it.next(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.elems {
+            Elems::Owned(ref it) => it.size_hint(),
+            Elems::Shared(ref it) => it.size_hint(),
+        }
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        match self.elems {
+            Elems::Owned(ref mut it) => it.next_back(),
+            Elems::Shared(ref mut it) => it.next_back(),
+        }
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for IntoIter<T> {}
+impl<T: Clone> FusedIterator for IntoIter<T> {}
+
+/// An iterator that yields clones of the elements of a `ArcSlice`,
+/// keeping them alive itself.
+///
+/// This is created by `ArcSlice::iter_cloned`.
+pub struct IterCloned<T> {
+    rest: ArcSlice<T>,
+}
+
+impl<T: Clone> Iterator for IterCloned<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        let (first, tail) = self.rest.split_first()?;
+        let first = first.clone();
+        self.rest.data = tail;
+        Some(first)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rest.len(), Some(self.rest.len()))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for IterCloned<T> {
+    fn next_back(&mut self) -> Option<T> {
+        let (last, init) = self.rest.split_last()?;
+        let last = last.clone();
+        self.rest.data = init;
+        Some(last)
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for IterCloned<T> {}
+impl<T: Clone> FusedIterator for IterCloned<T> {}
+
+impl<T> WeakSlice<T> {
+    /// Construct a new `WeakSlice` that doesn't point to anything, so
+    /// never upgrades, like `std::sync::Weak::new`.
+    ///
+    /// This doesn't allocate, and allows a `WeakSlice` field to be
+    /// initialized before the `ArcSlice` it will point to exists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::WeakSlice;
+    ///
+    /// let w = WeakSlice::<u8>::new();
+    /// assert!(w.upgrade().is_none());
+    /// ```
+    pub const fn new() -> WeakSlice<T> {
+        WeakSlice {
+            data: &[],
+            header: dead_header(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Attempt to upgrade `self` to a strongly-counted `ArcSlice`.
+    ///
+    /// Returns `None` if this is not possible (the data has already
+    /// been freed).
+    pub fn upgrade(&self) -> Option<ArcSlice<T>> {
+        if self.header == dead_header()
+            || is_counted(self.header) && !unsafe { self.header.as_ref() }.upgrade() {
+            return None
+        }
+        let slice = ArcSlice {
+            data: self.data,
+            header: self.header,
+            _marker: PhantomData,
+        };
+        slice.check();
+        Some(slice)
+    }
+}
+
+/// A `WeakSlice` that never upgrades, like `Weak::new`. This doesn't
+/// allocate.
+impl<T> Default for WeakSlice<T> {
+    fn default() -> WeakSlice<T> {
+        WeakSlice::new()
+    }
+}
+
+impl<T> Drop for WeakSlice<T> {
+    fn drop(&mut self) {
+        unsafe { release_weak::<T>(self.header) }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for ArcSlice<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ArcSlice<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ArcSlice<T>, D::Error> {
+        let v: Vec<T> = Deserialize::deserialize(deserializer)?;
+        Ok(ArcSlice::new(v.into_boxed_slice()))
+    }
+}
+
+impl<T> Clone for ArcRef<T> {
+    fn clone(&self) -> ArcRef<T> {
+        ArcRef { slice: self.slice.clone() }
+    }
+}
+
+impl<T> ops::Deref for ArcRef<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.slice[0]
+    }
+}
+
+impl<T> AsRef<T> for ArcRef<T> {
+    fn as_ref(&self) -> &T { self }
+}
+
+impl<T: PartialEq> PartialEq for ArcRef<T> {
+    fn eq(&self, other: &ArcRef<T>) -> bool { **self == **other }
+}
+impl<T: Eq> Eq for ArcRef<T> {}
+
+impl<T: PartialOrd> PartialOrd for ArcRef<T> {
+    fn partial_cmp(&self, other: &ArcRef<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+impl<T: Ord> Ord for ArcRef<T> {
+    fn cmp(&self, other: &ArcRef<T>) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl<T: Hash> Hash for ArcRef<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArcRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for ArcRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized> ArcView<U> {
+    /// Construct a new `ArcView` pointing to a value derived from
+    /// the one `self` points to by `f`, sharing the same allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSlice;
+    ///
+    /// let x = ArcSlice::from([(1, "one"), (2, "two")]);
+    /// let second = x.map_ref(|x| &x[1]);
+    /// let name = second.map(|&(_, name)| name);
+    /// assert_eq!(&*name, "two");
+    /// ```
+    pub fn map<V: ?Sized, F>(self, f: F) -> ArcView<V>
+        where F: FnOnce(&U) -> &V
+    {
+        let data: *const V = f(&self);
+        let view = ArcView { data, header: self.header, release: self.release };
+        mem::forget(self);
+        view
+    }
+}
+
+impl<U: ?Sized> Clone for ArcView<U> {
+    fn clone(&self) -> ArcView<U> {
+        retain(self.header);
+        ArcView { data: self.data, header: self.header, release: self.release }
+    }
+}
+
+impl<U: ?Sized> Drop for ArcView<U> {
+    fn drop(&mut self) {
+        unsafe { (self.release)(self.header) }
+    }
+}
+
+impl<U: ?Sized> ops::Deref for ArcView<U> {
+    type Target = U;
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> AsRef<U> for ArcView<U> {
+    fn as_ref(&self) -> &U { self }
+}
+
+impl<U: ?Sized + PartialEq> PartialEq for ArcView<U> {
+    fn eq(&self, other: &ArcView<U>) -> bool { **self == **other }
+}
+impl<U: ?Sized + Eq> Eq for ArcView<U> {}
+
+impl<U: ?Sized + Hash> Hash for ArcView<U> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<U: ?Sized + fmt::Debug> fmt::Debug for ArcView<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized + fmt::Display> fmt::Display for ArcView<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T> CompactArcSlice<T> {
+    /// Construct a new `CompactArcSlice` viewing the same elements as
+    /// `slice`.
+    ///
+    /// This fails, returning `slice`, if the view extends past index
+    /// `u32::MAX` in its allocation, or if it is a non-empty view of
+    /// static data from `ArcSlice::from_static`.
+    pub fn new(slice: ArcSlice<T>) -> Result<CompactArcSlice<T>, ArcSlice<T>> {
+        let offset = slice.offset();
+        let hi = offset + slice.len();
+        if hi > u32::MAX as usize || !is_counted(slice.header) && !slice.is_empty() {
+            return Err(slice)
+        }
+        let len = slice.len() as u32;
+        Ok(CompactArcSlice {
+            offset: offset as u32,
+            len,
+            header: slice.into_parts().1,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Construct a new `CompactArcSlice` that only points to elements
+    /// at indices `lo` (inclusive) through `hi` (exclusive).
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if either are strictly greater than
+    /// `self.len()`.
+    pub fn slice(mut self, lo: usize, hi: usize) -> CompactArcSlice<T> {
+        assert!(lo <= hi && hi <= self.len(),
+                "CompactArcSlice::slice: {}..{} out of bounds for length {}", lo, hi, self.len());
+        self.offset += lo as u32;
+        self.len = (hi - lo) as u32;
+        self
+    }
+}
+
+impl<T> From<CompactArcSlice<T>> for ArcSlice<T> {
+    fn from(slice: CompactArcSlice<T>) -> ArcSlice<T> {
+        let data: *const [T] = &*slice;
+        let slice = mem::ManuallyDrop::new(slice);
+        ArcSlice {
+            data,
+            header: slice.header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for CompactArcSlice<T> {
+    fn clone(&self) -> CompactArcSlice<T> {
+        retain(self.header);
+        CompactArcSlice {
+            header: self.header,
+            offset: self.offset,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for CompactArcSlice<T> {
+    fn drop(&mut self) {
+        unsafe { release_strong::<T>(self.header) }
+    }
+}
+
+impl<T> ops::Deref for CompactArcSlice<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        if !is_counted(self.header) {
+            return &[]
+        }
+        let lo = self.offset as usize;
+        let whole = unsafe { whole::<T>(self.header) };
+        if checks::ENABLED {
+            assert!(lo + self.len as usize <= whole.len(),
+                    "shared_slice: view {}+{} outside its allocation of length {}",
+                    lo, self.len, whole.len());
+        }
+        unsafe { whole.get_unchecked(lo..lo + self.len as usize) }
+    }
+}
+
+impl<T> AsRef<[T]> for CompactArcSlice<T> {
+    fn as_ref(&self) -> &[T] { self }
+}
+
+impl<T: PartialEq> PartialEq for CompactArcSlice<T> {
+    fn eq(&self, other: &CompactArcSlice<T>) -> bool { **self == **other }
+}
+impl<T: Eq> Eq for CompactArcSlice<T> {}
+
+impl<T: PartialOrd> PartialOrd for CompactArcSlice<T> {
+    fn partial_cmp(&self, other: &CompactArcSlice<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+impl<T: Ord> Ord for CompactArcSlice<T> {
+    fn cmp(&self, other: &CompactArcSlice<T>) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl<T: Hash> Hash for CompactArcSlice<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CompactArcSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl AnyArcSlice {
+    /// Construct a new `AnyArcSlice` pointing to the same elements as
+    /// `slice`.
+    pub fn new<T: Any + Send + Sync>(slice: ArcSlice<T>) -> AnyArcSlice {
+        let len = slice.len();
+        let (data, header) = slice.into_parts();
+        AnyArcSlice {
+            data: data as *const (),
+            len,
+            type_id: TypeId::of::<T>(),
+            header,
+            release: release_strong::<T>,
+        }
+    }
+
+    /// The number of elements in `self`.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether `self` contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The `TypeId` of the elements of `self`.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Whether the elements of `self` have type `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Get the elements of `self`, if they have type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&[T]> {
+        if self.is::<T>() {
+            Some(unsafe { &*ptr::slice_from_raw_parts(self.data as *const T, self.len) })
+        } else {
+            None
+        }
+    }
+
+    /// Convert `self` back to an `ArcSlice<T>`, or return it
+    /// unchanged if its elements do not have type `T`.
+    pub fn downcast<T: Any + Send + Sync>(self) -> Result<ArcSlice<T>, AnyArcSlice> {
+        if !self.is::<T>() {
+            return Err(self)
+        }
+        let this = mem::ManuallyDrop::new(self);
+        Ok(ArcSlice {
+            data: ptr::slice_from_raw_parts(this.data as *const T, this.len),
+            header: this.header,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Any + Send + Sync> From<ArcSlice<T>> for AnyArcSlice {
+    fn from(slice: ArcSlice<T>) -> AnyArcSlice {
+        AnyArcSlice::new(slice)
+    }
+}
+
+impl Clone for AnyArcSlice {
+    fn clone(&self) -> AnyArcSlice {
+        retain(self.header);
+        AnyArcSlice {
+            data: self.data,
+            len: self.len,
+            type_id: self.type_id,
+            header: self.header,
+            release: self.release,
+        }
+    }
+}
+
+impl Drop for AnyArcSlice {
+    fn drop(&mut self) {
+        unsafe { (self.release)(self.header) }
+    }
+}
+
+impl fmt::Debug for AnyArcSlice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnyArcSlice").field("len", &self.len).finish_non_exhaustive()
+    }
+}
+
+/// A builder for a `ArcSlice`, which writes the elements straight into
+/// the allocation the `ArcSlice` will use.
+///
+/// This is like building a `Vec` and converting it, but the elements
+/// aren't copied from the `Vec` into a new allocation at the end: the
+/// space is grown in place as elements are added, and shrunk to fit
+/// by `build`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcSliceBuilder;
+///
+/// let mut builder = ArcSliceBuilder::with_capacity(4);
+/// builder.push(1);
+/// builder.extend_from_slice(&[2, 3]);
+/// builder.extend(4..6);
+/// let x = builder.build();
+/// assert_eq!(&*x, [1, 2, 3, 4, 5]);
+/// ```
+pub struct ArcSliceBuilder<T> {
+    // only allocated when `cap > 0`
+    ptr: NonNull<Header>,
+    len: usize,
+    cap: usize,
+    _marker: PhantomData<T>,
+}
+unsafe impl<T: Send> Send for ArcSliceBuilder<T> {}
+unsafe impl<T: Sync> Sync for ArcSliceBuilder<T> {}
+
+impl<T> ArcSliceBuilder<T> {
+    /// Construct a new empty `ArcSliceBuilder`, which doesn't allocate
+    /// until elements are added.
+    pub fn new() -> ArcSliceBuilder<T> {
+        ArcSliceBuilder {
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Construct a new empty `ArcSliceBuilder` with space for at least
+    /// `cap` elements.
+    pub fn with_capacity(cap: usize) -> ArcSliceBuilder<T> {
+        let mut builder = ArcSliceBuilder::new();
+        builder.reserve(cap);
+        builder
+    }
+
+    /// The number of elements added so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no elements have been added.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements `self` has space for without growing.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Ensure there is space for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("ArcSliceBuilder: capacity overflow");
+        if needed <= self.cap {
+            return
+        }
+        let cap = cmp::max(cmp::max(needed, 2 * self.cap), 4);
+        self.ptr = if self.cap == 0 {
+            inner::allocate::<Header, T>(Header::new(cap), cap)
+        } else {
+            unsafe { inner::resize::<Header, T>(self.ptr, self.cap, cap) }
+        };
+        self.cap = cap;
+    }
+
+    /// Add `value` to the end.
+    pub fn push(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+        unsafe { ptr::write(inner::elems::<Header, T>(self.ptr).add(self.len), value) }
+        self.len += 1;
+    }
+
+    /// Construct the `ArcSliceMut` containing the elements added,
+    /// shrinking the allocation to fit them, so that they can still be
+    /// modified in place before being shared.
+    pub fn build_mut(self) -> ArcSliceMut<T> {
+        ArcSliceMut::from_unique(self.build())
+    }
+
+    /// Construct the `ArcSlice` containing the elements added, shrinking
+    /// the allocation to fit them.
+    pub fn build(self) -> ArcSlice<T> {
+        let this = mem::ManuallyDrop::new(self);
+        let (len, cap) = (this.len, this.cap);
+        if len == 0 {
+            if cap > 0 {
+                unsafe { inner::free::<Header, T>(this.ptr, cap) }
+            }
+            return ArcSlice::default()
+        }
+        unsafe {
+            let ptr = if cap == len {
+                this.ptr
+            } else {
+                inner::resize::<Header, T>(this.ptr, cap, len)
+            };
+            // the header records the length, for freeing
+            ptr::write(ptr.as_ptr(), Header::new(len));
+            ArcSlice::from_header(ptr, len)
+        }
+    }
+}
+
+impl<T: Clone> ArcSliceBuilder<T> {
+    /// Add clones of the elements of `slice` to the end.
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        self.reserve(slice.len());
+        for x in slice {
+            self.push(x.clone());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ArcSliceBuilder<u8> {
+    /// Read everything from `reader` onto the end, straight into the
+    /// allocation, growing it as needed.
+    fn read_to_end_from<R: io::Read>(&mut self, mut reader: R) -> io::Result<()> {
+        // the spare capacity up to here has been zeroed, so it can be
+        // passed to `read`
+        let mut zeroed = self.len;
+        loop {
+            if self.len == self.cap {
+                // probe before growing, so that space reserved for
+                // exactly the right length isn't doubled just to find
+                // the end
+                let mut probe = [0; 32];
+                match reader.read(&mut probe) {
+                    Ok(0) => return Ok(()),
+                    Ok(n) => self.extend_from_slice(&probe[..n]),
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+                continue
+            }
+            let elems = inner::elems::<Header, u8>(self.ptr);
+            zeroed = cmp::max(zeroed, self.len);
+            unsafe { ptr::write_bytes(elems.add(zeroed), 0, self.cap - zeroed) }
+            zeroed = self.cap;
+
+            let spare = unsafe { slice::from_raw_parts_mut(elems.add(self.len), self.cap - self.len) };
+            match reader.read(spare) {
+                Ok(0) => return Ok(()),
+                Ok(n) => {
+                    assert!(n <= self.cap - self.len, "ArcSliceBuilder: reader returned too many bytes");
+                    self.len += n;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<T> Default for ArcSliceBuilder<T> {
+    fn default() -> ArcSliceBuilder<T> {
+        ArcSliceBuilder::new()
+    }
+}
+
+impl<T> Extend<T> for ArcSliceBuilder<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+impl<T> ops::Deref for ArcSliceBuilder<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        // `ptr` is dangling until something is allocated
+        if self.cap == 0 {
+            return &[]
+        }
+        unsafe { slice::from_raw_parts(inner::elems::<Header, T>(self.ptr), self.len) }
+    }
+}
+
+impl<T> ops::DerefMut for ArcSliceBuilder<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if self.cap == 0 {
+            return &mut []
+        }
+        unsafe { slice::from_raw_parts_mut(inner::elems::<Header, T>(self.ptr), self.len) }
+    }
+}
+
+impl<T> Drop for ArcSliceBuilder<T> {
+    fn drop(&mut self) {
+        if self.cap > 0 {
+            unsafe {
+                inner::drop_elems::<Header, T>(self.ptr, self.len);
+                inner::free::<Header, T>(self.ptr, self.cap);
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArcSliceBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A thread-safe reference-counted slice, with exclusive access to its
+/// elements.
+///
+/// This is for filling in a buffer in place before sharing it: no
+/// other handle can see the elements of an `ArcSliceMut`, so it
+/// dereferences to `&mut [T]`, and `freeze` then converts it into an
+/// `ArcSlice` without copying. It can also be split into parts that
+/// are filled in on different threads, and then rejoined.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcSliceMut;
+///
+/// let mut buf = ArcSliceMut::repeat(0u8, 4);
+/// buf.copy_from_slice(b"data");
+/// let shared = buf.freeze();
+/// let copy = shared.clone();
+/// assert_eq!(&*copy, b"data");
+/// ```
+pub struct ArcSliceMut<T> {
+    data: *mut [T],
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
+}
+unsafe impl<T: Send> Send for ArcSliceMut<T> {}
+unsafe impl<T: Sync> Sync for ArcSliceMut<T> {}
+
+impl<T> ArcSliceMut<T> {
+    /// Construct a new `ArcSliceMut` containing the elements of
+    /// `slice`.
+    ///
+    /// The elements are moved into a new allocation, after the
+    /// reference counts.
+    pub fn new(slice: Box<[T]>) -> ArcSliceMut<T> {
+        ArcSliceMut::from_unique(ArcSlice::new(slice))
+    }
+
+    /// Construct a new `ArcSliceMut` containing `f(i)` for each index
+    /// `i` from 0 up to `len`, in order, like `ArcSlice::from_fn`.
+    pub fn from_fn<F: FnMut(usize) -> T>(len: usize, f: F) -> ArcSliceMut<T> {
+        ArcSliceMut::from_unique(ArcSlice::from_fn(len, f))
+    }
+
+    /// Convert `self` into a `ArcSlice` pointing to the same elements,
+    /// without copying them, so that it can be shared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if other parts split from the same allocation by
+    /// `split_at_mut` still exist, since an `ArcSlice` can see the
+    /// whole allocation (with `full_slice`). Rejoin them with
+    /// `unsplit` first.
+    pub fn freeze(self) -> ArcSlice<T> {
+        assert!(!is_counted(self.header) || unsafe { self.header.as_ref() }.strong() == 1,
+                "ArcSliceMut::freeze: other parts of the allocation still exist");
+        let this = mem::ManuallyDrop::new(self);
+        ArcSlice {
+            data: this.data,
+            header: this.header,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Divide `self` into two `ArcSliceMut`s, pointing to the elements
+    /// before and after index `mid`, without copying.
+    ///
+    /// The halves share the allocation, but each still has exclusive
+    /// access to its own elements, so they can be sent to different
+    /// threads to fill in, and then rejoined with `unsplit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::thread;
+    /// use shared_slice::arc::ArcSliceMut;
+    ///
+    /// let (mut left, mut right) = ArcSliceMut::repeat(0, 6).split_at_mut(2);
+    /// let t = thread::spawn(move || {
+    ///     right.fill(2);
+    ///     right
+    /// });
+    /// left.fill(1);
+    /// let right = t.join().unwrap();
+    ///
+    /// let whole = left.unsplit(right).unwrap().freeze();
+    /// assert_eq!(&*whole, [1, 1, 2, 2, 2, 2]);
+    /// ```
+    pub fn split_at_mut(self, mid: usize) -> (ArcSliceMut<T>, ArcSliceMut<T>) {
+        assert!(mid <= self.len(), "ArcSliceMut::split_at_mut: {} out of bounds for length {}",
+                mid, self.len());
+        retain(self.header);
+        let (data, header) = (self.data, self.header);
+        mem::forget(self);
+        let (lo, hi) = unsafe { (*data).split_at_mut(mid) };
+        (ArcSliceMut { data: lo, header, _marker: PhantomData },
+         ArcSliceMut { data: hi, header, _marker: PhantomData })
+    }
+
+    /// Join `self` and `other` back into one `ArcSliceMut`, if `other`
+    /// points to the elements straight after those of `self` in the
+    /// same allocation (such as the two halves from `split_at_mut`),
+    /// or if either is empty. Otherwise, they are returned unchanged.
+    ///
+    /// This doesn't copy any elements.
+    pub fn unsplit(self, other: ArcSliceMut<T>)
+                   -> Result<ArcSliceMut<T>, (ArcSliceMut<T>, ArcSliceMut<T>)> {
+        if other.is_empty() {
+            return Ok(self)
+        } else if self.is_empty() {
+            return Ok(other)
+        }
+        let end = unsafe { (self.data as *mut T).add(self.len()) };
+        if self.header != other.header || end != other.data as *mut T {
+            return Err((self, other))
+        }
+        let len = self.len() + other.len();
+        // `self` keeps the allocation alive, so this doesn't drop any
+        // elements
+        drop(other);
+        let mut this = self;
+        this.data = ptr::slice_from_raw_parts_mut(this.data as *mut T, len);
+        Ok(this)
+    }
+
+    /// Convert `slice`, which must be the only handle to its
+    /// allocation, and point to all of it, into a `ArcSliceMut`.
+    fn from_unique(slice: ArcSlice<T>) -> ArcSliceMut<T> {
+        let (data, header) = slice.into_parts();
+        ArcSliceMut {
+            data: data as *mut [T],
+            header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone> ArcSliceMut<T> {
+    /// Construct a new `ArcSliceMut` containing `n` clones of `value`,
+    /// like `ArcSlice::repeat`.
+    pub fn repeat(value: T, n: usize) -> ArcSliceMut<T> {
+        ArcSliceMut::from_unique(ArcSlice::repeat(value, n))
+    }
+}
+
+#[cfg(feature = "std")]
+impl ArcSliceMut<u8> {
+    /// Fill `self` with bytes from `reader`, like `Read::read_exact`.
+    ///
+    /// The bytes are read straight into the buffer, so a file or
+    /// socket can be read into memory that is then frozen and shared,
+    /// without going through a `Vec`. If this fails, the contents of
+    /// `self` are unspecified.
+    ///
+    /// This requires the `std` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSliceMut;
+    ///
+    /// let mut input: &[u8] = b"header+rest";
+    /// let mut header = ArcSliceMut::repeat(0, 6);
+    /// header.read_exact_from(&mut input).unwrap();
+    /// assert_eq!(&*header.freeze(), b"header");
+    /// assert_eq!(input, b"+rest");
+    /// ```
+    pub fn read_exact_from<R: io::Read>(&mut self, mut reader: R) -> io::Result<()> {
+        reader.read_exact(self)
+    }
+
+    /// Fill each of `bufs` in turn with bytes from `reader`, using
+    /// `Read::read_vectored`, so that readers that support it can fill
+    /// several buffers with each call.
+    ///
+    /// Like `read_exact_from`, this fails with `UnexpectedEof` if
+    /// `reader` ends before every buffer is full, and the contents of
+    /// the buffers are unspecified if this fails.
+    ///
+    /// This requires the `std` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcSliceMut;
+    ///
+    /// let mut bufs = [ArcSliceMut::repeat(0, 2), ArcSliceMut::repeat(0, 3)];
+    /// ArcSliceMut::read_exact_vectored_from(&mut bufs, &b"abcde"[..]).unwrap();
+    /// assert_eq!(&*bufs[0], b"ab");
+    /// assert_eq!(&*bufs[1], b"cde");
+    /// ```
+    pub fn read_exact_vectored_from<R: io::Read>(bufs: &mut [ArcSliceMut<u8>], mut reader: R)
+                                                 -> io::Result<()> {
+        let mut slices: Vec<_> = bufs.iter_mut().map(|buf| io::IoSliceMut::new(buf)).collect();
+        let mut slices = &mut slices[..];
+        // skip any empty buffers at the start
+        io::IoSliceMut::advance_slices(&mut slices, 0);
+        while !slices.is_empty() {
+            match reader.read_vectored(slices) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                                   "failed to fill whole buffers")),
+                Ok(n) => io::IoSliceMut::advance_slices(&mut slices, n),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An empty `ArcSliceMut`, which doesn't allocate.
+impl<T> Default for ArcSliceMut<T> {
+    fn default() -> ArcSliceMut<T> {
+        ArcSliceMut::from_unique(ArcSlice::empty())
+    }
+}
+
+impl<T> From<Vec<T>> for ArcSliceMut<T> {
+    fn from(v: Vec<T>) -> ArcSliceMut<T> {
+        ArcSliceMut::new(v.into_boxed_slice())
+    }
+}
+
+impl<T> Drop for ArcSliceMut<T> {
+    fn drop(&mut self) {
+        unsafe { release_strong::<T>(self.header) }
+    }
+}
+
+impl<T> ops::Deref for ArcSliceMut<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.data }
+    }
+}
+
+impl<T> ops::DerefMut for ArcSliceMut<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<T> AsRef<[T]> for ArcSliceMut<T> {
+    fn as_ref(&self) -> &[T] { self }
+}
+
+impl<T> AsMut<[T]> for ArcSliceMut<T> {
+    fn as_mut(&mut self) -> &mut [T] { self }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArcSliceMut<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A cursor for writing into a `ArcSliceMut<u8>`, implementing
+/// `io::Write` and `io::Seek`.
+///
+/// This is like `io::Cursor<&mut [u8]>`: writes overwrite the bytes
+/// at the current position, and stop at the end of the buffer, which
+/// doesn't grow. Encoders that write to an `impl io::Write` can then
+/// write straight into the buffer that is frozen and shared
+/// afterwards.
+///
+/// This requires the `std` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::{Seek, SeekFrom, Write};
+/// use shared_slice::arc::{ArcSliceMut, WriteCursor};
+///
+/// let mut cursor = WriteCursor::new(ArcSliceMut::repeat(b' ', 8));
+/// write!(cursor, "{}", 42).unwrap();
+/// cursor.seek(SeekFrom::End(-2)).unwrap();
+/// cursor.write_all(b"ok").unwrap();
+/// assert!(cursor.write_all(b"!").is_err());
+///
+/// let shared = cursor.into_inner().freeze();
+/// assert_eq!(&*shared, b"42    ok");
+/// ```
+#[cfg(feature = "std")]
+pub struct WriteCursor {
+    buf: ArcSliceMut<u8>,
+    pos: u64,
+}
+
+#[cfg(feature = "std")]
+impl WriteCursor {
+    /// Construct a new `WriteCursor` at the start of `buf`.
+    pub fn new(buf: ArcSliceMut<u8>) -> WriteCursor {
+        WriteCursor { buf, pos: 0 }
+    }
+
+    /// The current position in the buffer.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Move to position `pos` in the buffer, which may be past its
+    /// end.
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// The buffer being written to.
+    pub fn get_ref(&self) -> &ArcSliceMut<u8> {
+        &self.buf
+    }
+
+    /// The buffer being written to, mutably.
+    pub fn get_mut(&mut self) -> &mut ArcSliceMut<u8> {
+        &mut self.buf
+    }
+
+    /// Convert `self` into the buffer being written to.
+    pub fn into_inner(self) -> ArcSliceMut<u8> {
+        self.buf
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for WriteCursor {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let start = cmp::min(self.pos, self.buf.len() as u64) as usize;
+        let n = cmp::min(data.len(), self.buf.len() - start);
+        self.buf[start..start + n].copy_from_slice(&data[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Seek for WriteCursor {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(n) => {
+                self.pos = n;
+                return Ok(n)
+            }
+            io::SeekFrom::End(n) => (self.buf.len() as u64, n),
+            io::SeekFrom::Current(n) => (self.pos, n),
+        };
+        match base.checked_add_signed(offset) {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                       "invalid seek to a negative or overflowing position")),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for WriteCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WriteCursor")
+            .field("len", &self.buf.len())
+            .field("position", &self.pos)
+            .finish()
+    }
+}
+
+/// A `ArcSlice` that is initialized the first time it is needed,
+/// such as a precomputed table in a `static`.
+///
+/// Once initialized, each `get_or_init` just returns a new handle to
+/// the same slice. If several threads try to initialize it at once,
+/// only one runs its function, and the others wait for it to finish.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{ArcSlice, OnceSlice};
+///
+/// static SQUARES: OnceSlice<u32> = OnceSlice::new();
+///
+/// fn squares() -> ArcSlice<u32> {
+///     SQUARES.get_or_init(|| ArcSlice::from_fn(256, |i| (i * i) as u32))
+/// }
+///
+/// assert_eq!(squares()[12], 144);
+/// assert_eq!(squares().as_ptr(), squares().as_ptr());
+/// ```
+pub struct OnceSlice<T> {
+    state: AtomicUsize,
+    // initialized when `state` is `DONE`
+    value: UnsafeCell<MaybeUninit<ArcSlice<T>>>,
+}
+
+unsafe impl<T: Send + Sync> Send for OnceSlice<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceSlice<T> {}
+
+impl<T> OnceSlice<T> {
+    const UNINIT: usize = 0;
+    const RUNNING: usize = 1;
+    const DONE: usize = 2;
+
+    /// Construct a new uninitialized `OnceSlice`.
+    pub const fn new() -> OnceSlice<T> {
+        OnceSlice {
+            state: AtomicUsize::new(OnceSlice::<T>::UNINIT),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Get a new handle to the slice in `self`, or `None` if it
+    /// hasn't been initialized yet.
+    pub fn get(&self) -> Option<ArcSlice<T>> {
+        if self.state.load(Ordering::Acquire) == OnceSlice::<T>::DONE {
+            Some(unsafe { (*self.value.get()).assume_init_ref().clone() })
+        } else {
+            None
+        }
+    }
+
+    /// Get a new handle to the slice in `self`, initializing it with
+    /// `f` first if it hasn't been already.
+    ///
+    /// If `f` panics, the panic is propagated and `self` is left
+    /// uninitialized, so a later call can try again. Calling
+    /// `get_or_init` on `self` from inside `f` deadlocks.
+    pub fn get_or_init<F: FnOnce() -> ArcSlice<T>>(&self, f: F) -> ArcSlice<T> {
+        if let Some(slice) = self.get() {
+            return slice
+        }
+
+        /// Marks the `OnceSlice` uninitialized again if `f` panics.
+        struct Reset<'a>(&'a AtomicUsize);
+        impl<'a> Drop for Reset<'a> {
+            fn drop(&mut self) {
+                // the states don't depend on the element type, which
+                // this can't name
+                self.0.store(OnceSlice::<()>::UNINIT, Ordering::Release);
+            }
+        }
+
+        let mut f = Some(f);
+        loop {
+            match self.state.compare_exchange(OnceSlice::<T>::UNINIT, OnceSlice::<T>::RUNNING,
+                                              Ordering::Acquire, Ordering::Acquire) {
+                Ok(_) => {
+                    let reset = Reset(&self.state);
+                    let slice = f.take().unwrap()();
+                    unsafe { (*self.value.get()).write(slice.clone()); }
+                    mem::forget(reset);
+                    self.state.store(OnceSlice::<T>::DONE, Ordering::Release);
+                    return slice
+                }
+                Err(OnceSlice::<T>::DONE) => return self.get().unwrap(),
+                Err(_) => {
+                    #[cfg(feature = "std")]
+                    thread::yield_now();
+                    #[cfg(not(feature = "std"))]
+                    hint::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// Convert `self` into the slice it contains, or `None` if it was
+    /// never initialized.
+    pub fn into_inner(self) -> Option<ArcSlice<T>> {
+        let mut this = mem::ManuallyDrop::new(self);
+        if *this.state.get_mut() == OnceSlice::<T>::DONE {
+            Some(unsafe { this.value.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Drop for OnceSlice<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == OnceSlice::<T>::DONE {
+            unsafe { self.value.get_mut().assume_init_drop() }
+        }
+    }
+}
+
+impl<T> Default for OnceSlice<T> {
+    fn default() -> OnceSlice<T> {
+        OnceSlice::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.get() {
+            Some(slice) => f.debug_tuple("OnceSlice").field(&slice).finish(),
+            None => f.write_str("OnceSlice(<uninit>)"),
+        }
+    }
+}
+
+/// A reference-counted string slice.
+///
+/// This is to `str` as `ArcSlice<u8>` is to `[u8]`: a view into a
+/// shared allocation of UTF-8 text, which can be cloned and sliced
+/// without copying the text, and has no lifetime.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcStr;
+///
+/// let source = ArcStr::from("let x = 1;");
+/// let name = source.clone().slice(4..5);
+/// drop(source);
+/// assert_eq!(name, "x");
+/// ```
+#[derive(Clone, Default)]
+pub struct ArcStr {
+    bytes: ArcSlice<u8>,
+}
+
+impl ArcStr {
+    /// Construct a new `ArcStr` containing the text of `s`.
+    pub fn new(s: Box<str>) -> ArcStr {
+        ArcStr { bytes: ArcSlice::new(s.into_boxed_bytes()) }
+    }
+
+    /// Convert `bytes` into a `ArcStr`, without copying them, or
+    /// return an error if they aren't valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::{ArcSlice, ArcStr};
+    ///
+    /// let bytes = ArcSlice::from(*b"caf\xc3\xa9");
+    /// assert_eq!(ArcStr::from_utf8(bytes.clone()).unwrap(), "café");
+    /// assert!(ArcStr::from_utf8(bytes.slice_to(4)).is_err());
+    /// ```
+    pub fn from_utf8(bytes: ArcSlice<u8>) -> Result<ArcStr, str::Utf8Error> {
+        str::from_utf8(&bytes)?;
+        Ok(ArcStr { bytes })
+    }
+
+    /// Convert `bytes` into a `ArcStr`, without copying them or
+    /// checking that they are valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid UTF-8.
+    pub unsafe fn from_utf8_unchecked(bytes: ArcSlice<u8>) -> ArcStr {
+        ArcStr { bytes }
+    }
+
+    /// Construct a new `ArcStr` containing the text of each of `parts`
+    /// in turn, with `sep` between each, like `<[&str]>::join`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let words = [ArcStr::from("shared"), ArcStr::from("slice")];
+    /// assert_eq!(ArcStr::join(&words, "_"), "shared_slice");
+    /// ```
+    pub fn join(parts: &[ArcStr], sep: &str) -> ArcStr {
+        let mut pieces = Vec::with_capacity(2 * parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                pieces.push(sep.as_bytes());
+            }
+            pieces.push(part.as_bytes());
+        }
+        // joining valid UTF-8 gives valid UTF-8
+        ArcStr { bytes: ArcSlice::concat(pieces) }
+    }
+
+    /// Construct a new `ArcStr` containing the text encoded as UTF-16
+    /// in `units`, or return an error if it isn't valid UTF-16, like
+    /// `String::from_utf16`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let units = [0x66, 0x6f, 0xd834, 0xdd1e];
+    /// assert_eq!(ArcStr::from_utf16(&units).unwrap(), "fo𝄞");
+    /// assert!(ArcStr::from_utf16(&units[..3]).is_err());
+    /// ```
+    pub fn from_utf16(units: &[u16]) -> Result<ArcStr, FromUtf16Error> {
+        String::from_utf16(units).map(ArcStr::from)
+    }
+
+    /// Construct a new `ArcStr` containing the text encoded as UTF-16
+    /// in `units`, replacing invalid data with U+FFFD, like
+    /// `String::from_utf16_lossy`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let units = [0x66, 0xd834, 0x6f];
+    /// assert_eq!(ArcStr::from_utf16_lossy(&units), "f\u{fffd}o");
+    /// ```
+    pub fn from_utf16_lossy(units: &[u16]) -> ArcStr {
+        ArcStr::from(String::from_utf16_lossy(units))
+    }
+
+    /// The text of `self`.
+    pub fn as_str(&self) -> &str {
+        // the bytes are always valid UTF-8
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+
+    /// Construct a new `ArcStr` that only points to the text in
+    /// `range`, such as `2..`, `..=5` or `..`.
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications. Use `.clone()` if it is necessary to refer to
+    /// `self` after calling this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, if either
+    /// are out of bounds, or if either are not on a `char` boundary,
+    /// like indexing a `str`.
+    pub fn slice<R: ops::RangeBounds<usize>>(mut self, range: R) -> ArcStr {
+        let (lo, hi) = {
+            let s = &self[range::bounds(&range)];
+            let lo = s.as_ptr() as usize - self.as_ptr() as usize;
+            (lo, lo + s.len())
+        };
+        self.bytes = self.bytes.slice(lo, hi);
+        self
+    }
+
+    /// The bytes of the text of `self`, sharing its allocation.
+    pub fn as_bytes_owned(&self) -> &ArcSlice<u8> {
+        &self.bytes
+    }
+
+    /// Convert `self` into its bytes, without copying them.
+    pub fn into_bytes(self) -> ArcSlice<u8> {
+        self.bytes
+    }
+
+    /// Construct a new `ArcSlice` containing the text of `self` encoded
+    /// as UTF-16, like `str::encode_utf16`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("fo𝄞");
+    /// assert_eq!(&*s.encode_utf16_owned(), [0x66, 0x6f, 0xd834, 0xdd1e]);
+    /// ```
+    pub fn encode_utf16_owned(&self) -> ArcSlice<u16> {
+        let mut units = self.encode_utf16();
+        let len = self.encode_utf16().count();
+        ArcSlice::from_fn(len, |_| units.next().unwrap())
+    }
+
+    /// Divide `self` into `ArcStr`s pointing to the parts of the text
+    /// separated by `sep`, like `str::split`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sep` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("a, b,, c");
+    /// let parts: Vec<_> = s.split_owned(", ").collect();
+    /// assert_eq!(parts, ["a", "b,", "c"]);
+    /// ```
+    pub fn split_owned(self, sep: &str) -> StrSplitOwned<'_> {
+        assert!(!sep.is_empty(), "separator must be non-empty");
+        StrSplitOwned { rest: Some(self), sep }
+    }
+
+    /// Divide `self` into `ArcStr`s pointing to its lines, like
+    /// `str::lines`. Lines end with `\n` or `\r\n`, which is not
+    /// included, and a final line ending doesn't start an empty line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("one\r\ntwo\n\nthree\n");
+    /// let lines: Vec<_> = s.lines_owned().collect();
+    /// assert_eq!(lines, ["one", "two", "", "three"]);
+    /// ```
+    pub fn lines_owned(self) -> LinesOwned {
+        LinesOwned { rest: self }
+    }
+
+    /// Divide `self` into `ArcStr`s pointing to the parts of the text
+    /// separated by whitespace, like `str::split_whitespace`. None of
+    /// the parts are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from(" let  x =\t1; ");
+    /// let tokens: Vec<_> = s.split_whitespace_owned().collect();
+    /// assert_eq!(tokens, ["let", "x", "=", "1;"]);
+    /// ```
+    pub fn split_whitespace_owned(self) -> SplitWhitespaceOwned {
+        SplitWhitespaceOwned { rest: self }
+    }
+
+    /// Construct a new `ArcStr` containing the text of `self` in
+    /// lowercase, like `str::to_lowercase`, or a clone of `self` if it
+    /// is already lowercase.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("Ünïcode");
+    /// assert_eq!(s.to_lowercase_shared(), "ünïcode");
+    ///
+    /// let lower = ArcStr::from("already lower");
+    /// assert_eq!(lower.to_lowercase_shared().as_ptr(), lower.as_ptr());
+    /// ```
+    pub fn to_lowercase_shared(&self) -> ArcStr {
+        if self.contains('\u{3a3}') {
+            // the lowercase of a capital sigma depends on whether it
+            // ends a word, so let `str` handle it
+            return ArcStr::from(self.to_lowercase())
+        }
+        self.map_chars(char::to_lowercase)
+    }
+
+    /// Construct a new `ArcStr` containing the text of `self` in
+    /// uppercase, like `str::to_uppercase`, or a clone of `self` if it
+    /// is already uppercase.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("straße");
+    /// assert_eq!(s.to_uppercase_shared(), "STRASSE");
+    /// ```
+    pub fn to_uppercase_shared(&self) -> ArcStr {
+        self.map_chars(char::to_uppercase)
+    }
+
+    /// Construct a new `ArcStr` containing the text of `self` with
+    /// ASCII letters in lowercase, like `str::to_ascii_lowercase`, or
+    /// a clone of `self` if there are no ASCII uppercase letters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("Content-Type");
+    /// assert_eq!(s.to_ascii_lowercase_shared(), "content-type");
+    /// ```
+    pub fn to_ascii_lowercase_shared(&self) -> ArcStr {
+        if !self.bytes().any(|b| b.is_ascii_uppercase()) {
+            return self.clone()
+        }
+        let bytes = self.as_bytes();
+        // changing the case of ASCII letters keeps the text valid UTF-8
+        ArcStr { bytes: ArcSlice::from_fn(bytes.len(), |i| bytes[i].to_ascii_lowercase()) }
+    }
+
+    /// Construct a new `ArcStr` containing the text of `self` with each
+    /// `char` `c` replaced by the `char`s of `f(c)`, written straight
+    /// into one allocation, or a clone of `self` if that changes
+    /// nothing.
+    fn map_chars<I, F>(&self, f: F) -> ArcStr
+        where I: Iterator<Item = char>, F: Fn(char) -> I
+    {
+        let mut len = 0;
+        let mut changed = false;
+        for c in self.chars() {
+            for (i, m) in f(c).enumerate() {
+                len += m.len_utf8();
+                changed |= i > 0 || m != c;
+            }
+        }
+        if !changed {
+            return self.clone()
+        }
+        let mut builder = ArcSliceBuilder::with_capacity(len);
+        let mut buf = [0; 4];
+        for m in self.chars().flat_map(f) {
+            builder.extend_from_slice(m.encode_utf8(&mut buf).as_bytes());
+        }
+        ArcStr { bytes: builder.build() }
+    }
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl ArcStr {
+    /// Divide `self` into `ArcStr`s pointing to its grapheme clusters,
+    /// as defined by Unicode Standard Annex #29, like
+    /// `UnicodeSegmentation::graphemes`. If `extended` is true,
+    /// extended grapheme clusters are used, rather than legacy ones.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("ne\u{301}e 🇳🇿");
+    /// let graphemes: Vec<_> = s.graphemes_owned(true).collect();
+    /// assert_eq!(graphemes, ["n", "e\u{301}", "e", " ", "🇳🇿"]);
+    /// ```
+    pub fn graphemes_owned(self, extended: bool) -> SegmentsOwned {
+        let iter = Segments::Graphemes(unsafe { self.as_static_str() }.graphemes(extended));
+        SegmentsOwned { iter, text: self }
+    }
+
+    /// Divide `self` into `ArcStr`s pointing to its words, as defined
+    /// by Unicode Standard Annex #29, like
+    /// `UnicodeSegmentation::unicode_words`. Only the parts between
+    /// word boundaries that contain a letter or number are included,
+    /// so whitespace and punctuation are skipped.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("The quick (\"brown\") fox can't jump 32.3 feet.");
+    /// let words: Vec<_> = s.unicode_words_owned().collect();
+    /// assert_eq!(words, ["The", "quick", "brown", "fox", "can't", "jump", "32.3", "feet"]);
+    /// ```
+    pub fn unicode_words_owned(self) -> SegmentsOwned {
+        let iter = Segments::Words(unsafe { self.as_static_str() }.unicode_words());
+        SegmentsOwned { iter, text: self }
+    }
+
+    /// Divide `self` into `ArcStr`s pointing to the parts between
+    /// each of its word boundaries, as defined by Unicode Standard
+    /// Annex #29, like `UnicodeSegmentation::split_word_bounds`.
+    /// Concatenating the parts gives back the whole text.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::arc::ArcStr;
+    ///
+    /// let s = ArcStr::from("Hello, world!");
+    /// let parts: Vec<_> = s.split_word_bounds_owned().collect();
+    /// assert_eq!(parts, ["Hello", ",", " ", "world", "!"]);
+    /// ```
+    pub fn split_word_bounds_owned(self) -> SegmentsOwned {
+        let iter = Segments::WordBounds(unsafe { self.as_static_str() }.split_word_bounds());
+        SegmentsOwned { iter, text: self }
+    }
+
+    /// The text of `self`, with its lifetime extended.
+    ///
+    /// The text lives in the heap (or static data), so it stays put
+    /// when `self` moves. The result must not be used after `self`
+    /// is dropped.
+    unsafe fn as_static_str(&self) -> &'static str {
+        &*(self.as_str() as *const str)
+    }
+}
+
+impl From<String> for ArcStr {
+    fn from(s: String) -> ArcStr {
+        ArcStr::new(s.into_boxed_str())
+    }
+}
+
+impl From<Box<str>> for ArcStr {
+    fn from(s: Box<str>) -> ArcStr {
+        ArcStr::new(s)
+    }
+}
+
+impl<'a> From<&'a str> for ArcStr {
+    fn from(s: &'a str) -> ArcStr {
+        ArcStr { bytes: ArcSlice::from_slice(s.as_bytes()) }
+    }
+}
+
+impl ops::Deref for ArcStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for ArcStr {
+    fn as_ref(&self) -> &str { self }
+}
+
+impl AsRef<[u8]> for ArcStr {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl Borrow<str> for ArcStr {
+    fn borrow(&self) -> &str { self }
+}
+
+impl PartialEq for ArcStr {
+    fn eq(&self, other: &ArcStr) -> bool { **self == **other }
+}
+impl Eq for ArcStr {}
+
+impl PartialEq<str> for ArcStr {
+    fn eq(&self, other: &str) -> bool { **self == *other }
+}
+impl<'a> PartialEq<&'a str> for ArcStr {
+    fn eq(&self, other: &&'a str) -> bool { **self == **other }
+}
+impl PartialEq<ArcStr> for str {
+    fn eq(&self, other: &ArcStr) -> bool { *self == **other }
+}
+impl PartialEq<ArcStr> for &str {
+    fn eq(&self, other: &ArcStr) -> bool { **self == **other }
+}
+
+impl PartialOrd for ArcStr {
+    fn partial_cmp(&self, other: &ArcStr) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ArcStr {
+    fn cmp(&self, other: &ArcStr) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl Hash for ArcStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl fmt::Debug for ArcStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for ArcStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// An iterator over `ArcStr`s pointing to the parts of a `ArcStr`
+/// separated by a string.
+///
+/// This is created by `ArcStr::split_owned`.
+pub struct StrSplitOwned<'a> {
+    rest: Option<ArcStr>,
+    sep: &'a str,
+}
+
+impl<'a> Iterator for StrSplitOwned<'a> {
+    type Item = ArcStr;
+    fn next(&mut self) -> Option<ArcStr> {
+        let i = match self.rest.as_ref()?.find(self.sep) {
+            Some(i) => i,
+            None => return self.rest.take(),
+        };
+        let rest = self.rest.take()?;
+        self.rest = Some(rest.clone().slice(i + self.sep.len()..));
+        Some(rest.slice(..i))
+    }
+}
+
+impl<'a> DoubleEndedIterator for StrSplitOwned<'a> {
+    fn next_back(&mut self) -> Option<ArcStr> {
+        let i = match self.rest.as_ref()?.rfind(self.sep) {
+            Some(i) => i,
+            None => return self.rest.take(),
+        };
+        let rest = self.rest.take()?;
+        self.rest = Some(rest.clone().slice(..i));
+        Some(rest.slice(i + self.sep.len()..))
+    }
+}
+
+impl<'a> FusedIterator for StrSplitOwned<'a> {}
+
+/// An iterator over `ArcStr`s pointing to the lines of a `ArcStr`.
+///
+/// This is created by `ArcStr::lines_owned`.
+pub struct LinesOwned {
+    rest: ArcStr,
+}
+
+/// The end of the line ended by the `\n` at `i` in `s`, excluding
+/// the `\r` before it, if any.
+fn line_end(s: &str, i: usize) -> usize {
+    if s[..i].ends_with('\r') { i - 1 } else { i }
+}
+
+impl Iterator for LinesOwned {
+    type Item = ArcStr;
+    fn next(&mut self) -> Option<ArcStr> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = mem::take(&mut self.rest);
+        let line = match rest.find('\n') {
+            Some(i) => {
+                self.rest = rest.clone().slice(i + 1..);
+                let end = line_end(&rest, i);
+                rest.slice(..end)
+            }
+            None => rest,
+        };
+        Some(line)
+    }
+}
+
+impl DoubleEndedIterator for LinesOwned {
+    fn next_back(&mut self) -> Option<ArcStr> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = mem::take(&mut self.rest);
+        // a final line ending doesn't start another line
+        let end = if rest.ends_with('\n') { line_end(&rest, rest.len() - 1) } else { rest.len() };
+        let line = match rest[..end].rfind('\n') {
+            Some(i) => {
+                self.rest = rest.clone().slice(..i + 1);
+                rest.slice(i + 1..end)
+            }
+            None => rest.slice(..end),
+        };
+        Some(line)
+    }
+}
+
+impl FusedIterator for LinesOwned {}
+
+/// An iterator over `ArcStr`s pointing to the parts of a `ArcStr`
+/// separated by whitespace.
+///
+/// This is created by `ArcStr::split_whitespace_owned`.
+pub struct SplitWhitespaceOwned {
+    rest: ArcStr,
+}
+
+impl Iterator for SplitWhitespaceOwned {
+    type Item = ArcStr;
+    fn next(&mut self) -> Option<ArcStr> {
+        let start = self.rest.len() - self.rest.trim_start().len();
+        if start == self.rest.len() {
+            self.rest = ArcStr::default();
+            return None
+        }
+        let end = self.rest[start..].find(char::is_whitespace)
+            .map_or(self.rest.len(), |i| start + i);
+        let rest = mem::take(&mut self.rest);
+        self.rest = rest.clone().slice(end..);
+        Some(rest.slice(start..end))
+    }
+}
+
+impl DoubleEndedIterator for SplitWhitespaceOwned {
+    fn next_back(&mut self) -> Option<ArcStr> {
+        let end = self.rest.trim_end().len();
+        if end == 0 {
+            self.rest = ArcStr::default();
+            return None
+        }
+        let start = match self.rest[..end].char_indices().rev().find(|&(_, c)| c.is_whitespace()) {
+            Some((i, c)) => i + c.len_utf8(),
+            None => 0,
+        };
+        let rest = mem::take(&mut self.rest);
+        self.rest = rest.clone().slice(..start);
+        Some(rest.slice(start..end))
+    }
+}
+
+impl FusedIterator for SplitWhitespaceOwned {}
+
+/// An iterator over `ArcStr`s pointing to the grapheme clusters or
+/// words of a `ArcStr`.
+///
+/// This is created by `ArcStr::graphemes_owned`,
+/// `ArcStr::unicode_words_owned` and `ArcStr::split_word_bounds_owned`,
+/// and requires the `unicode-segmentation` feature.
+#[cfg(feature = "unicode-segmentation")]
+pub struct SegmentsOwned {
+    // borrows from `text`, so is declared (and dropped) first
+    iter: Segments,
+    text: ArcStr,
+}
+
+#[cfg(feature = "unicode-segmentation")]
+enum Segments {
+    Graphemes(Graphemes<'static>),
+    Words(UnicodeWords<'static>),
+    WordBounds(UWordBounds<'static>),
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl SegmentsOwned {
+    /// A `ArcStr` pointing to `s`, a part of `self.text`.
+    fn owned(&self, s: &str) -> ArcStr {
+        let lo = s.as_ptr() as usize - self.text.as_ptr() as usize;
+        self.text.clone().slice(lo..lo + s.len())
+    }
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl Iterator for SegmentsOwned {
+    type Item = ArcStr;
+    fn next(&mut self) -> Option<ArcStr> {
+        let s = match self.iter {
+            Segments::Graphemes(ref mut it) => it.next(),
+            Segments::Words(ref mut it) => it.next(),
+            Segments::WordBounds(ref mut it) => it.next(),
+        };
+        s.map(|s| self.owned(s))
+    }
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl DoubleEndedIterator for SegmentsOwned {
+    fn next_back(&mut self) -> Option<ArcStr> {
+        let s = match self.iter {
+            Segments::Graphemes(ref mut it) => it.next_back(),
+            Segments::Words(ref mut it) => it.next_back(),
+            Segments::WordBounds(ref mut it) => it.next_back(),
+        };
+        s.map(|s| self.owned(s))
+    }
+}
+
+// each of the underlying iterators keeps returning `None` once it is
+// exhausted
+#[cfg(feature = "unicode-segmentation")]
+impl FusedIterator for SegmentsOwned {}
+
+/// A reference-counted NUL-terminated C string.
+///
+/// This is to `CStr` as `ArcStr` is to `str`, for passing the same
+/// string to foreign code many times without copying it or tying it
+/// to a lifetime. The pointer from `as_ptr` stays valid as long as
+/// any clone of the `ArcCStr` exists.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{ArcCStr, ArcSlice};
+///
+/// let name = ArcCStr::from_bytes_with_nul(ArcSlice::from(*b"plugin\0")).unwrap();
+/// let copy = name.clone();
+/// assert_eq!(copy.as_c_str().to_str(), Ok("plugin"));
+/// assert_eq!(name.as_ptr(), copy.as_ptr());
+/// ```
+#[derive(Clone)]
+pub struct ArcCStr {
+    // includes the NUL
+    bytes: ArcSlice<u8>,
+}
+
+impl ArcCStr {
+    /// Construct a new `ArcCStr` containing a copy of `s`.
+    pub fn new(s: &CStr) -> ArcCStr {
+        ArcCStr { bytes: ArcSlice::from_slice(s.to_bytes_with_nul()) }
+    }
+
+    /// Convert `bytes` into an `ArcCStr`, without copying them, or
+    /// return an error if they don't end with the only NUL byte.
+    pub fn from_bytes_with_nul(bytes: ArcSlice<u8>) -> Result<ArcCStr, FromBytesWithNulError> {
+        CStr::from_bytes_with_nul(&bytes)?;
+        Ok(ArcCStr { bytes })
+    }
+
+    /// The string `self` points to.
+    pub fn as_c_str(&self) -> &CStr {
+        // the bytes always end with the only NUL
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.bytes) }
+    }
+
+    /// A pointer to the start of the string, for passing to foreign
+    /// code.
+    ///
+    /// The string must not be modified through the pointer, and the
+    /// pointer is only valid while `self` or a clone of it exists.
+    pub fn as_ptr(&self) -> *const c_char {
+        self.as_c_str().as_ptr()
+    }
+
+    /// Convert `self` into its bytes, including the NUL, without
+    /// copying them.
+    pub fn into_bytes_with_nul(self) -> ArcSlice<u8> {
+        self.bytes
+    }
+}
+
+impl Default for ArcCStr {
+    fn default() -> ArcCStr {
+        ArcCStr { bytes: ArcSlice::from_static(b"\0") }
+    }
+}
+
+impl From<CString> for ArcCStr {
+    fn from(s: CString) -> ArcCStr {
+        ArcCStr { bytes: ArcSlice::new(s.into_bytes_with_nul().into_boxed_slice()) }
+    }
+}
+
+impl<'a> From<&'a CStr> for ArcCStr {
+    fn from(s: &'a CStr) -> ArcCStr {
+        ArcCStr::new(s)
+    }
+}
+
+impl ops::Deref for ArcCStr {
+    type Target = CStr;
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl AsRef<CStr> for ArcCStr {
+    fn as_ref(&self) -> &CStr { self }
+}
+
+impl Borrow<CStr> for ArcCStr {
+    fn borrow(&self) -> &CStr { self }
+}
+
+impl PartialEq for ArcCStr {
+    fn eq(&self, other: &ArcCStr) -> bool { **self == **other }
+}
+impl Eq for ArcCStr {}
+
+impl PartialOrd for ArcCStr {
+    fn partial_cmp(&self, other: &ArcCStr) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ArcCStr {
+    fn cmp(&self, other: &ArcCStr) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl Hash for ArcCStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl fmt::Debug for ArcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A collector that defers freeing `ArcSlice` allocations to a
+/// thread of the user's choosing.
+///
+/// Once an allocation is registered with `defer`, the `Reclaimer`
+/// holds a reference to it, so dropping the last user-visible
+/// `ArcSlice` (or `WeakSlice`) never frees it. Instead, it is freed
+/// the next time `reclaim` is called. This allows threads that must
+/// not call into the allocator, like real-time audio threads, to
+/// drop `ArcSlice`s freely, as long as another thread calls
+/// `reclaim` periodically.
+///
+/// The reference the `Reclaimer` holds is a strong one, so that the
+/// elements aren't dropped early either, and it is visible until the
+/// allocation is reclaimed: `WeakSlice::upgrade` keeps succeeding
+/// after every other `ArcSlice` is gone, `strong_count` counts it,
+/// and `is_unique`, `get_mut` and `try_unwrap` treat the allocation as
+/// shared. An allocation is only reclaimed once there are no
+/// `WeakSlice`s to it either, since they could still be upgraded.
+///
+/// This requires the `std` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{ArcSlice, Reclaimer};
+/// use std::thread;
+///
+/// let reclaimer = Reclaimer::new();
+///
+/// let x = ArcSlice::new(vec![0.0f32; 1024].into_boxed_slice());
+/// reclaimer.defer(&x);
+///
+/// thread::spawn(move || {
+///     // pretend this is a real-time thread: this drop will not
+///     // deallocate
+///     drop(x);
+/// }).join().unwrap();
+///
+/// assert_eq!(reclaimer.reclaim(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub struct Reclaimer<T> {
+    // a reference to each registered allocation, by the address of
+    // its header
+    live: Mutex<HashMap<usize, ArcSlice<T>>>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Reclaimer<T> {
+    /// Construct a new `Reclaimer` with no registered allocations.
+    pub fn new() -> Reclaimer<T> {
+        Reclaimer { live: Mutex::new(HashMap::new()) }
+    }
+
+    /// Register the allocation that `slice` points into, so that it
+    /// is only freed by `reclaim`.
+    ///
+    /// Registering an allocation more than once has no effect. This
+    /// takes a lock and may allocate, so it should not be called on a
+    /// thread that must avoid those.
+    pub fn defer(&self, slice: &ArcSlice<T>) {
+        if !is_counted(slice.header) {
+            return
+        }
+        let key = slice.header.as_ptr() as usize;
+        self.live.lock().unwrap().entry(key).or_insert_with(|| slice.clone());
+    }
+
+    /// Free every registered allocation that is no longer referenced
+    /// by any `ArcSlice` or `WeakSlice`, returning the number freed.
+    pub fn reclaim(&self) -> usize {
+        let mut live = self.live.lock().unwrap();
+        let before = live.len();
+        // `is_unique` checks both counts under the weak lock, so a
+        // `WeakSlice` can't be upgraded and dropped between them; once
+        // it holds, nothing else can get a new reference.
+        live.retain(|_, s| !s.header().is_unique());
+        before - live.len()
+    }
+
+    /// The number of allocations registered and not yet freed.
+    pub fn len(&self) -> usize {
+        self.live.lock().unwrap().len()
+    }
+
+    /// Whether there are no allocations registered and not yet
+    /// freed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for Reclaimer<T> {
+    fn default() -> Reclaimer<T> {
+        Reclaimer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnyArcSlice, ArcCStr, ArcSlice, ArcSliceBuilder, ArcSliceMut, ArcRef, ArcStr,
+                CompactArcSlice, FrameCursor, MergeSorted, OnceSlice, WeakSlice};
+    use std::str;
+    use std::collections::HashSet;
+    use std::ffi::CString;
+    use std::sync::{Arc, Mutex};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::cmp::Ordering;
+    use std::ops;
+    #[test]
+    fn clone() {
+        let x = ArcSlice::new(Box::new([Cell::new(false)]));
+        let y = x.clone();
+
+        assert_eq!(x[0].get(), false);
+        assert_eq!(y[0].get(), false);
+
+        x[0].set(true);
+        assert_eq!(x[0].get(), true);
+        assert_eq!(y[0].get(), true);
+    }
+
+    #[test]
+    fn test_upgrade_downgrade() {
+        let x = ArcSlice::new(Box::new([1]));
+        let y: WeakSlice<_> = x.downgrade();
+
+        assert_eq!(y.upgrade(), Some(x.clone()));
+
+        drop(x);
+
+        assert!(y.upgrade().is_none())
+    }
+
+    #[test]
+    fn test_total_cmp() {
+        let x = ArcSlice::new(Box::new([1, 2, 3]));
+        let y = ArcSlice::new(Box::new([1, 2, 3]));
+        let z = ArcSlice::new(Box::new([1, 2, 4]));
+
+        assert_eq!(x, x);
+        assert_eq!(x, y);
+        assert!(x != z);
+        assert!(y != z);
+
+        assert!(x < z);
+        assert!(x <= z);
+        assert!(!(x > z));
+        assert!(!(x >= z));
+
+        assert!(!(z < x));
+        assert!(!(z <= x));
+        assert!(z > x);
+        assert!(z >= x);
+
+        assert_eq!(x.partial_cmp(&x), Some(Ordering::Equal));
+        assert_eq!(x.partial_cmp(&y), Some(Ordering::Equal));
+        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
+        assert_eq!(z.partial_cmp(&y), Some(Ordering::Greater));
+
+        assert_eq!(x.cmp(&x), Ordering::Equal);
+        assert_eq!(x.cmp(&y), Ordering::Equal);
+        assert_eq!(x.cmp(&z), Ordering::Less);
+        assert_eq!(z.cmp(&y), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_partial_cmp() {
+        use std::f64;
+        let x = ArcSlice::new(Box::new([1.0, f64::NAN]));
+        let y = ArcSlice::new(Box::new([1.0, f64::NAN]));
+        let z = ArcSlice::new(Box::new([2.0, f64::NAN]));
+        let w = ArcSlice::new(Box::new([f64::NAN, 1.0]));
+        assert!(!(x == y));
+        assert!(x != y);
+
+        assert!(!(x < y));
+        assert!(!(x <= y));
+        assert!(!(x > y));
+        assert!(!(x >= y));
+
+        assert!(x < z);
+        assert!(x <= z);
+        assert!(!(x > z));
+        assert!(!(x >= z));
+
+        assert!(!(z < w));
+        assert!(!(z <= w));
+        assert!(!(z > w));
+        assert!(!(z >= w));
+
+        assert_eq!(x.partial_cmp(&x), None);
+        assert_eq!(x.partial_cmp(&y), None);
+        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
+        assert_eq!(z.partial_cmp(&x), Some(Ordering::Greater));
+
+        assert_eq!(x.partial_cmp(&w), None);
+        assert_eq!(y.partial_cmp(&w), None);
+        assert_eq!(z.partial_cmp(&w), None);
+        assert_eq!(w.partial_cmp(&w), None);
+    }
+
+    #[test]
+    fn test_identity_fast_path() {
+        use std::cell::Cell;
+        thread_local!(static CALLS: Cell<usize> = const { Cell::new(0) });
+
+        #[derive(PartialEq, Eq)]
+        struct Counted(u8);
+        impl PartialOrd for Counted {
+            fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Counted {
+            fn cmp(&self, other: &Counted) -> Ordering {
+                CALLS.with(|c| c.set(c.get() + 1));
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let x = ArcSlice::new(Box::new([Counted(1), Counted(2)]));
+        let y = x.clone();
+        assert_eq!(x.cmp(&y), Ordering::Equal);
+        assert_eq!(CALLS.with(|c| c.get()), 0);
+
+        // different views of the same memory still need comparing
+        assert_eq!(x.clone().slice(0, 1).cmp(&y), Ordering::Less);
+        assert!(CALLS.with(|c| c.get()) > 0);
+    }
+
+    #[cfg(feature = "unstable")]
+    #[test]
+    fn test_identity_fast_path_eq() {
+        use std::cell::Cell;
+        thread_local!(static CALLS: Cell<usize> = const { Cell::new(0) });
+
+        #[derive(Eq)]
+        struct Counted(u8);
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Counted) -> bool {
+                CALLS.with(|c| c.set(c.get() + 1));
+                self.0 == other.0
+            }
+        }
+
+        let x = ArcSlice::new(Box::new([Counted(1), Counted(2)]));
+        assert!(x == x.clone());
+        assert_eq!(CALLS.with(|c| c.get()), 0);
+
+        assert!(x.clone().slice(1, 2) != x.clone().slice(0, 1));
+        assert!(CALLS.with(|c| c.get()) > 0);
+    }
+
+    #[test]
+    fn test_hash_bytes_one_shot() {
+        use std::hash::{Hash, Hasher};
+        // records the number of bytes passed to each call to `write`
+        struct Writes(Vec<usize>);
+        impl Hasher for Writes {
+            fn finish(&self) -> u64 { 0 }
+            fn write(&mut self, bytes: &[u8]) { self.0.push(bytes.len()) }
+        }
+
+        let x = ArcSlice::new(vec![0u8; 1000].into_boxed_slice()).slice(1, 1000);
+        let mut h = Writes(vec![]);
+        x.hash(&mut h);
+        // the length, then all the data at once
+        assert_eq!(h.0.len(), 2);
+        assert_eq!(h.0[1], 999);
+    }
+
+    #[test]
+    fn test_show() {
+        let x = ArcSlice::new(Box::new([1, 2]));
+        assert_eq!(format!("{:?}", x), "[1, 2]");
+
+        let y: ArcSlice<i32> = ArcSlice::new(Box::new([]));
+        assert_eq!(format!("{:?}", y), "[]");
+    }
+
+    #[test]
+    fn test_slice() {
+        let x = ArcSlice::new(Box::new([1, 2, 3]));
+        let real = [1, 2, 3];
+        for i in 0..3 + 1 {
+            for j in i..3 + 1 {
+                let slice: ArcSlice<_> = x.clone().slice(i, j);
+                assert_eq!(&*slice, &real[i..j]);
+            }
+            assert_eq!(&*x.clone().slice_to(i), &real[..i]);
+            assert_eq!(&*x.clone().slice_from(i), &real[i..]);
+        }
+    }
+
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Send>() {}
+
+        assert_send::<ArcSlice<u8>>();
+        assert_sync::<ArcSlice<u8>>();
+        assert_send::<WeakSlice<u8>>();
+        assert_sync::<WeakSlice<u8>>();
+        assert_send::<ArcRef<u8>>();
+        assert_sync::<ArcRef<u8>>();
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_shuffled() {
+        let mut rng = ::rand::thread_rng();
+        let x = ArcSlice::new((0..100).collect::<Vec<i32>>().into_boxed_slice());
+
+        // shared, so a copy is made
+        let y = x.clone().slice(10, 90).shuffled(&mut rng);
+        assert_eq!(&*x, &*(0..100).collect::<Vec<_>>());
+        let mut sorted = y.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (10..90).collect::<Vec<_>>());
+
+        // unique, so it is shuffled in place
+        let ptr = x.as_ptr();
+        let z = x.slice(10, 90).shuffled(&mut rng);
+        assert_eq!(z.as_ptr(), unsafe { ptr.offset(10) });
+        let mut sorted = z.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (10..90).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_choose_owned() {
+        let mut rng = ::rand::thread_rng();
+        let x = ArcSlice::new((0..10).collect::<Vec<i32>>().into_boxed_slice());
+
+        let y = x.choose_owned(&mut rng, 4);
+        assert_eq!(y.len(), 4);
+        for (i, a) in y.iter().enumerate() {
+            assert!(x.contains(a));
+            assert!(!y[i + 1..].contains(a));
+        }
+
+        assert_eq!(x.choose_owned(&mut rng, 20).len(), 10);
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn test_hash_with() {
+        use sha2::{Digest, Sha256};
+        let x = ArcSlice::new(Box::new(*b"hello world"));
+
+        assert_eq!(x.hash_with::<Sha256>(), Sha256::digest(b"hello world"));
+        assert_eq!(x.slice(6, 11).hash_with::<Sha256>(), Sha256::digest(b"world"));
+    }
+
+    #[cfg(feature = "xxhash-rust")]
+    #[test]
+    fn test_fingerprint64() {
+        let x = ArcSlice::new(Box::new(*b"hello world"));
+        // the XXH3 test vector for the empty input
+        assert_eq!(x.clone().slice(0, 0).fingerprint64(), 0x2d06_8005_38d3_94c2);
+        assert_eq!(x.clone().slice_from(6).fingerprint64(),
+                   ArcSlice::new(Box::new(*b"world")).fingerprint64());
+        assert!(x.fingerprint64() != x.clone().slice_to(5).fingerprint64());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        use regex::bytes::Regex;
+        let x = ArcSlice::new(Box::new(*b"a1 b22 c333 d"));
+
+        let re = Regex::new("[0-9]+").unwrap();
+        assert_eq!(&*x.find_owned(&re).unwrap(), b"1");
+        let all = x.find_iter_owned(&re).collect::<Vec<_>>();
+        let mut iter = x.find_iter_owned(&re).fuse();
+        assert_eq!(iter.by_ref().count(), 3);
+        assert!(iter.next().is_none());
+        assert_eq!(all, [&b"1"[..], b"22", b"333"].iter()
+                   .map(|s| ArcSlice::new(s.to_vec().into_boxed_slice()))
+                   .collect::<Vec<_>>());
+
+        let empty = Regex::new("[0-9]*").unwrap();
+        let starts = x.find_iter_owned(&empty)
+            .map(|m| m.as_ptr() as usize - x.as_ptr() as usize)
+            .collect::<Vec<_>>();
+        let expected = empty.find_iter(&x).map(|m| m.start()).collect::<Vec<_>>();
+        assert_eq!(starts, expected);
+
+        let re = Regex::new("([a-z])([0-9]+)?").unwrap();
+        let caps = x.clone().slice_from(12).captures_owned(&re).unwrap();
+        assert_eq!(caps.len(), 3);
+        assert_eq!(&**caps[1].as_ref().unwrap(), b"d");
+        assert!(caps[2].is_none());
+        assert!(x.clone().slice_to(0).captures_owned(&re).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_file() {
+        use std::{env, fs};
+        let path = env::temp_dir().join("shared_slice_arc_from_file");
+        fs::write(&path, b"hello").unwrap();
+
+        let x = ArcSlice::from_file(&path).unwrap();
+        assert_eq!(&*x, b"hello");
+
+        fs::remove_file(&path).unwrap();
+        assert!(ArcSlice::from_file(&path).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_as_io_slice() {
+        use std::io::Write;
+        let x = ArcSlice::new(Box::new(*b"hello world"));
+        let (hello, world) = (x.clone().slice_to(6), x.slice_from(6));
+        let bufs = [hello.as_io_slice(), world.as_io_slice()];
+        let mut out = vec![];
+        let n = out.write_vectored(&bufs).unwrap();
+        assert_eq!(n, 11);
+        assert_eq!(out, b"hello world");
+    }
+
+    #[test]
+    fn test_unchecked() {
+        let x = ArcSlice::new(Box::new([1, 2, 3, 4]));
+        let y = unsafe { x.clone().slice_unchecked(1, 3) };
+        assert_eq!(&*y, [2, 3]);
+        assert_eq!(y.as_ptr(), x[1..].as_ptr());
+        assert!(unsafe { y.clone().slice_unchecked(2, 2) }.is_empty());
+        assert_eq!(*unsafe { y.get_ref_unchecked(1) }, 3);
+    }
+
+    #[test]
+    fn test_get_ref() {
+        let x = ArcSlice::new(Box::new([1, 2, 3]));
+        let y = x.clone().slice_from(1);
+
+        let a: ArcRef<_> = y.get_ref(0).unwrap();
+        let b = y.get_ref(1).unwrap();
+        assert!(y.get_ref(2).is_none());
+        drop((x, y));
+
+        assert_eq!(*a, 2);
+        assert_eq!(*b, 3);
+        assert!(a < b);
+        assert_eq!(a.clone(), a);
+        assert_eq!(format!("{:?} {}", a, b), "2 3");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_par_map_reduce() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let x = ArcSlice::new((0..1000).collect::<Vec<u64>>().into_boxed_slice());
+
+        for &(nthreads, chunk_size) in &[(1, 1), (3, 7), (10, 100), (20, 2000)] {
+            let sum = x.par_map_reduce(nthreads, chunk_size,
+                                       |c| c.iter().sum::<u64>(), |a, b| a + b);
+            assert_eq!(sum, Some(499_500));
+
+            // concatenation is associative but not commutative
+            let all = x.par_map_reduce(nthreads, chunk_size,
+                                       |c| c.to_vec(), |mut a, b| { a.extend(b); a });
+            assert_eq!(all.as_ref().map(|v| &v[..]), Some(&*x));
+
+            let seen = AtomicUsize::new(0);
+            x.par_for_each_chunks(nthreads, chunk_size, |c| {
+                assert!(c.len() <= chunk_size);
+                seen.fetch_add(c.len(), Ordering::Relaxed);
+            });
+            assert_eq!(seen.load(Ordering::Relaxed), 1000);
+        }
+
+        let empty = x.slice(0, 0);
+        assert_eq!(empty.par_map_reduce(4, 4, |c| c.len(), |a, b| a + b), None);
+    }
+
+    #[test]
+    fn test_compact() {
+        use std::mem::size_of;
+        assert!(size_of::<CompactArcSlice<u8>>() < size_of::<ArcSlice<u8>>());
+
+        let x = ArcSlice::new(Box::new([1, 2, 3, 4, 5]));
+        let y = CompactArcSlice::new(x.clone().slice(1, 4)).unwrap();
+        assert_eq!(&*y, [2, 3, 4]);
+        assert_eq!(&*y.clone().slice(1, 3), [3, 4]);
+        assert_eq!(y.clone().slice(3, 3).len(), 0);
+        assert_eq!(y, y.clone());
+
+        let z: ArcSlice<_> = y.slice(1, 2).into();
+        assert_eq!(z, x.slice(2, 3));
+
+        let zsts = ArcSlice::new(vec![(); 10].into_boxed_slice()).slice(2, 5);
+        assert_eq!(CompactArcSlice::new(zsts).unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reclaimer() {
+        use super::Reclaimer;
+        use std::thread;
+        let drop_flag = Arc::new(Mutex::new(0));
+        struct Foo(Arc<Mutex<i32>>);
+
+        impl Drop for Foo {
+            fn drop(&mut self) {
+                let mut n = self.0.lock().unwrap();
+                *n += 1;
+            }
+        }
+
+        let reclaimer = Reclaimer::new();
+        let x = ArcSlice::new(Box::new([Foo(drop_flag.clone()), Foo(drop_flag.clone())]));
+        reclaimer.defer(&x);
+        reclaimer.defer(&x.clone().slice(1, 2));
+        assert_eq!(reclaimer.len(), 1);
+
+        let weak = x.downgrade();
+        let part = x.clone().slice(0, 1);
+        thread::spawn(move || drop((x, part))).join().unwrap();
+
+        // only the reclaimer holds it, but the weak reference could
+        // still be upgraded
+        assert_eq!(reclaimer.reclaim(), 0);
+        assert_eq!(*drop_flag.lock().unwrap(), 0);
+        let y = weak.upgrade().unwrap();
+        assert_eq!((y.strong_count(), y.is_unique()), (2, false));
+        drop(y);
+
+        drop(weak);
+        assert_eq!(*drop_flag.lock().unwrap(), 0);
+        assert_eq!(reclaimer.reclaim(), 1);
+        assert_eq!(*drop_flag.lock().unwrap(), 2);
+        assert!(reclaimer.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reclaimer_concurrent_upgrade() {
+        use super::Reclaimer;
+        use std::thread;
+
+        let reclaimer = Reclaimer::new();
+        for _ in 0..200 {
+            let x = ArcSlice::new(Box::new([1, 2, 3]));
+            reclaimer.defer(&x);
+            let weak = x.downgrade();
+            drop(x);
+
+            // upgrade, then drop the only weak reference, while the
+            // reclaimer is checking the counts
+            let upgrader = thread::spawn(move || {
+                let y = weak.upgrade().unwrap();
+                drop(weak);
+                y
+            });
+            while !upgrader.is_finished() {
+                assert_eq!(reclaimer.reclaim(), 0);
+            }
+            let y = upgrader.join().unwrap();
+            // the reclaimer still holds its reference
+            assert_eq!(y.strong_count(), 2);
+            drop(y);
+            assert_eq!(reclaimer.reclaim(), 1);
+        }
+    }
+
+    #[test]
+    fn test_any() {
+        use std::any::TypeId;
+        let x = ArcSlice::new(Box::new([1u32, 2, 3])).slice_from(1);
+        let any = AnyArcSlice::from(x.clone());
+        assert_eq!(any.len(), 2);
+        assert_eq!(any.type_id(), TypeId::of::<u32>());
+        assert!(any.is::<u32>() && !any.is::<i32>());
+        assert_eq!(any.downcast_ref::<u32>(), Some(&[2, 3][..]));
+        assert_eq!(any.downcast_ref::<u8>(), None);
+
+        let any = any.downcast::<i32>().unwrap_err();
+        let y = any.downcast::<u32>().unwrap();
+        assert_eq!(y, x);
+        assert_eq!(y.as_ptr(), x.as_ptr());
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let whole = ArcSlice::new(Box::new([1, 4, 7, 2, 5, 3, 6, 8, 9]));
+        let runs = vec![whole.clone().slice(0, 3),
+                        whole.clone().slice(3, 5),
+                        whole.clone().slice(5, 5),
+                        whole.clone().slice(5, 9)];
+        let merged = ArcSlice::merge_sorted(runs.clone());
+        assert_eq!(&*merged, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let mut iter = MergeSorted::new(runs);
+        assert_eq!(iter.len(), 9);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.len(), 8);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), [2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.fuse().next(), None);
+
+        assert!(ArcSlice::<u8>::merge_sorted(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_merge_sorted_stable() {
+        // compares only by the first field
+        #[derive(Clone, Debug, PartialEq)]
+        struct Key(u8, char);
+        impl Eq for Key {}
+        impl PartialOrd for Key {
+            fn partial_cmp(&self, other: &Key) -> Option<::std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Key {
+            fn cmp(&self, other: &Key) -> ::std::cmp::Ordering { self.0.cmp(&other.0) }
+        }
+
+        let a = ArcSlice::new(Box::new([Key(1, 'a'), Key(2, 'a')]));
+        let b = ArcSlice::new(Box::new([Key(1, 'b'), Key(2, 'b')]));
+        let merged = ArcSlice::merge_sorted(vec![a, b]);
+        assert_eq!(merged.iter().map(|k| k.1).collect::<String>(), "abab");
+    }
+
+    #[test]
+    fn test_search() {
+        let x = ArcSlice::new(Box::new(*b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"));
+        assert_eq!(x.find_subslice(b"\r\n\r\n"), Some(23));
+        assert_eq!(x.find_subslice(b""), Some(0));
+        assert_eq!(x.find_subslice(b"POST"), None);
+        assert!(x.contains_subslice(b"Host"));
+
+        let lines: Vec<_> = x.clone().split_byte_owned(b'\n').collect();
+        assert_eq!(lines.iter().map(|l| &**l).collect::<Vec<_>>(),
+                   [&b"GET / HTTP/1.1\r"[..], b"Host: a\r", b"\r", b""]);
+        assert_eq!(lines[1].as_ptr(), x[16..].as_ptr());
+        let mut back: Vec<_> = x.clone().split_byte_owned(b'\n').rev().collect();
+        back.reverse();
+        assert_eq!(back, lines);
+        assert_eq!(back[1].as_ptr(), lines[1].as_ptr());
+        let mut parts = ArcSlice::new(Box::new(*b"a,b,c")).split_byte_owned(b',');
+        assert_eq!(&*parts.next_back().unwrap(), b"c");
+        assert_eq!(&*parts.next().unwrap(), b"a");
+        assert_eq!(&*parts.next_back().unwrap(), b"b");
+        assert!(parts.next().is_none() && parts.next_back().is_none());
+        assert_eq!(ArcSlice::new(Box::new([])).split_byte_owned(b',').count(), 1);
+    }
+
+    #[test]
+    fn test_frame_cursor() {
+        let x = ArcSlice::new(Box::new(*b"\x00\x03abcline\nrest"));
+        let mut cursor = FrameCursor::new(x.clone());
+        let header = cursor.take_bytes(2).unwrap();
+        assert_eq!(&*header, [0, 3]);
+        assert_eq!(&*cursor.take_bytes(3).unwrap(), b"abc");
+        assert_eq!(cursor.peek(100), None);
+        assert!(cursor.take_bytes(100).is_none());
+
+        let line = cursor.take_until(b'\n').unwrap();
+        assert_eq!(&*line, b"line");
+        assert_eq!(line.as_ptr(), x[5..].as_ptr());
+        assert!(cursor.take_until(b'\n').is_none());
+        assert_eq!(cursor.remaining(), b"rest");
+        assert_eq!(&*cursor.remaining_owned(), b"rest");
+        assert!(!cursor.is_empty());
+        assert_eq!(&*cursor.take_bytes(4).unwrap(), b"rest");
+        assert!(cursor.is_empty());
+        assert_eq!(cursor.peek(0), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_cdc_chunks() {
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let x = ArcSlice::new(data.clone().into_boxed_slice());
+        let chunks: Vec<_> = x.clone().cdc_chunks(32, 128, 512).collect();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.iter().flat_map(|c| c.iter().cloned()).collect::<Vec<_>>(), data);
+        assert_eq!(chunks[1].as_ptr(), x[chunks[0].len()..].as_ptr());
+        assert!(chunks.iter().all(|c| c.len() <= 512));
+        let mut iter = x.clone().cdc_chunks(32, 128, 512).fuse();
+        assert_eq!(iter.by_ref().count(), chunks.len());
+        assert!(iter.next().is_none());
+
+        assert_eq!(ArcSlice::new(Box::new([])).cdc_chunks(1, 1, 1).count(), 0);
+    }
+
+    #[cfg(all(feature = "flate2", feature = "std"))]
+    #[test]
+    fn test_decompress_gzip() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let data = b"hello hello hello hello".repeat(100);
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let x = ArcSlice::decompress_gzip(&compressed).unwrap();
+        assert_eq!(&*x, &*data);
+        let twice = [&compressed[..], &compressed[..]].concat();
+        assert_eq!(&*ArcSlice::decompress_gzip(&twice).unwrap(), &*data.repeat(2));
+        assert!(ArcSlice::decompress_gzip(&compressed[..10]).is_err());
+    }
+
+    #[cfg(feature = "encoding_rs")]
+    #[test]
+    fn test_decode() {
+        use encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+        let (text, had_errors) = ArcSlice::from(*b"\x93\xfa\x96\x7b").decode(SHIFT_JIS);
+        assert_eq!((&*text, had_errors), ("日本", false));
+        let (text, had_errors) = ArcSlice::from(*b"a\xffb").decode(UTF_8);
+        assert_eq!((&*text, had_errors), ("a\u{fffd}b", true));
+
+        let x = ArcSlice::from(*b"\xef\xbb\xbfhello");
+        let (text, had_errors) = x.decode(UTF_8);
+        assert_eq!((&*text, had_errors), ("hello", false));
+        assert_eq!(text.as_ptr(), x[3..].as_ptr());
+        assert_eq!(&*ArcSlice::from(*b"\x80").decode(WINDOWS_1252).0, "€");
+        assert!(ArcSlice::<u8>::default().decode(UTF_8).0.is_empty());
+    }
+
+    #[cfg(all(feature = "zstd", feature = "std"))]
+    #[test]
+    fn test_decompress_zstd() {
+        let data = b"hello hello hello hello".repeat(100);
+        let compressed = ::zstd::encode_all(&data[..], 0).unwrap();
+
+        let x = ArcSlice::decompress_zstd(&compressed).unwrap();
+        assert_eq!(&*x, &*data);
+        let twice = [&compressed[..], &compressed[..]].concat();
+        assert_eq!(&*ArcSlice::decompress_zstd(&twice).unwrap(), &*data.repeat(2));
+        assert!(ArcSlice::decompress_zstd(b"not zstd").is_err());
+    }
+
+    #[test]
+    fn test_try_unwrap() {
+        let drops = Arc::new(Mutex::new(0));
+        #[derive(Debug)]
+        struct Foo(u8, Arc<Mutex<i32>>);
+        impl Drop for Foo {
+            fn drop(&mut self) { *self.1.lock().unwrap() += 1 }
+        }
+
+        let x = ArcSlice::new((0..4).map(|i| Foo(i, drops.clone())).collect::<Vec<_>>()
+                                    .into_boxed_slice());
+        let w = x.downgrade();
+        let y = x.clone().slice(1, 3);
+        let x = x.try_unwrap().unwrap_err();
+        drop(x);
+
+        let elems = y.try_unwrap().ok().unwrap();
+        assert_eq!(elems.iter().map(|f| f.0).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(*drops.lock().unwrap(), 2);
+        assert!(w.upgrade().is_none());
+        drop(elems);
+        assert_eq!(*drops.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut x = ArcSlice::new(Box::new([1, 2, 3]));
+        assert!(x.is_unique());
+        x.get_mut().unwrap()[1] = 20;
+        assert_eq!(&*x, [1, 20, 3]);
+
+        let w = x.downgrade();
+        assert!(!x.is_unique());
+        assert!(x.get_mut().is_none());
+        drop(w);
+        let y = x.clone().slice_to(1);
+        assert!(!x.is_unique() && !y.is_unique());
+        assert!(x.get_mut().is_none());
+        drop(y);
+        assert!(x.is_unique());
+        assert_eq!(x.get_mut(), Some(&mut [1, 20, 3][..]));
+    }
+
+    #[test]
+    fn test_make_mut() {
+        let mut x = ArcSlice::new(Box::new([1, 2, 3, 4])).slice(1, 3);
+        let w = x.downgrade();
+        x.make_mut()[0] = 20;
+        assert_eq!(&*x, [20, 3]);
+        assert_eq!(x.full_slice().len(), 2);
+        assert!(w.upgrade().is_none());
+
+        let p = x.as_ptr();
+        x.make_mut()[1] = 30;
+        assert_eq!(x.as_ptr(), p);
+        assert_eq!(&*x, [20, 30]);
+    }
+
+    #[test]
+    fn test_slice_range() {
+        let x = ArcSlice::from([1, 2, 3, 4, 5]);
+        assert_eq!(&*x.clone().slice_range(..), [1, 2, 3, 4, 5]);
+        assert_eq!(&*x.clone().slice_range(..2), [1, 2]);
+        assert_eq!(&*x.clone().slice_range(..=2), [1, 2, 3]);
+        assert_eq!(&*x.clone().slice_range(3..), [4, 5]);
+        assert_eq!(&*x.clone().slice_range(1..4).slice_range(1..), [3, 4]);
+        assert!(x.clone().slice_range(5..).is_empty());
+        let bounds = (ops::Bound::Excluded(0), ops::Bound::Included(1));
+        assert_eq!(&*x.slice_range(bounds), [2]);
+    }
+
+    #[test]
+    fn test_split_at() {
+        let x = ArcSlice::from([1, 2, 3, 4]);
+        let (a, b) = x.clone().split_at(1);
+        assert_eq!(&*a, [1]);
+        assert_eq!(&*b, [2, 3, 4]);
+        assert_eq!(b.offset(), 1);
+
+        let (a, b) = x.clone().split_at(4);
+        assert_eq!((&*a, &*b), (&[1, 2, 3, 4][..], &[][..]));
+        let (a, b) = x.split_at(0);
+        assert_eq!((&*a, &*b), (&[][..], &[1, 2, 3, 4][..]));
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut x = ArcSlice::from([1, 2, 3, 4, 5]);
+        let y = x.split_off(3);
+        assert_eq!(&*x, [1, 2, 3]);
+        assert_eq!(&*y, [4, 5]);
+        assert_eq!(y.offset(), 3);
+
+        let z = x.split_off(3);
+        assert!(z.is_empty());
+        assert_eq!(&*x, [1, 2, 3]);
+        assert_eq!(&*x.split_off(0), [1, 2, 3]);
+        assert!(x.is_empty());
+    }
+
+    #[test]
+    fn test_weak_new() {
+        struct Node {
+            parent: WeakSlice<u8>,
+        }
+        const ROOT: Node = Node { parent: WeakSlice::new() };
+        let root = ROOT;
+        assert!(root.parent.upgrade().is_none());
+
+        let x = ArcSlice::from([1, 2]);
+        let child = Node { parent: x.downgrade() };
+        assert_eq!(x.weak_count(), 1);
+        drop(root);
+        assert_eq!(&*child.parent.upgrade().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_counts() {
+        let x = ArcSlice::from([1, 2, 3]);
+        assert_eq!((x.strong_count(), x.weak_count()), (1, 0));
+        let y = x.clone().slice_to(1);
+        let w = x.downgrade();
+        let v = y.downgrade();
+        assert_eq!((y.strong_count(), y.weak_count()), (2, 2));
+        drop((w, v));
+        drop(x);
+        assert_eq!((y.strong_count(), y.weak_count()), (1, 0));
+
+        let empty = ArcSlice::<i32>::default();
+        assert_eq!((empty.strong_count(), empty.weak_count()), (1, 0));
+    }
+
+    #[test]
+    fn test_ptr_eq() {
+        let x = ArcSlice::from([1, 2, 3, 4]);
+        let y = x.clone().slice(1, 3);
+        assert!(x.ptr_eq(&x.clone()));
+        assert!(!x.ptr_eq(&y));
+        assert!(x.same_allocation(&y));
+        assert!(y.ptr_eq(&x.clone().slice(1, 3)));
+
+        let z = ArcSlice::from([1, 2, 3, 4]);
+        assert!(!x.ptr_eq(&z) && !x.same_allocation(&z));
+        let empty = ArcSlice::<i32>::default();
+        assert!(empty.ptr_eq(&ArcSlice::default()));
+        assert!(!empty.same_allocation(&x));
+        assert!(!x.same_allocation(&empty));
+        assert!(!ArcSlice::from([(); 2]).ptr_eq(&ArcSlice::from([(); 2])));
+    }
+
+    #[test]
+    fn test_split_to() {
+        let mut x = ArcSlice::from([1, 2, 3, 4, 5]);
+        let y = x.split_to(2);
+        assert_eq!(&*y, [1, 2]);
+        assert_eq!(&*x, [3, 4, 5]);
+        assert_eq!(x.offset(), 2);
+        assert_eq!(y.offset(), 0);
+
+        assert!(x.split_to(0).is_empty());
+        assert_eq!(&*x.split_to(3), [3, 4, 5]);
+        assert!(x.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_to_out_of_bounds() {
+        ArcSlice::from([1, 2]).split_to(3);
+    }
+
+    #[test]
+    fn test_advance_truncate() {
+        let rc = Rc::new(0);
+        let mut x = ArcSlice::from(vec![rc.clone(), rc.clone(), rc.clone(), rc.clone()]);
+        x.advance(1);
+        assert_eq!(x.len(), 3);
+        assert_eq!(x.offset(), 1);
+        x.truncate(2);
+        assert_eq!(x.len(), 2);
+        x.truncate(5);
+        assert_eq!(x.len(), 2);
+        assert_eq!(Rc::strong_count(&rc), 5);
+        x.advance(2);
+        assert!(x.is_empty());
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_advance_out_of_bounds() {
+        ArcSlice::from([1, 2]).advance(3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_at_out_of_bounds() {
+        ArcSlice::from([1, 2]).split_at(3);
+    }
+
+    #[test]
+    fn test_chunks_owned() {
+        let x = ArcSlice::from([1, 2, 3, 4, 5, 6, 7]);
+        let chunks = x.clone().chunks_owned(3);
+        assert_eq!(chunks.len(), 3);
+        let v: Vec<_> = chunks.collect();
+        assert_eq!(v, [ArcSlice::from([1, 2, 3]), ArcSlice::from([4, 5, 6]), ArcSlice::from([7])]);
+        assert_eq!(v[2].offset(), 6);
+
+        let v: Vec<_> = x.clone().chunks_owned(3).rev().collect();
+        assert_eq!(v, [ArcSlice::from([7]), ArcSlice::from([4, 5, 6]), ArcSlice::from([1, 2, 3])]);
+        let v: Vec<_> = x.slice_to(6).chunks_owned(2).rev().collect();
+        assert_eq!(v, [ArcSlice::from([5, 6]), ArcSlice::from([3, 4]), ArcSlice::from([1, 2])]);
+
+        let mut it = ArcSlice::from([1, 2, 3]).chunks_owned(2);
+        assert_eq!(it.next_back().map(|c| c.to_vec()), Some(vec![3]));
+        assert_eq!(it.next().map(|c| c.to_vec()), Some(vec![1, 2]));
+        assert!(it.next().is_none());
+        assert!(it.next_back().is_none());
+
+        assert_eq!(ArcSlice::<u8>::default().chunks_owned(4).count(), 0);
+    }
+
+    #[test]
+    fn test_chunks_exact_owned() {
+        let x = ArcSlice::from([1, 2, 3, 4, 5, 6, 7]);
+        let chunks = x.clone().chunks_exact_owned(3);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(&**chunks.remainder(), [7]);
+        assert_eq!(chunks.remainder().offset(), 6);
+        let v: Vec<_> = chunks.rev().collect();
+        assert_eq!(v, [ArcSlice::from([4, 5, 6]), ArcSlice::from([1, 2, 3])]);
+
+        let chunks = x.clone().chunks_exact_owned(8);
+        assert!(chunks.remainder()[..] == x[..]);
+        assert_eq!(chunks.count(), 0);
+        assert!(x.slice_to(6).chunks_exact_owned(3).remainder().is_empty());
     }
 
     #[test]
-    fn test_upgrade_downgrade() {
-        let x = ArcSlice::new(Box::new([1]));
-        let y: WeakSlice<_> = x.downgrade();
+    fn test_windows_owned() {
+        let x = ArcSlice::from([1, 2, 3, 4, 5]);
+        let windows = x.clone().windows_owned(2);
+        assert_eq!(windows.len(), 4);
+        let v: Vec<_> = windows.map(|w| w.to_vec()).collect();
+        assert_eq!(v, [[1, 2], [2, 3], [3, 4], [4, 5]]);
 
-        assert_eq!(y.upgrade(), Some(x.clone()));
+        let mut windows = x.clone().windows_owned(3);
+        assert_eq!(&*windows.next_back().unwrap(), [3, 4, 5]);
+        assert_eq!(&*windows.next().unwrap(), [1, 2, 3]);
+        assert_eq!(windows.len(), 1);
+        let last = windows.next_back().unwrap();
+        assert_eq!((&*last, last.offset()), (&[2, 3, 4][..], 1));
+        assert!(windows.next().is_none());
+
+        assert_eq!(x.clone().windows_owned(5).count(), 1);
+        assert_eq!(x.windows_owned(6).len(), 0);
+    }
+
+    #[test]
+    fn test_split_owned() {
+        let x = ArcSlice::from(*b",a,,bc,");
+        let parts: Vec<_> = x.clone().split_owned(|&b| b == b',').collect();
+        assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(),
+                   [&b""[..], b"a", b"", b"bc", b""]);
+        assert_eq!(parts[3].offset(), 4);
+
+        let parts: Vec<_> = x.clone().split_owned(|&b| b == b',').rev().collect();
+        assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(),
+                   [&b""[..], b"bc", b"", b"a", b""]);
+
+        let parts: Vec<_> = x.clone().splitn_owned(3, |&b| b == b',').collect();
+        assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(),
+                   [&b""[..], b"a", b",bc,"]);
+        assert_eq!(x.clone().splitn_owned(0, |_| true).count(), 0);
+        assert_eq!(&*x.clone().splitn_owned(1, |_| true).next().unwrap(), &*x);
+
+        let mut it = ArcSlice::<u8>::default().split_owned(|_| true);
+        assert!(it.next().unwrap().is_empty());
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_rchunks_owned() {
+        let x = ArcSlice::from([1, 2, 3, 4, 5, 6, 7]);
+        let chunks = x.clone().rchunks_owned(3);
+        assert_eq!(chunks.len(), 3);
+        let v: Vec<_> = chunks.collect();
+        assert_eq!(v, [ArcSlice::from([5, 6, 7]), ArcSlice::from([2, 3, 4]), ArcSlice::from([1])]);
+        assert_eq!(v[0].offset(), 4);
+
+        let v: Vec<_> = x.clone().rchunks_owned(3).rev().collect();
+        assert_eq!(v, [ArcSlice::from([1]), ArcSlice::from([2, 3, 4]), ArcSlice::from([5, 6, 7])]);
+        let v: Vec<_> = x.slice_from(1).rchunks_owned(3).rev().collect();
+        assert_eq!(v, [ArcSlice::from([2, 3, 4]), ArcSlice::from([5, 6, 7])]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_chunks_owned_zero() {
+        ArcSlice::from([1, 2]).chunks_owned(0);
+    }
+
+    #[test]
+    fn test_arc_str() {
+        let s = ArcStr::from(String::from("héllo wörld"));
+        let world = s.clone().slice(7..);
+        assert_eq!(world, "wörld");
+        assert_eq!("wörld", world);
+        assert_eq!(world.as_bytes_owned().offset(), 7);
+        assert_eq!(&*s.clone().slice(..=2), "hé");
+        assert_eq!(world.to_string(), "wörld");
+        assert_eq!(format!("{:?}", world), "\"wörld\"");
+
+        let mut set = HashSet::new();
+        set.insert(world.clone());
+        assert!(set.contains("wörld"));
+
+        let (a, b) = (ArcStr::from("a"), ArcStr::from("b"));
+        assert!(a < b);
+        assert_eq!(ArcStr::default(), "");
+        assert_eq!(&*world.into_bytes(), "wörld".as_bytes());
+    }
+
+    #[test]
+    fn test_arc_str_split() {
+        let s = ArcStr::from("a--b----c");
+        let parts: Vec<_> = s.clone().split_owned("--").collect();
+        assert_eq!(parts, ["a", "b", "", "c"]);
+        let parts: Vec<_> = s.split_owned("--").rev().collect();
+        assert_eq!(parts, ["c", "", "b", "a"]);
+        assert_eq!(ArcStr::default().split_owned(",").collect::<Vec<_>>(), [""]);
+
+        let s = ArcStr::from("x\r\n\ny\nz\r\n");
+        let lines: Vec<_> = s.clone().lines_owned().collect();
+        assert_eq!(lines, ["x", "", "y", "z"]);
+        let lines: Vec<_> = s.lines_owned().rev().collect();
+        assert_eq!(lines, ["z", "y", "", "x"]);
+        assert_eq!(ArcStr::from("\n").lines_owned().collect::<Vec<_>>(), [""]);
+        assert_eq!(ArcStr::from("\n").lines_owned().rev().collect::<Vec<_>>(), [""]);
+        assert_eq!(ArcStr::from("\n").lines_owned().rev().collect::<Vec<_>>(), [""]);
+        // only a `\r` right before a `\n` is part of the line ending
+        let s = ArcStr::from("a\rb\r\r\nc\r");
+        assert_eq!(s.clone().lines_owned().collect::<Vec<_>>(), ["a\rb\r", "c\r"]);
+        assert_eq!(s.lines_owned().rev().collect::<Vec<_>>(), ["c\r", "a\rb\r"]);
+
+        let s = ArcStr::from("\tfoo bär\u{3000}baz  ");
+        let words: Vec<_> = s.clone().split_whitespace_owned().collect();
+        assert_eq!(words, ["foo", "bär", "baz"]);
+        assert_eq!(words[1].as_bytes_owned().offset(), 5);
+        let mut it = s.split_whitespace_owned();
+        assert_eq!(it.next_back().unwrap(), "baz");
+        assert_eq!(it.next().unwrap(), "foo");
+        assert_eq!(it.next_back().unwrap(), "bär");
+        assert!(it.next().is_none());
+        assert!(it.next_back().is_none());
+    }
 
+    #[test]
+    fn test_arc_str_from_utf8() {
+        let bytes = ArcSlice::from(*b"\xffok\xe2\x9c\x93");
+        let s = ArcStr::from_utf8(bytes.clone().slice_from(1)).unwrap();
+        assert_eq!(s, "ok\u{2713}");
+        assert_eq!(s.as_bytes_owned().offset(), 1);
+
+        let err = ArcStr::from_utf8(bytes.clone().slice_to(5)).unwrap_err();
+        assert_eq!(err.valid_up_to(), 0);
+        let err = ArcStr::from_utf8(bytes.slice(1, 5)).unwrap_err();
+        assert_eq!(err.valid_up_to(), 2);
+    }
+
+    #[test]
+    fn test_arc_str_utf16() {
+        let s = ArcStr::from("a\u{e9}\u{1f600}");
+        let units = s.encode_utf16_owned();
+        assert_eq!(&*units, [0x61, 0xe9, 0xd83d, 0xde00]);
+        assert_eq!(ArcStr::from_utf16(&units).unwrap(), s);
+        assert!(ArcStr::from_utf16(&units[..3]).is_err());
+        assert_eq!(ArcStr::from_utf16_lossy(&units[..3]), "a\u{e9}\u{fffd}");
+        assert!(ArcStr::from_utf16(&[]).unwrap().is_empty());
+        assert!(ArcStr::default().encode_utf16_owned().is_empty());
+    }
+
+    #[test]
+    fn test_arc_str_case() {
+        let s = ArcStr::from("Hello, ΑΒΓ Wörld ΣΑΣ!");
+        assert_eq!(*s.to_lowercase_shared(), s.to_lowercase());
+        assert_eq!(*s.to_uppercase_shared(), s.to_uppercase());
+        assert_eq!(*s.to_ascii_lowercase_shared(), s.to_ascii_lowercase());
+
+        let s = ArcStr::from("İx");
+        assert_eq!(s.to_lowercase_shared(), "i\u{307}x");
+        assert_eq!(s.to_ascii_lowercase_shared(), "İx");
+        assert_eq!(s.to_ascii_lowercase_shared().as_ptr(), s.as_ptr());
+
+        let s = ArcStr::from("ὈΔΥΣΣΕΎΣ ǅ").slice(3..);
+        assert_eq!(s.to_lowercase_shared(), "δυσσεύς ǆ");
+        assert_eq!(s.to_uppercase_shared(), "ΔΥΣΣΕΎΣ Ǆ");
+        let upper = ArcStr::from("ABC 123").slice(1..);
+        assert_eq!(upper.to_uppercase_shared().as_ptr(), upper.as_ptr());
+        assert!(ArcStr::default().to_lowercase_shared().is_empty());
+    }
+
+    #[test]
+    fn test_arc_str_join() {
+        let s = ArcStr::from("α,β");
+        let parts: Vec<_> = s.split_owned(",").collect();
+        assert_eq!(ArcStr::join(&parts, " + "), "α + β");
+        assert_eq!(ArcStr::join(&[], "-"), "");
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn test_arc_str_segments() {
+        let s = ArcStr::from("  a\u{308}b, c  ").slice(1..);
+        let graphemes: Vec<_> = s.clone().graphemes_owned(true).collect();
+        assert_eq!(graphemes, [" ", "a\u{308}", "b", ",", " ", "c", " ", " "]);
+        assert_eq!(graphemes[1].as_ptr(), s[1..].as_ptr());
+
+        let mut back: Vec<_> = s.clone().graphemes_owned(true).rev().collect();
+        back.reverse();
+        assert_eq!(back, graphemes);
+        let mut words = s.clone().unicode_words_owned();
+        assert_eq!(words.next_back().unwrap(), "c");
+        assert_eq!(words.next().unwrap(), "a\u{308}b");
+        assert!(words.next().is_none());
+        assert!(words.fuse().next_back().is_none());
+
+        let parts: Vec<_> = s.clone().split_word_bounds_owned().collect();
+        assert_eq!(parts.concat(), *s);
+        assert_eq!(parts[parts.len() - 1], "  ");
+        assert_eq!(parts[parts.len() - 1].as_ptr(), s[s.len() - 2..].as_ptr());
+
+        let iter = ArcStr::from(String::from("x y")).unicode_words_owned();
+        assert_eq!(iter.collect::<Vec<_>>(), ["x", "y"]);
+        assert_eq!(ArcStr::default().graphemes_owned(false).count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_arc_str_char_boundary() {
+        ArcStr::from("héllo").slice(2..);
+    }
+
+    #[test]
+    fn test_arc_c_str() {
+        let bytes = ArcSlice::from(*b"hello\0world\0");
+        let world = ArcCStr::from_bytes_with_nul(bytes.clone().slice_from(6)).unwrap();
+        assert_eq!(world.to_bytes(), b"world");
+        assert_eq!(world.as_ptr() as *const u8, bytes[6..].as_ptr());
+        assert!(ArcCStr::from_bytes_with_nul(bytes.clone()).is_err());
+        assert!(ArcCStr::from_bytes_with_nul(bytes.slice_to(5)).is_err());
+
+        let owned = ArcCStr::from(CString::new("world").unwrap());
+        assert_eq!(owned, world);
+        assert_eq!(ArcCStr::new(&owned), world);
+        assert_eq!(format!("{:?}", owned), "\"world\"");
+        assert_eq!(&*owned.into_bytes_with_nul(), b"world\0");
+        assert_eq!(ArcCStr::default().to_bytes(), b"");
+    }
+
+    #[test]
+    fn test_map_ref() {
+        let x = ArcSlice::from([Arc::new(1), Arc::new(2)]);
+        let one = x[0].clone();
+        let view = x.clone().map_ref(|x| &*x[1]);
         drop(x);
+        assert_eq!(*view, 2);
+        assert_eq!(Arc::strong_count(&one), 2);
 
-        assert!(y.upgrade().is_none())
+        let s = ArcSlice::from(*b"key: value").map_ref(|b| str::from_utf8(b).unwrap());
+        let value = s.clone().map(|s| &s[5..]);
+        drop(s);
+        assert_eq!(&*value, "value");
+        assert_eq!(format!("{:?}", value), "\"value\"");
+
+        drop(view);
+        assert_eq!(Arc::strong_count(&one), 1);
     }
 
     #[test]
-    fn test_total_cmp() {
-        let x = ArcSlice::new(Box::new([1, 2, 3]));
-        let y = ArcSlice::new(Box::new([1, 2, 3]));
-        let z = ArcSlice::new(Box::new([1, 2, 4]));
+    fn test_into_ref() {
+        let x = ArcSlice::from([1, 2, 3]).slice_from(1);
+        let r = x.clone().into_ref(1).unwrap();
+        assert_eq!(*r, 3);
+        assert!(r == x.get_ref(1).unwrap());
+        assert!(x.into_ref(2).is_none());
+        assert_eq!(*r, 3);
+    }
 
-        assert_eq!(x, x);
-        assert_eq!(x, y);
-        assert!(x != z);
-        assert!(y != z);
+    #[test]
+    fn test_split_first_last_owned() {
+        let x = ArcSlice::from([1, 2, 3]);
+        let (first, rest) = x.clone().split_first_owned().unwrap();
+        assert_eq!((*first, &*rest), (1, &[2, 3][..]));
+        let (last, rest) = x.split_last_owned().unwrap();
+        drop(rest);
+        assert_eq!(*last, 3);
 
-        assert!(x < z);
-        assert!(x <= z);
-        assert!(!(x > z));
-        assert!(!(x >= z));
+        let (only, rest) = ArcSlice::from([4]).split_last_owned().unwrap();
+        assert_eq!(*only, 4);
+        assert!(rest.is_empty());
+        assert!(ArcSlice::<i32>::default().split_first_owned().is_none());
+        assert!(ArcSlice::<i32>::default().split_last_owned().is_none());
+    }
 
-        assert!(!(z < x));
-        assert!(!(z <= x));
-        assert!(z > x);
-        assert!(z >= x);
+    #[test]
+    fn test_iter_cloned() {
+        let x = ArcSlice::from([1, 2, 3, 4]).slice_from(1);
+        let mut it = x.iter_cloned();
+        drop(x);
+        assert_eq!(it.len(), 3);
+        assert_eq!(it.next_back(), Some(4));
+        assert_eq!(it.collect::<Vec<_>>(), [2, 3]);
+    }
 
-        assert_eq!(x.partial_cmp(&x), Some(Ordering::Equal));
-        assert_eq!(x.partial_cmp(&y), Some(Ordering::Equal));
-        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
-        assert_eq!(z.partial_cmp(&y), Some(Ordering::Greater));
+    #[test]
+    fn test_into_iter() {
+        let x = ArcSlice::from([Rc::new(1), Rc::new(2), Rc::new(3)]);
+        let y = x.clone().slice_from(1);
+        let first = x[0].clone();
 
-        assert_eq!(x.cmp(&x), Ordering::Equal);
-        assert_eq!(x.cmp(&y), Ordering::Equal);
-        assert_eq!(x.cmp(&z), Ordering::Less);
-        assert_eq!(z.cmp(&y), Ordering::Greater);
+        // shared, so cloned
+        let mut it = y.clone().into_iter();
+        assert_eq!(it.len(), 2);
+        let three = it.next_back().unwrap();
+        assert_eq!(Rc::strong_count(&three), 2);
+        assert_eq!(it.collect::<Vec<_>>(), [Rc::new(2)]);
+
+        // unique, so moved
+        drop(x);
+        let v: Vec<_> = y.into_iter().rev().collect();
+        assert_eq!(v, [Rc::new(3), Rc::new(2)]);
+        assert_eq!(Rc::strong_count(&v[1]), 1);
+        assert_eq!(Rc::strong_count(&first), 1);
+
+        assert_eq!(ArcSlice::<i32>::default().into_iter().count(), 0);
     }
 
     #[test]
-    fn test_partial_cmp() {
-        use std::f64;
-        let x = ArcSlice::new(Box::new([1.0, f64::NAN]));
-        let y = ArcSlice::new(Box::new([1.0, f64::NAN]));
-        let z = ArcSlice::new(Box::new([2.0, f64::NAN]));
-        let w = ArcSlice::new(Box::new([f64::NAN, 1.0]));
-        assert!(!(x == y));
-        assert!(x != y);
+    fn test_try_slice() {
+        let x = ArcSlice::from([1, 2, 3, 4]);
+        assert_eq!(&*x.clone().try_slice(1..3).unwrap(), [2, 3]);
+        assert_eq!(&*x.get_owned(..=1).unwrap(), [1, 2]);
+        assert!(x.get_owned(5..).is_none());
+        let (hi, lo) = (2, 3);
+        assert!(x.get_owned(lo..hi).is_none());
 
-        assert!(!(x < y));
-        assert!(!(x <= y));
-        assert!(!(x > y));
-        assert!(!(x >= y));
+        let err = x.try_slice(2..9).unwrap_err();
+        assert_eq!(err.to_string(), "range 2..9 out of bounds for slice of length 4");
+    }
 
-        assert!(x < z);
-        assert!(x <= z);
-        assert!(!(x > z));
-        assert!(!(x >= z));
+    #[test]
+    #[should_panic]
+    fn test_slice_range_out_of_bounds() {
+        ArcSlice::from([1, 2, 3]).slice_range(2..=3);
+    }
 
-        assert!(!(z < w));
-        assert!(!(z <= w));
-        assert!(!(z > w));
-        assert!(!(z >= w));
+    #[test]
+    fn test_full_slice() {
+        let x = ArcSlice::new(Box::new([1, 2, 3, 4, 5]));
+        let y = x.clone().slice(1, 4).slice_from(2);
+        drop(x);
+        assert_eq!(&*y, [4]);
+        assert_eq!(y.offset(), 3);
+        let z = y.full_slice();
+        assert_eq!(&*z, [1, 2, 3, 4, 5]);
+        assert_eq!(z.offset(), 0);
+        assert_eq!(z.slice_to(0).full_slice().len(), 5);
+    }
 
-        assert_eq!(x.partial_cmp(&x), None);
-        assert_eq!(x.partial_cmp(&y), None);
-        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
-        assert_eq!(z.partial_cmp(&x), Some(Ordering::Greater));
+    #[test]
+    fn test_from_vec() {
+        let mut v = Vec::with_capacity(10);
+        v.extend_from_slice(&[1, 2, 3]);
+        let x: ArcSlice<i32> = v.into();
+        assert_eq!(&*x, [1, 2, 3]);
+        assert!(ArcSlice::from(Vec::<u8>::new()).is_empty());
+    }
 
-        assert_eq!(x.partial_cmp(&w), None);
-        assert_eq!(y.partial_cmp(&w), None);
-        assert_eq!(z.partial_cmp(&w), None);
-        assert_eq!(w.partial_cmp(&w), None);
+    #[test]
+    fn test_repeat() {
+        let rc = Rc::new(0);
+        let x = ArcSlice::repeat(rc.clone(), 3);
+        assert_eq!(x.len(), 3);
+        assert_eq!(Rc::strong_count(&rc), 4);
+        drop(x);
+        assert!(ArcSlice::repeat(rc.clone(), 0).is_empty());
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        assert_eq!(&*ArcSlice::repeat(7u8, 5), [7; 5]);
     }
 
     #[test]
-    fn test_show() {
-        let x = ArcSlice::new(Box::new([1, 2]));
-        assert_eq!(format!("{:?}", x), "[1, 2]");
+    fn test_from_fn() {
+        let x = ArcSlice::from_fn(4, |i| vec![i; i]);
+        assert_eq!(x.iter().map(|v| v.len()).sum::<usize>(), 6);
+        assert_eq!(&*x[3], [3, 3, 3]);
 
-        let y: ArcSlice<i32> = ArcSlice::new(Box::new([]));
-        assert_eq!(format!("{:?}", y), "[]");
+        let mut calls = 0;
+        assert!(ArcSlice::<u8>::from_fn(0, |_| { calls += 1; 0 }).is_empty());
+        assert_eq!(calls, 0);
     }
 
     #[test]
-    fn test_slice() {
-        let x = ArcSlice::new(Box::new([1, 2, 3]));
-        let real = [1, 2, 3];
-        for i in (0..3 + 1) {
-            for j in (i..3 + 1) {
-                let slice: ArcSlice<_> = x.clone().slice(i, j);
-                assert_eq!(&*slice, &real[i..j]);
+    fn test_from_fn_panic() {
+        use std::panic;
+
+        let rc = Rc::new(0);
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            ArcSlice::from_fn(5, |i| if i < 3 { rc.clone() } else { panic!("oops") })
+        }));
+        assert!(result.is_err());
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_new_uninit() {
+        let mut x = ArcSlice::<Rc<i32>>::new_uninit(2);
+        let rc = Rc::new(1);
+        for elem in x.get_mut().unwrap() {
+            elem.write(rc.clone());
+        }
+        let x = unsafe { x.assume_init() };
+        assert_eq!(Rc::strong_count(&rc), 3);
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let z = unsafe { ArcSlice::<u64>::new_zeroed(3).assume_init() };
+        assert_eq!(&*z, [0, 0, 0]);
+        assert!(unsafe { ArcSlice::<u64>::new_zeroed(0).assume_init() }.is_empty());
+    }
+
+    #[test]
+    fn test_once_slice() {
+        use std::{panic, thread};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        static ONCE: OnceSlice<usize> = OnceSlice::new();
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        assert!(ONCE.get().is_none());
+        assert_eq!(format!("{:?}", ONCE), "OnceSlice(<uninit>)");
+
+        let threads: Vec<_> = (0..4).map(|_| thread::spawn(|| {
+            ONCE.get_or_init(|| {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(10));
+                ArcSlice::from([1, 2, 3])
+            })
+        })).collect();
+        let slices: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        assert!(slices.iter().all(|x| x.as_ptr() == slices[0].as_ptr()));
+        assert_eq!(ONCE.get_or_init(|| panic!()), slices[0]);
+        assert_eq!(format!("{:?}", ONCE), "OnceSlice([1, 2, 3])");
+
+        let once = OnceSlice::new();
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.get_or_init(|| -> ArcSlice<i32> { panic!() })
+        }));
+        assert!(result.is_err());
+        assert!(once.get().is_none());
+        let x = once.get_or_init(|| ArcSlice::from([4]));
+        assert_eq!(once.into_inner(), Some(x));
+        assert_eq!(OnceSlice::<i32>::default().into_inner(), None);
+    }
+
+    #[test]
+    fn test_builder() {
+        let rc = Rc::new(0);
+        let mut b = ArcSliceBuilder::new();
+        assert_eq!(b.capacity(), 0);
+        for _ in 0..10 {
+            b.push(rc.clone());
+        }
+        assert!(b.capacity() >= 10);
+        b.extend_from_slice(&[rc.clone(), rc.clone()]);
+        b[0] = Rc::new(1);
+        assert_eq!(b.len(), 12);
+        let x = b.build();
+        assert_eq!(x.len(), 12);
+        assert_eq!(*x[0], 1);
+        assert_eq!(Rc::strong_count(&rc), 12);
+        let y = x.clone().slice_from(5);
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 12);
+        drop(y);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // dropped without building
+        let mut b = ArcSliceBuilder::with_capacity(3);
+        b.extend(vec![rc.clone(); 5]);
+        drop(b);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        assert!(ArcSliceBuilder::<u8>::with_capacity(8).build().is_empty());
+        assert!(ArcSliceBuilder::<u8>::new().build().is_empty());
+        let mut b = ArcSliceBuilder::<u64>::new();
+        assert_eq!(&*b, &[0u64; 0]);
+        assert!(b.iter_mut().next().is_none());
+
+        let mut b = ArcSliceBuilder::with_capacity(4);
+        b.extend(1..5);
+        assert_eq!(b.capacity(), 4);
+        assert_eq!(&*b.build(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_slice_mut() {
+        let rc = Rc::new(0);
+        let mut x = ArcSliceMut::repeat(rc.clone(), 3);
+        x[1] = Rc::new(1);
+        assert_eq!(Rc::strong_count(&rc), 3);
+        let y = x.freeze();
+        assert_eq!(*y[1], 1);
+        assert!(y.is_unique());
+        drop(y);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let mut b = ArcSliceBuilder::new();
+        b.extend(0..5);
+        let mut x = b.build_mut();
+        x.reverse();
+        assert_eq!(&*x, [4, 3, 2, 1, 0]);
+        assert_eq!(&*x.freeze(), [4, 3, 2, 1, 0]);
+
+        let mut x = ArcSliceMut::from_fn(3, |i| i);
+        x.as_mut()[0] = 7;
+        assert_eq!(x.as_ref(), [7, 1, 2]);
+        assert_eq!(&*ArcSliceMut::from(vec![1, 2]).freeze(), [1, 2]);
+        let mut empty = ArcSliceMut::<u8>::default();
+        assert!(empty.iter_mut().next().is_none());
+        assert!(empty.freeze().is_empty());
+        assert!(ArcSliceBuilder::<u8>::new().build_mut().is_empty());
+    }
+
+    #[test]
+    fn test_slice_mut_split() {
+        let rc = Rc::new(0);
+        let x = ArcSliceMut::from_fn(6, |i| (i, rc.clone()));
+        let (a, rest) = x.split_at_mut(1);
+        let (mut b, c) = rest.split_at_mut(5);
+        assert_eq!((a.len(), b.len(), c.len()), (1, 5, 0));
+        b[0].0 = 10;
+        drop(a);
+        assert_eq!(Rc::strong_count(&rc), 7);
+
+        let (b, d) = b.unsplit(c).unwrap().split_at_mut(2);
+        let (d, b) = d.unsplit(b).unwrap_err();
+        let x = b.unsplit(d).unwrap().freeze();
+        assert_eq!(x.iter().map(|e| e.0).collect::<Vec<_>>(), [10, 2, 3, 4, 5]);
+        assert_eq!(&*x.full_slice()[0].1, &0);
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let (a, b) = ArcSliceMut::from(vec![1, 2]).split_at_mut(0);
+        let (c, _) = ArcSliceMut::from(vec![3]).split_at_mut(1);
+        let (b, c) = b.unsplit(c).unwrap_err();
+        assert_eq!(&*a.unsplit(b).unwrap().unsplit(ArcSliceMut::default()).unwrap(), [1, 2]);
+        assert_eq!(&*ArcSliceMut::default().unsplit(c).unwrap(), [3]);
+        let (a, b) = ArcSliceMut::<u8>::default().split_at_mut(0);
+        assert!(a.unsplit(b).unwrap().freeze().is_empty());
+    }
+
+    #[test]
+    fn test_try_into_mut() {
+        let x = ArcSlice::from([1, 2, 3, 4]);
+        let w = x.downgrade();
+        let x = x.try_into_mut().unwrap_err();
+        drop(w);
+        let (a, b) = x.split_at(2);
+        let a = a.try_into_mut().unwrap_err();
+        drop(b);
+        assert!(a.try_into_mut().is_err());
+
+        let x = ArcSlice::from(vec![5, 6]);
+        let mut m = x.try_into_mut().unwrap();
+        m[1] = 7;
+        let x = m.freeze();
+        let ptr = x.as_ptr();
+        let m = x.try_into_mut().unwrap();
+        assert_eq!((&*m, m.as_ptr()), (&[5, 7][..], ptr));
+
+        static DATA: [u8; 2] = [1, 2];
+        assert!(ArcSlice::from_static(&DATA).try_into_mut().is_err());
+        assert!(ArcSlice::<u8>::default().try_into_mut().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_mut_freeze_part() {
+        let (a, _b) = ArcSliceMut::repeat(0, 4).split_at_mut(2);
+        a.freeze();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_exact_from() {
+        use std::io::{self, Read};
+
+        // a reader that returns a few bytes at a time, and is
+        // interrupted once
+        struct Trickle<'a>(&'a [u8], usize);
+        impl<'a> Read for Trickle<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                if self.1 == 3 {
+                    return Err(io::Error::from(io::ErrorKind::Interrupted))
+                }
+                let n = buf.len().min(self.0.len()).min(2);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
             }
-            assert_eq!(&*x.clone().slice_to(i), &real[..i]);
-            assert_eq!(&*x.clone().slice_from(i), &real[i..]);
         }
+
+        let mut x = ArcSliceMut::repeat(0u8, 5);
+        x.read_exact_from(Trickle(b"abcdefg", 0)).unwrap();
+        assert_eq!(&*x, b"abcde");
+        let err = x.read_exact_from(Trickle(b"abc", 0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut bufs = [ArcSliceMut::default(), ArcSliceMut::repeat(0u8, 3),
+                        ArcSliceMut::default(), ArcSliceMut::repeat(0u8, 4)];
+        ArcSliceMut::read_exact_vectored_from(&mut bufs, Trickle(b"0123456789", 0)).unwrap();
+        assert_eq!(&*bufs[1], b"012");
+        assert_eq!(&*bufs[3], b"3456");
+        ArcSliceMut::read_exact_vectored_from(&mut bufs, &b"abcdefg"[..]).unwrap();
+        assert_eq!(&*bufs[3], b"defg");
+        let err = ArcSliceMut::read_exact_vectored_from(&mut bufs, &b"abcdef"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        ArcSliceMut::read_exact_vectored_from(&mut [], io::empty()).unwrap();
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_cursor() {
+        use std::io::{Seek, SeekFrom, Write};
+        use super::WriteCursor;
+
+        let mut c = WriteCursor::new(ArcSliceMut::repeat(0u8, 6));
+        assert_eq!(c.write(b"abcd").unwrap(), 4);
+        assert_eq!(c.position(), 4);
+        assert_eq!(c.write(b"efgh").unwrap(), 2);
+        assert_eq!(c.write(b"ijkl").unwrap(), 0);
+        assert_eq!(c.seek(SeekFrom::Current(-5)).unwrap(), 1);
+        c.write_all(b"BC").unwrap();
+        assert_eq!(c.seek(SeekFrom::End(2)).unwrap(), 8);
+        assert_eq!(c.write(b"x").unwrap(), 0);
+        assert!(c.seek(SeekFrom::Current(-9)).is_err());
+        assert_eq!(c.position(), 8);
+        c.set_position(0);
+        c.get_mut()[5] = b'F';
+        assert_eq!(c.get_ref().len(), 6);
+        c.flush().unwrap();
+        assert_eq!(&*c.into_inner().freeze(), b"aBCdeF");
+    }
 
     #[test]
-    fn test_send_sync() {
-        fn assert_send<T: Send>() {}
-        fn assert_sync<T: Send>() {}
+    fn test_concat() {
+        let x = ArcSlice::from([1, 2, 3]);
+        let y = ArcSlice::concat(vec![x.clone().slice_from(1), ArcSlice::default(), x.clone()]);
+        assert_eq!(&*y, [2, 3, 1, 2, 3]);
+        assert_eq!(y.offset(), 0);
+        assert_eq!(&*ArcSlice::concat([&[4][..], &[5, 6]]), [4, 5, 6]);
+        assert!(ArcSlice::<u8>::concat(Vec::<Vec<u8>>::new()).is_empty());
+    }
 
-        assert_send::<ArcSlice<u8>>();
-        assert_sync::<ArcSlice<u8>>();
-        assert_send::<WeakSlice<u8>>();
-        assert_sync::<WeakSlice<u8>>();
+    #[test]
+    #[should_panic]
+    fn test_concat_inconsistent() {
+        // a part that grows each time it's looked at
+        struct Growing(Cell<usize>, [u8; 8]);
+        impl AsRef<[u8]> for Growing {
+            fn as_ref(&self) -> &[u8] {
+                self.0.set(self.0.get() + 1);
+                &self.1[..self.0.get()]
+            }
+        }
+        ArcSlice::concat([Growing(Cell::new(0), [0; 8])]);
+    }
+
+    #[test]
+    fn test_join() {
+        let x = ArcSlice::from([1, 2, 3]);
+        let parts = [x.clone().slice_to(1), ArcSlice::default(), x.slice_from(1)];
+        assert_eq!(&*ArcSlice::join(&parts, &[0, 0]), [1, 0, 0, 0, 0, 2, 3]);
+        assert_eq!(&*ArcSlice::join(&parts[..1], &[0]), [1]);
+        assert!(ArcSlice::join(&[], &[0]).is_empty());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let x = ArcSlice::from([1, 2, 3]);
+        let nested = ArcSlice::from([x.clone().slice_from(2), ArcSlice::default(), x.clone()]);
+        assert_eq!(&*ArcSlice::flatten(nested), [3, 1, 2, 3]);
+        assert!(ArcSlice::flatten(ArcSlice::<ArcSlice<u8>>::default()).is_empty());
+    }
+
+    #[test]
+    fn test_from_array() {
+        let x = ArcSlice::from([1, 2, 3]);
+        assert_eq!(&*x, [1, 2, 3]);
+        let y: ArcSlice<String> = ["a".to_string()].into();
+        assert_eq!(&*y, ["a"]);
+        assert!(ArcSlice::<String>::from([]).is_empty());
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let x = ArcSlice::from_slice(&[1u8, 2, 3][..]);
+        assert_eq!(&*x, [1, 2, 3]);
+        let y: ArcSlice<String> = ArcSlice::from(&["a".to_string(), "b".to_string()][..]);
+        assert_eq!(&*y, ["a", "b"]);
+        assert!(ArcSlice::<String>::from_slice(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_from_slice_panic() {
+        use std::panic;
+
+        let drops = Arc::new(Mutex::new(0));
+        struct Foo(bool, Arc<Mutex<i32>>);
+        impl Clone for Foo {
+            fn clone(&self) -> Foo {
+                assert!(!self.0, "clone failed");
+                Foo(self.0, self.1.clone())
+            }
+        }
+        impl Drop for Foo {
+            fn drop(&mut self) { *self.1.lock().unwrap() += 1 }
+        }
+
+        let v = vec![Foo(false, drops.clone()), Foo(false, drops.clone()), Foo(true, drops.clone())];
+        let r = panic::catch_unwind(panic::AssertUnwindSafe(|| ArcSlice::from_slice(&v)));
+        assert!(r.is_err());
+        // the two successful clones were dropped
+        assert_eq!(*drops.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_default() {
+        let x = ArcSlice::<String>::default();
+        assert!(x.is_empty());
+        assert_eq!(x.offset(), 0);
+        assert!(x.full_slice().is_empty());
+        let y = x.clone();
+        let w = y.downgrade();
+        drop((x, y));
+        assert!(w.upgrade().unwrap().is_empty());
+        assert!(CompactArcSlice::new(ArcSlice::<u8>::new(Box::new([]))).unwrap().is_empty());
+
+        assert!(WeakSlice::<String>::default().upgrade().is_none());
+    }
+
+    #[test]
+    fn test_from_static() {
+        static DATA: [u32; 3] = [1, 2, 3];
+        let x = ArcSlice::from_static(&DATA);
+        let mut y = x.clone().slice_from(1);
+        assert_eq!(&*y, [2, 3]);
+        assert_eq!(y.offset(), 0);
+        assert!(y.get_mut().is_none());
+        let w = x.downgrade();
+        drop(x);
+        assert_eq!(&*w.upgrade().unwrap(), [1, 2, 3]);
+        assert!(CompactArcSlice::new(y.clone()).is_err());
+
+        y.make_mut()[0] = 20;
+        assert_eq!(&*y, [20, 3]);
+        assert_eq!(DATA, [1, 2, 3]);
+        assert!(y.try_unwrap().is_ok());
+        assert!(ArcSlice::from_static(&DATA).try_unwrap().is_err());
+
+        let mut empty = ArcSlice::from_static(&DATA).slice_to(0);
+        assert!(!empty.is_unique());
+        assert_eq!(empty.get_mut().map(|e| e.len()), Some(0));
+        assert!(empty.try_unwrap().unwrap().is_empty());
+        let mut empty = ArcSlice::<String>::empty();
+        assert_eq!(empty.get_mut().map(|e| e.len()), Some(0));
+        assert!(empty.try_unwrap().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_const() {
+        static DATA: [u32; 3] = [1, 2, 3];
+        const X: &ArcSlice<u32> = &ArcSlice::from_static(&DATA);
+        const LEN: usize = X.len();
+        const EMPTY: &ArcSlice<u32> = &ArcSlice::empty();
+        const IS_EMPTY: bool = EMPTY.is_empty();
+        assert_eq!((LEN, IS_EMPTY), (3, true));
+        assert_eq!(ArcSlice::<u32>::empty(), ArcSlice::default());
+    }
+
+    #[test]
+    fn test_layout() {
+        // elements more aligned than the header, and zero-sized ones
+        #[repr(align(64))]
+        #[derive(Debug, PartialEq)]
+        struct Big(u8);
+        let x = ArcSlice::new(Box::new([Big(1), Big(2)]));
+        assert_eq!(x.as_ptr() as usize % 64, 0);
+        assert_eq!(x.clone().slice_from(1)[0], Big(2));
+
+        let y = ArcSlice::new(vec![(); 5].into_boxed_slice());
+        let w = y.downgrade();
+        assert_eq!(y.slice(1, 4).len(), 3);
+        assert!(w.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_take() {
+        let mut x = ArcSlice::new(Box::new([1, 2, 3])).slice_from(1);
+        let y = x.downgrade();
+
+        let z = x.take();
+        assert_eq!(&*z, [2, 3]);
+        assert!(x.is_empty());
+        assert_eq!(x.take().len(), 0);
+
+        drop(z);
+        assert!(y.upgrade().is_none());
+    }
+
+    #[cfg(all(feature = "checked", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "outside its allocation")]
+    fn test_checked() {
+        use std::marker::PhantomData;
+        let x = ArcSlice::new(Box::new([1, 2, 3]));
+        let y = ArcSlice::new(Box::new([4, 5]));
+        // simulate misuse of unsafe code mixing up allocations
+        let bad = ArcSlice { data: x.data, header: y.clone().into_parts().1, _marker: PhantomData };
+        let _ = bad.first();
     }
 
     #[test]
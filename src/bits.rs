@@ -0,0 +1,438 @@
+//! A thread-safe reference-counted bit slice type.
+
+use core::{cmp, fmt, ops};
+use core::hash::{Hash, Hasher};
+
+use arc::ArcSlice;
+
+const BITS: usize = usize::BITS as usize;
+
+/// A reference-counted slice of bits.
+///
+/// This stores bits packed into an `ArcSlice<usize>`, so it can be
+/// cloned cheaply and sent between threads, and sub-slices share the
+/// same allocation. Bit `i` is stored in bit `i % usize::BITS` (least
+/// significant first) of word `i / usize::BITS`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::bits::ArcBitSlice;
+///
+/// let x = ArcBitSlice::from_bools(&[true, false, true, true]);
+/// assert_eq!(x.len(), 4);
+/// assert_eq!(x.get(1), Some(false));
+/// assert_eq!(x.count_ones(), 3);
+///
+/// let y = x.slice(2, 4);
+/// assert!(y[0] && y[1]);
+/// ```
+pub struct ArcBitSlice {
+    words: ArcSlice<usize>,
+    // the index of the first bit in `words[0]`, always less than
+    // `BITS` (or zero, if there are no words).
+    start: usize,
+    len: usize,
+}
+
+impl ArcBitSlice {
+    /// Construct a new `ArcBitSlice` containing all the bits of
+    /// `words`.
+    pub fn new(words: ArcSlice<usize>) -> ArcBitSlice {
+        let len = words.len() * BITS;
+        ArcBitSlice::from_words(words, len)
+    }
+
+    /// Construct a new `ArcBitSlice` containing the first `len` bits
+    /// of `words`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` contains fewer than `len` bits.
+    pub fn from_words(words: ArcSlice<usize>, len: usize) -> ArcBitSlice {
+        assert!(len <= words.len() * BITS,
+                "ArcBitSlice::from_words: {} bits requested from {} words", len, words.len());
+        ArcBitSlice {
+            words,
+            start: 0,
+            len,
+        }
+    }
+
+    /// Construct a new `ArcBitSlice` containing the values of `bools`
+    /// as bits.
+    pub fn from_bools(bools: &[bool]) -> ArcBitSlice {
+        let mut chunks = bools.chunks(BITS);
+        let words = ArcSlice::from_fn(bools.len().div_ceil(BITS), |_| {
+            chunks.next().unwrap().iter().enumerate()
+                .fold(0, |w, (i, &b)| w | ((b as usize) << i))
+        });
+        ArcBitSlice::from_words(words, bools.len())
+    }
+
+    /// The number of bits in `self`.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether `self` contains no bits.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the bit at index `i`, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<bool> {
+        if i < self.len {
+            let i = self.start + i;
+            Some(self.words[i / BITS] & (1 << (i % BITS)) != 0)
+        } else {
+            None
+        }
+    }
+
+    /// Construct a new `ArcBitSlice` that only points to bits at
+    /// indices `lo` (inclusive) through `hi` (exclusive).
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications. Use `.clone()` if it is necessary to refer to
+    /// `self` after calling this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if either are strictly greater than
+    /// `self.len()`.
+    pub fn slice(self, lo: usize, hi: usize) -> ArcBitSlice {
+        assert!(lo <= hi && hi <= self.len,
+                "ArcBitSlice::slice: {}..{} out of bounds for length {}", lo, hi, self.len);
+        let (lo, hi) = (self.start + lo, self.start + hi);
+        let first = lo / BITS;
+        let last = cmp::max(first, hi.div_ceil(BITS));
+        ArcBitSlice {
+            words: self.words.slice(first, last),
+            start: lo % BITS,
+            len: hi - lo,
+        }
+    }
+
+    /// The number of bits in `self` that are set.
+    pub fn count_ones(&self) -> usize {
+        self.count_ones_to(self.len)
+    }
+
+    /// The number of bits in `self` that are not set.
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// The number of bits that are set among the first `i` bits of
+    /// `self`, that is, in `self.clone().slice(0, i)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i > self.len()`.
+    pub fn rank(&self, i: usize) -> usize {
+        assert!(i <= self.len, "ArcBitSlice::rank: {} out of bounds for length {}", i, self.len);
+        self.count_ones_to(i)
+    }
+
+    fn count_ones_to(&self, i: usize) -> usize {
+        let (lo, hi) = (self.start, self.start + i);
+        let mut count = 0;
+        for (w, &word) in self.words[..hi.div_ceil(BITS)].iter().enumerate() {
+            let mut word = word;
+            if w == 0 {
+                word &= !0 << lo;
+            }
+            if w == (hi - 1) / BITS && hi % BITS != 0 {
+                word &= !(!0 << (hi % BITS));
+            }
+            count += word.count_ones() as usize;
+        }
+        count
+    }
+
+    /// Iterate over the bits in `self`.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { bits: self, pos: 0, end: self.len }
+    }
+
+    /// The `w`th word of bits of `self`, as if it started at the
+    /// beginning of a word, with any bits past the end cleared.
+    fn word(&self, w: usize) -> usize {
+        let i = self.start + w * BITS;
+        let (q, r) = (i / BITS, i % BITS);
+        let mut word = self.words[q] >> r;
+        if r != 0 && q + 1 < self.words.len() {
+            word |= self.words[q + 1] << (BITS - r);
+        }
+        let remaining = self.len - w * BITS;
+        if remaining < BITS {
+            word &= !(!0 << remaining);
+        }
+        word
+    }
+
+    /// Construct a new `ArcBitSlice` of the same length as `self`,
+    /// whose words are `f` applied to the words of `self`.
+    fn map_words<F: FnMut(usize, usize) -> usize>(&self, mut f: F) -> ArcBitSlice {
+        let words = ArcSlice::from_fn(self.len.div_ceil(BITS), |w| f(w, self.word(w)));
+        ArcBitSlice::from_words(words, self.len)
+    }
+
+    /// Construct a new `ArcBitSlice` whose bits are `f` applied to the
+    /// corresponding bits of `self` and `other`, a word at a time.
+    fn zip_words<F: Fn(usize, usize) -> usize>(&self, other: &ArcBitSlice, op: &str, f: F)
+                                               -> ArcBitSlice {
+        assert!(self.len == other.len,
+                "ArcBitSlice: `{}` of slices of lengths {} and {}", op, self.len, other.len);
+        self.map_words(|w, word| f(word, other.word(w)))
+    }
+}
+
+impl Clone for ArcBitSlice {
+    fn clone(&self) -> ArcBitSlice {
+        ArcBitSlice {
+            words: self.words.clone(),
+            start: self.start,
+            len: self.len,
+        }
+    }
+}
+
+/// The bits that are set in both `self` and `rhs`, in a new
+/// allocation.
+///
+/// # Panics
+///
+/// Panics if `self` and `rhs` have different lengths.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::bits::ArcBitSlice;
+///
+/// let x = ArcBitSlice::from_bools(&[true, true, false, false]);
+/// let y = ArcBitSlice::from_bools(&[true, false, true, false]);
+/// assert_eq!(&x & &y, ArcBitSlice::from_bools(&[true, false, false, false]));
+/// assert_eq!(&x | &y, ArcBitSlice::from_bools(&[true, true, true, false]));
+/// assert_eq!(&x ^ &y, ArcBitSlice::from_bools(&[false, true, true, false]));
+/// assert_eq!(!&x, ArcBitSlice::from_bools(&[false, false, true, true]));
+/// ```
+impl ops::BitAnd<&ArcBitSlice> for &ArcBitSlice {
+    type Output = ArcBitSlice;
+    fn bitand(self, rhs: &ArcBitSlice) -> ArcBitSlice {
+        self.zip_words(rhs, "&", |a, b| a & b)
+    }
+}
+
+/// The bits that are set in either `self` or `rhs`, in a new
+/// allocation.
+///
+/// # Panics
+///
+/// Panics if `self` and `rhs` have different lengths.
+impl ops::BitOr<&ArcBitSlice> for &ArcBitSlice {
+    type Output = ArcBitSlice;
+    fn bitor(self, rhs: &ArcBitSlice) -> ArcBitSlice {
+        self.zip_words(rhs, "|", |a, b| a | b)
+    }
+}
+
+/// The bits that are set in exactly one of `self` and `rhs`, in a
+/// new allocation.
+///
+/// # Panics
+///
+/// Panics if `self` and `rhs` have different lengths.
+impl ops::BitXor<&ArcBitSlice> for &ArcBitSlice {
+    type Output = ArcBitSlice;
+    fn bitxor(self, rhs: &ArcBitSlice) -> ArcBitSlice {
+        self.zip_words(rhs, "^", |a, b| a ^ b)
+    }
+}
+
+/// The bits that are not set in `self`, in a new allocation.
+impl ops::Not for &ArcBitSlice {
+    type Output = ArcBitSlice;
+    fn not(self) -> ArcBitSlice {
+        let len = self.len;
+        self.map_words(|w, word| {
+            let remaining = len - w * BITS;
+            if remaining < BITS { !word & !(!0 << remaining) } else { !word }
+        })
+    }
+}
+
+static TRUE: bool = true;
+static FALSE: bool = false;
+
+impl ops::Index<usize> for ArcBitSlice {
+    type Output = bool;
+    fn index(&self, i: usize) -> &bool {
+        match self.get(i) {
+            Some(true) => &TRUE,
+            Some(false) => &FALSE,
+            None => panic!("ArcBitSlice: index {} out of bounds for length {}", i, self.len),
+        }
+    }
+}
+
+impl PartialEq for ArcBitSlice {
+    fn eq(&self, other: &ArcBitSlice) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+impl Eq for ArcBitSlice {}
+
+impl Hash for ArcBitSlice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for b in self.iter() {
+            b.hash(state)
+        }
+    }
+}
+
+impl fmt::Debug for ArcBitSlice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[")?;
+        for b in self.iter() {
+            f.write_str(if b { "1" } else { "0" })?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl<'a> IntoIterator for &'a ArcBitSlice {
+    type Item = bool;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the bits of an `ArcBitSlice`.
+pub struct Iter<'a> {
+    bits: &'a ArcBitSlice,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = bool;
+    fn next(&mut self) -> Option<bool> {
+        if self.pos < self.end {
+            self.pos += 1;
+            self.bits.get(self.pos - 1)
+        } else {
+            None
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.pos;
+        (n, Some(n))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<bool> {
+        if self.pos < self.end {
+            self.end -= 1;
+            self.bits.get(self.end)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcBitSlice, BITS};
+    use arc::ArcSlice;
+
+    fn pattern(n: usize) -> Vec<bool> {
+        (0..n).map(|i| i % 3 == 0 || i % 7 == 0).collect()
+    }
+
+    #[test]
+    fn test_get() {
+        let bools = pattern(3 * BITS + 5);
+        let x = ArcBitSlice::from_bools(&bools);
+        assert_eq!(x.len(), bools.len());
+        for (i, &b) in bools.iter().enumerate() {
+            assert_eq!(x.get(i), Some(b));
+            assert_eq!(x[i], b);
+        }
+        assert_eq!(x.get(bools.len()), None);
+        assert_eq!(x.iter().collect::<Vec<_>>(), bools);
+        assert_eq!(x.iter().rev().collect::<Vec<_>>(),
+                   bools.iter().rev().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_slice_count() {
+        let bools = pattern(2 * BITS + 10);
+        let x = ArcBitSlice::from_bools(&bools);
+        for lo in 0..bools.len() + 1 {
+            for hi in lo..bools.len() + 1 {
+                let y = x.clone().slice(lo, hi);
+                let expected = &bools[lo..hi];
+                assert_eq!(y.len(), expected.len());
+                assert_eq!(y.iter().collect::<Vec<_>>(), expected);
+                assert_eq!(y.count_ones(), expected.iter().filter(|&&b| b).count());
+                assert_eq!(y.count_zeros(), expected.iter().filter(|&&b| !b).count());
+                assert_eq!(y, ArcBitSlice::from_bools(expected));
+            }
+            assert_eq!(x.rank(lo), bools[..lo].iter().filter(|&&b| b).count());
+        }
+    }
+
+    #[test]
+    fn test_words() {
+        let x = ArcBitSlice::new(ArcSlice::new(Box::new([0b1011, !0])));
+        assert_eq!(x.len(), 2 * BITS);
+        assert_eq!(x.count_ones(), 3 + BITS);
+        assert_eq!(x.clone().slice(1, 4).iter().collect::<Vec<_>>(), [true, false, true]);
+
+        let y = ArcBitSlice::from_words(ArcSlice::new(Box::new([0b1011])), 2);
+        assert_eq!(format!("{:?}", y), "[11]");
+        assert!(ArcBitSlice::from_bools(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let a = pattern(3 * BITS + 7);
+        let b: Vec<bool> = (0..a.len()).map(|i| i % 5 < 2).collect();
+        let x = ArcBitSlice::from_bools(&a);
+        let y = ArcBitSlice::from_bools(&b);
+        for &(lo, hi) in &[(0, a.len()), (3, a.len() - 2), (BITS + 1, 2 * BITS + 1), (5, 5)] {
+            // differently aligned views of the same bits
+            let xs = x.clone().slice(lo, hi);
+            let ys = y.clone().slice(lo - lo % 2, hi - lo % 2);
+            let (a, b) = (&a[lo..hi], &b[lo - lo % 2..hi - lo % 2]);
+            let zip = |f: fn(bool, bool) -> bool| -> Vec<bool> {
+                a.iter().zip(b).map(|(&a, &b)| f(a, b)).collect()
+            };
+            assert_eq!((&xs & &ys).iter().collect::<Vec<_>>(), zip(|a, b| a & b));
+            assert_eq!((&xs | &ys).iter().collect::<Vec<_>>(), zip(|a, b| a | b));
+            assert_eq!((&xs ^ &ys).iter().collect::<Vec<_>>(), zip(|a, b| a ^ b));
+            let not = !&xs;
+            assert_eq!(not.iter().collect::<Vec<_>>(), a.iter().map(|&a| !a).collect::<Vec<_>>());
+            assert_eq!(not.count_ones(), xs.count_zeros());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bitwise_length_mismatch() {
+        let _ = &ArcBitSlice::from_bools(&[true]) & &ArcBitSlice::from_bools(&[true, false]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_words_too_long() {
+        ArcBitSlice::from_words(ArcSlice::new(Box::new([0])), BITS + 1);
+    }
+}
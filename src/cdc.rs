@@ -0,0 +1,118 @@
+//! Content-defined chunking, in the style of FastCDC.
+//!
+//! Chunk boundaries are placed where a rolling "gear" hash of the
+//! preceding bytes has enough zero bits, so they depend only on the
+//! nearby content: inserting or removing bytes only moves the
+//! boundaries around the edit, and the other chunks are unchanged.
+//! This is what makes the chunks useful for deduplication.
+
+/// Random values for each byte, generated by SplitMix64.
+const GEAR: [u64; 256] = {
+    let mut table = [0; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// A mask of the `bits` most significant bits of a `u64`, which are
+/// the ones influenced by the most bytes of the rolling hash.
+fn mask(bits: u32) -> u64 {
+    match bits {
+        0 => 0,
+        _ => !0 << (64 - bits.min(64)),
+    }
+}
+
+/// Check that the chunk size parameters are valid.
+pub fn check_sizes(min: usize, avg: usize, max: usize) {
+    assert!(0 < min && min <= avg && avg <= max,
+            "content-defined chunk sizes must satisfy 0 < min <= avg <= max, not {}, {}, {}",
+            min, avg, max);
+}
+
+/// The length of the first chunk of `data`.
+///
+/// This is at least `min` and at most `max` (or `data.len()`, if that
+/// is smaller), and is usually around `avg`. Before `avg`, a stricter
+/// condition is used, and after it, a looser one (FastCDC's
+/// "normalized chunking"), to concentrate the sizes near `avg`.
+pub fn cut_point(data: &[u8], min: usize, avg: usize, max: usize) -> usize {
+    let n = data.len();
+    if n <= min {
+        return n
+    }
+    let (normal, max) = (avg.min(n), max.min(n));
+    let bits = avg.next_power_of_two().trailing_zeros();
+    let (strict, loose) = (mask(bits + 1), mask(bits.saturating_sub(1)));
+
+    let mut hash = 0u64;
+    let mut i = min;
+    while i < normal {
+        hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+        if hash & strict == 0 {
+            return i + 1
+        }
+        i += 1;
+    }
+    while i < max {
+        hash = (hash << 1).wrapping_add(GEAR[data[i] as usize]);
+        if hash & loose == 0 {
+            return i + 1
+        }
+        i += 1;
+    }
+    max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cut_point;
+
+    fn data(n: usize, mut seed: u32) -> Vec<u8> {
+        (0..n).map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) as u8
+        }).collect()
+    }
+
+    fn chunks(mut data: &[u8]) -> Vec<&[u8]> {
+        let mut out = vec![];
+        while !data.is_empty() {
+            let cut = cut_point(data, 64, 256, 1024);
+            out.push(&data[..cut]);
+            data = &data[cut..];
+        }
+        out
+    }
+
+    #[test]
+    fn test_sizes() {
+        let data = data(100_000, 1);
+        let chunks = chunks(&data);
+        assert_eq!(chunks.concat(), data);
+        for c in &chunks[..chunks.len() - 1] {
+            assert!(64 <= c.len() && c.len() <= 1024);
+        }
+        let mean = data.len() / chunks.len();
+        assert!((128..=512).contains(&mean), "mean chunk size {}", mean);
+    }
+
+    #[test]
+    fn test_edit_is_local() {
+        let original = data(50_000, 2);
+        let mut edited = data(10, 3);
+        edited.extend_from_slice(&original);
+
+        let (a, b) = (chunks(&original), chunks(&edited));
+        let shared = b.iter().filter(|c| a.contains(c)).count();
+        assert!(shared + 3 >= a.len(), "{} of {} chunks shared", shared, a.len());
+    }
+}
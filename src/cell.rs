@@ -0,0 +1,346 @@
+//! Thread-safe slots holding an `ArcSlice`, which can be replaced
+//! while other threads are reading it.
+//!
+//! This is re-exported from the `arc` module.
+
+use core::{fmt, hint, mem};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+use alloc::boxed::Box;
+
+use arc::ArcSlice;
+
+/// A cell holding an `ArcSlice<T>`, which can be loaded and replaced
+/// atomically from many threads at once, like the `arc-swap` crate.
+///
+/// This suits data that is read far more often than it changes, such
+/// as configuration or routing tables that are reloaded while worker
+/// threads keep using them: `load` never blocks, and returns a
+/// snapshot that stays valid however many times the cell is changed
+/// afterwards.
+///
+/// Storing a new slice waits for any `load`s that might be reading
+/// the old one to finish (which takes a few instructions each), and
+/// for any other stores in progress, but never for the snapshots
+/// themselves to be dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{ArcSlice, ArcSliceCell};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let routes = Arc::new(ArcSliceCell::new(ArcSlice::from(["/a", "/b"])));
+/// let snapshot = routes.load();
+///
+/// let writer = routes.clone();
+/// thread::spawn(move || writer.store(ArcSlice::from(["/c"]))).join().unwrap();
+///
+/// assert_eq!(&*snapshot, ["/a", "/b"]);
+/// assert_eq!(&*routes.load(), ["/c"]);
+/// ```
+pub struct ArcSliceCell<T> {
+    // always a `Box` from `Box::into_raw`
+    current: AtomicPtr<ArcSlice<T>>,
+    // the number of `load`s in progress, split in two: each `load`
+    // counts itself in `readers[epoch]`
+    readers: [AtomicUsize; 2],
+    epoch: AtomicUsize,
+    // whether a store is in progress
+    writing: AtomicBool,
+}
+
+unsafe impl<T: Send + Sync> Send for ArcSliceCell<T> {}
+unsafe impl<T: Send + Sync> Sync for ArcSliceCell<T> {}
+
+impl<T> ArcSliceCell<T> {
+    /// Construct a new `ArcSliceCell` holding `slice`.
+    pub fn new(slice: ArcSlice<T>) -> ArcSliceCell<T> {
+        ArcSliceCell {
+            current: AtomicPtr::new(Box::into_raw(Box::new(slice))),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            epoch: AtomicUsize::new(0),
+            writing: AtomicBool::new(false),
+        }
+    }
+
+    /// Get a new handle to the slice currently in `self`.
+    ///
+    /// This doesn't block, and the handle is unaffected by later
+    /// changes to `self`.
+    pub fn load(&self) -> ArcSlice<T> {
+        let readers = &self.readers[self.epoch.load(Ordering::SeqCst)];
+        readers.fetch_add(1, Ordering::SeqCst);
+        // the box can't be freed until `readers` is decremented,
+        // since it is swapped out before a writer waits for that
+        let slice = unsafe { (*self.current.load(Ordering::SeqCst)).clone() };
+        readers.fetch_sub(1, Ordering::Release);
+        slice
+    }
+
+    /// Replace the slice in `self` with `slice`.
+    pub fn store(&self, slice: ArcSlice<T>) {
+        drop(self.swap(slice))
+    }
+
+    /// Replace the slice in `self` with `slice`, returning the one
+    /// that was there.
+    pub fn swap(&self, slice: ArcSlice<T>) -> ArcSlice<T> {
+        self.lock();
+        let old = self.replace(slice);
+        self.writing.store(false, Ordering::Release);
+        old
+    }
+
+    /// Replace the slice in `self` with `new` if it is still
+    /// `current`, returning the one that was there, or give `new`
+    /// back if it isn't.
+    ///
+    /// The slice in `self` is taken to be `current` if it points to
+    /// the same elements, such as if `current` was returned by
+    /// `load`.
+    pub fn compare_and_swap(&self, current: &ArcSlice<T>, new: ArcSlice<T>)
+                            -> Result<ArcSlice<T>, ArcSlice<T>> {
+        self.lock();
+        // stores can't free the box while we're one of them
+        let same = unsafe {
+            let slice = &*self.current.load(Ordering::Acquire);
+            slice.as_ptr() == current.as_ptr() && slice.len() == current.len()
+        };
+        let result = if same { Ok(self.replace(new)) } else { Err(new) };
+        self.writing.store(false, Ordering::Release);
+        result
+    }
+
+    /// Wait until no other store is in progress, and start one.
+    fn lock(&self) {
+        while self.writing.compare_exchange_weak(false, true, Ordering::Acquire,
+                                                 Ordering::Relaxed).is_err() {
+            hint::spin_loop();
+        }
+    }
+
+    /// Replace the slice in `self` with `slice`, returning the one
+    /// that was there. Only one store can do this at a time.
+    fn replace(&self, slice: ArcSlice<T>) -> ArcSlice<T> {
+        let new = Box::into_raw(Box::new(slice));
+        let old = self.current.swap(new, Ordering::SeqCst);
+        // Any `load` counted after one of the counts is seen to be
+        // zero will find `new`, so wait to see each at zero. Flipping
+        // the epoch first sends new `load`s to the other count, so
+        // they can't hold it up indefinitely.
+        for _ in 0..2 {
+            let epoch = self.epoch.load(Ordering::Relaxed);
+            self.epoch.store(1 - epoch, Ordering::SeqCst);
+            while self.readers[epoch].load(Ordering::SeqCst) != 0 {
+                hint::spin_loop();
+            }
+        }
+        unsafe { *Box::from_raw(old) }
+    }
+
+    /// A reference to the slice in `self`, which can be used directly
+    /// since nothing else can access `self`.
+    pub fn get_mut(&mut self) -> &mut ArcSlice<T> {
+        unsafe { &mut **self.current.get_mut() }
+    }
+
+    /// Convert `self` into the slice it holds.
+    pub fn into_inner(self) -> ArcSlice<T> {
+        let mut this = mem::ManuallyDrop::new(self);
+        unsafe { *Box::from_raw(*this.current.get_mut()) }
+    }
+}
+
+impl<T> Drop for ArcSliceCell<T> {
+    fn drop(&mut self) {
+        unsafe { drop(Box::from_raw(*self.current.get_mut())) }
+    }
+}
+
+impl<T> Default for ArcSliceCell<T> {
+    fn default() -> ArcSliceCell<T> {
+        ArcSliceCell::new(ArcSlice::default())
+    }
+}
+
+impl<T> From<ArcSlice<T>> for ArcSliceCell<T> {
+    fn from(slice: ArcSlice<T>) -> ArcSliceCell<T> {
+        ArcSliceCell::new(slice)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArcSliceCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ArcSliceCell").field(&self.load()).finish()
+    }
+}
+
+/// A shared slice that is updated by read-copy-update: readers take
+/// snapshots, and writers build a modified copy and publish it.
+///
+/// This packages up the usual way of using an `ArcSliceCell` for a
+/// table that is mostly read: `update` computes the new contents from
+/// the current ones, and if another update was published in the
+/// meantime, it starts again from that, so no update is lost.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::{ArcSlice, RcuSlice};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let table = Arc::new(RcuSlice::new(ArcSlice::from([1, 2])));
+/// let threads: Vec<_> = (3..7).map(|i| {
+///     let table = table.clone();
+///     thread::spawn(move || {
+///         table.update(|old| old.iter().cloned().chain(Some(i)).collect());
+///     })
+/// }).collect();
+/// for t in threads {
+///     t.join().unwrap();
+/// }
+///
+/// let mut snapshot = table.read().to_vec();
+/// snapshot.sort();
+/// assert_eq!(snapshot, [1, 2, 3, 4, 5, 6]);
+/// ```
+pub struct RcuSlice<T> {
+    cell: ArcSliceCell<T>,
+}
+
+impl<T> RcuSlice<T> {
+    /// Construct a new `RcuSlice` containing `slice`.
+    pub fn new(slice: ArcSlice<T>) -> RcuSlice<T> {
+        RcuSlice { cell: ArcSliceCell::new(slice) }
+    }
+
+    /// Get a snapshot of the current contents of `self`, which is
+    /// unaffected by later updates.
+    pub fn read(&self) -> ArcSlice<T> {
+        self.cell.load()
+    }
+
+    /// Replace the contents of `self` with `f(old)`, where `old` is
+    /// the current contents, returning the new contents.
+    ///
+    /// If another update is published while `f` is running, its
+    /// result is discarded and `f` is called again with the newer
+    /// contents, so `f` may be called more than once.
+    pub fn update<F: FnMut(&[T]) -> Box<[T]>>(&self, mut f: F) -> ArcSlice<T> {
+        let mut old = self.cell.load();
+        loop {
+            let new = ArcSlice::new(f(&old));
+            match self.cell.compare_and_swap(&old, new.clone()) {
+                Ok(_) => return new,
+                Err(_) => old = self.cell.load(),
+            }
+        }
+    }
+
+    /// Convert `self` into its current contents.
+    pub fn into_inner(self) -> ArcSlice<T> {
+        self.cell.into_inner()
+    }
+}
+
+impl<T> Default for RcuSlice<T> {
+    fn default() -> RcuSlice<T> {
+        RcuSlice::new(ArcSlice::default())
+    }
+}
+
+impl<T> From<ArcSlice<T>> for RcuSlice<T> {
+    fn from(slice: ArcSlice<T>) -> RcuSlice<T> {
+        RcuSlice::new(slice)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcuSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RcuSlice").field(&self.read()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcSliceCell, RcuSlice};
+    use arc::ArcSlice;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_swap() {
+        let cell = ArcSliceCell::new(ArcSlice::from([1, 2]));
+        let first = cell.load();
+        let old = cell.swap(ArcSlice::from([3]));
+        assert_eq!(old.as_ptr(), first.as_ptr());
+        assert_eq!(&*cell.load(), [3]);
+        cell.store(ArcSlice::default());
+        assert!(cell.load().is_empty());
+        assert_eq!(&*first, [1, 2]);
+        assert_eq!(format!("{:?}", cell), "ArcSliceCell([])");
+
+        let mut cell = ArcSliceCell::from(first);
+        cell.get_mut().advance(1);
+        assert_eq!(&*cell.into_inner(), [2]);
+    }
+
+    #[test]
+    fn test_concurrent() {
+        let cell = Arc::new(ArcSliceCell::new(ArcSlice::from(vec![0; 8])));
+        let threads: Vec<_> = (0..4).map(|t| {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    if t == 0 {
+                        cell.store(ArcSlice::from(vec![i; 8]));
+                    } else {
+                        let x = cell.load();
+                        assert!(x.iter().all(|&y| y == x[0]));
+                    }
+                }
+            })
+        }).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(&*cell.load(), [999; 8]);
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let cell = ArcSliceCell::new(ArcSlice::from([1, 2, 3]));
+        let current = cell.load();
+        assert!(cell.compare_and_swap(&current.clone().slice_to(2), ArcSlice::default()).is_err());
+        assert!(cell.compare_and_swap(&ArcSlice::from([1, 2, 3]), ArcSlice::default()).is_err());
+        let old = cell.compare_and_swap(&current, ArcSlice::from([4])).unwrap();
+        assert_eq!(old.as_ptr(), current.as_ptr());
+        assert!(cell.compare_and_swap(&current, ArcSlice::default()).unwrap_err().is_empty());
+        assert_eq!(&*cell.load(), [4]);
+    }
+
+    #[test]
+    fn test_rcu() {
+        let rcu = Arc::new(RcuSlice::new(ArcSlice::from(vec![0])));
+        let before = rcu.read();
+        let threads: Vec<_> = (0..4).map(|_| {
+            let rcu = rcu.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    rcu.update(|old| old.iter().map(|x| x + 1).collect());
+                }
+            })
+        }).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(&*before, [0]);
+        assert_eq!(&*rcu.read(), [400]);
+        let new = rcu.update(|old| vec![old[0], 1].into_boxed_slice());
+        assert_eq!(format!("{:?}", rcu), "RcuSlice([400, 1])");
+        assert_eq!(Arc::try_unwrap(rcu).unwrap().into_inner(), new);
+    }
+}
@@ -0,0 +1,64 @@
+//! Debugging aids for catching misuse of unsafe code early: checks
+//! of internal invariants, and poisoning and quarantining of freed
+//! memory.
+//!
+//! The checks and poisoning only run in debug builds, with the
+//! `checked` and `poison` features respectively; see `ENABLED` and
+//! `POISON`. Quarantining runs with the `quarantine` feature.
+
+use core::{mem, ptr};
+use core::alloc::Layout;
+
+#[cfg(feature = "quarantine")]
+use quarantine;
+
+/// Whether the checks should run.
+pub const ENABLED: bool = cfg!(all(feature = "checked", debug_assertions));
+
+/// Whether freed elements should be poisoned.
+pub const POISON: bool = cfg!(all(feature = "poison", debug_assertions));
+
+/// The byte written over the elements of freed allocations.
+pub const POISON_BYTE: u8 = 0xA5;
+
+/// Overwrite the `size` bytes at `ptr`, which belonged to dropped
+/// elements, with `POISON_BYTE`, if enabled.
+pub unsafe fn poison(ptr: *mut u8, size: usize) {
+    if POISON {
+        ptr::write_bytes(ptr, POISON_BYTE, size);
+    }
+}
+
+/// Quarantine the allocation `ptr` with layout `layout`, if enabled,
+/// or otherwise free it.
+pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+    #[cfg(feature = "quarantine")]
+    quarantine::defer(ptr, layout);
+    #[cfg(not(feature = "quarantine"))]
+    alloc::alloc::dealloc(ptr, layout);
+}
+
+/// Check that `view` points to whole elements inside `whole`, and
+/// that the allocation is still alive according to its `strong`
+/// count.
+pub fn view<T>(view: *const [T], whole: &[T], strong: usize) {
+    assert!(strong > 0, "shared_slice: slice used after its allocation was freed");
+
+    let size = mem::size_of::<T>();
+    let len = view.len();
+    if size == 0 {
+        assert!(len <= whole.len(),
+                "shared_slice: view of {} elements in an allocation of {}", len, whole.len());
+        return
+    }
+
+    let start = view as *const T as usize;
+    let lo = whole.as_ptr() as usize;
+    let hi = lo + mem::size_of_val(whole);
+    assert!(lo <= start && start <= hi && len <= (hi - start) / size,
+            "shared_slice: view {:#x}+{} outside its allocation {:#x}..{:#x}",
+            start, len, lo, hi);
+    assert!((start - lo).is_multiple_of(size),
+            "shared_slice: view {:#x} not aligned to an element of its allocation {:#x}",
+            start, lo);
+}
@@ -0,0 +1,244 @@
+//! A compressed sparse row structure over thread-safe shared slices.
+
+use core::fmt;
+use core::iter::FusedIterator;
+
+use alloc::vec::Vec;
+
+use arc::ArcSlice;
+
+/// A compressed sparse row (CSR) structure: a sequence of rows of
+/// varying length stored contiguously in one `ArcSlice<T>`.
+///
+/// Row `i` consists of the values at indices `offsets[i]` (inclusive)
+/// through `offsets[i + 1]` (exclusive). This can represent the
+/// adjacency lists of a graph (with `T` a node index) or the rows of
+/// a sparse matrix (with `T` a column index and value pair). Rows can
+/// be extracted as `ArcSlice`s sharing the same allocation, so they
+/// can be sent to other threads without copying.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::csr::SharedCsr;
+///
+/// // 0 -> 1, 2; 1 -> (nothing); 2 -> 0
+/// let graph = SharedCsr::from_rows(&[&[1, 2][..], &[], &[0]]);
+/// assert_eq!(graph.rows(), 3);
+///
+/// let row = graph.row_owned(0);
+/// drop(graph);
+/// assert_eq!(&*row, [1, 2]);
+/// ```
+pub struct SharedCsr<T> {
+    offsets: ArcSlice<usize>,
+    values: ArcSlice<T>,
+}
+
+impl<T> SharedCsr<T> {
+    /// Construct a new `SharedCsr` with the row boundaries `offsets`
+    /// into `values`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offsets` is empty, is not sorted in non-decreasing
+    /// order, or contains an offset greater than `values.len()`.
+    pub fn new(offsets: ArcSlice<usize>, values: ArcSlice<T>) -> SharedCsr<T> {
+        assert!(!offsets.is_empty(), "SharedCsr::new: offsets must be non-empty");
+        assert!(offsets.windows(2).all(|w| w[0] <= w[1]),
+                "SharedCsr::new: offsets must be non-decreasing");
+        assert!(offsets[offsets.len() - 1] <= values.len(),
+                "SharedCsr::new: offset {} out of bounds for {} values",
+                offsets[offsets.len() - 1], values.len());
+        SharedCsr {
+            offsets,
+            values,
+        }
+    }
+
+    /// The number of rows in `self`.
+    pub fn rows(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// The row boundaries of `self`.
+    pub fn offsets(&self) -> &ArcSlice<usize> {
+        &self.offsets
+    }
+
+    /// The values of all the rows of `self`.
+    pub fn values(&self) -> &ArcSlice<T> {
+        &self.values
+    }
+
+    /// Get the values in row `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.rows()`.
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.values[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Construct a new `ArcSlice` pointing to the values in row `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.rows()`.
+    pub fn row_owned(&self, i: usize) -> ArcSlice<T> {
+        self.values.clone().slice(self.offsets[i], self.offsets[i + 1])
+    }
+
+    /// Iterate over the rows of `self` as `ArcSlice`s.
+    pub fn rows_owned(&self) -> RowsOwned<T> {
+        RowsOwned {
+            csr: self.clone(),
+            pos: 0,
+            end: self.rows(),
+        }
+    }
+
+    /// Construct a new `SharedCsr` containing only the rows at
+    /// indices `lo` (inclusive) through `hi` (exclusive), sharing the
+    /// same allocations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if either are strictly greater than
+    /// `self.rows()`.
+    pub fn slice_rows(self, lo: usize, hi: usize) -> SharedCsr<T> {
+        assert!(lo <= hi && hi <= self.rows(),
+                "SharedCsr::slice_rows: {}..{} out of bounds for {} rows", lo, hi, self.rows());
+        SharedCsr {
+            offsets: self.offsets.slice(lo, hi + 1),
+            values: self.values,
+        }
+    }
+}
+
+impl<T: Clone> SharedCsr<T> {
+    /// Construct a new `SharedCsr` by copying the values in `rows`.
+    pub fn from_rows<I>(rows: I) -> SharedCsr<T>
+        where I: IntoIterator, I::Item: AsRef<[T]>
+    {
+        let mut offsets = vec![0];
+        let mut values = Vec::new();
+        for row in rows {
+            values.extend_from_slice(row.as_ref());
+            offsets.push(values.len());
+        }
+        SharedCsr {
+            offsets: ArcSlice::new(offsets.into_boxed_slice()),
+            values: ArcSlice::new(values.into_boxed_slice()),
+        }
+    }
+}
+
+impl<T> Clone for SharedCsr<T> {
+    fn clone(&self) -> SharedCsr<T> {
+        SharedCsr {
+            offsets: self.offsets.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedCsr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries((0..self.rows()).map(|i| self.row(i))).finish()
+    }
+}
+
+/// An iterator over the rows of a `SharedCsr`, as `ArcSlice`s.
+///
+/// This is created by `SharedCsr::rows_owned`.
+pub struct RowsOwned<T> {
+    csr: SharedCsr<T>,
+    // the rows not yet yielded from either end
+    pos: usize,
+    end: usize,
+}
+
+impl<T> Iterator for RowsOwned<T> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        if self.pos < self.end {
+            self.pos += 1;
+            Some(self.csr.row_owned(self.pos - 1))
+        } else {
+            None
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.pos;
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for RowsOwned<T> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        if self.pos < self.end {
+            self.end -= 1;
+            Some(self.csr.row_owned(self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for RowsOwned<T> {}
+
+impl<T> FusedIterator for RowsOwned<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedCsr;
+    use arc::ArcSlice;
+
+    #[test]
+    fn test_rows() {
+        let csr = SharedCsr::from_rows(vec![vec![1, 2, 3], vec![], vec![4], vec![5, 6]]);
+        assert_eq!(csr.rows(), 4);
+        assert_eq!(&**csr.offsets(), [0, 3, 3, 4, 6]);
+        assert_eq!(&**csr.values(), [1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(csr.row(0), [1, 2, 3]);
+        assert!(csr.row(1).is_empty());
+        assert_eq!(&*csr.row_owned(3), [5, 6]);
+        assert_eq!(csr.rows_owned().map(|r| r.len()).collect::<Vec<_>>(), [3, 0, 1, 2]);
+        assert_eq!(csr.rows_owned().rev().map(|r| r.len()).collect::<Vec<_>>(), [2, 1, 0, 3]);
+        let mut rows = csr.rows_owned();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(&*rows.next_back().unwrap(), [5, 6]);
+        assert_eq!(&*rows.next().unwrap(), [1, 2, 3]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows.by_ref().count(), 2);
+        assert!(rows.next().is_none() && rows.next_back().is_none());
+        assert_eq!(format!("{:?}", csr), "[[1, 2, 3], [], [4], [5, 6]]");
+
+        let middle = csr.slice_rows(1, 3);
+        assert_eq!(middle.rows(), 2);
+        assert_eq!(middle.row(1), [4]);
+        assert_eq!(middle.clone().slice_rows(0, 0).rows(), 0);
+    }
+
+    #[test]
+    fn test_new() {
+        let values = ArcSlice::new(Box::new(['a', 'b', 'c']));
+        let csr = SharedCsr::new(ArcSlice::new(Box::new([1, 1, 3])), values);
+        assert!(csr.row(0).is_empty());
+        assert_eq!(csr.row(1), ['b', 'c']);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_unsorted() {
+        SharedCsr::new(ArcSlice::new(Box::new([0, 2, 1])), ArcSlice::new(Box::new([0, 0])));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_out_of_bounds() {
+        SharedCsr::new(ArcSlice::new(Box::new([0, 3])), ArcSlice::new(Box::new([0, 0])));
+    }
+}
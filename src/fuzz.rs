@@ -0,0 +1,305 @@
+//! An interpreter for random sequences of operations on shared
+//! slices, for fuzzing the reference counting.
+//!
+//! Each run replays a list of `Op`s against a set of live handles,
+//! comparing them against a simple model, and checks that every
+//! element is dropped exactly once: as soon as the last strong handle
+//! to its allocation goes away, and never while one still exists.
+//! This is driven by the targets in the `fuzz` directory (run with
+//! `cargo fuzz run rc_ops` or `cargo fuzz run arc_ops`), and requires
+//! the `fuzzing` feature.
+
+use std::{cmp, mem, panic, thread};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+
+use arbitrary::Arbitrary;
+
+use arc::{self, ArcSlice};
+use rc::{self, RcSlice};
+
+/// An operation on the handles alive during a run.
+///
+/// Indices are taken modulo the number of handles of the relevant
+/// kind, and operations do nothing if there are no such handles.
+#[derive(Arbitrary, Clone, Debug)]
+pub enum Op {
+    /// Create a new slice containing these elements.
+    New(Vec<u8>),
+    /// Clone a strong handle.
+    Clone(u8),
+    /// Narrow a strong handle to `lo..hi`, each taken modulo its
+    /// length plus one, and swapped if necessary.
+    Slice(u8, u16, u16),
+    /// Take the contents of a strong handle, leaving it empty.
+    Take(u8),
+    /// Create a weak handle from a strong one.
+    Downgrade(u8),
+    /// Try to create a strong handle from a weak one.
+    Upgrade(u8),
+    /// Drop a strong handle.
+    Drop(u8),
+    /// Drop a weak handle.
+    DropWeak(u8),
+    /// Move a strong handle to another thread, which drops it
+    /// concurrently with the later operations. For `RcSlice`, this is
+    /// the same as `Drop`.
+    Send(u8),
+}
+
+/// Run `ops` against `RcSlice`s.
+///
+/// # Panics
+///
+/// Panics if the handles disagree with the model, or if an element
+/// is leaked, dropped early or dropped twice.
+pub fn run_rc(ops: &[Op]) {
+    run::<RcSlice<Tracked>>(ops)
+}
+
+/// Run `ops` against `ArcSlice`s.
+///
+/// # Panics
+///
+/// Panics if the handles disagree with the model, or if an element
+/// is leaked, dropped early or dropped twice.
+pub fn run_arc(ops: &[Op]) {
+    run::<ArcSlice<Tracked>>(ops)
+}
+
+/// An element that counts how many elements of its allocation are
+/// alive.
+struct Tracked {
+    value: u8,
+    live: Arc<AtomicUsize>,
+}
+
+impl Tracked {
+    fn new(value: u8, live: &Arc<AtomicUsize>) -> Tracked {
+        live.fetch_add(1, Ordering::SeqCst);
+        Tracked { value, live: live.clone() }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let old = self.live.fetch_sub(1, Ordering::SeqCst);
+        assert!(old > 0, "element dropped twice");
+    }
+}
+
+/// The operations of `RcSlice` and `ArcSlice` used by `run`.
+trait Shared: Clone + Sized {
+    type Weak;
+    fn new(elems: Box<[Tracked]>) -> Self;
+    fn slice(self, lo: usize, hi: usize) -> Self;
+    fn take(&mut self) -> Self;
+    fn downgrade(&self) -> Self::Weak;
+    fn upgrade(weak: &Self::Weak) -> Option<Self>;
+    fn values(&self) -> Vec<u8>;
+    /// Move `self` to a new thread (checking that it contains
+    /// `expected` there) if possible, otherwise drop it. Returns
+    /// whether it was moved.
+    fn send(self, expected: Vec<u8>, threads: &mut Vec<JoinHandle<()>>) -> bool;
+}
+
+impl Shared for RcSlice<Tracked> {
+    type Weak = rc::WeakSlice<Tracked>;
+    fn new(elems: Box<[Tracked]>) -> Self { RcSlice::new(elems) }
+    fn slice(self, lo: usize, hi: usize) -> Self { RcSlice::slice(self, lo, hi) }
+    fn take(&mut self) -> Self { RcSlice::take(self) }
+    fn downgrade(&self) -> Self::Weak { RcSlice::downgrade(self) }
+    fn upgrade(weak: &Self::Weak) -> Option<Self> { weak.upgrade() }
+    fn values(&self) -> Vec<u8> { self.iter().map(|t| t.value).collect() }
+    fn send(self, _expected: Vec<u8>, _threads: &mut Vec<JoinHandle<()>>) -> bool {
+        false
+    }
+}
+
+impl Shared for ArcSlice<Tracked> {
+    type Weak = arc::WeakSlice<Tracked>;
+    fn new(elems: Box<[Tracked]>) -> Self { ArcSlice::new(elems) }
+    fn slice(self, lo: usize, hi: usize) -> Self { ArcSlice::slice(self, lo, hi) }
+    fn take(&mut self) -> Self { ArcSlice::take(self) }
+    fn downgrade(&self) -> Self::Weak { ArcSlice::downgrade(self) }
+    fn upgrade(weak: &Self::Weak) -> Option<Self> { weak.upgrade() }
+    fn values(&self) -> Vec<u8> { self.iter().map(|t| t.value).collect() }
+    fn send(self, expected: Vec<u8>, threads: &mut Vec<JoinHandle<()>>) -> bool {
+        threads.push(thread::spawn(move || {
+            let copy = self.clone();
+            drop(self);
+            assert_eq!(copy.values(), expected);
+        }));
+        true
+    }
+}
+
+/// The model of an allocation.
+struct Alloc {
+    live: Arc<AtomicUsize>,
+    // whether this is a real allocation; empty slices don't allocate,
+    // so are never freed and their weak handles always upgrade
+    counted: bool,
+    // the number of strong handles on this thread
+    strong: usize,
+    // whether a strong handle has been moved to another thread, which
+    // may or may not have dropped it yet
+    sent: bool,
+}
+
+struct Strong<S> {
+    slice: S,
+    alloc: usize,
+    expected: Vec<u8>,
+}
+
+struct Weak<W> {
+    weak: W,
+    alloc: usize,
+    expected: Vec<u8>,
+}
+
+fn pick(len: usize, i: u8) -> Option<usize> {
+    if len == 0 { None } else { Some(i as usize % len) }
+}
+
+fn new_alloc(allocs: &mut Vec<Alloc>, counted: bool) -> usize {
+    allocs.push(Alloc { live: Arc::new(AtomicUsize::new(0)), counted, strong: 1, sent: false });
+    allocs.len() - 1
+}
+
+fn push<S: Shared>(strong: &mut Vec<Strong<S>>, s: Strong<S>) {
+    assert_eq!(s.slice.values(), s.expected);
+    strong.push(s)
+}
+
+/// Record that a strong handle to `alloc` has been dropped.
+fn release(alloc: &mut Alloc) {
+    alloc.strong -= 1;
+    if alloc.strong == 0 && !alloc.sent {
+        assert_eq!(alloc.live.load(Ordering::SeqCst), 0,
+                   "elements not dropped with their last strong handle");
+    }
+}
+
+fn run<S: Shared>(ops: &[Op]) {
+    let mut allocs = vec![];
+    let mut strong: Vec<Strong<S>> = vec![];
+    let mut weak: Vec<Weak<S::Weak>> = vec![];
+    let mut threads = vec![];
+
+    for op in ops {
+        match *op {
+            Op::New(ref values) => {
+                let alloc = new_alloc(&mut allocs, !values.is_empty());
+                let elems = values.iter()
+                    .map(|&v| Tracked::new(v, &allocs[alloc].live))
+                    .collect::<Vec<_>>();
+                let slice = S::new(elems.into_boxed_slice());
+                push(&mut strong, Strong { slice, alloc, expected: values.clone() });
+            }
+            Op::Clone(i) => if let Some(i) = pick(strong.len(), i) {
+                let s = Strong {
+                    slice: strong[i].slice.clone(),
+                    alloc: strong[i].alloc,
+                    expected: strong[i].expected.clone(),
+                };
+                allocs[s.alloc].strong += 1;
+                push(&mut strong, s);
+            },
+            Op::Slice(i, lo, hi) => if let Some(i) = pick(strong.len(), i) {
+                let s = strong.swap_remove(i);
+                let n = s.expected.len() + 1;
+                let (lo, hi) = (lo as usize % n, hi as usize % n);
+                let (lo, hi) = (cmp::min(lo, hi), cmp::max(lo, hi));
+                let expected = s.expected[lo..hi].to_vec();
+                push(&mut strong, Strong { slice: s.slice.slice(lo, hi), alloc: s.alloc, expected });
+            },
+            Op::Take(i) => if let Some(i) = pick(strong.len(), i) {
+                let empty = new_alloc(&mut allocs, false);
+                let s = &mut strong[i];
+                let taken = Strong {
+                    slice: s.slice.take(),
+                    alloc: mem::replace(&mut s.alloc, empty),
+                    expected: mem::take(&mut s.expected),
+                };
+                assert_eq!(s.slice.values(), s.expected);
+                push(&mut strong, taken);
+            },
+            Op::Downgrade(i) => if let Some(i) = pick(strong.len(), i) {
+                let s = &strong[i];
+                weak.push(Weak { weak: s.slice.downgrade(), alloc: s.alloc, expected: s.expected.clone() });
+            },
+            Op::Upgrade(i) => if let Some(i) = pick(weak.len(), i) {
+                let w = &weak[i];
+                let alloc = &mut allocs[w.alloc];
+                match S::upgrade(&w.weak) {
+                    Some(slice) => {
+                        assert!(alloc.strong > 0 || alloc.sent || !alloc.counted,
+                                "upgraded a slice that was freed");
+                        alloc.strong += 1;
+                        push(&mut strong, Strong { slice, alloc: w.alloc, expected: w.expected.clone() });
+                    }
+                    None => assert!(alloc.strong == 0 && alloc.counted, "failed to upgrade a live slice"),
+                }
+            },
+            Op::Drop(i) => if let Some(i) = pick(strong.len(), i) {
+                let s = strong.swap_remove(i);
+                let alloc = s.alloc;
+                drop(s);
+                release(&mut allocs[alloc]);
+            },
+            Op::DropWeak(i) => if let Some(i) = pick(weak.len(), i) {
+                weak.swap_remove(i);
+            },
+            Op::Send(i) => if let Some(i) = pick(strong.len(), i) {
+                let s = strong.swap_remove(i);
+                if s.slice.send(s.expected, &mut threads) {
+                    allocs[s.alloc].sent = true;
+                }
+                release(&mut allocs[s.alloc]);
+            },
+        }
+    }
+
+    drop(strong);
+    drop(weak);
+    for t in threads {
+        if let Err(e) = t.join() {
+            panic::resume_unwind(e)
+        }
+    }
+    for alloc in &allocs {
+        assert_eq!(alloc.live.load(Ordering::SeqCst), 0, "elements leaked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_arc, run_rc, Op};
+
+    fn ops() -> Vec<Op> {
+        vec![Op::New(vec![1, 2, 3, 4]),
+             Op::Clone(0),
+             Op::Slice(1, 1, 3),
+             Op::Downgrade(1),
+             Op::Send(0),
+             Op::Take(0),
+             Op::Upgrade(0),
+             Op::Drop(0),
+             Op::Drop(0),
+             Op::Upgrade(0),
+             Op::New(vec![]),
+             Op::Downgrade(0),
+             Op::Drop(0),
+             Op::Upgrade(1),
+             Op::DropWeak(0)]
+    }
+
+    #[test]
+    fn test_run() {
+        run_rc(&ops());
+        run_arc(&ops());
+    }
+}
@@ -0,0 +1,37 @@
+//! Comparisons that can skip looking at the elements when both sides
+//! are the same view of the same memory.
+//!
+//! Otherwise these defer to the comparisons of `[T]` itself, which
+//! `core` already specializes to a single `memcmp` for byte-like
+//! element types (`u8`, `i8`, `bool`, and friends for equality; `u8`
+//! for ordering), so they shouldn't be replaced with element-wise
+//! loops.
+
+use core::cmp::Ordering;
+
+/// Whether `a` and `b` are the same view of the same memory.
+pub fn same_view<T>(a: &[T], b: &[T]) -> bool {
+    a.as_ptr() == b.as_ptr() && a.len() == b.len()
+}
+
+/// Compare `a` and `b` for equality.
+///
+/// With the `unstable` feature, this is immediately true for
+/// identical views when `T: Eq`; otherwise (like for floats, where
+/// `NaN != NaN`) the elements always need to be compared.
+#[cfg(not(feature = "unstable"))]
+pub fn slice_eq<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+    a == b
+}
+#[cfg(feature = "unstable")]
+pub use specialize::slice_eq;
+
+/// Compare `a` and `b`, which is immediately `Equal` for identical
+/// views, since `Ord` is reflexive.
+pub fn slice_cmp<T: Ord>(a: &[T], b: &[T]) -> Ordering {
+    if same_view(a, b) {
+        Ordering::Equal
+    } else {
+        a.cmp(b)
+    }
+}
@@ -0,0 +1,281 @@
+//! The allocations behind `RcSlice` and `ArcSlice`.
+//!
+//! Each allocation is a single block of memory holding a header with
+//! the reference counts, followed directly by the elements, like
+//! `Rc<[T]>` and `Arc<[T]>`. The header type `H` is specific to each
+//! module (`RcSlice` counts with `Cell`s, `ArcSlice` with atomics),
+//! but the layout and the allocating and freeing of the memory are
+//! shared here.
+
+use core::{mem, ptr};
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+
+use alloc::alloc::{alloc, handle_alloc_error, realloc};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use checks;
+
+/// The largest a reference count can get before `count_overflow` is
+/// called, like `Arc`: far more references than can fit in memory,
+/// so it can only be reached by leaking them, and far enough below
+/// `usize::MAX` that other threads can't overflow it before the
+/// process aborts.
+pub const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+/// Abort the process because a reference count is too large, since
+/// letting it wrap around would free the elements while references to
+/// them remain.
+#[cold]
+pub fn count_overflow() -> ! {
+    #[cfg(any(test, feature = "std"))]
+    ::std::process::abort();
+
+    #[cfg(not(any(test, feature = "std")))]
+    {
+        // without `std`, panicking while panicking aborts
+        struct Abort;
+        impl Drop for Abort {
+            fn drop(&mut self) {
+                panic!("shared_slice: reference count overflow")
+            }
+        }
+        let _abort = Abort;
+        panic!("shared_slice: reference count overflow")
+    }
+}
+
+/// The layout of an allocation with header `H` and `len` elements of
+/// type `T`, along with the offset of the first element.
+fn layout<H, T>(len: usize) -> (Layout, usize) {
+    let (layout, offset) = Layout::array::<T>(len).ok()
+        .and_then(|elems| Layout::new::<H>().extend(elems).ok())
+        .expect("shared_slice: allocation too large");
+    (layout.pad_to_align(), offset)
+}
+
+/// A pointer to the first element of the allocation `ptr`.
+pub fn elems<H, T>(ptr: NonNull<H>) -> *mut T {
+    // the offset doesn't depend on the number of elements
+    let offset = layout::<H, T>(0).1;
+    unsafe { (ptr.as_ptr() as *mut u8).add(offset) as *mut T }
+}
+
+/// Allocate space for `header` followed by `len` elements of type
+/// `T`, and write `header` into it. The elements are left
+/// uninitialized.
+pub fn allocate<H, T>(header: H, len: usize) -> NonNull<H> {
+    let layout = layout::<H, T>(len).0;
+    unsafe {
+        let ptr = match NonNull::new(alloc(layout) as *mut H) {
+            Some(ptr) => ptr,
+            None => handle_alloc_error(layout),
+        };
+        ptr::write(ptr.as_ptr(), header);
+        ptr
+    }
+}
+
+/// Allocate space for `header` followed by the elements of `slice`,
+/// and move them into it.
+pub fn from_box<H, T>(header: H, slice: Box<[T]>) -> NonNull<H> {
+    let len = slice.len();
+    let ptr = allocate::<H, T>(header, len);
+    unsafe {
+        let raw = Box::into_raw(slice);
+        ptr::copy_nonoverlapping(raw as *const T, elems::<H, T>(ptr), len);
+        // free the old allocation without dropping the moved elements
+        drop(Box::from_raw(raw as *mut [ManuallyDrop<T>]));
+    }
+    ptr
+}
+
+/// Allocate space for `header` followed by the elements of `array`,
+/// and move them into it.
+pub fn from_array<H, T, const N: usize>(header: H, array: [T; N]) -> NonNull<H> {
+    let ptr = allocate::<H, T>(header, N);
+    let array = ManuallyDrop::new(array);
+    unsafe { ptr::copy_nonoverlapping(array.as_ptr(), elems::<H, T>(ptr), N) }
+    ptr
+}
+
+/// Allocate space for `header` followed by the elements of `slice`,
+/// and clone them into it.
+pub fn from_slice<H, T: Clone>(header: H, slice: &[T]) -> NonNull<H> {
+    from_parts(header, &[slice], slice.len())
+}
+
+/// Allocate space for `header` followed by the `len` elements of
+/// `parts` in order, and clone them into it.
+///
+/// # Panics
+///
+/// Panics if `parts` don't have `len` elements in total, such as if
+/// an `AsRef` implementation returns slices of different lengths.
+pub fn from_parts<H, T: Clone, P: AsRef<[T]>>(header: H, parts: &[P], len: usize) -> NonNull<H> {
+    let mut guard = Guard::<H, T> {
+        ptr: allocate::<H, T>(header, len),
+        len,
+        init: 0,
+        _marker: PhantomData,
+    };
+    for part in parts {
+        let part = part.as_ref();
+        assert!(part.len() <= len - guard.init, "shared_slice: parts changed length");
+        unsafe { clone_into(part, elems::<H, T>(guard.ptr), &mut guard.init) }
+    }
+    assert!(guard.init == len, "shared_slice: parts changed length");
+    let ptr = guard.ptr;
+    mem::forget(guard);
+    ptr
+}
+
+/// A partially initialized allocation, which is freed along with its
+/// first `init` elements if it is dropped, such as when cloning an
+/// element panics.
+struct Guard<H, T> {
+    ptr: NonNull<H>,
+    len: usize,
+    init: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<H, T> Drop for Guard<H, T> {
+    fn drop(&mut self) {
+        unsafe {
+            drop_elems::<H, T>(self.ptr, self.init);
+            free::<H, T>(self.ptr, self.len);
+        }
+    }
+}
+
+/// Allocate space for `header` followed by `len` clones of `value`,
+/// and write them into it.
+pub fn from_elem<H, T: Clone>(header: H, value: T, len: usize) -> NonNull<H> {
+    let mut guard = Guard::<H, T> {
+        ptr: allocate::<H, T>(header, len),
+        len,
+        init: 0,
+        _marker: PhantomData,
+    };
+    unsafe { fill(value, elems::<H, T>(guard.ptr), len, &mut guard.init) }
+    let ptr = guard.ptr;
+    mem::forget(guard);
+    ptr
+}
+
+/// Allocate space for `header` followed by `len` elements, and write
+/// `f(i)` into it for each index `i` in order.
+pub fn from_fn<H, T, F: FnMut(usize) -> T>(header: H, len: usize, mut f: F) -> NonNull<H> {
+    let mut guard = Guard::<H, T> {
+        ptr: allocate::<H, T>(header, len),
+        len,
+        init: 0,
+        _marker: PhantomData,
+    };
+    let elems = elems::<H, T>(guard.ptr);
+    while guard.init < len {
+        unsafe { ptr::write(elems.add(guard.init), f(guard.init)) }
+        guard.init += 1;
+    }
+    let ptr = guard.ptr;
+    mem::forget(guard);
+    ptr
+}
+
+/// Clone the elements of `src` into the uninitialized `dst`, counting
+/// them in `init` as they are written.
+///
+/// With the `unstable` feature, this is a single copy when `T: Copy`.
+#[cfg(not(feature = "unstable"))]
+unsafe fn clone_into<T: Clone>(src: &[T], dst: *mut T, init: &mut usize) {
+    for x in src {
+        ptr::write(dst.add(*init), x.clone());
+        *init += 1;
+    }
+}
+#[cfg(feature = "unstable")]
+use specialize::clone_into;
+
+/// Write clones of `value` into the uninitialized `dst` until `len`
+/// elements are initialized, counting them in `init`. The last one is
+/// `value` itself.
+///
+/// With the `unstable` feature, this is a single `memset` for bytes.
+#[cfg(not(feature = "unstable"))]
+unsafe fn fill<T: Clone>(value: T, dst: *mut T, len: usize, init: &mut usize) {
+    while *init + 1 < len {
+        ptr::write(dst.add(*init), value.clone());
+        *init += 1;
+    }
+    if *init < len {
+        ptr::write(dst.add(*init), value);
+        *init += 1;
+    }
+}
+#[cfg(feature = "unstable")]
+use specialize::fill;
+
+/// Drop the `len` elements of the allocation `ptr`, then poison them
+/// if enabled.
+///
+/// # Safety
+///
+/// The elements must be initialized, and not used again.
+pub unsafe fn drop_elems<H, T>(ptr: NonNull<H>, len: usize) {
+    let elems = elems::<H, T>(ptr);
+    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(elems, len));
+    checks::poison(elems as *mut u8, len * mem::size_of::<T>());
+}
+
+/// Move the `len` elements starting at index `lo` out of the
+/// allocation `ptr` of `total` elements into a new `Box`, dropping
+/// the others, then poison them all if enabled.
+///
+/// # Safety
+///
+/// The elements must be initialized, and not used again.
+pub unsafe fn take_elems<H, T>(ptr: NonNull<H>, total: usize, lo: usize, len: usize) -> Box<[T]> {
+    let elems = elems::<H, T>(ptr);
+    let mut v = Vec::with_capacity(len);
+    ptr::copy_nonoverlapping(elems.add(lo), v.as_mut_ptr(), len);
+    v.set_len(len);
+    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(elems, lo));
+    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(elems.add(lo + len), total - lo - len));
+    checks::poison(elems as *mut u8, total * mem::size_of::<T>());
+    v.into_boxed_slice()
+}
+
+/// Resize the allocation `ptr` from space for `old` elements to space
+/// for `new`, moving it if necessary. The header and the first
+/// `min(old, new)` elements are kept.
+///
+/// # Safety
+///
+/// `ptr` must have been allocated by `allocate::<H, T>(_, old)` (or
+/// resized to `old`), and not be used again; any elements beyond
+/// `new` must already have been dropped.
+pub unsafe fn resize<H, T>(ptr: NonNull<H>, old: usize, new: usize) -> NonNull<H> {
+    let new_layout = layout::<H, T>(new).0;
+    let old_layout = layout::<H, T>(old).0;
+    let ptr = realloc(ptr.as_ptr() as *mut u8, old_layout, new_layout.size());
+    match NonNull::new(ptr as *mut H) {
+        Some(ptr) => ptr,
+        None => handle_alloc_error(new_layout),
+    }
+}
+
+/// Free the allocation `ptr`, whose `len` elements have already been
+/// dropped.
+///
+/// # Safety
+///
+/// `ptr` must have been allocated by `allocate::<H, T>(_, len)`, and
+/// not be used again.
+pub unsafe fn free<H, T>(ptr: NonNull<H>, len: usize) {
+    ptr::drop_in_place(ptr.as_ptr());
+    checks::dealloc(ptr.as_ptr() as *mut u8, layout::<H, T>(len).0);
+}
@@ -0,0 +1,195 @@
+//! Deduplication of equal slices into a single shared allocation.
+//!
+//! This is re-exported from the `arc` module, and requires the `std`
+//! feature.
+
+use std::{cmp, fmt};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+
+use arc::{ArcSlice, WeakSlice};
+
+/// A set of interned slices, which returns the same `ArcSlice` for
+/// equal contents.
+///
+/// `intern` returns a handle to the existing allocation if an equal
+/// slice has been interned before and is still alive, and only
+/// copies the slice into a new one otherwise. This saves memory when
+/// the same values turn up again and again, such as the field values
+/// of log records or the names in a symbol table, and makes comparing
+/// interned slices as cheap as comparing pointers.
+///
+/// The interner only holds weak references to the slices, so it
+/// doesn't keep them alive: once every handle to a slice is dropped,
+/// its allocation is freed, and the interner forgets it the next time
+/// it purges its dead entries, which it does periodically as entries
+/// are added.
+///
+/// To share an interner between threads, put it in a `Mutex`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::Interner;
+///
+/// let mut interner = Interner::new();
+/// let a = interner.intern(b"GET");
+/// let b = interner.intern(b"GET");
+/// assert_eq!(a.as_ptr(), b.as_ptr());
+///
+/// drop((a, b));
+/// interner.purge();
+/// assert!(interner.is_empty());
+/// ```
+pub struct Interner<T, S = RandomState> {
+    // the slices, by the hash of their elements
+    buckets: HashMap<u64, Vec<WeakSlice<T>>>,
+    hasher: S,
+    len: usize,
+    // the number of entries at which to next purge the dead ones
+    purge_at: usize,
+}
+
+const MIN_PURGE: usize = 16;
+
+impl<T: Eq + Hash + Clone> Interner<T> {
+    /// Construct a new empty `Interner`.
+    pub fn new() -> Interner<T> {
+        Interner::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: Eq + Hash + Clone, S: BuildHasher> Interner<T, S> {
+    /// Construct a new empty `Interner`, which hashes slices with
+    /// `hasher`.
+    pub fn with_hasher(hasher: S) -> Interner<T, S> {
+        Interner {
+            buckets: HashMap::new(),
+            hasher,
+            len: 0,
+            purge_at: MIN_PURGE,
+        }
+    }
+
+    /// Get a handle to the interned slice equal to `slice`, copying
+    /// it into a new allocation if there is none.
+    pub fn intern(&mut self, slice: &[T]) -> ArcSlice<T> {
+        let hash = self.hasher.hash_one(slice);
+        let bucket = self.buckets.entry(hash).or_default();
+        let mut found = None;
+        let before = bucket.len();
+        bucket.retain(|weak| match weak.upgrade() {
+            Some(strong) => {
+                if found.is_none() && *strong == *slice {
+                    found = Some(strong);
+                }
+                true
+            }
+            None => false,
+        });
+        self.len -= before - bucket.len();
+        if let Some(strong) = found {
+            return strong
+        }
+
+        let strong = ArcSlice::from_slice(slice);
+        bucket.push(strong.downgrade());
+        self.len += 1;
+        if self.len >= self.purge_at {
+            self.purge();
+            self.purge_at = cmp::max(2 * self.len, MIN_PURGE);
+        }
+        strong
+    }
+
+    /// Get a handle to the interned slice equal to `slice`, or `None`
+    /// if there is none.
+    pub fn get(&self, slice: &[T]) -> Option<ArcSlice<T>> {
+        let bucket = self.buckets.get(&self.hasher.hash_one(slice))?;
+        bucket.iter()
+            .filter_map(|weak| weak.upgrade())
+            .find(|strong| **strong == *slice)
+    }
+}
+
+impl<T, S> Interner<T, S> {
+    /// The number of slices in `self`, including any that have been
+    /// dropped since it last purged them.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether `self` contains no slices.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Forget the slices in `self` that have been dropped.
+    pub fn purge(&mut self) {
+        let mut len = 0;
+        self.buckets.retain(|_, bucket| {
+            bucket.retain(|weak| weak.upgrade().is_some());
+            len += bucket.len();
+            !bucket.is_empty()
+        });
+        self.len = len;
+    }
+}
+
+impl<T: Eq + Hash + Clone, S: BuildHasher + Default> Default for Interner<T, S> {
+    fn default() -> Interner<T, S> {
+        Interner::with_hasher(S::default())
+    }
+}
+
+impl<T, S> fmt::Debug for Interner<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Interner").field("len", &self.len).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interner;
+    use std::hash::BuildHasherDefault;
+    use std::collections::hash_map::DefaultHasher;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+        let a = interner.intern(&[1, 2, 3]);
+        let b = interner.intern(&[1, 2, 3]);
+        let c = interner.intern(&[1, 2]);
+        assert_eq!(a.as_ptr(), b.as_ptr());
+        assert!(a.as_ptr() != c.as_ptr());
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get(&[1, 2]).unwrap().as_ptr(), c.as_ptr());
+        assert!(interner.get(&[3]).is_none());
+
+        drop((a, b));
+        assert!(interner.get(&[1, 2, 3]).is_none());
+        assert_eq!(interner.len(), 2);
+        interner.purge();
+        assert_eq!(interner.len(), 1);
+        let d = interner.intern(&[1, 2, 3]);
+        assert!(d.as_ptr() != c.as_ptr());
+        assert_eq!(format!("{:?}", interner), "Interner { len: 2 }");
+    }
+
+    #[test]
+    fn test_purge() {
+        let mut interner: Interner<u8, BuildHasherDefault<DefaultHasher>> = Interner::default();
+        let kept: Vec<_> = (0..10u8).map(|i| interner.intern(&[i])).collect();
+        for i in 0..100u8 {
+            interner.intern(&[i, i]);
+        }
+        // the dropped ones are purged as more are added
+        assert!(interner.len() < 60);
+        for (i, x) in kept.iter().enumerate() {
+            assert_eq!(interner.intern(&[i as u8]).as_ptr(), x.as_ptr());
+        }
+        interner.purge();
+        assert_eq!(interner.len(), 10);
+    }
+}
@@ -1,5 +1,4 @@
-#![feature(no_std)]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
 //! Thread-local and thread-safe shared slice types, like `&[T]` but
 //! without lifetimes.
@@ -7,6 +6,60 @@
 //! This library depends only on `alloc` and `core`, so can be used in
 //! environments without `std`.
 //!
+//! # Features
+//!
+//! - `rc`, `arc`: the thread-local `rc` module and the thread-safe
+//!   `arc` module (along with the modules built on it: `bits`, `csr`,
+//!   `line_index`, `matrix`, `segmented` and `string_table`). Both
+//!   are on by default; users who only need one can disable default
+//!   features and enable just that one. With neither, the crate
+//!   doesn't use `alloc`, and only provides the heap-free `pool`
+//!   module.
+//! - `std`: enables functionality that needs the standard library,
+//!   such as constructing slices by reading files, and the `ArcOsStr`,
+//!   `ArcPath`, `Interner`, `SlicePool` and `WriteCursor` types. This
+//!   is off by default, so the crate remains `no_std` unless it is
+//!   requested.
+//! - `rand`: adds random shuffling and sampling of slices, via the
+//!   `rand` crate.
+//! - `digest`: adds one-call content hashing of byte slices with any
+//!   hasher implementing `digest::Digest`.
+//! - `memchr`: accelerates searching byte slices with SIMD, via the
+//!   `memchr` crate.
+//! - `xxhash-rust`: adds fast 64-bit fingerprints of byte slices, via
+//!   XXH3.
+//! - `encoding_rs`: adds decoding of byte slices in legacy encodings,
+//!   such as Shift_JIS or windows-1252, into shared strings.
+//! - `unicode-segmentation`: adds splitting shared strings into
+//!   grapheme clusters and words that share the original text.
+//! - `regex`: adds regular expression searches over byte slices that
+//!   return matches as shared slices, via the `regex` crate.
+//! - `flate2`, `zstd`: with `std`, adds decompression of gzip and
+//!   zstd data directly into a new shared slice.
+//! - `mlock`: adds `LockedSlice`, a shared byte slice for secrets
+//!   that is kept out of swap and zeroed when freed.
+//! - `checked`: in debug builds, validates the internal invariants of
+//!   the slice types on every access, such as that each view lies
+//!   within its allocation, to catch misuse of unsafe code early.
+//! - `poison`: in debug builds, overwrites the elements of each
+//!   allocation with the byte `0xA5` after dropping them, so that
+//!   use-after-free bugs in unsafe code using the slices show up as
+//!   obvious garbage.
+//! - `quarantine`: delays freeing allocations, keeping the most
+//!   recently freed ones in a quarantine (see the `quarantine`
+//!   module), so that stale pointers into them are not silently
+//!   reused. This is intended for testing, with `poison` or a
+//!   sanitizer.
+//! - `unstable`: uses nightly-only specialization so that comparing
+//!   two views of exactly the same memory with `==` returns
+//!   immediately when the elements are `Eq`. (`Ord` comparisons do
+//!   this without the feature.)
+//! - `serde`: implements `Serialize` and `Deserialize` for the slice
+//!   types. With `std` too, the `sharing` module can serialize
+//!   repeated references to the same allocation only once.
+//! - `fuzzing`: exposes the operation interpreter used by the
+//!   `cargo fuzz` targets in the `fuzz` directory.
+//!
 //! # Examples
 //!
 //! Alice has a long list of numbers which she needs to sum up before
@@ -18,13 +71,15 @@
 //! chunks and distribute them across some threads.
 //!
 //! ```rust
-//! #![feature(std_misc)]
 //! extern crate shared_slice;
 //! extern crate rand;
 //!
+//! # #[cfg(feature = "arc")]
 //! use shared_slice::arc::ArcSlice;
-//! use std::{cmp, sync};
+//! use std::{cmp, thread};
 //!
+//! # #[cfg(not(feature = "arc"))] fn main() {}
+//! # #[cfg(feature = "arc")]
 //! # fn main() {
 //!
 //! // Alice's numbers (the Mad Hatter doesn't care which numbers,
@@ -41,7 +96,7 @@
 //! // number of elements per thread (rounded up)
 //! let per_thread = (numbers.len() + NTHREADS - 1) / NTHREADS;
 //!
-//! let mut futures = (0..NTHREADS).map(|i| {
+//! let threads = (0..NTHREADS).map(|i| {
 //!     // compute the bounds
 //!     let lo = i * per_thread;
 //!     let hi = cmp::min(numbers.len(), lo + per_thread);
@@ -54,13 +109,13 @@
 //!     let my_numbers: ArcSlice<_> = numbers.clone().slice(lo, hi);
 //!
 //!     // do this part of the sum:
-//!     sync::Future::spawn(move || {
+//!     thread::spawn(move || {
 //!         my_numbers.iter().fold(0, |a, &b| a + b)
 //!     })
-//! }).collect::<Vec<sync::Future<u64>>>();
+//! }).collect::<Vec<thread::JoinHandle<u64>>>();
 //!
 //! // sum up the results from each subsum.
-//! let sum = futures.iter_mut().fold(0, |a, b| a + b.get());
+//! let sum = threads.into_iter().fold(0, |a, t| a + t.join().unwrap());
 //!
 //! println!("the sum is {}", sum);
 //!
@@ -73,10 +128,101 @@
 //! is likely that one will be able to use conventional borrowed
 //! `&[T]` slices directly.)
 
-#![feature(core, alloc)]
+#![cfg_attr(feature = "unstable", feature(specialization))]
+#![cfg_attr(feature = "unstable", allow(incomplete_features))]
 
+#[cfg(any(feature = "rc", feature = "arc"))]
+#[cfg_attr(feature = "arc", macro_use)]
 extern crate alloc;
+// `no_std` already links `core` at the crate root
+#[cfg(any(test, feature = "std"))]
 extern crate core;
+#[cfg(feature = "rand")]
+extern crate rand;
+#[cfg(feature = "digest")]
+extern crate digest;
+#[cfg(all(test, feature = "digest"))]
+extern crate sha2;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "xxhash-rust")]
+extern crate xxhash_rust;
+#[cfg(feature = "memchr")]
+extern crate memchr;
+#[cfg(feature = "encoding_rs")]
+extern crate encoding_rs;
+#[cfg(feature = "unicode-segmentation")]
+extern crate unicode_segmentation;
+#[cfg(all(feature = "mlock", unix))]
+extern crate libc;
+#[cfg(all(feature = "mlock", windows))]
+extern crate windows_sys;
+#[cfg(feature = "flate2")]
+extern crate flate2;
+#[cfg(feature = "zstd")]
+extern crate zstd;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde", feature = "rc", feature = "arc"))]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "fuzzing")]
+extern crate arbitrary;
+
+#[cfg(any(feature = "rc", feature = "arc"))]
+pub use range::SliceError;
 
+#[cfg(feature = "rc")]
 pub mod rc;
+#[cfg(feature = "arc")]
 pub mod arc;
+#[cfg(feature = "arc")]
+pub mod bits;
+#[cfg(feature = "arc")]
+pub mod csr;
+#[cfg(feature = "arc")]
+pub mod line_index;
+#[cfg(feature = "arc")]
+pub mod matrix;
+#[cfg(feature = "arc")]
+pub mod segmented;
+#[cfg(feature = "arc")]
+pub mod string_table;
+pub mod pool;
+
+#[cfg(feature = "arc")]
+mod cell;
+#[cfg(any(feature = "rc", feature = "arc"))]
+mod cdc;
+#[cfg(any(feature = "rc", feature = "arc"))]
+mod checks;
+mod identity;
+#[cfg(any(feature = "rc", feature = "arc"))]
+mod inner;
+#[cfg(any(feature = "rc", feature = "arc"))]
+mod range;
+#[cfg(any(feature = "rc", feature = "arc"))]
+mod search;
+#[cfg(feature = "arc")]
+mod small;
+#[cfg(feature = "unstable")]
+mod specialize;
+#[cfg(all(feature = "serde", feature = "std", any(feature = "rc", feature = "arc")))]
+pub mod sharing;
+#[cfg(all(feature = "mlock", any(unix, windows)))]
+pub mod locked;
+#[cfg(all(feature = "arc", feature = "std"))]
+mod interner;
+#[cfg(all(feature = "arc", feature = "std"))]
+mod path;
+#[cfg(all(feature = "arc", feature = "std"))]
+mod recycle;
+#[cfg(all(feature = "quarantine", any(feature = "rc", feature = "arc")))]
+pub mod quarantine;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzz;
+#[cfg(all(kani, any(feature = "rc", feature = "arc")))]
+mod verify;
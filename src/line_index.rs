@@ -0,0 +1,220 @@
+//! Thread-safe shared text with a precomputed index of its lines.
+
+use core::{fmt, ops};
+
+use arc::{ArcSlice, ArcStr};
+use range;
+
+/// A `ArcStr` along with the offset of the start of each of its
+/// lines, for quickly converting between byte offsets and line
+/// numbers, such as for an editor or for reporting diagnostics.
+///
+/// Lines end with `\n` or `\r\n`, which is not included in the text
+/// of a line. Unlike `ArcStr::lines_owned`, a final line ending starts
+/// an empty last line, so that every offset up to and including the
+/// length of the text lies in some line, and there is always at least
+/// one line. Lines and columns count from zero, and columns are byte
+/// offsets within a line.
+///
+/// Cloning a `LineIndexedStr` shares both the text and the index.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcStr;
+/// use shared_slice::line_index::LineIndexedStr;
+///
+/// let source = LineIndexedStr::new(ArcStr::from("fn main() {\r\n    foo();\n}\n"));
+/// assert_eq!(source.line_count(), 4);
+/// assert_eq!(source.line(1), "    foo();");
+///
+/// let offset = source.text().find("foo").unwrap();
+/// assert_eq!(source.offset_to_line_col(offset), (1, 4));
+/// assert_eq!(source.line_range_owned(1..3), "    foo();\n}");
+/// ```
+#[derive(Clone)]
+pub struct LineIndexedStr {
+    text: ArcStr,
+    // the offset of the start of each line, which is 0 for the first,
+    // and one past a `\n` for each other
+    starts: ArcSlice<usize>,
+}
+
+impl LineIndexedStr {
+    /// Construct a new `LineIndexedStr` by finding each line in
+    /// `text`.
+    pub fn new(text: ArcStr) -> LineIndexedStr {
+        let mut starts = vec![0];
+        starts.extend(text.bytes().enumerate().filter(|&(_, b)| b == b'\n').map(|(i, _)| i + 1));
+        LineIndexedStr { text, starts: ArcSlice::from(starts) }
+    }
+
+    /// The whole text of `self`.
+    pub fn text(&self) -> &ArcStr {
+        &self.text
+    }
+
+    /// The number of lines in `self`, which is at least one.
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The offset of the start of line `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n >= self.line_count()`.
+    pub fn line_start(&self, n: usize) -> usize {
+        self.check_line(n, "line_start");
+        self.starts[n]
+    }
+
+    /// Construct a new `ArcStr` pointing to the text of line `n`,
+    /// without its line ending.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n >= self.line_count()`.
+    pub fn line(&self, n: usize) -> ArcStr {
+        self.check_line(n, "line");
+        self.text.clone().slice(self.starts[n]..self.line_end(n))
+    }
+
+    /// Construct a new `ArcStr` pointing to the text of the lines in
+    /// `lines`, such as `2..5` or `..=3`, including the line endings
+    /// between them, but not the one after the last.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lines` is decreasing or out of bounds, like
+    /// indexing a slice.
+    pub fn line_range_owned<R: ops::RangeBounds<usize>>(&self, lines: R) -> ArcStr {
+        let (lo, hi) = match range::check(&lines, self.line_count()) {
+            Ok(bounds) => bounds,
+            Err(e) => panic!("LineIndexedStr::line_range_owned: lines {}", e),
+        };
+        let (start, end) = if lo == hi {
+            let at = self.starts.get(lo).map_or(self.text.len(), |&s| s);
+            (at, at)
+        } else {
+            (self.starts[lo], self.line_end(hi - 1))
+        };
+        self.text.clone().slice(start..end)
+    }
+
+    /// The line containing byte `offset` of the text, and the column
+    /// of `offset` within that line.
+    ///
+    /// An offset in a line ending is in the line that it ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset > self.text().len()`.
+    pub fn offset_to_line_col(&self, offset: usize) -> (usize, usize) {
+        assert!(offset <= self.text.len(),
+                "LineIndexedStr::offset_to_line_col: {} out of bounds for text of length {}",
+                offset, self.text.len());
+        // the last line starting at or before `offset`; there is one,
+        // since the first starts at 0
+        let line = match self.starts.binary_search(&offset) {
+            Ok(n) => n,
+            Err(n) => n - 1,
+        };
+        (line, offset - self.starts[line])
+    }
+
+    fn check_line(&self, n: usize, method: &str) {
+        assert!(n < self.line_count(), "LineIndexedStr::{}: {} out of bounds for {} lines",
+                method, n, self.line_count());
+    }
+
+    /// The end of line `n`, before its line ending, if any.
+    fn line_end(&self, n: usize) -> usize {
+        match self.starts.get(n + 1) {
+            Some(&next) if self.text[..next - 1].ends_with('\r') => next - 2,
+            Some(&next) => next - 1,
+            None => self.text.len(),
+        }
+    }
+}
+
+impl From<ArcStr> for LineIndexedStr {
+    fn from(text: ArcStr) -> LineIndexedStr {
+        LineIndexedStr::new(text)
+    }
+}
+
+impl Default for LineIndexedStr {
+    fn default() -> LineIndexedStr {
+        LineIndexedStr::new(ArcStr::default())
+    }
+}
+
+impl fmt::Debug for LineIndexedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LineIndexedStr")
+            .field("text", &self.text)
+            .field("lines", &self.line_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arc::ArcStr;
+    use super::LineIndexedStr;
+
+    #[test]
+    fn test_lines() {
+        let text = ArcStr::from("one\r\ntwo\n\nthree\r");
+        let index = LineIndexedStr::new(text.clone());
+        assert_eq!(index.line_count(), 4);
+        let lines: Vec<_> = (0..4).map(|n| index.line(n)).collect();
+        assert_eq!(lines, ["one", "two", "", "three\r"]);
+        assert_eq!(lines[1].as_ptr(), text[5..].as_ptr());
+        assert_eq!(index.line_start(3), 10);
+
+        let index = LineIndexedStr::new(ArcStr::from("a\n"));
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(index.line(1), "");
+        let index = LineIndexedStr::default();
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.line(0), "");
+        assert_eq!(index.offset_to_line_col(0), (0, 0));
+    }
+
+    #[test]
+    fn test_line_range_owned() {
+        let index = LineIndexedStr::from(ArcStr::from("a\r\nbc\nd\n"));
+        assert_eq!(index.line_range_owned(..), "a\r\nbc\nd\n");
+        assert_eq!(index.line_range_owned(..3), "a\r\nbc\nd");
+        assert_eq!(index.line_range_owned(..=1), "a\r\nbc");
+        assert_eq!(index.line_range_owned(1..1), "");
+        assert_eq!(index.line_range_owned(4..), "");
+    }
+
+    #[test]
+    fn test_offset_to_line_col() {
+        let index = LineIndexedStr::new(ArcStr::from("ab\r\n\ncd"));
+        let positions: Vec<_> = (0..=7).map(|i| index.offset_to_line_col(i)).collect();
+        assert_eq!(positions, [(0, 0), (0, 1), (0, 2), (0, 3), (1, 0), (2, 0), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_line_out_of_bounds() {
+        LineIndexedStr::new(ArcStr::from("a\nb")).line(2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_line_range_out_of_bounds() {
+        LineIndexedStr::new(ArcStr::from("a\nb")).line_range_owned(1..3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_offset_out_of_bounds() {
+        LineIndexedStr::new(ArcStr::from("a\nb")).offset_to_line_col(4);
+    }
+}
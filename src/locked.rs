@@ -0,0 +1,213 @@
+//! Thread-safe shared byte slices in locked memory, for secrets.
+//!
+//! This requires the `mlock` feature.
+
+use std::{fmt, io, ops, ptr};
+use std::alloc::{self, Layout};
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::sync::atomic::{self, Ordering};
+
+/// A reference-counted byte slice stored in memory that is locked
+/// into RAM, so it is never written to swap.
+///
+/// This behaves like `ArcSlice<u8>`, but is intended for credentials
+/// and keys: the allocation is locked with `mlock` (or `VirtualLock`
+/// on Windows) for its whole lifetime, and is overwritten with zeros
+/// and unlocked when the last `LockedSlice` pointing into it is
+/// dropped. Each allocation occupies whole pages, so unlocking one
+/// never unlocks another.
+///
+/// To avoid leaking the contents, `LockedSlice`'s `Debug` output does
+/// not include them, and it does not implement comparisons, which
+/// would not run in constant time.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::locked::LockedSlice;
+///
+/// let key = LockedSlice::new(b"hunter2").unwrap();
+/// let shared = key.clone().slice_from(1);
+/// assert_eq!(&*shared, b"unter2");
+/// assert_eq!(format!("{:?}", key), "LockedSlice([REDACTED; 7])");
+/// ```
+pub struct LockedSlice {
+    data: *const [u8],
+    buf: Arc<LockedBuf>,
+}
+
+unsafe impl Send for LockedSlice {}
+unsafe impl Sync for LockedSlice {}
+
+/// A locked allocation of whole pages.
+struct LockedBuf {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+unsafe impl Send for LockedBuf {}
+unsafe impl Sync for LockedBuf {}
+
+#[cfg(unix)]
+mod sys {
+    use libc;
+
+    pub fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+    pub unsafe fn lock(ptr: *mut u8, len: usize) -> bool {
+        libc::mlock(ptr as *const libc::c_void, len) == 0
+    }
+    pub unsafe fn unlock(ptr: *mut u8, len: usize) {
+        libc::munlock(ptr as *const libc::c_void, len);
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use std::mem::MaybeUninit;
+
+    use windows_sys::Win32::System::Memory::{VirtualLock, VirtualUnlock};
+    use windows_sys::Win32::System::SystemInformation::GetSystemInfo;
+
+    pub fn page_size() -> usize {
+        let mut info = MaybeUninit::uninit();
+        unsafe {
+            GetSystemInfo(info.as_mut_ptr());
+            info.assume_init().dwPageSize as usize
+        }
+    }
+    pub unsafe fn lock(ptr: *mut u8, len: usize) -> bool {
+        VirtualLock(ptr as *const c_void, len) != 0
+    }
+    pub unsafe fn unlock(ptr: *mut u8, len: usize) {
+        VirtualUnlock(ptr as *const c_void, len);
+    }
+}
+
+impl LockedBuf {
+    /// Allocate and lock whole pages with space for at least `len`
+    /// bytes.
+    fn new(len: usize) -> io::Result<LockedBuf> {
+        let page = sys::page_size();
+        let size = len.max(1).div_ceil(page) * page;
+        let layout = Layout::from_size_align(size, page)
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "locked allocation too large"))?;
+        let ptr = match NonNull::new(unsafe { alloc::alloc_zeroed(layout) }) {
+            Some(ptr) => ptr,
+            None => alloc::handle_alloc_error(layout),
+        };
+        if unsafe { sys::lock(ptr.as_ptr(), size) } {
+            Ok(LockedBuf { ptr, layout })
+        } else {
+            let err = io::Error::last_os_error();
+            unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+            Err(err)
+        }
+    }
+}
+
+impl Drop for LockedBuf {
+    fn drop(&mut self) {
+        let (ptr, size) = (self.ptr.as_ptr(), self.layout.size());
+        unsafe {
+            // volatile, so the zeroing isn't optimised out
+            for i in 0..size {
+                ptr::write_volatile(ptr.add(i), 0);
+            }
+            atomic::compiler_fence(Ordering::SeqCst);
+            sys::unlock(ptr, size);
+            alloc::dealloc(ptr, self.layout);
+        }
+    }
+}
+
+impl LockedSlice {
+    /// Construct a new `LockedSlice` containing a copy of `bytes`.
+    ///
+    /// Callers should clear `bytes` themselves afterwards, if it held
+    /// a secret.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the memory cannot be locked, such as when the limit on
+    /// locked memory for the process has been reached.
+    pub fn new(bytes: &[u8]) -> io::Result<LockedSlice> {
+        let buf = LockedBuf::new(bytes.len())?;
+        let data = unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buf.ptr.as_ptr(), bytes.len());
+            ptr::slice_from_raw_parts(buf.ptr.as_ptr(), bytes.len())
+        };
+        Ok(LockedSlice { data, buf: Arc::new(buf) })
+    }
+
+    /// Construct a new `LockedSlice` that only points to bytes at
+    /// indices `lo` (inclusive) through `hi` (exclusive).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if either are strictly greater than
+    /// `self.len()`.
+    pub fn slice(mut self, lo: usize, hi: usize) -> LockedSlice {
+        self.data = &self[lo..hi];
+        self
+    }
+
+    /// Construct a new `LockedSlice` that only points to bytes at
+    /// indices `lo` (inclusive) through `self.len()`.
+    pub fn slice_from(self, lo: usize) -> LockedSlice {
+        let hi = self.len();
+        self.slice(lo, hi)
+    }
+
+    /// Construct a new `LockedSlice` that only points to bytes at
+    /// indices 0 (inclusive) through `hi` (exclusive).
+    pub fn slice_to(self, hi: usize) -> LockedSlice {
+        self.slice(0, hi)
+    }
+}
+
+impl Clone for LockedSlice {
+    fn clone(&self) -> LockedSlice {
+        LockedSlice { data: self.data, buf: self.buf.clone() }
+    }
+}
+
+impl ops::Deref for LockedSlice {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        unsafe { &*self.data }
+    }
+}
+
+impl AsRef<[u8]> for LockedSlice {
+    fn as_ref(&self) -> &[u8] { self }
+}
+
+impl fmt::Debug for LockedSlice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LockedSlice([REDACTED; {}])", self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LockedSlice;
+    use std::thread;
+
+    #[test]
+    fn test_locked() {
+        let x = LockedSlice::new(b"correct horse battery staple").unwrap();
+        let y = x.clone().slice(8, 13);
+        drop(x);
+        assert_eq!(&*y, b"horse");
+
+        let z = thread::spawn(move || y.slice_to(2)).join().unwrap();
+        assert_eq!(&*z, b"ho");
+        assert_eq!(format!("{:?}", z), "LockedSlice([REDACTED; 2])");
+
+        assert!(LockedSlice::new(b"").unwrap().is_empty());
+    }
+}
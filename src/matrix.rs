@@ -0,0 +1,585 @@
+//! Two-dimensional and strided views of thread-safe shared slices.
+
+use core::{fmt, ops};
+use core::iter::FusedIterator;
+
+use arc::ArcSlice;
+use range;
+
+/// A reference-counted two-dimensional array, such as an image or a
+/// matrix, stored in row-major order in an `ArcSlice<T>`.
+///
+/// Row `i` starts `i * stride` elements after the start of row 0, so
+/// a sub-matrix can point into the same allocation as the matrix it
+/// came from, with the same stride. Rows and sub-matrices can be
+/// extracted without copying, so tiles of an image or blocks of a
+/// matrix can be handed to other threads cheaply.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcSlice;
+/// use shared_slice::matrix::ArcSlice2D;
+///
+/// let m = ArcSlice2D::new(ArcSlice::from_fn(12, |i| i), 3, 4);
+/// // 0  1  2  3
+/// // 4  5  6  7
+/// // 8  9 10 11
+/// assert_eq!(m[(1, 2)], 6);
+///
+/// let block = m.submatrix(1..3, 1..3);
+/// drop(m);
+/// assert_eq!(&*block.row(0), [5, 6]);
+/// assert_eq!(&*block.row(1), [9, 10]);
+/// ```
+pub struct ArcSlice2D<T> {
+    // starts at the first element of the first row, and contains the
+    // whole of the last one
+    data: ArcSlice<T>,
+    rows: usize,
+    cols: usize,
+    stride: usize,
+}
+
+impl<T> ArcSlice2D<T> {
+    /// Construct a new `ArcSlice2D` with `rows` rows of `cols`
+    /// elements each, stored contiguously in `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` doesn't have `rows * cols` elements.
+    pub fn new(data: ArcSlice<T>, rows: usize, cols: usize) -> ArcSlice2D<T> {
+        assert!(rows.checked_mul(cols) == Some(data.len()),
+                "ArcSlice2D::new: {} elements can't be {} rows of {}", data.len(), rows, cols);
+        ArcSlice2D::with_stride(data, rows, cols, cols)
+    }
+
+    /// Construct a new `ArcSlice2D` with `rows` rows of `cols`
+    /// elements each, where each row starts `stride` elements after
+    /// the previous one in `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride < cols` (when there is more than one row),
+    /// or if `data` is too short to contain the last row.
+    pub fn with_stride(data: ArcSlice<T>, rows: usize, cols: usize, stride: usize)
+                       -> ArcSlice2D<T> {
+        assert!(rows <= 1 || stride >= cols,
+                "ArcSlice2D::with_stride: stride {} is less than {} columns", stride, cols);
+        let needed = if rows == 0 || cols == 0 {
+            0
+        } else {
+            (rows - 1).checked_mul(stride).and_then(|n| n.checked_add(cols))
+                .expect("ArcSlice2D::with_stride: size overflows usize")
+        };
+        assert!(needed <= data.len(),
+                "ArcSlice2D::with_stride: {} elements needed, but only {} given",
+                needed, data.len());
+        ArcSlice2D {
+            data: data.slice_to(needed),
+            rows,
+            cols,
+            stride,
+        }
+    }
+
+    /// The number of rows in `self`.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns in `self`.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The distance between the starts of consecutive rows of `self`
+    /// in the underlying slice.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Whether `self` contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0 || self.cols == 0
+    }
+
+    /// Get the element in row `i` and column `j`, or `None` if either
+    /// is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> Option<&T> {
+        if i < self.rows && j < self.cols {
+            Some(&self.data[i * self.stride + j])
+        } else {
+            None
+        }
+    }
+
+    /// Construct a new `ArcSlice` pointing to row `i` of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.rows()`.
+    pub fn row(&self, i: usize) -> ArcSlice<T> {
+        assert!(i < self.rows, "ArcSlice2D::row: {} out of bounds for {} rows", i, self.rows);
+        if self.cols == 0 {
+            // `data` is empty, even if `stride` isn't
+            return self.data.clone()
+        }
+        let lo = i * self.stride;
+        self.data.clone().slice(lo, lo + self.cols)
+    }
+
+    /// Construct a new `ArcSlice2D` pointing to the elements of
+    /// `self` in `rows` and `cols`, such as `1..3` or `..`, in the
+    /// same allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is decreasing or out of bounds, like
+    /// indexing a slice.
+    pub fn submatrix<R, C>(&self, rows: R, cols: C) -> ArcSlice2D<T>
+        where R: ops::RangeBounds<usize>, C: ops::RangeBounds<usize>
+    {
+        let (r0, r1) = match range::check(&rows, self.rows) {
+            Ok(bounds) => bounds,
+            Err(e) => panic!("ArcSlice2D::submatrix: rows {}", e),
+        };
+        let (c0, c1) = match range::check(&cols, self.cols) {
+            Ok(bounds) => bounds,
+            Err(e) => panic!("ArcSlice2D::submatrix: columns {}", e),
+        };
+        let (rows, cols) = (r1 - r0, c1 - c0);
+        let data = if rows == 0 || cols == 0 {
+            self.data.clone().slice_to(0)
+        } else {
+            self.data.clone().slice_from(r0 * self.stride + c0)
+        };
+        ArcSlice2D::with_stride(data, rows, cols, self.stride)
+    }
+
+    /// Construct a new `StridedArcSlice` pointing to column `j` of
+    /// `self`, without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.cols()`.
+    pub fn column(&self, j: usize) -> StridedArcSlice<T> {
+        assert!(j < self.cols, "ArcSlice2D::column: {} out of bounds for {} columns", j, self.cols);
+        let data = if self.rows == 0 { self.data.clone() } else { self.data.clone().slice_from(j) };
+        StridedArcSlice::with_len(data, self.rows, self.stride)
+    }
+
+    /// Iterate over the rows of `self`, as `ArcSlice`s.
+    pub fn iter_rows(&self) -> Rows<T> {
+        Rows { matrix: self.clone(), front: 0, back: self.rows }
+    }
+}
+
+impl<T> Clone for ArcSlice2D<T> {
+    fn clone(&self) -> ArcSlice2D<T> {
+        ArcSlice2D {
+            data: self.data.clone(),
+            rows: self.rows,
+            cols: self.cols,
+            stride: self.stride,
+        }
+    }
+}
+
+impl<T> Default for ArcSlice2D<T> {
+    fn default() -> ArcSlice2D<T> {
+        ArcSlice2D::new(ArcSlice::default(), 0, 0)
+    }
+}
+
+impl<T> ops::Index<(usize, usize)> for ArcSlice2D<T> {
+    type Output = T;
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        match self.get(i, j) {
+            Some(x) => x,
+            None => panic!("ArcSlice2D: index ({}, {}) out of bounds for {}x{}",
+                           i, j, self.rows, self.cols),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArcSlice2D<T> {
+    fn eq(&self, other: &ArcSlice2D<T>) -> bool {
+        self.rows == other.rows && self.cols == other.cols
+            && self.iter_rows().zip(other.iter_rows()).all(|(a, b)| a == b)
+    }
+}
+impl<T: Eq> Eq for ArcSlice2D<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for ArcSlice2D<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter_rows()).finish()
+    }
+}
+
+/// An iterator over the rows of an `ArcSlice2D`, as `ArcSlice`s.
+///
+/// This is created by `ArcSlice2D::iter_rows`.
+pub struct Rows<T> {
+    matrix: ArcSlice2D<T>,
+    front: usize,
+    back: usize,
+}
+
+impl<T> Iterator for Rows<T> {
+    type Item = ArcSlice<T>;
+    fn next(&mut self) -> Option<ArcSlice<T>> {
+        if self.front == self.back {
+            return None
+        }
+        self.front += 1;
+        Some(self.matrix.row(self.front - 1))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for Rows<T> {
+    fn next_back(&mut self) -> Option<ArcSlice<T>> {
+        if self.front == self.back {
+            return None
+        }
+        self.back -= 1;
+        Some(self.matrix.row(self.back))
+    }
+}
+
+impl<T> ExactSizeIterator for Rows<T> {}
+impl<T> FusedIterator for Rows<T> {}
+
+/// A reference-counted view of every `step`th element of a slice,
+/// such as a column of a row-major matrix.
+///
+/// This points into the same allocation as the `ArcSlice` it was made
+/// from, and can be indexed, iterated over and sliced further without
+/// copying.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcSlice;
+///
+/// // 3 rows of 2 columns
+/// let data = ArcSlice::from([1, 10, 2, 20, 3, 30]);
+/// let second = data.slice_from(1).step_by_owned(2);
+/// assert_eq!(second.len(), 3);
+/// assert_eq!(second[2], 30);
+/// assert_eq!(second.iter().sum::<i32>(), 60);
+/// assert_eq!(&*second.slice(1..).to_contiguous(), [20, 30]);
+/// ```
+pub struct StridedArcSlice<T> {
+    // starts at the first element, and ends at the last one
+    data: ArcSlice<T>,
+    len: usize,
+    step: usize,
+}
+
+impl<T> StridedArcSlice<T> {
+    /// Construct a new `StridedArcSlice` pointing to every `step`th
+    /// element of `data`, starting with the first. This is also
+    /// `ArcSlice::step_by_owned`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is 0.
+    pub fn new(data: ArcSlice<T>, step: usize) -> StridedArcSlice<T> {
+        assert!(step != 0, "StridedArcSlice::new: step must be non-zero");
+        let len = data.len().div_ceil(step);
+        StridedArcSlice::with_len(data, len, step)
+    }
+
+    /// Construct a new `StridedArcSlice` pointing to the `len`
+    /// elements of `data` at multiples of `step`, which must all be
+    /// in bounds.
+    fn with_len(data: ArcSlice<T>, len: usize, step: usize) -> StridedArcSlice<T> {
+        let end = if len == 0 { 0 } else { (len - 1) * step + 1 };
+        StridedArcSlice { data: data.slice_to(end), len, step }
+    }
+
+    /// The number of elements in `self`.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether `self` contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The distance between consecutive elements of `self` in the
+    /// underlying slice.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Get the element at index `i`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i < self.len {
+            Some(&self.data[i * self.step])
+        } else {
+            None
+        }
+    }
+
+    /// Construct a new `StridedArcSlice` that only points to the
+    /// elements of `self` in `range`, such as `2..` or `..=5`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, or if either
+    /// are out of bounds, like indexing a slice.
+    pub fn slice<R: ops::RangeBounds<usize>>(&self, range: R) -> StridedArcSlice<T> {
+        let (lo, hi) = match range::check(&range, self.len) {
+            Ok(bounds) => bounds,
+            Err(e) => panic!("StridedArcSlice::slice: {}", e),
+        };
+        let data = if lo == hi {
+            self.data.clone().slice_to(0)
+        } else {
+            self.data.clone().slice_from(lo * self.step)
+        };
+        StridedArcSlice::with_len(data, hi - lo, self.step)
+    }
+
+    /// Construct a new `StridedArcSlice` pointing to every `step`th
+    /// element of `self`, starting with the first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is 0.
+    pub fn step_by(self, step: usize) -> StridedArcSlice<T> {
+        assert!(step != 0, "StridedArcSlice::step_by: step must be non-zero");
+        let len = self.len.div_ceil(step);
+        // this can only overflow when `len <= 1`, and then the step
+        // isn't used
+        StridedArcSlice::with_len(self.data, len, self.step.saturating_mul(step))
+    }
+
+    /// Iterate over the elements of `self`.
+    pub fn iter(&self) -> StridedIter<'_, T> {
+        StridedIter { slice: self, front: 0, back: self.len }
+    }
+}
+
+impl<T: Clone> StridedArcSlice<T> {
+    /// Construct a new `ArcSlice` containing clones of the elements of
+    /// `self`.
+    ///
+    /// This only copies the elements if they aren't already
+    /// contiguous.
+    pub fn to_contiguous(&self) -> ArcSlice<T> {
+        if self.step == 1 || self.len <= 1 {
+            self.data.clone()
+        } else {
+            let data = &self.data;
+            let step = self.step;
+            ArcSlice::from_fn(self.len, |i| data[i * step].clone())
+        }
+    }
+}
+
+impl<T> Clone for StridedArcSlice<T> {
+    fn clone(&self) -> StridedArcSlice<T> {
+        StridedArcSlice {
+            data: self.data.clone(),
+            len: self.len,
+            step: self.step,
+        }
+    }
+}
+
+impl<T> Default for StridedArcSlice<T> {
+    fn default() -> StridedArcSlice<T> {
+        StridedArcSlice::new(ArcSlice::default(), 1)
+    }
+}
+
+impl<T> ops::Index<usize> for StridedArcSlice<T> {
+    type Output = T;
+    fn index(&self, i: usize) -> &T {
+        match self.get(i) {
+            Some(x) => x,
+            None => panic!("StridedArcSlice: index {} out of bounds for length {}", i, self.len),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a StridedArcSlice<T> {
+    type Item = &'a T;
+    type IntoIter = StridedIter<'a, T>;
+    fn into_iter(self) -> StridedIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for StridedArcSlice<T> {
+    fn eq(&self, other: &StridedArcSlice<T>) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+impl<T: Eq> Eq for StridedArcSlice<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for StridedArcSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the elements of a `StridedArcSlice`.
+///
+/// This is created by `StridedArcSlice::iter`.
+pub struct StridedIter<'a, T: 'a> {
+    slice: &'a StridedArcSlice<T>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T> Iterator for StridedIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None
+        }
+        self.front += 1;
+        self.slice.get(self.front - 1)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n, Some(n))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for StridedIter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None
+        }
+        self.back -= 1;
+        self.slice.get(self.back)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for StridedIter<'a, T> {}
+impl<'a, T> FusedIterator for StridedIter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcSlice2D, StridedArcSlice};
+    use arc::ArcSlice;
+
+    fn example() -> ArcSlice2D<usize> {
+        ArcSlice2D::new(ArcSlice::from_fn(20, |i| i), 4, 5)
+    }
+
+    #[test]
+    fn test_index() {
+        let m = example();
+        assert_eq!((m.rows(), m.cols(), m.stride()), (4, 5, 5));
+        assert_eq!(m[(2, 3)], 13);
+        assert_eq!(m.get(4, 0), None);
+        assert_eq!(m.get(0, 5), None);
+        assert_eq!(&*m.row(3), [15, 16, 17, 18, 19]);
+        assert_eq!(m.row(1).offset(), 5);
+        assert_eq!(m.iter_rows().rev().map(|r| r[0]).collect::<Vec<_>>(), [15, 10, 5, 0]);
+        assert_eq!(m.iter_rows().len(), 4);
+    }
+
+    #[test]
+    fn test_submatrix() {
+        let m = example();
+        let s = m.submatrix(1..3, 2..);
+        assert_eq!((s.rows(), s.cols(), s.stride()), (2, 3, 5));
+        assert_eq!(format!("{:?}", s), "[[7, 8, 9], [12, 13, 14]]");
+        assert_eq!(s.row(1).as_ptr(), m.row(2)[2..].as_ptr());
+
+        let t = s.submatrix(1.., ..1);
+        assert_eq!(format!("{:?}", t), "[[12]]");
+        assert!(m.submatrix(4.., ..).is_empty());
+        assert!(m.submatrix(.., 5..).is_empty());
+        assert!(m.submatrix(.., ..) == m);
+        assert!(s != t);
+        assert!(ArcSlice2D::<u8>::default().is_empty());
+    }
+
+    #[test]
+    fn test_no_columns() {
+        let m = example().submatrix(.., 5..);
+        assert_eq!((m.rows(), m.cols(), m.stride()), (4, 0, 5));
+        assert!(m.row(3).is_empty());
+        assert_eq!(m.iter_rows().count(), 4);
+        assert_eq!(format!("{:?}", m), "[[], [], [], []]");
+        assert!(m == ArcSlice2D::new(ArcSlice::default(), 4, 0));
+    }
+
+    #[test]
+    fn test_with_stride() {
+        // two 2x2 images side by side, in 2 rows of 5 with padding
+        let data = ArcSlice::from([1, 2, 3, 4, 0, 5, 6, 7, 8, 0]);
+        let right = ArcSlice2D::with_stride(data.clone().slice_from(2), 2, 2, 5);
+        assert_eq!(format!("{:?}", right), "[[3, 4], [7, 8]]");
+        assert_eq!(right, ArcSlice2D::new(ArcSlice::from([3, 4, 7, 8]), 2, 2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_submatrix_out_of_bounds() {
+        example().submatrix(..5, ..);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_stride_too_short() {
+        ArcSlice2D::with_stride(ArcSlice::from([1, 2, 3]), 2, 2, 2);
+    }
+
+    #[test]
+    fn test_strided() {
+        let x = ArcSlice::from_fn(10, |i| i).step_by_owned(3);
+        assert_eq!((x.len(), x.step()), (4, 3));
+        assert_eq!(x.iter().cloned().collect::<Vec<_>>(), [0, 3, 6, 9]);
+        assert_eq!(x.iter().rev().cloned().collect::<Vec<_>>(), [9, 6, 3, 0]);
+        assert_eq!(x.get(4), None);
+        assert_eq!(format!("{:?}", x.slice(1..3)), "[3, 6]");
+        assert!(x.slice(4..).is_empty());
+        assert_eq!(x.clone().step_by(2), StridedArcSlice::new(ArcSlice::from([0, 6]), 1));
+        assert_eq!(&*x.to_contiguous(), [0, 3, 6, 9]);
+
+        let one = x.slice(2..3);
+        assert_eq!(one.to_contiguous().as_ptr(), &x[2] as *const usize);
+        assert_eq!(StridedArcSlice::new(ArcSlice::from([1, 2]), 5).len(), 1);
+        assert!(StridedArcSlice::<u8>::default().is_empty());
+    }
+
+    #[test]
+    fn test_strided_huge_step() {
+        let x = StridedArcSlice::new(ArcSlice::from([1, 2, 3]), 2).step_by(usize::MAX);
+        assert_eq!(x.iter().cloned().collect::<Vec<_>>(), [1]);
+        let x = x.step_by(usize::MAX).step_by(2);
+        assert_eq!((x.len(), x.step(), x[0]), (1, usize::MAX, 1));
+    }
+
+    #[test]
+    fn test_column() {
+        let m = example();
+        let c = m.column(2);
+        assert_eq!(c.iter().cloned().collect::<Vec<_>>(), [2, 7, 12, 17]);
+        assert_eq!(&c[1] as *const usize, &m[(1, 2)] as *const usize);
+        let s = m.submatrix(1..3, 1..4).column(2);
+        assert_eq!(format!("{:?}", s), "[8, 13]");
+        assert!(m.submatrix(..0, ..).column(1).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_strided_zero_step() {
+        ArcSlice::from([1, 2]).step_by_owned(0);
+    }
+}
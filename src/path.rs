@@ -0,0 +1,276 @@
+//! Thread-safe shared OS strings and paths.
+//!
+//! These are re-exported from the `arc` module, and require the `std`
+//! feature.
+
+use std::{cmp, fmt, ops};
+use std::borrow::Borrow;
+use std::ffi::{OsStr, OsString};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use arc::{ArcSlice, ArcStr};
+
+/// A reference-counted OS string slice.
+///
+/// This is to `OsStr` as `ArcStr` is to `str`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcOsStr;
+/// use std::ffi::OsString;
+///
+/// let name = ArcOsStr::from(OsString::from("report.pdf"));
+/// let copy = name.clone();
+/// assert_eq!(copy.to_str(), Some("report.pdf"));
+/// ```
+#[derive(Clone, Default)]
+pub struct ArcOsStr {
+    // in the platform's encoding, as from `OsStr::as_encoded_bytes`
+    bytes: ArcSlice<u8>,
+}
+
+/// A reference-counted path slice.
+///
+/// This is to `Path` as `ArcStr` is to `str`. Paths that are parts
+/// of another, such as its parent directory, can point into the same
+/// allocation rather than being copied.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcPath;
+/// use std::path::{Path, PathBuf};
+///
+/// let path = ArcPath::from(PathBuf::from("/srv/data/log.txt"));
+/// let dir = path.parent_owned().unwrap();
+/// let name = path.file_name_owned().unwrap();
+/// drop(path);
+/// assert_eq!(&*dir, Path::new("/srv/data"));
+/// assert_eq!(name.to_str(), Some("log.txt"));
+/// ```
+#[derive(Clone, Default)]
+pub struct ArcPath {
+    bytes: ArcSlice<u8>,
+}
+
+/// The part of `bytes` that `sub`, a subslice of it, points to.
+fn subview(bytes: &ArcSlice<u8>, sub: &OsStr) -> ArcSlice<u8> {
+    let sub = sub.as_encoded_bytes();
+    let lo = sub.as_ptr() as usize - bytes.as_ptr() as usize;
+    bytes.clone().slice(lo, lo + sub.len())
+}
+
+impl ArcOsStr {
+    /// Construct a new `ArcOsStr` containing a copy of `s`.
+    pub fn new(s: &OsStr) -> ArcOsStr {
+        ArcOsStr { bytes: ArcSlice::from_slice(s.as_encoded_bytes()) }
+    }
+
+    /// The string `self` points to.
+    pub fn as_os_str(&self) -> &OsStr {
+        // the bytes always came from an `OsStr`, or a `str`
+        unsafe { OsStr::from_encoded_bytes_unchecked(&self.bytes) }
+    }
+}
+
+impl ArcPath {
+    /// Construct a new `ArcPath` containing a copy of `path`.
+    pub fn new(path: &Path) -> ArcPath {
+        ArcPath { bytes: ArcOsStr::new(path.as_os_str()).bytes }
+    }
+
+    /// The path `self` points to.
+    pub fn as_path(&self) -> &Path {
+        Path::new(unsafe { OsStr::from_encoded_bytes_unchecked(&self.bytes) })
+    }
+
+    /// Construct a new `ArcPath` pointing to the parent of `self`, or
+    /// `None` if it has none, like `Path::parent`.
+    ///
+    /// This shares the allocation of `self`, rather than copying.
+    pub fn parent_owned(&self) -> Option<ArcPath> {
+        let parent = self.parent()?;
+        Some(ArcPath { bytes: subview(&self.bytes, parent.as_os_str()) })
+    }
+
+    /// Construct a new `ArcOsStr` pointing to the final component of
+    /// `self`, or `None` if there is none, like `Path::file_name`.
+    ///
+    /// This shares the allocation of `self`, rather than copying.
+    pub fn file_name_owned(&self) -> Option<ArcOsStr> {
+        let name = self.file_name()?;
+        Some(ArcOsStr { bytes: subview(&self.bytes, name) })
+    }
+
+    /// Convert `self` into an `ArcOsStr`, without copying.
+    pub fn into_os_str(self) -> ArcOsStr {
+        ArcOsStr { bytes: self.bytes }
+    }
+}
+
+impl From<OsString> for ArcOsStr {
+    fn from(s: OsString) -> ArcOsStr {
+        ArcOsStr { bytes: ArcSlice::from(s.into_encoded_bytes()) }
+    }
+}
+
+impl<'a> From<&'a OsStr> for ArcOsStr {
+    fn from(s: &'a OsStr) -> ArcOsStr {
+        ArcOsStr::new(s)
+    }
+}
+
+impl From<ArcStr> for ArcOsStr {
+    fn from(s: ArcStr) -> ArcOsStr {
+        ArcOsStr { bytes: s.into_bytes() }
+    }
+}
+
+impl From<PathBuf> for ArcPath {
+    fn from(path: PathBuf) -> ArcPath {
+        ArcPath { bytes: ArcOsStr::from(path.into_os_string()).bytes }
+    }
+}
+
+impl<'a> From<&'a Path> for ArcPath {
+    fn from(path: &'a Path) -> ArcPath {
+        ArcPath::new(path)
+    }
+}
+
+impl From<ArcOsStr> for ArcPath {
+    fn from(s: ArcOsStr) -> ArcPath {
+        ArcPath { bytes: s.bytes }
+    }
+}
+
+impl From<ArcStr> for ArcPath {
+    fn from(s: ArcStr) -> ArcPath {
+        ArcPath { bytes: s.into_bytes() }
+    }
+}
+
+impl ops::Deref for ArcOsStr {
+    type Target = OsStr;
+    fn deref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl ops::Deref for ArcPath {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<OsStr> for ArcOsStr {
+    fn as_ref(&self) -> &OsStr { self }
+}
+
+impl AsRef<Path> for ArcOsStr {
+    fn as_ref(&self) -> &Path { Path::new(self.as_os_str()) }
+}
+
+impl AsRef<Path> for ArcPath {
+    fn as_ref(&self) -> &Path { self }
+}
+
+impl AsRef<OsStr> for ArcPath {
+    fn as_ref(&self) -> &OsStr { self.as_os_str() }
+}
+
+impl Borrow<OsStr> for ArcOsStr {
+    fn borrow(&self) -> &OsStr { self }
+}
+
+impl Borrow<Path> for ArcPath {
+    fn borrow(&self) -> &Path { self }
+}
+
+impl PartialEq for ArcOsStr {
+    fn eq(&self, other: &ArcOsStr) -> bool { **self == **other }
+}
+impl Eq for ArcOsStr {}
+
+impl PartialEq for ArcPath {
+    fn eq(&self, other: &ArcPath) -> bool { **self == **other }
+}
+impl Eq for ArcPath {}
+
+impl PartialOrd for ArcOsStr {
+    fn partial_cmp(&self, other: &ArcOsStr) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ArcOsStr {
+    fn cmp(&self, other: &ArcOsStr) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl PartialOrd for ArcPath {
+    fn partial_cmp(&self, other: &ArcPath) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ArcPath {
+    fn cmp(&self, other: &ArcPath) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl Hash for ArcOsStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl Hash for ArcPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl fmt::Debug for ArcOsStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Debug for ArcPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcOsStr, ArcPath};
+    use arc::ArcStr;
+    use std::collections::HashSet;
+    use std::ffi::OsStr;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_path() {
+        let path = ArcPath::from(PathBuf::from("a/bc/d.txt"));
+        let parent = path.parent_owned().unwrap();
+        assert_eq!(&*parent, Path::new("a/bc"));
+        assert_eq!(parent.bytes.as_ptr(), path.bytes.as_ptr());
+        let name = parent.file_name_owned().unwrap();
+        assert_eq!(&*name, OsStr::new("bc"));
+        assert_eq!(name.bytes.offset(), 2);
+
+        let root = ArcPath::from(Path::new("/"));
+        assert!(root.parent_owned().is_none());
+        assert!(root.file_name_owned().is_none());
+        assert_eq!(format!("{:?}", root), "\"/\"");
+
+        let mut set = HashSet::new();
+        set.insert(path.clone());
+        assert!(set.contains(Path::new("a/bc/d.txt")));
+        assert_eq!(ArcPath::from(ArcOsStr::from(OsStr::new("a/bc/d.txt"))), path);
+        assert_eq!(ArcPath::from(ArcStr::from("a/bc/d.txt")), path);
+        assert_eq!(path.into_os_str(), ArcOsStr::from(ArcStr::from("a/bc/d.txt")));
+        assert_eq!(&*ArcPath::default(), Path::new(""));
+    }
+}
@@ -0,0 +1,424 @@
+//! Reference-counted slices allocated from a fixed-size static pool,
+//! for targets without a heap.
+
+use core::{cmp, fmt, mem, ops, ptr};
+use core::alloc::Layout;
+use core::cell::UnsafeCell;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{self, AtomicUsize, Ordering};
+
+use identity;
+
+// `Pool::state` stores the number of live blocks in its upper half
+// and the offset of the first free byte in its lower half.
+const HALF: u32 = usize::BITS / 2;
+const NEXT_MASK: usize = (1 << HALF) - 1;
+
+/// A fixed-size region of memory from which `PoolSlice`s are
+/// allocated, without using the heap.
+///
+/// This is a bump allocator over `N` bytes, intended to be placed in
+/// a `static`: memory is handed out in order, and is only reused once
+/// every slice allocated from the pool (including any `WeakSlice`s)
+/// has been dropped, at which point the whole pool becomes available
+/// again. `N` must be less than `2^(usize::BITS / 2)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::pool::Pool;
+///
+/// static POOL: Pool<1024> = Pool::new();
+///
+/// let x = POOL.try_from_slice(&[1, 2, 3]).unwrap();
+/// let y = x.clone().slice(1, 3);
+/// assert_eq!(&*y, [2, 3]);
+///
+/// drop((x, y));
+/// assert_eq!(POOL.used(), 0);
+/// ```
+pub struct Pool<const N: usize> {
+    state: AtomicUsize,
+    buf: UnsafeCell<MaybeUninit<[u8; N]>>,
+}
+
+unsafe impl<const N: usize> Sync for Pool<N> {}
+
+/// A reference-counted slice allocated from a `Pool`.
+///
+/// This behaves like `ArcSlice`: it is like `&[T]` without lifetimes,
+/// and the elements are dropped once all `PoolSlice`s pointing to
+/// them have disappeared.
+pub struct PoolSlice<T> {
+    data: *const [T],
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send + Sync> Send for PoolSlice<T> {}
+unsafe impl<T: Send + Sync> Sync for PoolSlice<T> {}
+
+/// A non-owning reference-counted slice allocated from a `Pool`.
+///
+/// This is to `PoolSlice` as `arc::WeakSlice` is to `ArcSlice`. The
+/// memory of the allocation is not returned to the pool until these
+/// have disappeared too.
+pub struct WeakSlice<T> {
+    data: *const [T],
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send + Sync> Send for WeakSlice<T> {}
+unsafe impl<T: Send + Sync> Sync for WeakSlice<T> {}
+
+/// The start of every allocation, followed by the elements.
+struct Header {
+    strong: AtomicUsize,
+    // one more than the number of `WeakSlice`s while `strong` is
+    // non-zero
+    weak: AtomicUsize,
+    len: usize,
+    pool: &'static AtomicUsize,
+}
+
+fn block_layout<T>(len: usize) -> Option<(Layout, usize)> {
+    Layout::new::<Header>().extend(Layout::array::<T>(len).ok()?).ok()
+}
+
+/// The elements of the allocation starting at `header`.
+unsafe fn elements<T>(header: NonNull<Header>) -> *mut [T] {
+    let len = (*header.as_ptr()).len;
+    let (_, offset) = block_layout::<T>(len).unwrap();
+    let elems = (header.as_ptr() as *mut u8).add(offset) as *mut T;
+    ptr::slice_from_raw_parts_mut(elems, len)
+}
+
+/// Return the allocation starting at `header` to its pool.
+unsafe fn free(header: NonNull<Header>) {
+    let pool = (*header.as_ptr()).pool;
+    let mut state = pool.load(Ordering::Relaxed);
+    loop {
+        let live = (state >> HALF) - 1;
+        let new = if live == 0 { 0 } else { live << HALF | (state & NEXT_MASK) };
+        match pool.compare_exchange_weak(state, new, Ordering::Release, Ordering::Relaxed) {
+            Ok(_) => return,
+            Err(s) => state = s,
+        }
+    }
+}
+
+impl<const N: usize> Pool<N> {
+    /// Construct a new, empty pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is too large.
+    pub const fn new() -> Pool<N> {
+        assert!(N <= NEXT_MASK, "Pool::new: pool too large");
+        Pool {
+            state: AtomicUsize::new(0),
+            buf: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// The size of the pool in bytes.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of bytes that have been handed out since the pool
+    /// was last empty.
+    pub fn used(&self) -> usize {
+        self.state.load(Ordering::Relaxed) & NEXT_MASK
+    }
+
+    /// Allocate `layout` from the pool.
+    fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let base = self.buf.get() as *mut u8;
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            let (live, next) = (state >> HALF, state & NEXT_MASK);
+            let start = next + base.wrapping_add(next).align_offset(layout.align());
+            let end = start.checked_add(layout.size())?;
+            if end > N {
+                return None
+            }
+            let new = (live + 1) << HALF | end;
+            match self.state.compare_exchange_weak(state, new, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return NonNull::new(unsafe { base.add(start) }),
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// Construct a new `PoolSlice` of length `len` in this pool, with
+    /// the element at index `i` given by `f(i)`, or `None` if there is
+    /// not enough space left.
+    pub fn try_from_fn<T, F>(&'static self, len: usize, mut f: F) -> Option<PoolSlice<T>>
+        where F: FnMut(usize) -> T
+    {
+        // if `f` panics, drop the elements so far and free the block
+        struct Guard<T> {
+            header: NonNull<Header>,
+            elems: *mut T,
+            done: usize,
+        }
+        impl<T> Drop for Guard<T> {
+            fn drop(&mut self) {
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.elems, self.done));
+                    free(self.header);
+                }
+            }
+        }
+
+        let (layout, offset) = block_layout::<T>(len)?;
+        let header = self.alloc(layout)?.cast::<Header>();
+        unsafe {
+            header.as_ptr().write(Header {
+                strong: AtomicUsize::new(1),
+                weak: AtomicUsize::new(1),
+                len,
+                pool: &self.state,
+            });
+        }
+        let elems = unsafe { (header.as_ptr() as *mut u8).add(offset) as *mut T };
+        let mut guard = Guard { header, elems, done: 0 };
+        while guard.done < len {
+            unsafe { elems.add(guard.done).write(f(guard.done)) }
+            guard.done += 1;
+        }
+        mem::forget(guard);
+
+        Some(PoolSlice {
+            data: ptr::slice_from_raw_parts(elems, len),
+            header,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Construct a new `PoolSlice` in this pool containing clones of
+    /// the elements of `slice`, or `None` if there is not enough space
+    /// left.
+    pub fn try_from_slice<T: Clone>(&'static self, slice: &[T]) -> Option<PoolSlice<T>> {
+        self.try_from_fn(slice.len(), |i| slice[i].clone())
+    }
+}
+
+impl<const N: usize> Default for Pool<N> {
+    fn default() -> Pool<N> {
+        Pool::new()
+    }
+}
+
+impl<T> PoolSlice<T> {
+    fn header(&self) -> &Header {
+        unsafe { self.header.as_ref() }
+    }
+
+    /// Construct a new `PoolSlice` that only points to elements at
+    /// indices `lo` (inclusive) through `hi` (exclusive).
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications. Use `.clone()` if it is necessary to refer to
+    /// `self` after calling this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if either are strictly greater than
+    /// `self.len()`.
+    pub fn slice(mut self, lo: usize, hi: usize) -> PoolSlice<T> {
+        self.data = &self[lo..hi];
+        self
+    }
+
+    /// Construct a new `PoolSlice` that only points to elements at
+    /// indices `lo` (inclusive) through `self.len()`.
+    pub fn slice_from(self, lo: usize) -> PoolSlice<T> {
+        let hi = self.len();
+        self.slice(lo, hi)
+    }
+
+    /// Construct a new `PoolSlice` that only points to elements at
+    /// indices 0 (inclusive) through `hi` (exclusive).
+    pub fn slice_to(self, hi: usize) -> PoolSlice<T> {
+        self.slice(0, hi)
+    }
+
+    /// Construct a new `WeakSlice`, pointing to the same slice as
+    /// `self`.
+    pub fn downgrade(&self) -> WeakSlice<T> {
+        self.header().weak.fetch_add(1, Ordering::Relaxed);
+        WeakSlice { data: self.data, header: self.header, _marker: PhantomData }
+    }
+}
+
+impl<T> Clone for PoolSlice<T> {
+    fn clone(&self) -> PoolSlice<T> {
+        self.header().strong.fetch_add(1, Ordering::Relaxed);
+        PoolSlice { data: self.data, header: self.header, _marker: PhantomData }
+    }
+}
+
+impl<T> Drop for PoolSlice<T> {
+    fn drop(&mut self) {
+        if self.header().strong.fetch_sub(1, Ordering::Release) != 1 {
+            return
+        }
+        atomic::fence(Ordering::Acquire);
+        unsafe { ptr::drop_in_place(elements::<T>(self.header)) }
+        // release the weak reference held by the strong ones
+        drop(WeakSlice::<T> { data: self.data, header: self.header, _marker: PhantomData });
+    }
+}
+
+impl<T> ops::Deref for PoolSlice<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.data }
+    }
+}
+
+impl<T> AsRef<[T]> for PoolSlice<T> {
+    fn as_ref(&self) -> &[T] { self }
+}
+
+impl<T: PartialEq> PartialEq for PoolSlice<T> {
+    fn eq(&self, other: &PoolSlice<T>) -> bool { identity::slice_eq(self, other) }
+}
+impl<T: Eq> Eq for PoolSlice<T> {}
+
+impl<T: PartialOrd> PartialOrd for PoolSlice<T> {
+    fn partial_cmp(&self, other: &PoolSlice<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+impl<T: Ord> Ord for PoolSlice<T> {
+    fn cmp(&self, other: &PoolSlice<T>) -> cmp::Ordering { identity::slice_cmp(self, other) }
+}
+
+impl<T: Hash> Hash for PoolSlice<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> WeakSlice<T> {
+    /// Attempt to upgrade `self` to a strongly-counted `PoolSlice`.
+    ///
+    /// Returns `None` if this is not possible (the elements have
+    /// already been dropped).
+    pub fn upgrade(&self) -> Option<PoolSlice<T>> {
+        let strong = unsafe { &self.header.as_ref().strong };
+        let mut n = strong.load(Ordering::Relaxed);
+        loop {
+            if n == 0 {
+                return None
+            }
+            match strong.compare_exchange_weak(n, n + 1, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return Some(PoolSlice {
+                    data: self.data,
+                    header: self.header,
+                    _marker: PhantomData,
+                }),
+                Err(m) => n = m,
+            }
+        }
+    }
+}
+
+impl<T> Clone for WeakSlice<T> {
+    fn clone(&self) -> WeakSlice<T> {
+        unsafe { self.header.as_ref() }.weak.fetch_add(1, Ordering::Relaxed);
+        WeakSlice { data: self.data, header: self.header, _marker: PhantomData }
+    }
+}
+
+impl<T> Drop for WeakSlice<T> {
+    fn drop(&mut self) {
+        if unsafe { self.header.as_ref() }.weak.fetch_sub(1, Ordering::Release) == 1 {
+            atomic::fence(Ordering::Acquire);
+            unsafe { free(self.header) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pool;
+    use std::cell::Cell;
+    use std::panic;
+
+    struct Noisy<'a>(&'a Cell<usize>);
+    impl<'a> Drop for Noisy<'a> {
+        fn drop(&mut self) { self.0.set(self.0.get() + 1) }
+    }
+
+    #[test]
+    fn test_clone_slice() {
+        static POOL: Pool<256> = Pool::new();
+        let x = POOL.try_from_slice(&[1u32, 2, 3, 4]).unwrap();
+        let y = x.clone().slice(1, 3);
+        assert_eq!(&*y, [2, 3]);
+        assert_eq!(x.clone().slice_from(2), x.clone().slice_to(4).slice(2, 4));
+        assert!(POOL.used() > 0);
+        assert_eq!(POOL.capacity(), 256);
+
+        drop(x);
+        assert_eq!(&*y, [2, 3]);
+        drop(y);
+        assert_eq!(POOL.used(), 0);
+    }
+
+    #[test]
+    fn test_weak() {
+        static POOL: Pool<256> = Pool::new();
+        let drops = Cell::new(0);
+        let x = POOL.try_from_fn(3, |_| Noisy(&drops)).unwrap();
+        let weak = x.clone().slice_to(1).downgrade();
+        assert_eq!(weak.upgrade().unwrap().len(), 1);
+
+        drop(x);
+        assert_eq!(drops.get(), 3);
+        assert!(weak.upgrade().is_none());
+        assert!(POOL.used() > 0);
+        drop(weak.clone());
+        drop(weak);
+        assert_eq!(POOL.used(), 0);
+    }
+
+    #[test]
+    fn test_exhausted() {
+        static POOL: Pool<128> = Pool::new();
+        assert!(POOL.try_from_fn(1000, |i| i).is_none());
+        let xs = (0..100).map(|_| POOL.try_from_slice(&[0u8; 8])).collect::<Vec<_>>();
+        assert!(xs[0].is_some());
+        assert!(xs[99].is_none());
+
+        drop(xs);
+        assert_eq!(POOL.used(), 0);
+        assert!(POOL.try_from_slice(&[0u8; 8]).is_some());
+    }
+
+    #[test]
+    fn test_panic() {
+        static POOL: Pool<256> = Pool::new();
+        let drops = Cell::new(0);
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            POOL.try_from_fn(5, |i| if i < 3 { Noisy(&drops) } else { panic!() })
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 3);
+        assert_eq!(POOL.used(), 0);
+    }
+}
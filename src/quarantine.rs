@@ -0,0 +1,78 @@
+//! A quarantine for freed allocations, for testing.
+//!
+//! With the `quarantine` feature, when the last handle to an
+//! allocation (including `WeakSlice`s) is dropped, the memory is not
+//! returned to the allocator immediately. Instead, it is kept here,
+//! and freed in bulk once the quarantine holds more than `CAPACITY`
+//! bytes, oldest first, or when `flush` is called. This stops a
+//! use-after-free through a stale raw pointer from silently reading
+//! or corrupting a new allocation that reused the memory; with the
+//! `poison` feature too, such reads see obvious garbage.
+
+use std::collections::VecDeque;
+use std::alloc::{self, Layout};
+use std::sync::Mutex;
+
+/// The number of bytes the quarantine holds before it starts freeing
+/// allocations.
+pub const CAPACITY: usize = 16 << 20;
+
+struct Quarantine {
+    allocs: VecDeque<(usize, Layout)>,
+    bytes: usize,
+}
+
+static QUARANTINE: Mutex<Quarantine> = Mutex::new(Quarantine {
+    allocs: VecDeque::new(),
+    bytes: 0,
+});
+
+/// Quarantine the allocation `ptr` with layout `layout`.
+///
+/// It will be freed with `alloc::dealloc` later, so must have been
+/// allocated by the global allocator.
+pub(crate) unsafe fn defer(ptr: *mut u8, layout: Layout) {
+    let mut q = QUARANTINE.lock().unwrap_or_else(|e| e.into_inner());
+    q.allocs.push_back((ptr as usize, layout));
+    q.bytes += layout.size();
+    while q.bytes > CAPACITY {
+        let (ptr, layout) = q.allocs.pop_front().unwrap();
+        q.bytes -= layout.size();
+        alloc::dealloc(ptr as *mut u8, layout);
+    }
+}
+
+/// Free every allocation in the quarantine, returning how many there
+/// were.
+pub fn flush() -> usize {
+    let mut q = QUARANTINE.lock().unwrap_or_else(|e| e.into_inner());
+    let n = q.allocs.len();
+    for (ptr, layout) in q.allocs.drain(..) {
+        unsafe { alloc::dealloc(ptr as *mut u8, layout) }
+    }
+    q.bytes = 0;
+    n
+}
+
+/// The number of bytes currently held in the quarantine.
+pub fn bytes() -> usize {
+    QUARANTINE.lock().unwrap_or_else(|e| e.into_inner()).bytes
+}
+
+#[cfg(all(test, feature = "arc"))]
+mod tests {
+    use super::{bytes, flush};
+    use arc::ArcSlice;
+
+    #[test]
+    fn test_quarantine() {
+        // other tests may add to the quarantine concurrently, but this
+        // is the only one that flushes it
+        let x = ArcSlice::new(vec![0u64; 1000].into_boxed_slice());
+        let y = x.clone();
+        drop(x);
+        drop(y);
+        assert!(bytes() >= 8000);
+        assert!(flush() >= 1);
+    }
+}
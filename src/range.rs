@@ -0,0 +1,65 @@
+//! Ranges of indices into shared slices.
+
+use core::fmt;
+use core::ops::{Bound, RangeBounds};
+
+/// The error returned when a range of indices into a slice is out of
+/// bounds, or decreasing.
+///
+/// This is returned by the `try_slice` methods of `RcSlice` and
+/// `ArcSlice`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SliceError {
+    start: usize,
+    end: usize,
+    len: usize,
+}
+
+impl SliceError {
+    fn new<R: RangeBounds<usize>>(range: &R, len: usize) -> SliceError {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i.saturating_add(1),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => len,
+        };
+        SliceError { start, end, len }
+    }
+}
+
+impl fmt::Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "range {}..{} out of bounds for slice of length {}",
+               self.start, self.end, self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for SliceError {}
+
+/// The start and end of `range` in a slice of length `len`, or an
+/// error if it is out of bounds.
+pub fn check<R: RangeBounds<usize>>(range: &R, len: usize) -> Result<(usize, usize), SliceError> {
+    let err = SliceError::new(range, len);
+    if err.start <= err.end && err.end <= len {
+        Ok((err.start, err.end))
+    } else {
+        Err(err)
+    }
+}
+
+/// `range` as a pair of bounds, which slices can be indexed by.
+pub fn bounds<R: RangeBounds<usize>>(range: &R) -> (Bound<usize>, Bound<usize>) {
+    (range.start_bound().cloned(), range.end_bound().cloned())
+}
+
+/// Get the elements of `slice` in `range`, or an error if it is out
+/// of bounds.
+pub fn get<T, R: RangeBounds<usize>>(slice: &[T], range: R) -> Result<&[T], SliceError> {
+    let (lo, hi) = check(&range, slice.len())?;
+    Ok(&slice[lo..hi])
+}
@@ -1,12 +1,56 @@
 //! A thread-local reference-counted slice type.
 
-use core::prelude::*;
-
-use core::{cmp, fmt, ops};
+use core::{cmp, fmt, mem, ops, ptr, slice, str};
+use core::borrow::Borrow;
+use core::cell::{Cell, UnsafeCell};
 use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
 
-use alloc::rc::{Rc, Weak};
 use alloc::boxed::Box;
+use alloc::string::{FromUtf16Error, String};
+
+use cdc;
+use checks;
+use identity;
+use inner;
+use range::{self, SliceError};
+use search;
+
+use alloc::collections::BinaryHeap;
+use alloc::collections::binary_heap::PeekMut;
+use alloc::vec::{self, Vec};
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "rand")]
+use rand::seq::SliceRandom;
+
+#[cfg(feature = "digest")]
+use digest::{Digest, Output};
+
+#[cfg(feature = "xxhash-rust")]
+use xxhash_rust::xxh3::xxh3_64;
+
+#[cfg(feature = "encoding_rs")]
+use alloc::borrow::Cow;
+#[cfg(feature = "encoding_rs")]
+use encoding_rs::Encoding;
+
+#[cfg(feature = "unicode-segmentation")]
+use unicode_segmentation::{Graphemes, UWordBounds, UnicodeSegmentation, UnicodeWords};
+
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "std")]
+use std::{fs, io, path};
+#[cfg(all(feature = "flate2", feature = "std"))]
+use flate2::read::MultiGzDecoder;
 
 
 /// A reference-counted slice type.
@@ -22,7 +66,7 @@ use alloc::boxed::Box;
 /// ```rust
 /// use shared_slice::rc::RcSlice;
 ///
-/// let x = RcSlice::new(Box::new(["foo", "bar", "baz"]));
+/// let x = RcSlice::from(["foo", "bar", "baz"]);
 /// println!("{:?}", x); // ["foo", "bar", "baz"]
 /// println!("{:?}", x.slice(1, 3)); // ["bar", "baz"]
 /// ```
@@ -42,7 +86,8 @@ use alloc::boxed::Box;
 /// ```
 pub struct RcSlice<T> {
     data: *const [T],
-    counts: Rc<Box<[T]>>,
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
 }
 
 /// A non-owning reference-counted slice type.
@@ -52,25 +97,321 @@ pub struct RcSlice<T> {
 /// being deallocated.
 pub struct WeakSlice<T> {
     data: *const [T],
-    counts: Weak<Box<[T]>>,
+    header: NonNull<Header>,
+    _marker: PhantomData<T>,
+}
+
+/// A reference-counted reference to a single element of a `RcSlice`.
+///
+/// This keeps the whole allocation alive, like a `RcSlice`, but
+/// dereferences to a `&T`. It is created by `RcSlice::get_ref`.
+pub struct RcRef<T> {
+    slice: RcSlice<T>,
+}
+
+/// A reference-counted reference to a value derived from the
+/// elements of a `RcSlice`, such as a field of one of them.
+///
+/// This keeps the whole allocation alive, like a `RcSlice`, but
+/// dereferences to a `&U`. It is created by `RcSlice::map_ref`.
+pub struct RcView<U: ?Sized> {
+    data: *const U,
+    header: NonNull<Header>,
+    // `release_strong` for the element type
+    release: unsafe fn(NonNull<Header>),
+}
+
+/// A compact reference-counted slice type.
+///
+/// This behaves like `RcSlice`, but stores the position of the view
+/// in its allocation as 32-bit integers, making it two words in size
+/// rather than three. It can only point to elements whose indices
+/// fit in a `u32`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::rc::{CompactRcSlice, RcSlice};
+///
+/// let x = RcSlice::new(Box::new([1, 2, 3, 4]));
+/// let y = CompactRcSlice::new(x.slice(1, 3)).unwrap();
+/// assert_eq!(&*y, [2, 3]);
+///
+/// let z: RcSlice<_> = y.into();
+/// assert_eq!(&*z, [2, 3]);
+/// ```
+pub struct CompactRcSlice<T> {
+    header: NonNull<Header>,
+    offset: u32,
+    len: u32,
+    _marker: PhantomData<T>,
+}
+
+/// The header of a `RcSlice` allocation, which is followed by the
+/// elements.
+struct Header {
+    strong: Cell<usize>,
+    // the number of `WeakSlice`s, plus one for all the `RcSlice`s
+    // together while there are any
+    weak: Cell<usize>,
+    len: usize,
+}
+
+impl Header {
+    fn new(len: usize) -> Header {
+        Header { strong: Cell::new(1), weak: Cell::new(1), len }
+    }
+
+    fn strong(&self) -> usize {
+        self.strong.get()
+    }
+
+    fn weak(&self) -> usize {
+        self.weak.get()
+    }
+
+    fn inc_strong(&self) {
+        self.strong.set(inc(self.strong.get()))
+    }
+
+    fn inc_weak(&self) {
+        self.weak.set(inc(self.weak.get()))
+    }
+
+    /// Add a strong reference, unless the elements have already been
+    /// dropped. Returns whether it was added.
+    fn upgrade(&self) -> bool {
+        match self.strong.get() {
+            0 => false,
+            n => { self.strong.set(inc(n)); true }
+        }
+    }
+
+    /// Whether there is exactly one strong reference, and no weak
+    /// ones.
+    fn is_unique(&self) -> bool {
+        self.strong.get() == 1 && self.weak.get() == 1
+    }
+}
+
+/// `count + 1`, aborting if that is too large a reference count.
+fn inc(count: usize) -> usize {
+    if count >= inner::MAX_REFCOUNT {
+        inner::count_overflow()
+    }
+    count + 1
+}
+
+/// The header of slices that don't own an allocation: empty ones, and
+/// those from `from_static`. It is never dereferenced, and they
+/// aren't reference counted.
+const fn static_header() -> NonNull<Header> {
+    NonNull::dangling()
+}
+
+/// The header of `WeakSlice`s that never upgrade, which also isn't
+/// dereferenced or reference counted.
+const fn dead_header() -> NonNull<Header> {
+    unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(usize::MAX)) }
+}
+
+/// Whether `header` is the header of a real allocation, with
+/// reference counts.
+fn is_counted(header: NonNull<Header>) -> bool {
+    header != static_header() && header != dead_header()
+}
+
+/// Add a strong reference to the allocation `header`, if it is
+/// counted.
+fn retain(header: NonNull<Header>) {
+    if is_counted(header) {
+        unsafe { header.as_ref() }.inc_strong();
+    }
+}
+
+/// All of the elements of the allocation `header`.
+unsafe fn whole<'a, T>(header: NonNull<Header>) -> &'a [T] {
+    &*ptr::slice_from_raw_parts(inner::elems::<Header, T>(header), header.as_ref().len)
+}
+
+/// Release a strong reference to the allocation `header`, dropping
+/// the elements if it was the last one.
+unsafe fn release_strong<T>(header: NonNull<Header>) {
+    if !is_counted(header) {
+        return
+    }
+    let h = header.as_ref();
+    h.strong.set(h.strong.get() - 1);
+    if h.strong.get() == 0 {
+        inner::drop_elems::<Header, T>(header, h.len);
+        release_weak::<T>(header);
+    }
+}
+
+/// Release a weak reference to the allocation `header` (or the one
+/// held by all the strong references together), freeing it if it was
+/// the last one.
+unsafe fn release_weak<T>(header: NonNull<Header>) {
+    if !is_counted(header) {
+        return
+    }
+    let h = header.as_ref();
+    h.weak.set(h.weak.get() - 1);
+    if h.weak.get() == 0 {
+        let len = h.len;
+        inner::free::<Header, T>(header, len);
+    }
 }
 
 impl<T> RcSlice<T> {
     /// Construct a new `RcSlice` containing the elements of `slice`.
     ///
-    /// This reuses the allocation of `slice`.
+    /// The elements are moved into a new allocation, after the
+    /// reference counts.
     pub fn new(slice: Box<[T]>) -> RcSlice<T> {
+        if slice.is_empty() {
+            return RcSlice::default()
+        }
+        let len = slice.len();
+        RcSlice::from_header(inner::from_box::<Header, T>(Header::new(len), slice), len)
+    }
+
+    /// Construct a new `RcSlice` pointing to the elements of the
+    /// static `slice`, without allocating.
+    ///
+    /// This isn't reference counted, so cloning and dropping it (and
+    /// the `RcSlice`s sliced from it) costs nothing, and APIs taking
+    /// `RcSlice`s can be passed baked-in data directly. As it doesn't
+    /// own an allocation, it is never unique: `get_mut` and
+    /// `try_unwrap` fail, and `make_mut` copies the elements into a
+    /// new allocation. Similarly, `full_slice` can't undo slicing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// static TABLE: [u8; 4] = [1, 2, 4, 8];
+    /// const POWERS: RcSlice<u8> = RcSlice::from_static(&TABLE);
+    ///
+    /// let x = POWERS.slice_from(2);
+    /// assert_eq!(&*x, [4, 8]);
+    /// ```
+    pub const fn from_static(slice: &'static [T]) -> RcSlice<T> {
+        RcSlice {
+            data: slice,
+            header: static_header(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Construct a new empty `RcSlice`, without allocating.
+    ///
+    /// This is the same as `RcSlice::default()`, but can be used in
+    /// constants.
+    pub const fn empty() -> RcSlice<T> {
+        RcSlice {
+            data: &[],
+            header: static_header(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of elements in `self`.
+    ///
+    /// This is the same as the slice method, but can be used in
+    /// constants.
+    pub const fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether `self` contains no elements.
+    ///
+    /// This is the same as the slice method, but can be used in
+    /// constants.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Construct a new `RcSlice` containing `f(i)` for each index `i`
+    /// from 0 up to `len`, in order.
+    ///
+    /// The elements are written straight into the new allocation. If
+    /// `f` panics, the elements already written are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let squares = RcSlice::from_fn(5, |i| i * i);
+    /// assert_eq!(&*squares, [0, 1, 4, 9, 16]);
+    /// ```
+    pub fn from_fn<F: FnMut(usize) -> T>(len: usize, f: F) -> RcSlice<T> {
+        if len == 0 {
+            return RcSlice::default()
+        }
+        let header = inner::from_fn::<Header, T, F>(Header::new(len), len, f);
+        RcSlice::from_header(header, len)
+    }
+
+    /// Construct a new `RcSlice` with space for `len` elements, which
+    /// are left uninitialized, like `Rc::new_uninit_slice`.
+    ///
+    /// The new `RcSlice` is unique, so the elements can be written
+    /// through `get_mut`, and then `assume_init` used to convert it to
+    /// a `RcSlice<T>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut x = RcSlice::<u32>::new_uninit(3);
+    /// for (i, elem) in x.get_mut().unwrap().iter_mut().enumerate() {
+    ///     elem.write(i as u32 * 10);
+    /// }
+    /// let x = unsafe { x.assume_init() };
+    /// assert_eq!(&*x, [0, 10, 20]);
+    /// ```
+    pub fn new_uninit(len: usize) -> RcSlice<MaybeUninit<T>> {
+        if len == 0 {
+            return RcSlice::default()
+        }
+        let header = inner::allocate::<Header, MaybeUninit<T>>(Header::new(len), len);
+        RcSlice::from_header(header, len)
+    }
+
+    /// Construct a new `RcSlice` with space for `len` elements, whose
+    /// bytes are all set to zero, like `Rc::new_zeroed_slice`.
+    ///
+    /// This is `new_uninit`, for types where zero bytes may be a valid
+    /// value, such as integers.
+    pub fn new_zeroed(len: usize) -> RcSlice<MaybeUninit<T>> {
+        let x = RcSlice::<T>::new_uninit(len);
+        unsafe { ptr::write_bytes(x.data as *mut MaybeUninit<T>, 0, len) }
+        x
+    }
+
+    /// Construct a new `RcSlice` pointing to all `len` elements of
+    /// the new allocation `header`.
+    fn from_header(header: NonNull<Header>, len: usize) -> RcSlice<T> {
         RcSlice {
-            data: &*slice,
-            counts: Rc::new(slice),
+            data: ptr::slice_from_raw_parts(inner::elems::<Header, T>(header), len),
+            header,
+            _marker: PhantomData,
         }
     }
 
     /// Downgrade self into a weak slice.
     pub fn downgrade(&self) -> WeakSlice<T> {
+        if is_counted(self.header) {
+            self.header().inc_weak();
+        }
         WeakSlice {
             data: self.data,
-            counts: self.counts.downgrade()
+            header: self.header,
+            _marker: PhantomData,
         }
     }
 
@@ -116,108 +457,3342 @@ impl<T> RcSlice<T> {
         let hi = self.len();
         self.slice(lo, hi)
     }
-}
 
-impl<T> Clone for RcSlice<T> {
-    fn clone(&self) -> RcSlice<T> {
-        RcSlice {
-            data: self.data,
-            counts: self.counts.clone()
-        }
+    /// Construct a new `RcSlice` that only points to the elements in
+    /// `range`, such as `2..`, `..=5` or `..`.
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications. Use `.clone()` if it is necessary to refer to
+    /// `self` after calling this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, or if either
+    /// are out of bounds, like indexing a slice.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3, 4, 5]);
+    /// assert_eq!(&*x.clone().slice_range(2..), [3, 4, 5]);
+    /// assert_eq!(&*x.slice_range(1..=2), [2, 3]);
+    /// ```
+    pub fn slice_range<R: ops::RangeBounds<usize>>(mut self, range: R) -> RcSlice<T> {
+        self.data = &self[range::bounds(&range)];
+        self
     }
-}
 
-impl<T> ops::Deref for RcSlice<T> {
-    type Target = [T];
-    fn deref<'a>(&'a self) -> &'a [T] {
-        unsafe {&*self.data}
+    /// Construct a new `RcSlice` that only points to the elements in
+    /// `range`, or return an error if it is out of bounds.
+    ///
+    /// This is `slice_range` for indices that may be invalid, such as
+    /// lengths read from untrusted input.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from(*b"\x03abcdef");
+    /// let n = x[0] as usize;
+    /// assert_eq!(&*x.clone().try_slice(1..1 + n).unwrap(), b"abc");
+    /// assert!(x.try_slice(1..1 + 3 * n).is_err());
+    /// ```
+    pub fn try_slice<R: ops::RangeBounds<usize>>(mut self, range: R) -> Result<RcSlice<T>, SliceError> {
+        self.data = range::get(&self, range)?;
+        Ok(self)
     }
-}
-
-impl<T> AsRef<[T]> for RcSlice<T> {
-    fn as_ref(&self) -> &[T] { &**self }
-}
-
-impl<T: PartialEq> PartialEq for RcSlice<T> {
-    fn eq(&self, other: &RcSlice<T>) -> bool { **self == **other }
-    fn ne(&self, other: &RcSlice<T>) -> bool { **self != **other }
-}
-impl<T: Eq> Eq for RcSlice<T> {}
 
-impl<T: PartialOrd> PartialOrd for RcSlice<T> {
-    fn partial_cmp(&self, other: &RcSlice<T>) -> Option<cmp::Ordering> {
-        (**self).partial_cmp(&**other)
+    /// Construct a new `RcSlice` pointing to the elements of `self` in
+    /// `range`, or `None` if it is out of bounds.
+    ///
+    /// This is like `<[T]>::get`, but the result shares ownership of
+    /// the elements.
+    pub fn get_owned<R: ops::RangeBounds<usize>>(&self, range: R) -> Option<RcSlice<T>> {
+        let data: *const [T] = range::get(self, range).ok()?;
+        let mut slice = self.clone();
+        slice.data = data;
+        Some(slice)
     }
-    fn lt(&self, other: &RcSlice<T>) -> bool { **self < **other }
-    fn le(&self, other: &RcSlice<T>) -> bool { **self <= **other }
-    fn gt(&self, other: &RcSlice<T>) -> bool { **self > **other }
-    fn ge(&self, other: &RcSlice<T>) -> bool { **self >= **other }
-}
-impl<T: Ord> Ord for RcSlice<T> {
-    fn cmp(&self, other: &RcSlice<T>) -> cmp::Ordering { (**self).cmp(&**other) }
-}
 
-impl<T: Hash> Hash for RcSlice<T> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        Hash::hash(&**self, state)
+    /// Divide `self` into two `RcSlice`s at index `mid`, the first
+    /// pointing to the elements before it and the second to those
+    /// from it onwards.
+    ///
+    /// This only increments the reference count once, for the second
+    /// half.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let (left, right) = RcSlice::from([1, 2, 3, 4, 5]).split_at(2);
+    /// assert_eq!(&*left, [1, 2]);
+    /// assert_eq!(&*right, [3, 4, 5]);
+    /// ```
+    pub fn split_at(mut self, mid: usize) -> (RcSlice<T>, RcSlice<T>) {
+        let other = self.split_off(mid);
+        (self, other)
     }
-}
 
-impl<T: fmt::Debug> fmt::Debug for RcSlice<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
+    /// Shorten `self` to the elements before index `at`, returning a
+    /// new `RcSlice` pointing to the rest.
+    ///
+    /// This is like `Vec::split_off`, but doesn't copy the elements:
+    /// both halves share the original allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut buf = RcSlice::from(*b"headbody");
+    /// let body = buf.split_off(4);
+    /// assert_eq!(&*buf, b"head");
+    /// assert_eq!(&*body, b"body");
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> RcSlice<T> {
+        let rest: *const [T] = &self[at..];
+        let mut other = self.clone();
+        other.data = rest;
+        self.data = &self[..at];
+        other
     }
-}
 
-impl<T> WeakSlice<T> {
-    /// Attempt to upgrade `self` to a strongly-counted `RcSlice`.
+    /// Shorten `self` to the elements from index `at` onwards,
+    /// returning a new `RcSlice` pointing to the ones before it.
     ///
-    /// Returns `None` if this is not possible (the data has already
-    /// been freed).
-    pub fn upgrade(&self) -> Option<RcSlice<T>> {
-        self.counts.upgrade().map(|counts| {
-            RcSlice {
-                data: self.data,
-                counts: counts
-            }
-        })
+    /// This is the reverse of `split_off`, and is like
+    /// `bytes::Bytes::split_to`: it peels frames off the front of a
+    /// buffer without copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut buf = RcSlice::from(*b"\x03abc\x02de");
+    /// let mut frames = vec![];
+    /// while !buf.is_empty() {
+    ///     let len = buf[0] as usize;
+    ///     buf.advance(1);
+    ///     frames.push(buf.split_to(len));
+    /// }
+    /// assert_eq!(frames, [RcSlice::from(*b"abc"), RcSlice::from(*b"de")]);
+    /// ```
+    pub fn split_to(&mut self, at: usize) -> RcSlice<T> {
+        let rest = self.split_off(at);
+        mem::replace(self, rest)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use std::rc::Rc;
-    use super::{RcSlice, WeakSlice};
-    use std::cell::Cell;
-    use std::cmp::Ordering;
-    
-    #[test]
-    fn clone() {
-        let x = RcSlice::new(Box::new([Cell::new(false)]));
-        let y = x.clone();
 
-        assert_eq!(x[0].get(), false);
-        assert_eq!(y[0].get(), false);
+    /// Drop the first `n` elements from the view of `self`, in place.
+    ///
+    /// This is like `self.slice_from(n)`, but doesn't need `self` to
+    /// be moved out and reassigned, and doesn't touch the reference
+    /// counts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut buf = RcSlice::from(*b"GET /index.html");
+    /// buf.advance(4);
+    /// assert_eq!(&*buf, b"/index.html");
+    /// ```
+    pub fn advance(&mut self, n: usize) {
+        assert!(n <= self.len(), "RcSlice::advance: {} out of bounds for length {}", n, self.len());
+        self.data = &self[n..];
+    }
 
-        x[0].set(true);
-        assert_eq!(x[0].get(), true);
-        assert_eq!(y[0].get(), true);
+    /// Shorten the view of `self` to its first `len` elements, in
+    /// place. This has no effect if `len >= self.len()`, like
+    /// `Vec::truncate`.
+    ///
+    /// The elements are still owned by the allocation, so aren't
+    /// dropped until it is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut line = RcSlice::from(*b"hello\r\n");
+    /// line.truncate(5);
+    /// assert_eq!(&*line, b"hello");
+    /// line.truncate(10);
+    /// assert_eq!(&*line, b"hello");
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.data = &self[..len];
+        }
     }
 
-    #[test]
-    fn test_upgrade_downgrade() {
-        let x = RcSlice::new(Box::new([1]));
-        let y: WeakSlice<_> = x.downgrade();
+    /// Divide `self` into `RcSlice`s of `size` elements each (except
+    /// possibly the last), like `<[T]>::chunks`.
+    ///
+    /// Each chunk shares ownership of the elements, so they can be
+    /// moved elsewhere independently.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3, 4, 5]);
+    /// let chunks: Vec<_> = x.chunks_owned(2).collect();
+    /// assert_eq!(chunks, [RcSlice::from([1, 2]), RcSlice::from([3, 4]), RcSlice::from([5])]);
+    /// ```
+    pub fn chunks_owned(self, size: usize) -> ChunksOwned<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        ChunksOwned { rest: self, size }
+    }
 
-        assert_eq!(y.upgrade(), Some(x.clone()));
+    /// Divide `self` into `RcSlice`s of exactly `size` elements each,
+    /// like `<[T]>::chunks_exact`.
+    ///
+    /// The `len() % size` elements at the end that don't make up a
+    /// whole chunk are not yielded, but can be retrieved with the
+    /// iterator's `remainder` method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let chunks = RcSlice::from([1, 2, 3, 4, 5]).chunks_exact_owned(2);
+    /// assert_eq!(&**chunks.remainder(), [5]);
+    /// let chunks: Vec<_> = chunks.collect();
+    /// assert_eq!(chunks, [RcSlice::from([1, 2]), RcSlice::from([3, 4])]);
+    /// ```
+    pub fn chunks_exact_owned(mut self, size: usize) -> ChunksExactOwned<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        let len = self.len();
+        let rem = self.split_off(len - len % size);
+        ChunksExactOwned { rest: self, rem, size }
+    }
 
-        drop(x);
+    /// Divide `self` into `RcSlice`s of `size` elements each, starting
+    /// at the end, like `<[T]>::rchunks`. The last chunk yielded
+    /// (which holds the start of `self`) may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3, 4, 5]);
+    /// let chunks: Vec<_> = x.rchunks_owned(2).collect();
+    /// assert_eq!(chunks, [RcSlice::from([4, 5]), RcSlice::from([2, 3]), RcSlice::from([1])]);
+    /// ```
+    pub fn rchunks_owned(self, size: usize) -> RChunksOwned<T> {
+        assert!(size != 0, "chunk size must be non-zero");
+        RChunksOwned { rest: self, size }
+    }
 
-        assert!(y.upgrade().is_none())
+    /// Construct `RcSlice`s pointing to each contiguous window of
+    /// `size` elements of `self`, like `<[T]>::windows`. The windows
+    /// overlap, and there are none if `self` is shorter than `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3, 4]);
+    /// let windows: Vec<_> = x.windows_owned(3).collect();
+    /// assert_eq!(windows, [RcSlice::from([1, 2, 3]), RcSlice::from([2, 3, 4])]);
+    /// ```
+    pub fn windows_owned(self, size: usize) -> WindowsOwned<T> {
+        assert!(size != 0, "window size must be non-zero");
+        WindowsOwned { rest: self, size }
     }
 
-    #[test]
-    fn test_total_cmp() {
+    /// Divide `self` into `RcSlice`s pointing to the runs of elements
+    /// separated by elements that match `pred`, like `<[T]>::split`.
+    /// The matching elements are not included in any part.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 0, 2, 3, 0, 0, 4]);
+    /// let parts: Vec<_> = x.split_owned(|&n| n == 0).map(|p| p.to_vec()).collect();
+    /// assert_eq!(parts, [vec![1], vec![2, 3], vec![], vec![4]]);
+    /// ```
+    pub fn split_owned<F: FnMut(&T) -> bool>(self, pred: F) -> SplitOwned<T, F> {
+        SplitOwned { rest: Some(self), pred }
+    }
+
+    /// Divide `self` into at most `n` `RcSlice`s pointing to the runs
+    /// of elements separated by elements that match `pred`, like
+    /// `<[T]>::splitn`. The last part contains the remainder of
+    /// `self`, including any further matching elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let line = RcSlice::from(*b"GET /index.html HTTP/1.1");
+    /// let parts: Vec<_> = line.splitn_owned(2, |&b| b == b' ').collect();
+    /// assert_eq!(&*parts[0], b"GET");
+    /// assert_eq!(&*parts[1], b"/index.html HTTP/1.1");
+    /// ```
+    pub fn splitn_owned<F: FnMut(&T) -> bool>(self, n: usize, pred: F) -> SplitNOwned<T, F> {
+        SplitNOwned { inner: self.split_owned(pred), n }
+    }
+
+    /// Construct a new `RcSlice` that only points to elements at
+    /// indices `lo` (inclusive) through `hi` (exclusive), without
+    /// checking the bounds.
+    ///
+    /// This is `slice` for code that has already validated the
+    /// indices.
+    ///
+    /// # Safety
+    ///
+    /// `lo <= hi` and `hi <= self.len()` must hold.
+    pub unsafe fn slice_unchecked(mut self, lo: usize, hi: usize) -> RcSlice<T> {
+        self.data = self.get_unchecked(lo..hi);
+        self
+    }
+
+    /// Construct a new `RcRef` pointing to the element at index
+    /// `i`, or `None` if `i` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::new(Box::new(["foo", "bar", "baz"]));
+    /// let bar = x.get_ref(1).unwrap();
+    /// drop(x);
+    /// assert_eq!(*bar, "bar");
+    /// ```
+    pub fn get_ref(&self, i: usize) -> Option<RcRef<T>> {
+        if i < self.len() {
+            Some(RcRef { slice: self.clone().slice(i, i + 1) })
+        } else {
+            None
+        }
+    }
+
+    /// Convert `self` into a `RcRef` pointing to the element at index
+    /// `i`, or `None` if `i` is out of bounds.
+    ///
+    /// This is `get_ref` without the reference-count modifications,
+    /// for when `self` isn't needed afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let table = RcSlice::from([("alice", 3), ("bob", 5)]);
+    /// let bob = table.clone().into_ref(1).unwrap();
+    /// drop(table);
+    /// assert_eq!(bob.1, 5);
+    /// ```
+    pub fn into_ref(self, i: usize) -> Option<RcRef<T>> {
+        if i < self.len() {
+            Some(RcRef { slice: self.slice(i, i + 1) })
+        } else {
+            None
+        }
+    }
+
+    /// Convert `self` into a `RcView` pointing to a value derived
+    /// from its elements by `f`.
+    ///
+    /// This allows keeping a reference into the elements, such as a
+    /// field of one of them or a `str` validated from bytes, without
+    /// a lifetime tied to a borrow of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let bytes = RcSlice::from(*b"name=value");
+    /// let value = bytes.map_ref(|b| std::str::from_utf8(&b[5..]).unwrap());
+    /// assert_eq!(&*value, "value");
+    /// ```
+    pub fn map_ref<U: ?Sized, F>(self, f: F) -> RcView<U>
+        where F: FnOnce(&[T]) -> &U
+    {
+        let data: *const U = f(&self);
+        let (_, header) = self.into_parts();
+        RcView { data, header, release: release_strong::<T> }
+    }
+
+    /// Construct a new `RcRef` pointing to the element at index
+    /// `i`, without checking the bounds.
+    ///
+    /// # Safety
+    ///
+    /// `i < self.len()` must hold.
+    pub unsafe fn get_ref_unchecked(&self, i: usize) -> RcRef<T> {
+        RcRef { slice: self.clone().slice_unchecked(i, i + 1) }
+    }
+
+    /// Divide `self` into its first element and the rest, or return
+    /// `None` if it is empty, like `<[T]>::split_first`.
+    ///
+    /// Both parts keep the allocation alive, so they can be stored or
+    /// sent elsewhere, unlike the borrowed parts from `split_first`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// fn sum(x: RcSlice<i32>) -> i32 {
+    ///     match x.split_first_owned() {
+    ///         Some((head, tail)) => *head + sum(tail),
+    ///         None => 0,
+    ///     }
+    /// }
+    /// assert_eq!(sum(RcSlice::from([1, 2, 3])), 6);
+    /// ```
+    pub fn split_first_owned(self) -> Option<(RcRef<T>, RcSlice<T>)> {
+        if self.is_empty() {
+            return None
+        }
+        let (first, rest) = self.split_at(1);
+        Some((RcRef { slice: first }, rest))
+    }
+
+    /// Divide `self` into its last element and the rest, or return
+    /// `None` if it is empty, like `<[T]>::split_last`.
+    pub fn split_last_owned(self) -> Option<(RcRef<T>, RcSlice<T>)> {
+        let len = self.len();
+        if len == 0 {
+            return None
+        }
+        let (rest, last) = self.split_at(len - 1);
+        Some((RcRef { slice: last }, rest))
+    }
+
+    /// Replace `self` with an empty `RcSlice`, returning the previous
+    /// value.
+    ///
+    /// This is like `mem::take`, and allows a `RcSlice` to be moved
+    /// out of a field that can't be left uninitialized. The empty
+    /// `RcSlice` left behind doesn't allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut x = RcSlice::new(Box::new([1, 2, 3]));
+    /// let y = x.take();
+    /// assert_eq!(&*x, []);
+    /// assert_eq!(&*y, [1, 2, 3]);
+    /// ```
+    pub fn take(&mut self) -> RcSlice<T> {
+        mem::take(self)
+    }
+
+    /// Get the elements viewed by `self` back as a `Box<[T]>`, if
+    /// `self` is the only `RcSlice` pointing into its allocation, or
+    /// return `self` unchanged otherwise.
+    ///
+    /// Like `Rc::try_unwrap`, this succeeds even if there are
+    /// `WeakSlice`s, which will then fail to upgrade. The elements
+    /// are moved (not cloned) into a new allocation, and any elements
+    /// of the allocation outside the view are dropped. An empty slice
+    /// that doesn't point into an allocation, like `RcSlice::empty()`,
+    /// always succeeds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::new(Box::new([1, 2, 3]));
+    /// let y = x.clone();
+    /// let x = x.try_unwrap().unwrap_err();
+    /// drop(y);
+    /// assert_eq!(&*x.slice_from(1).try_unwrap().unwrap(), [2, 3]);
+    /// ```
+    pub fn try_unwrap(self) -> Result<Box<[T]>, RcSlice<T>> {
+        if !is_counted(self.header) {
+            return if self.is_empty() { Ok(Box::new([])) } else { Err(self) }
+        }
+        if self.header().strong() != 1 {
+            return Err(self)
+        }
+        let (lo, len) = (self.offset(), self.len());
+        let (_, header) = self.into_parts();
+        unsafe {
+            let h = header.as_ref();
+            h.strong.set(0);
+            let elems = inner::take_elems::<Header, T>(header, h.len, lo, len);
+            release_weak::<T>(header);
+            Ok(elems)
+        }
+    }
+
+    /// Split `self` into its parts, without running its destructor.
+    fn into_parts(self) -> (*const [T], NonNull<Header>) {
+        let this = mem::ManuallyDrop::new(self);
+        (this.data, this.header)
+    }
+
+    /// The header of the allocation that `self` points into.
+    fn header(&self) -> &Header {
+        unsafe { self.header.as_ref() }
+    }
+
+    /// Check the internal invariants of `self`, if enabled.
+    #[inline]
+    fn check(&self) {
+        if checks::ENABLED && is_counted(self.header) {
+            checks::view(self.data, unsafe { whole(self.header) }, self.header().strong());
+        }
+    }
+
+    /// The index of the first element of `self` in the whole
+    /// allocation it points into.
+    ///
+    /// For zero-sized `T`, this is always 0, as the position of a
+    /// view can't be recovered from its address.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::new(Box::new([1, 2, 3, 4])).slice(1, 3);
+    /// assert_eq!(x.offset(), 1);
+    /// assert_eq!(&*x.full_slice(), [1, 2, 3, 4]);
+    /// ```
+    pub fn offset(&self) -> usize {
+        match ::core::mem::size_of::<T>() {
+            0 => 0,
+            _ if !is_counted(self.header) => 0,
+            size => (self.data as *const T as usize - inner::elems::<Header, T>(self.header) as usize) / size
+        }
+    }
+
+    /// Construct a new `RcSlice` pointing to every element of the
+    /// allocation that `self` points into, undoing any slicing.
+    pub fn full_slice(&self) -> RcSlice<T> {
+        let mut full = self.clone();
+        if is_counted(self.header) {
+            full.data = unsafe { whole(self.header) };
+        }
+        full
+    }
+
+    /// Whether `self` and `other` are the same view of the same
+    /// elements: the same elements of the same allocation, rather than
+    /// just equal ones.
+    ///
+    /// This is a cheap identity check, like `Rc::ptr_eq`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3]);
+    /// assert!(x.ptr_eq(&x.clone()));
+    /// assert!(!x.ptr_eq(&x.clone().slice_to(2)));
+    /// assert!(!x.ptr_eq(&RcSlice::from([1, 2, 3])));
+    /// ```
+    pub fn ptr_eq(&self, other: &RcSlice<T>) -> bool {
+        self.header == other.header && ptr::eq(self.data, other.data)
+    }
+
+    /// Whether `self` and `other` point into the same allocation,
+    /// possibly viewing different elements of it.
+    ///
+    /// Slices that don't own an allocation, like empty ones, only
+    /// count as sharing one if they are `ptr_eq`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3]);
+    /// assert!(x.same_allocation(&x.clone().slice_from(2)));
+    /// assert!(!x.same_allocation(&RcSlice::from([1, 2, 3])));
+    /// ```
+    pub fn same_allocation(&self, other: &RcSlice<T>) -> bool {
+        if is_counted(self.header) {
+            self.header == other.header
+        } else {
+            self.ptr_eq(other)
+        }
+    }
+
+    /// The number of `RcSlice`s pointing into the allocation that
+    /// `self` points into, including `self`, like `Rc::strong_count`.
+    ///
+    /// Each subslice counts separately, so this helps find long-lived
+    /// views that keep a large allocation alive. Slices that don't own
+    /// an allocation, like empty ones, aren't counted, and this
+    /// returns 1 for them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([1, 2, 3]);
+    /// let y = x.clone().slice_from(1);
+    /// let w = y.downgrade();
+    /// assert_eq!(x.strong_count(), 2);
+    /// assert_eq!(x.weak_count(), 1);
+    /// ```
+    pub fn strong_count(&self) -> usize {
+        if is_counted(self.header) { self.header().strong() } else { 1 }
+    }
+
+    /// The number of `WeakSlice`s pointing into the allocation that
+    /// `self` points into, like `Rc::weak_count`.
+    ///
+    /// Slices that don't own an allocation, like empty ones, aren't
+    /// counted, and this returns 0 for them.
+    pub fn weak_count(&self) -> usize {
+        if !is_counted(self.header) {
+            return 0
+        }
+        // one is held by the strong references together
+        self.header().weak() - 1
+    }
+
+    /// The whole allocation that `self` points into, along with an
+    /// address that identifies it uniquely while it is alive.
+    #[cfg(all(feature = "serde", feature = "std"))]
+    pub(crate) fn allocation(&self) -> (&[T], usize) {
+        if is_counted(self.header) {
+            (unsafe { whole(self.header) }, self.header.as_ptr() as usize)
+        } else {
+            (self, self.data as *const T as usize)
+        }
+    }
+
+    /// Whether `self` is the only `RcSlice` or `WeakSlice` pointing
+    /// into its allocation, so `get_mut` will succeed.
+    ///
+    /// Slices that don't point into an allocation, like
+    /// `RcSlice::empty()` and those from `from_static`, aren't unique,
+    /// though `get_mut` still succeeds for empty ones.
+    pub fn is_unique(&self) -> bool {
+        is_counted(self.header) && self.header().is_unique()
+    }
+
+    /// Get mutable access to the elements viewed by `self`, if there
+    /// are no other `RcSlice`s or `WeakSlice`s pointing into the same
+    /// allocation, or if `self` is empty and doesn't point into an
+    /// allocation.
+    ///
+    /// This is like `Rc::get_mut`, and allows a slice to be modified
+    /// while it is uniquely owned, before it is shared.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut x = RcSlice::new(Box::new([0; 4])).slice_from(1);
+    /// x.get_mut().unwrap()[0] = 1;
+    ///
+    /// let y = x.clone();
+    /// assert!(!x.is_unique());
+    /// assert!(x.get_mut().is_none());
+    /// drop(y);
+    /// assert_eq!(&*x, [1, 0, 0]);
+    /// ```
+    pub fn get_mut(&mut self) -> Option<&mut [T]> {
+        if self.is_unique() {
+            Some(unsafe { &mut *(self.data as *mut [T]) })
+        } else if self.is_empty() && !is_counted(self.header) {
+            // there are no elements for anything else to see
+            Some(&mut [])
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> RcSlice<MaybeUninit<T>> {
+    /// Convert `self` into a `RcSlice<T>`, pointing to the same
+    /// elements.
+    ///
+    /// # Safety
+    ///
+    /// All of the elements of the allocation must be initialized, not
+    /// just the ones `self` points to, since they are dropped as `T`s
+    /// when the last `RcSlice<T>` pointing to them is.
+    pub unsafe fn assume_init(self) -> RcSlice<T> {
+        let (data, header) = self.into_parts();
+        RcSlice {
+            data: data as *const [T],
+            header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone> RcSlice<T> {
+    /// Construct a new `RcSlice` containing clones of the elements of
+    /// `slice`.
+    ///
+    /// The elements are cloned straight into the new allocation. With
+    /// the `unstable` feature, they are copied all at once when
+    /// `T: Copy`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from_slice(b"header: value");
+    /// assert_eq!(&*x.slice_to(6), b"header");
+    /// ```
+    pub fn from_slice(slice: &[T]) -> RcSlice<T> {
+        if slice.is_empty() {
+            return RcSlice::default()
+        }
+        let header = inner::from_slice::<Header, T>(Header::new(slice.len()), slice);
+        RcSlice::from_header(header, slice.len())
+    }
+
+    /// Construct a new `RcSlice` containing `n` clones of `value`, like
+    /// `vec![value; n]`.
+    ///
+    /// The elements are written straight into the new allocation.
+    /// With the `unstable` feature, this is a single `memset` for
+    /// `u8`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let zeros = RcSlice::repeat(0u8, 4096);
+    /// assert!(zeros.iter().all(|&b| b == 0));
+    /// assert_eq!(&*RcSlice::repeat("ab", 2), ["ab", "ab"]);
+    /// ```
+    pub fn repeat(value: T, n: usize) -> RcSlice<T> {
+        if n == 0 {
+            return RcSlice::default()
+        }
+        let header = inner::from_elem::<Header, T>(Header::new(n), value, n);
+        RcSlice::from_header(header, n)
+    }
+
+    /// Construct a new `RcSlice` containing clones of the elements of
+    /// each of `parts` in turn, like `<[T]>::concat`.
+    ///
+    /// The elements are cloned straight into a single new allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total length overflows `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let body = RcSlice::from(*b"hello");
+    /// let message = RcSlice::concat([&b"len=5;"[..], &body]);
+    /// assert_eq!(&*message, b"len=5;hello");
+    /// ```
+    pub fn concat<I>(parts: I) -> RcSlice<T>
+        where I: IntoIterator, I::Item: AsRef<[T]>
+    {
+        let parts: Vec<I::Item> = parts.into_iter().collect();
+        let len = parts.iter()
+            .try_fold(0usize, |len, part| len.checked_add(part.as_ref().len()))
+            .expect("RcSlice::concat: total length overflows usize");
+        if len == 0 {
+            return RcSlice::default()
+        }
+        let header = inner::from_parts::<Header, T, I::Item>(Header::new(len), &parts, len);
+        RcSlice::from_header(header, len)
+    }
+
+    /// Construct a new `RcSlice` containing clones of the elements of
+    /// each of `parts` in turn, with the elements of `sep` between
+    /// each, like `<[T]>::join`.
+    ///
+    /// The elements are cloned straight into a single new allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let parts = [RcSlice::from(*b"a"), RcSlice::from(*b"bc")];
+    /// assert_eq!(&*RcSlice::join(&parts, b", "), b"a, bc");
+    /// ```
+    pub fn join(parts: &[RcSlice<T>], sep: &[T]) -> RcSlice<T> {
+        let mut pieces = Vec::with_capacity(2 * parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                pieces.push(sep);
+            }
+            pieces.push(&**part);
+        }
+        RcSlice::concat(pieces)
+    }
+
+    /// Construct a new `RcSlice` containing clones of the elements of
+    /// each of the slices in `nested` in turn, like `<[[T]]>::concat`.
+    ///
+    /// The elements are cloned straight into a single new allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let batches = RcSlice::from([RcSlice::from([1, 2]), RcSlice::from([3])]);
+    /// assert_eq!(&*RcSlice::flatten(batches), [1, 2, 3]);
+    /// ```
+    pub fn flatten(nested: RcSlice<RcSlice<T>>) -> RcSlice<T> {
+        RcSlice::concat(&*nested)
+    }
+
+    /// Get mutable access to the elements viewed by `self`, first
+    /// copying them into a new allocation if any other `RcSlice` or
+    /// `WeakSlice` points into the current one.
+    ///
+    /// This is like `Rc::make_mut`, for copy-on-write: the other
+    /// handles are left pointing to the original elements. Only the
+    /// elements viewed by `self` are copied.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let mut x = RcSlice::new(Box::new([1, 2, 3]));
+    /// let y = x.clone();
+    /// x.make_mut()[0] = 10;
+    /// assert_eq!(&*x, [10, 2, 3]);
+    /// assert_eq!(&*y, [1, 2, 3]);
+    /// ```
+    pub fn make_mut(&mut self) -> &mut [T] {
+        if !self.is_unique() {
+            *self = RcSlice::from_slice(self);
+        }
+        unsafe { &mut *(self.data as *mut [T]) }
+    }
+
+    /// Construct an iterator over clones of the elements of `self`.
+    ///
+    /// Unlike `self.iter().cloned()`, this holds its own reference to
+    /// the elements rather than borrowing `self`, so it can outlive
+    /// `self`, or be moved elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// fn evens(x: &RcSlice<u32>) -> impl Iterator<Item = u32> + 'static {
+    ///     x.iter_cloned().filter(|n| n % 2 == 0)
+    /// }
+    /// let it = evens(&RcSlice::from([1, 2, 3, 4]));
+    /// assert_eq!(it.collect::<Vec<_>>(), [2, 4]);
+    /// ```
+    pub fn iter_cloned(&self) -> IterCloned<T> {
+        IterCloned { rest: self.clone() }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<T: Clone> RcSlice<T> {
+    /// Construct a `RcSlice` containing the elements of `self` in a
+    /// random order.
+    ///
+    /// If `self` is the only reference to its allocation, the
+    /// elements are shuffled in place, otherwise they are copied into
+    /// a new allocation and the original is left untouched.
+    ///
+    /// This requires the `rand` feature.
+    pub fn shuffled<R: Rng + ?Sized>(mut self, rng: &mut R) -> RcSlice<T> {
+        if let Some(data) = self.get_mut() {
+            data.shuffle(rng);
+            return self
+        }
+
+        let mut copy = RcSlice::from_slice(&self);
+        copy.get_mut().unwrap().shuffle(rng);
+        copy
+    }
+
+    /// Construct a new `RcSlice` containing `k` elements of `self`
+    /// chosen at random, without replacement.
+    ///
+    /// If `k` is greater than `self.len()`, all the elements are
+    /// chosen. The order of the result is not specified.
+    ///
+    /// This requires the `rand` feature.
+    pub fn choose_owned<R: Rng + ?Sized>(&self, rng: &mut R, k: usize) -> RcSlice<T> {
+        let mut builder = RcSliceBuilder::new();
+        builder.extend(self.choose_multiple(rng, k).cloned());
+        builder.build()
+    }
+}
+
+#[cfg(feature = "digest")]
+impl RcSlice<u8> {
+    /// Compute the digest of the bytes viewed by `self` with the hash
+    /// function `D`.
+    ///
+    /// This requires the `digest` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate shared_slice; extern crate sha2;
+    /// use shared_slice::rc::RcSlice;
+    /// use sha2::{Digest, Sha256};
+    ///
+    /// # fn main() {
+    /// let x = RcSlice::new(Box::new(*b"hello world"));
+    /// assert_eq!(x.hash_with::<Sha256>(), Sha256::digest(b"hello world"));
+    /// # }
+    /// ```
+    pub fn hash_with<D: Digest>(&self) -> Output<D> {
+        D::digest(&**self)
+    }
+}
+
+#[cfg(feature = "xxhash-rust")]
+impl RcSlice<u8> {
+    /// Compute a fast, non-cryptographic 64-bit fingerprint of the
+    /// bytes viewed by `self`, with XXH3.
+    ///
+    /// This is stable across runs and platforms, so can be used as a
+    /// key for content-addressed caches, but is not resistant to
+    /// deliberate collisions.
+    ///
+    /// This requires the `xxhash-rust` feature.
+    pub fn fingerprint64(&self) -> u64 {
+        xxh3_64(self)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl RcSlice<u8> {
+    /// Find the leftmost-first match of `re` in `self`, returning it
+    /// as a `RcSlice` pointing into the same allocation.
+    ///
+    /// This requires the `regex` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate shared_slice; extern crate regex;
+    /// use shared_slice::rc::RcSlice;
+    /// use regex::bytes::Regex;
+    ///
+    /// # fn main() {
+    /// let re = Regex::new("[0-9]+").unwrap();
+    /// let x = RcSlice::new(Box::new(*b"abc 123 def"));
+    /// assert_eq!(&*x.find_owned(&re).unwrap(), b"123");
+    /// # }
+    /// ```
+    pub fn find_owned(&self, re: &Regex) -> Option<RcSlice<u8>> {
+        re.find(self).map(|m| self.clone().slice(m.start(), m.end()))
+    }
+
+    /// Create an iterator over the successive non-overlapping matches
+    /// of `re` in `self`, yielding each as a `RcSlice` pointing into
+    /// the same allocation.
+    ///
+    /// This requires the `regex` feature.
+    pub fn find_iter_owned<'r>(&self, re: &'r Regex) -> FindOwned<'r> {
+        FindOwned {
+            re,
+            haystack: self.clone(),
+            pos: 0,
+            last_end: None,
+        }
+    }
+
+    /// Match `re` against `self`, returning the capture groups (with
+    /// the whole match at index 0) as `RcSlice`s pointing into the
+    /// same allocation.
+    ///
+    /// Groups that did not participate in the match are `None`.
+    ///
+    /// This requires the `regex` feature.
+    pub fn captures_owned(&self, re: &Regex) -> Option<Vec<Option<RcSlice<u8>>>> {
+        re.captures(self).map(|caps| {
+            caps.iter()
+                .map(|m| m.map(|m| self.clone().slice(m.start(), m.end())))
+                .collect()
+        })
+    }
+}
+
+/// An iterator over the matches of a regular expression in a
+/// `RcSlice<u8>`.
+///
+/// This is created by `RcSlice::find_iter_owned`, and requires the
+/// `regex` feature.
+#[cfg(feature = "regex")]
+pub struct FindOwned<'r> {
+    re: &'r Regex,
+    haystack: RcSlice<u8>,
+    pos: usize,
+    last_end: Option<usize>,
+}
+
+#[cfg(feature = "regex")]
+impl<'r> Iterator for FindOwned<'r> {
+    type Item = RcSlice<u8>;
+
+    fn next(&mut self) -> Option<RcSlice<u8>> {
+        loop {
+            if self.pos > self.haystack.len() {
+                return None
+            }
+            let m = self.re.find_at(&self.haystack, self.pos)?;
+            if m.start() == m.end() {
+                // step over empty matches, so that we always make
+                // progress, and skip those directly after the
+                // previous match.
+                self.pos = m.end() + 1;
+                if Some(m.end()) == self.last_end {
+                    continue
+                }
+            } else {
+                self.pos = m.end();
+            }
+            self.last_end = Some(m.end());
+            return Some(self.haystack.clone().slice(m.start(), m.end()))
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl<'r> FusedIterator for FindOwned<'r> {}
+
+#[cfg(feature = "std")]
+impl RcSlice<u8> {
+    /// Construct a new `RcSlice` containing the contents of the file
+    /// at `path`.
+    ///
+    /// This requires the `std` feature.
+    pub fn from_file<P: AsRef<path::Path>>(path: P) -> io::Result<RcSlice<u8>> {
+        let mut file = fs::File::open(path)?;
+        // the length is only a guess, since the file may change while
+        // it is being read
+        let size = file.metadata().map_or(0, |m| m.len() as usize);
+        let mut builder = RcSliceBuilder::with_capacity(size);
+        builder.read_to_end_from(&mut file)?;
+        Ok(builder.build())
+    }
+
+    /// View `self` as an `IoSlice`, for writing it along with other
+    /// buffers with `Write::write_vectored` without copying them into
+    /// one contiguous buffer first.
+    ///
+    /// This requires the `std` feature.
+    pub fn as_io_slice(&self) -> io::IoSlice<'_> {
+        io::IoSlice::new(self)
+    }
+}
+
+#[cfg(all(feature = "flate2", feature = "std"))]
+impl RcSlice<u8> {
+    /// Construct a new `RcSlice` containing the decompression of the
+    /// gzip data `input`.
+    ///
+    /// The output is decompressed straight into its allocation, which
+    /// is sized up front with the uncompressed size recorded at the
+    /// end of `input` (within the limits of compression, to avoid huge
+    /// allocations for corrupt data), so that it is allocated once when
+    /// that size is accurate. If
+    /// `input` has several gzip members, they are decompressed one
+    /// after the other.
+    ///
+    /// This requires the `flate2` and `std` features.
+    pub fn decompress_gzip(input: &[u8]) -> io::Result<RcSlice<u8>> {
+        // deflate cannot compress by more than a factor of 1032
+        let size = match *input {
+            [.., a, b, c, d] => u32::from_le_bytes([a, b, c, d]) as usize,
+            _ => 0,
+        };
+        let mut out = RcSliceBuilder::with_capacity(cmp::min(size, input.len().saturating_mul(1032)));
+        out.read_to_end_from(MultiGzDecoder::new(input))?;
+        Ok(out.build())
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+impl RcSlice<u8> {
+    /// Decode the text in `self` from `encoding` into a `RcStr`,
+    /// along with whether there were any malformed sequences, which
+    /// are replaced with U+FFFD.
+    ///
+    /// A byte order mark for `encoding` at the start is removed. When
+    /// the bytes are already valid UTF-8 that decodes to itself (such
+    /// as ASCII text in an ASCII-compatible encoding), the result
+    /// points into `self`'s allocation, rather than copying it.
+    ///
+    /// This requires the `encoding_rs` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate shared_slice; extern crate encoding_rs;
+    /// use shared_slice::rc::RcSlice;
+    /// use encoding_rs::WINDOWS_1252;
+    ///
+    /// # fn main() {
+    /// let (text, had_errors) = RcSlice::from(*b"caf\xe9").decode(WINDOWS_1252);
+    /// assert_eq!((&*text, had_errors), ("café", false));
+    ///
+    /// let ascii = RcSlice::from(*b"plain");
+    /// assert_eq!(ascii.decode(WINDOWS_1252).0.as_ptr(), ascii.as_ptr());
+    /// # }
+    /// ```
+    pub fn decode(&self, encoding: &'static Encoding) -> (RcStr, bool) {
+        let (text, had_errors) = encoding.decode_with_bom_removal(self);
+        let text = match text {
+            Cow::Borrowed(s) => {
+                // a suffix of `self`, after any byte order mark
+                let lo = s.as_ptr() as usize - self.as_ptr() as usize;
+                unsafe { RcStr::from_utf8_unchecked(self.clone().slice(lo, lo + s.len())) }
+            }
+            Cow::Owned(s) => RcStr::from(s),
+        };
+        (text, had_errors)
+    }
+}
+
+#[cfg(all(feature = "zstd", feature = "std"))]
+impl RcSlice<u8> {
+    /// Construct a new `RcSlice` containing the decompression of the
+    /// zstd data `input`.
+    ///
+    /// The output is decompressed straight into its allocation. If
+    /// `input` declares its uncompressed size, that is allocated up
+    /// front with that size, up to 64 MiB (so that corrupt data can't
+    /// force a huge allocation). If `input` has several
+    /// frames, they are decompressed one after the other.
+    ///
+    /// This requires the `zstd` and `std` features.
+    pub fn decompress_zstd(input: &[u8]) -> io::Result<RcSlice<u8>> {
+        const MAX_PREALLOCATE: u64 = 64 << 20;
+        let size = match zstd::zstd_safe::get_frame_content_size(input) {
+            Ok(Some(size)) => cmp::min(size, MAX_PREALLOCATE) as usize,
+            _ => 0,
+        };
+        let mut out = RcSliceBuilder::with_capacity(size);
+        out.read_to_end_from(zstd::stream::read::Decoder::with_buffer(input)?)?;
+        Ok(out.build())
+    }
+}
+
+impl RcSlice<u8> {
+    /// Whether `needle` occurs in `self`.
+    ///
+    /// This uses SIMD with the `memchr` feature.
+    pub fn contains_subslice(&self, needle: &[u8]) -> bool {
+        self.find_subslice(needle).is_some()
+    }
+
+    /// The index of the first occurrence of `needle` in `self`, or
+    /// `None` if there is none.
+    ///
+    /// This uses SIMD with the `memchr` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::new(Box::new(*b"key: value\r\n"));
+    /// assert_eq!(x.find_subslice(b"\r\n"), Some(10));
+    /// assert!(!x.contains_subslice(b"\n\n"));
+    /// ```
+    pub fn find_subslice(&self, needle: &[u8]) -> Option<usize> {
+        search::find(self, needle)
+    }
+
+    /// Iterate over the parts of `self` separated by the byte
+    /// `delim`, as `RcSlice`s pointing into the same allocation.
+    ///
+    /// Like `<[u8]>::split`, this yields an empty slice for each pair
+    /// of adjacent delimiters, and at the start or end if `self`
+    /// starts or ends with `delim`. This uses SIMD with the `memchr`
+    /// feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::new(Box::new(*b"a,bc,,d"));
+    /// let parts: Vec<_> = x.split_byte_owned(b',').collect();
+    /// assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(), [&b"a"[..], b"bc", b"", b"d"]);
+    /// ```
+    pub fn split_byte_owned(self, delim: u8) -> SplitByteOwned {
+        SplitByteOwned { rest: Some(self), delim }
+    }
+
+    /// Split `self` into content-defined chunks, sharing the same
+    /// allocation.
+    ///
+    /// The boundaries between chunks are chosen by a rolling hash of
+    /// the bytes before them (as in FastCDC), so editing the data only
+    /// changes the chunks near the edit. This allows deduplicating
+    /// storage of similar data. Each chunk has length between
+    /// `min_size` and `max_size`, except possibly the last, and they
+    /// are usually around `avg_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < min_size <= avg_size <= max_size`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let data: Vec<u8> = (0..10_000u32).map(|i| (i * i >> 3) as u8).collect();
+    /// let x = RcSlice::new(data.into_boxed_slice());
+    ///
+    /// let chunks: Vec<_> = x.clone().cdc_chunks(256, 1024, 4096).collect();
+    /// assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), x.len());
+    /// ```
+    pub fn cdc_chunks(self, min_size: usize, avg_size: usize, max_size: usize) -> CdcChunks {
+        cdc::check_sizes(min_size, avg_size, max_size);
+        CdcChunks {
+            rest: self,
+            sizes: (min_size, avg_size, max_size),
+        }
+    }
+}
+
+impl<T: Ord + Clone> RcSlice<T> {
+    /// Construct a new `RcSlice` containing the elements of all of the
+    /// sorted `slices`, in sorted order.
+    ///
+    /// Equal elements are ordered by the position of their slice in
+    /// `slices`. The result is built in a single allocation; use
+    /// `MergeSorted` to produce the elements lazily instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let runs = vec![RcSlice::new(Box::new([1, 4, 7])),
+    ///                 RcSlice::new(Box::new([2, 3, 8]))];
+    /// let merged = RcSlice::merge_sorted(runs);
+    /// assert_eq!(&*merged, [1, 2, 3, 4, 7, 8]);
+    /// ```
+    pub fn merge_sorted<I>(slices: I) -> RcSlice<T>
+        where I: IntoIterator<Item = RcSlice<T>>
+    {
+        let iter = MergeSorted::new(slices);
+        let mut builder = RcSliceBuilder::with_capacity(iter.len());
+        for x in iter {
+            builder.push(x);
+        }
+        builder.build()
+    }
+}
+
+impl<T> Clone for RcSlice<T> {
+    fn clone(&self) -> RcSlice<T> {
+        retain(self.header);
+        RcSlice {
+            data: self.data,
+            header: self.header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// An empty `RcSlice`, which doesn't allocate.
+impl<T> Default for RcSlice<T> {
+    fn default() -> RcSlice<T> {
+        RcSlice::empty()
+    }
+}
+
+impl<T> From<Vec<T>> for RcSlice<T> {
+    fn from(v: Vec<T>) -> RcSlice<T> {
+        RcSlice::new(v.into_boxed_slice())
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for RcSlice<T> {
+    fn from(array: [T; N]) -> RcSlice<T> {
+        if N == 0 {
+            return RcSlice::default()
+        }
+        RcSlice::from_header(inner::from_array::<Header, T, N>(Header::new(N), array), N)
+    }
+}
+
+impl<'a, T: Clone> From<&'a [T]> for RcSlice<T> {
+    fn from(slice: &'a [T]) -> RcSlice<T> {
+        RcSlice::from_slice(slice)
+    }
+}
+
+/// Consume a `RcSlice`, yielding its elements by value.
+///
+/// If this is the only `RcSlice` pointing to the allocation, the
+/// elements are moved out of it and it is freed, otherwise they are
+/// cloned as needed.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::rc::RcSlice;
+///
+/// let x = RcSlice::from([String::from("a"), String::from("b")]);
+/// let y = x.clone();
+/// let cloned: Vec<String> = x.into_iter().collect();
+/// let moved: Vec<String> = y.into_iter().collect();
+/// assert_eq!(cloned, moved);
+/// ```
+impl<T: Clone> IntoIterator for RcSlice<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        match self.try_unwrap() {
+            Ok(elems) => IntoIter { elems: Elems::Owned(elems.into_vec().into_iter()) },
+            Err(rest) => IntoIter { elems: Elems::Shared(IterCloned { rest }) },
+        }
+    }
+}
+
+impl<T> Drop for RcSlice<T> {
+    fn drop(&mut self) {
+        unsafe { release_strong::<T>(self.header) }
+    }
+}
+
+impl<T> ops::Deref for RcSlice<T> {
+    type Target = [T];
+    fn deref<'a>(&'a self) -> &'a [T] {
+        self.check();
+        unsafe {&*self.data}
+    }
+}
+
+impl<T> AsRef<[T]> for RcSlice<T> {
+    fn as_ref(&self) -> &[T] { &**self }
+}
+
+impl<T: PartialEq> PartialEq for RcSlice<T> {
+    fn eq(&self, other: &RcSlice<T>) -> bool { identity::slice_eq(self, other) }
+}
+impl<T: Eq> Eq for RcSlice<T> {}
+
+impl<T: PartialOrd> PartialOrd for RcSlice<T> {
+    fn partial_cmp(&self, other: &RcSlice<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+    fn lt(&self, other: &RcSlice<T>) -> bool { **self < **other }
+    fn le(&self, other: &RcSlice<T>) -> bool { **self <= **other }
+    fn gt(&self, other: &RcSlice<T>) -> bool { **self > **other }
+    fn ge(&self, other: &RcSlice<T>) -> bool { **self >= **other }
+}
+impl<T: Ord> Ord for RcSlice<T> {
+    fn cmp(&self, other: &RcSlice<T>) -> cmp::Ordering { identity::slice_cmp(self, other) }
+}
+
+impl<T: Hash> Hash for RcSlice<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// An iterator merging several sorted `RcSlice`s, yielding clones
+/// of their elements in sorted order.
+///
+/// Equal elements are yielded in the order of the position of their
+/// slice in the input.
+pub struct MergeSorted<T> {
+    heap: BinaryHeap<MergeHead<T>>,
+    remaining: usize,
+}
+
+// A non-empty slice being merged, ordered so that the `BinaryHeap`
+// yields the smallest first element (then the earliest input) first.
+struct MergeHead<T> {
+    slice: RcSlice<T>,
+    index: usize,
+}
+
+impl<T: Ord> MergeSorted<T> {
+    /// Construct a new `MergeSorted` over the sorted `slices`.
+    pub fn new<I>(slices: I) -> MergeSorted<T>
+        where I: IntoIterator<Item = RcSlice<T>>
+    {
+        let heap: BinaryHeap<_> = slices.into_iter()
+            .enumerate()
+            .filter(|(_, slice)| !slice.is_empty())
+            .map(|(index, slice)| MergeHead { slice, index })
+            .collect();
+        let remaining = heap.iter().map(|head| head.slice.len()).sum();
+        MergeSorted { heap, remaining }
+    }
+}
+
+impl<T: Ord + Clone> Iterator for MergeSorted<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        let mut head = self.heap.peek_mut()?;
+        let x = head.slice[0].clone();
+        if head.slice.len() == 1 {
+            PeekMut::pop(head);
+        } else {
+            let rest: *const [T] = &head.slice[1..];
+            head.slice.data = rest;
+        }
+        self.remaining -= 1;
+        Some(x)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Ord + Clone> ExactSizeIterator for MergeSorted<T> {}
+
+impl<T: Ord + Clone> FusedIterator for MergeSorted<T> {}
+
+impl<T: Ord> PartialEq for MergeHead<T> {
+    fn eq(&self, other: &MergeHead<T>) -> bool { self.cmp(other) == cmp::Ordering::Equal }
+}
+impl<T: Ord> Eq for MergeHead<T> {}
+
+impl<T: Ord> PartialOrd for MergeHead<T> {
+    fn partial_cmp(&self, other: &MergeHead<T>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: Ord> Ord for MergeHead<T> {
+    fn cmp(&self, other: &MergeHead<T>) -> cmp::Ordering {
+        other.slice[0].cmp(&self.slice[0]).then(other.index.cmp(&self.index))
+    }
+}
+
+/// A cursor for decoding binary data from a `RcSlice<u8>` without
+/// copying.
+///
+/// Each `take_...` method consumes bytes from the front of the
+/// remaining data, returning them as a `RcSlice` pointing into the
+/// same allocation, or returns `None` and leaves the cursor unchanged
+/// if there is not enough data.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::rc::{FrameCursor, RcSlice};
+///
+/// // a length-prefixed payload, followed by a line of text
+/// let data = RcSlice::new(Box::new(*b"\x05helloworld\n"));
+/// let mut cursor = FrameCursor::new(data);
+///
+/// let len = cursor.take_bytes(1).unwrap()[0] as usize;
+/// let payload = cursor.take_bytes(len).unwrap();
+/// assert_eq!(&*payload, b"hello");
+///
+/// assert_eq!(cursor.peek(3), Some(&b"wor"[..]));
+/// assert_eq!(&*cursor.take_until(b'\n').unwrap(), b"world");
+/// assert!(cursor.is_empty());
+/// ```
+pub struct FrameCursor {
+    rest: RcSlice<u8>,
+}
+
+impl FrameCursor {
+    /// Construct a new `FrameCursor` at the start of `data`.
+    pub fn new(data: RcSlice<u8>) -> FrameCursor {
+        FrameCursor { rest: data }
+    }
+
+    /// The data that has not been consumed yet.
+    pub fn remaining(&self) -> &[u8] {
+        &self.rest
+    }
+
+    /// Construct a new `RcSlice` pointing to the data that has not been
+    /// consumed yet.
+    pub fn remaining_owned(&self) -> RcSlice<u8> {
+        self.rest.clone()
+    }
+
+    /// Whether all the data has been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    /// The next `n` bytes, without consuming them, or `None` if fewer
+    /// than `n` remain.
+    pub fn peek(&self, n: usize) -> Option<&[u8]> {
+        self.rest.get(..n)
+    }
+
+    /// Consume the next `n` bytes, or return `None` if fewer than `n`
+    /// remain.
+    pub fn take_bytes(&mut self, n: usize) -> Option<RcSlice<u8>> {
+        if n > self.rest.len() {
+            return None
+        }
+        let taken = self.rest.clone().slice_to(n);
+        let rest: *const [u8] = &self.rest[n..];
+        self.rest.data = rest;
+        Some(taken)
+    }
+
+    /// Consume the bytes up to and including the next occurrence of
+    /// `delim`, returning those before it, or return `None` if `delim`
+    /// does not occur.
+    pub fn take_until(&mut self, delim: u8) -> Option<RcSlice<u8>> {
+        let i = search::find_byte(delim, &self.rest)?;
+        let taken = self.take_bytes(i);
+        let rest: *const [u8] = &self.rest[1..];
+        self.rest.data = rest;
+        taken
+    }
+}
+
+impl Clone for FrameCursor {
+    fn clone(&self) -> FrameCursor {
+        FrameCursor { rest: self.rest.clone() }
+    }
+}
+
+impl fmt::Debug for FrameCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrameCursor").field("remaining", &self.rest.len()).finish()
+    }
+}
+
+/// An iterator over the parts of a `RcSlice<u8>` separated by a byte.
+///
+/// This is created by `RcSlice::split_byte_owned`.
+pub struct SplitByteOwned {
+    rest: Option<RcSlice<u8>>,
+    delim: u8,
+}
+
+impl Iterator for SplitByteOwned {
+    type Item = RcSlice<u8>;
+    fn next(&mut self) -> Option<RcSlice<u8>> {
+        let rest = self.rest.as_mut()?;
+        match search::find_byte(self.delim, rest) {
+            Some(i) => {
+                let part = rest.clone().slice_to(i);
+                let after: *const [u8] = &rest[i + 1..];
+                rest.data = after;
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.rest {
+            Some(ref rest) => (1, Some(rest.len() + 1)),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl DoubleEndedIterator for SplitByteOwned {
+    fn next_back(&mut self) -> Option<RcSlice<u8>> {
+        let rest = self.rest.as_mut()?;
+        match search::rfind_byte(self.delim, rest) {
+            Some(i) => {
+                let part = rest.clone().slice_from(i + 1);
+                let before: *const [u8] = &rest[..i];
+                rest.data = before;
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+}
+
+impl FusedIterator for SplitByteOwned {}
+
+/// An iterator over the content-defined chunks of a `RcSlice<u8>`.
+///
+/// This is created by `RcSlice::cdc_chunks`.
+pub struct CdcChunks {
+    rest: RcSlice<u8>,
+    sizes: (usize, usize, usize),
+}
+
+impl Iterator for CdcChunks {
+    type Item = RcSlice<u8>;
+    fn next(&mut self) -> Option<RcSlice<u8>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let (min, avg, max) = self.sizes;
+        let cut = cdc::cut_point(&self.rest, min, avg, max);
+        let chunk = self.rest.clone().slice_to(cut);
+        let rest: *const [u8] = &self.rest[cut..];
+        self.rest.data = rest;
+        Some(chunk)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len();
+        (n.div_ceil(self.sizes.2), Some(n.div_ceil(self.sizes.0)))
+    }
+}
+
+impl FusedIterator for CdcChunks {}
+
+/// An iterator over `RcSlice`s pointing to chunks of a `RcSlice`.
+///
+/// This is created by `RcSlice::chunks_owned`.
+pub struct ChunksOwned<T> {
+    rest: RcSlice<T>,
+    size: usize,
+}
+
+impl<T> Iterator for ChunksOwned<T> {
+    type Item = RcSlice<T>;
+    fn next(&mut self) -> Option<RcSlice<T>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let n = cmp::min(self.size, self.rest.len());
+        let rest = self.rest.split_off(n);
+        Some(mem::replace(&mut self.rest, rest))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len().div_ceil(self.size);
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for ChunksOwned<T> {
+    fn next_back(&mut self) -> Option<RcSlice<T>> {
+        let len = self.rest.len();
+        if len == 0 {
+            return None
+        }
+        let n = match len % self.size {
+            0 => self.size,
+            n => n,
+        };
+        Some(self.rest.split_off(len - n))
+    }
+}
+
+impl<T> ExactSizeIterator for ChunksOwned<T> {}
+impl<T> FusedIterator for ChunksOwned<T> {}
+
+/// An iterator over `RcSlice`s pointing to chunks of exactly the same
+/// length of a `RcSlice`.
+///
+/// This is created by `RcSlice::chunks_exact_owned`.
+pub struct ChunksExactOwned<T> {
+    rest: RcSlice<T>,
+    rem: RcSlice<T>,
+    size: usize,
+}
+
+impl<T> ChunksExactOwned<T> {
+    /// The elements at the end that don't make up a whole chunk, and
+    /// so are never yielded.
+    pub fn remainder(&self) -> &RcSlice<T> {
+        &self.rem
+    }
+}
+
+impl<T> Iterator for ChunksExactOwned<T> {
+    type Item = RcSlice<T>;
+    fn next(&mut self) -> Option<RcSlice<T>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = self.rest.split_off(self.size);
+        Some(mem::replace(&mut self.rest, rest))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len() / self.size;
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for ChunksExactOwned<T> {
+    fn next_back(&mut self) -> Option<RcSlice<T>> {
+        let len = self.rest.len();
+        if len == 0 {
+            return None
+        }
+        Some(self.rest.split_off(len - self.size))
+    }
+}
+
+impl<T> ExactSizeIterator for ChunksExactOwned<T> {}
+impl<T> FusedIterator for ChunksExactOwned<T> {}
+
+/// An iterator over `RcSlice`s pointing to chunks of a `RcSlice`,
+/// starting at the end.
+///
+/// This is created by `RcSlice::rchunks_owned`.
+pub struct RChunksOwned<T> {
+    rest: RcSlice<T>,
+    size: usize,
+}
+
+impl<T> Iterator for RChunksOwned<T> {
+    type Item = RcSlice<T>;
+    fn next(&mut self) -> Option<RcSlice<T>> {
+        let len = self.rest.len();
+        if len == 0 {
+            return None
+        }
+        Some(self.rest.split_off(len - cmp::min(self.size, len)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.rest.len().div_ceil(self.size);
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for RChunksOwned<T> {
+    fn next_back(&mut self) -> Option<RcSlice<T>> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let n = match self.rest.len() % self.size {
+            0 => self.size,
+            n => n,
+        };
+        let rest = self.rest.split_off(n);
+        Some(mem::replace(&mut self.rest, rest))
+    }
+}
+
+impl<T> ExactSizeIterator for RChunksOwned<T> {}
+impl<T> FusedIterator for RChunksOwned<T> {}
+
+/// An iterator over `RcSlice`s pointing to overlapping windows of a
+/// `RcSlice`.
+///
+/// This is created by `RcSlice::windows_owned`.
+pub struct WindowsOwned<T> {
+    // the elements covered by the windows not yet yielded
+    rest: RcSlice<T>,
+    size: usize,
+}
+
+impl<T> Iterator for WindowsOwned<T> {
+    type Item = RcSlice<T>;
+    fn next(&mut self) -> Option<RcSlice<T>> {
+        if self.rest.len() < self.size {
+            return None
+        }
+        let window = self.rest.clone().slice_to(self.size);
+        self.rest.data = &self.rest[1..];
+        Some(window)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.rest.len() + 1).saturating_sub(self.size);
+        (n, Some(n))
+    }
+}
+
+impl<T> DoubleEndedIterator for WindowsOwned<T> {
+    fn next_back(&mut self) -> Option<RcSlice<T>> {
+        let len = self.rest.len();
+        if len < self.size {
+            return None
+        }
+        let window = self.rest.clone().slice_from(len - self.size);
+        self.rest.data = &self.rest[..len - 1];
+        Some(window)
+    }
+}
+
+impl<T> ExactSizeIterator for WindowsOwned<T> {}
+impl<T> FusedIterator for WindowsOwned<T> {}
+
+/// An iterator over `RcSlice`s pointing to the parts of a `RcSlice`
+/// separated by elements that match a predicate.
+///
+/// This is created by `RcSlice::split_owned`.
+pub struct SplitOwned<T, F> {
+    rest: Option<RcSlice<T>>,
+    pred: F,
+}
+
+impl<T, F: FnMut(&T) -> bool> Iterator for SplitOwned<T, F> {
+    type Item = RcSlice<T>;
+    fn next(&mut self) -> Option<RcSlice<T>> {
+        let rest = self.rest.as_mut()?;
+        match rest.iter().position(&mut self.pred) {
+            Some(i) => {
+                let part = rest.clone().slice_to(i);
+                rest.data = &rest[i + 1..];
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.rest {
+            Some(ref rest) => (1, Some(rest.len() + 1)),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> DoubleEndedIterator for SplitOwned<T, F> {
+    fn next_back(&mut self) -> Option<RcSlice<T>> {
+        let rest = self.rest.as_mut()?;
+        match rest.iter().rposition(&mut self.pred) {
+            Some(i) => {
+                let part = rest.clone().slice_from(i + 1);
+                rest.data = &rest[..i];
+                Some(part)
+            }
+            None => self.rest.take(),
+        }
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> FusedIterator for SplitOwned<T, F> {}
+
+/// An iterator over at most a certain number of `RcSlice`s pointing to
+/// the parts of a `RcSlice` separated by elements that match a
+/// predicate.
+///
+/// This is created by `RcSlice::splitn_owned`.
+pub struct SplitNOwned<T, F> {
+    inner: SplitOwned<T, F>,
+    n: usize,
+}
+
+impl<T, F: FnMut(&T) -> bool> Iterator for SplitNOwned<T, F> {
+    type Item = RcSlice<T>;
+    fn next(&mut self) -> Option<RcSlice<T>> {
+        match self.n {
+            0 => None,
+            1 => {
+                self.n = 0;
+                self.inner.rest.take()
+            }
+            _ => {
+                self.n -= 1;
+                self.inner.next()
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.inner.size_hint();
+        (cmp::min(lo, self.n), Some(hi.map_or(self.n, |hi| cmp::min(hi, self.n))))
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> FusedIterator for SplitNOwned<T, F> {}
+
+/// An iterator that yields the elements of a `RcSlice` by value.
+///
+/// This is created by `RcSlice::into_iter`.
+pub struct IntoIter<T> {
+    elems: Elems<T>,
+}
+
+enum Elems<T> {
+    // moved out of a unique allocation
+    Owned(vec::IntoIter<T>),
+    // still shared, so cloned
+    Shared(IterCloned<T>),
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        match self.elems {
+            Elems::Owned(ref mut it) => it.next(),
+            Elems::Shared(ref mut it) => it.next(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.elems {
+            Elems::Owned(ref it) => it.size_hint(),
+            Elems::Shared(ref it) => it.size_hint(),
+        }
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        match self.elems {
+            Elems::Owned(ref mut it) => it.next_back(),
+            Elems::Shared(ref mut it) => it.next_back(),
+        }
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for IntoIter<T> {}
+impl<T: Clone> FusedIterator for IntoIter<T> {}
+
+/// An iterator that yields clones of the elements of a `RcSlice`,
+/// keeping them alive itself.
+///
+/// This is created by `RcSlice::iter_cloned`.
+pub struct IterCloned<T> {
+    rest: RcSlice<T>,
+}
+
+impl<T: Clone> Iterator for IterCloned<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        let (first, tail) = self.rest.split_first()?;
+        let first = first.clone();
+        self.rest.data = tail;
+        Some(first)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rest.len(), Some(self.rest.len()))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for IterCloned<T> {
+    fn next_back(&mut self) -> Option<T> {
+        let (last, init) = self.rest.split_last()?;
+        let last = last.clone();
+        self.rest.data = init;
+        Some(last)
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for IterCloned<T> {}
+impl<T: Clone> FusedIterator for IterCloned<T> {}
+
+impl<T> WeakSlice<T> {
+    /// Construct a new `WeakSlice` that doesn't point to anything, so
+    /// never upgrades, like `std::rc::Weak::new`.
+    ///
+    /// This doesn't allocate, and allows a `WeakSlice` field to be
+    /// initialized before the `RcSlice` it will point to exists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::WeakSlice;
+    ///
+    /// let w = WeakSlice::<u8>::new();
+    /// assert!(w.upgrade().is_none());
+    /// ```
+    pub const fn new() -> WeakSlice<T> {
+        WeakSlice {
+            data: &[],
+            header: dead_header(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Attempt to upgrade `self` to a strongly-counted `RcSlice`.
+    ///
+    /// Returns `None` if this is not possible (the data has already
+    /// been freed).
+    pub fn upgrade(&self) -> Option<RcSlice<T>> {
+        if self.header == dead_header()
+            || is_counted(self.header) && !unsafe { self.header.as_ref() }.upgrade() {
+            return None
+        }
+        let slice = RcSlice {
+            data: self.data,
+            header: self.header,
+            _marker: PhantomData,
+        };
+        slice.check();
+        Some(slice)
+    }
+}
+
+/// A `WeakSlice` that never upgrades, like `Weak::new`. This doesn't
+/// allocate.
+impl<T> Default for WeakSlice<T> {
+    fn default() -> WeakSlice<T> {
+        WeakSlice::new()
+    }
+}
+
+impl<T> Drop for WeakSlice<T> {
+    fn drop(&mut self) {
+        unsafe { release_weak::<T>(self.header) }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for RcSlice<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RcSlice<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RcSlice<T>, D::Error> {
+        let v: Vec<T> = Deserialize::deserialize(deserializer)?;
+        Ok(RcSlice::new(v.into_boxed_slice()))
+    }
+}
+
+impl<T> Clone for RcRef<T> {
+    fn clone(&self) -> RcRef<T> {
+        RcRef { slice: self.slice.clone() }
+    }
+}
+
+impl<T> ops::Deref for RcRef<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.slice[0]
+    }
+}
+
+impl<T> AsRef<T> for RcRef<T> {
+    fn as_ref(&self) -> &T { self }
+}
+
+impl<T: PartialEq> PartialEq for RcRef<T> {
+    fn eq(&self, other: &RcRef<T>) -> bool { **self == **other }
+}
+impl<T: Eq> Eq for RcRef<T> {}
+
+impl<T: PartialOrd> PartialOrd for RcRef<T> {
+    fn partial_cmp(&self, other: &RcRef<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+impl<T: Ord> Ord for RcRef<T> {
+    fn cmp(&self, other: &RcRef<T>) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl<T: Hash> Hash for RcRef<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for RcRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized> RcView<U> {
+    /// Construct a new `RcView` pointing to a value derived from
+    /// the one `self` points to by `f`, sharing the same allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcSlice;
+    ///
+    /// let x = RcSlice::from([(1, "one"), (2, "two")]);
+    /// let second = x.map_ref(|x| &x[1]);
+    /// let name = second.map(|&(_, name)| name);
+    /// assert_eq!(&*name, "two");
+    /// ```
+    pub fn map<V: ?Sized, F>(self, f: F) -> RcView<V>
+        where F: FnOnce(&U) -> &V
+    {
+        let data: *const V = f(&self);
+        let view = RcView { data, header: self.header, release: self.release };
+        mem::forget(self);
+        view
+    }
+}
+
+impl<U: ?Sized> Clone for RcView<U> {
+    fn clone(&self) -> RcView<U> {
+        retain(self.header);
+        RcView { data: self.data, header: self.header, release: self.release }
+    }
+}
+
+impl<U: ?Sized> Drop for RcView<U> {
+    fn drop(&mut self) {
+        unsafe { (self.release)(self.header) }
+    }
+}
+
+impl<U: ?Sized> ops::Deref for RcView<U> {
+    type Target = U;
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> AsRef<U> for RcView<U> {
+    fn as_ref(&self) -> &U { self }
+}
+
+impl<U: ?Sized + PartialEq> PartialEq for RcView<U> {
+    fn eq(&self, other: &RcView<U>) -> bool { **self == **other }
+}
+impl<U: ?Sized + Eq> Eq for RcView<U> {}
+
+impl<U: ?Sized + Hash> Hash for RcView<U> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<U: ?Sized + fmt::Debug> fmt::Debug for RcView<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized + fmt::Display> fmt::Display for RcView<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T> CompactRcSlice<T> {
+    /// Construct a new `CompactRcSlice` viewing the same elements as
+    /// `slice`.
+    ///
+    /// This fails, returning `slice`, if the view extends past index
+    /// `u32::MAX` in its allocation.
+    pub fn new(slice: RcSlice<T>) -> Result<CompactRcSlice<T>, RcSlice<T>> {
+        let offset = slice.offset();
+        let hi = offset + slice.len();
+        if hi > u32::MAX as usize || !is_counted(slice.header) && !slice.is_empty() {
+            return Err(slice)
+        }
+        let len = slice.len() as u32;
+        Ok(CompactRcSlice {
+            offset: offset as u32,
+            len,
+            header: slice.into_parts().1,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Construct a new `CompactRcSlice` that only points to elements
+    /// at indices `lo` (inclusive) through `hi` (exclusive).
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi` or if either are strictly greater than
+    /// `self.len()`.
+    pub fn slice(mut self, lo: usize, hi: usize) -> CompactRcSlice<T> {
+        assert!(lo <= hi && hi <= self.len(),
+                "CompactRcSlice::slice: {}..{} out of bounds for length {}", lo, hi, self.len());
+        self.offset += lo as u32;
+        self.len = (hi - lo) as u32;
+        self
+    }
+}
+
+impl<T> From<CompactRcSlice<T>> for RcSlice<T> {
+    fn from(slice: CompactRcSlice<T>) -> RcSlice<T> {
+        let data: *const [T] = &*slice;
+        let slice = mem::ManuallyDrop::new(slice);
+        RcSlice {
+            data,
+            header: slice.header,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for CompactRcSlice<T> {
+    fn clone(&self) -> CompactRcSlice<T> {
+        retain(self.header);
+        CompactRcSlice {
+            header: self.header,
+            offset: self.offset,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for CompactRcSlice<T> {
+    fn drop(&mut self) {
+        unsafe { release_strong::<T>(self.header) }
+    }
+}
+
+impl<T> ops::Deref for CompactRcSlice<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        if !is_counted(self.header) {
+            return &[]
+        }
+        let lo = self.offset as usize;
+        let whole = unsafe { whole::<T>(self.header) };
+        if checks::ENABLED {
+            assert!(lo + self.len as usize <= whole.len(),
+                    "shared_slice: view {}+{} outside its allocation of length {}",
+                    lo, self.len, whole.len());
+        }
+        unsafe { whole.get_unchecked(lo..lo + self.len as usize) }
+    }
+}
+
+impl<T> AsRef<[T]> for CompactRcSlice<T> {
+    fn as_ref(&self) -> &[T] { self }
+}
+
+impl<T: PartialEq> PartialEq for CompactRcSlice<T> {
+    fn eq(&self, other: &CompactRcSlice<T>) -> bool { **self == **other }
+}
+impl<T: Eq> Eq for CompactRcSlice<T> {}
+
+impl<T: PartialOrd> PartialOrd for CompactRcSlice<T> {
+    fn partial_cmp(&self, other: &CompactRcSlice<T>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+impl<T: Ord> Ord for CompactRcSlice<T> {
+    fn cmp(&self, other: &CompactRcSlice<T>) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl<T: Hash> Hash for CompactRcSlice<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CompactRcSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A builder for a `RcSlice`, which writes the elements straight into
+/// the allocation the `RcSlice` will use.
+///
+/// This is like building a `Vec` and converting it, but the elements
+/// aren't copied from the `Vec` into a new allocation at the end: the
+/// space is grown in place as elements are added, and shrunk to fit
+/// by `build`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::rc::RcSliceBuilder;
+///
+/// let mut builder = RcSliceBuilder::with_capacity(4);
+/// builder.push(1);
+/// builder.extend_from_slice(&[2, 3]);
+/// builder.extend(4..6);
+/// let x = builder.build();
+/// assert_eq!(&*x, [1, 2, 3, 4, 5]);
+/// ```
+pub struct RcSliceBuilder<T> {
+    // only allocated when `cap > 0`
+    ptr: NonNull<Header>,
+    len: usize,
+    cap: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> RcSliceBuilder<T> {
+    /// Construct a new empty `RcSliceBuilder`, which doesn't allocate
+    /// until elements are added.
+    pub fn new() -> RcSliceBuilder<T> {
+        RcSliceBuilder {
+            ptr: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Construct a new empty `RcSliceBuilder` with space for at least
+    /// `cap` elements.
+    pub fn with_capacity(cap: usize) -> RcSliceBuilder<T> {
+        let mut builder = RcSliceBuilder::new();
+        builder.reserve(cap);
+        builder
+    }
+
+    /// The number of elements added so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no elements have been added.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements `self` has space for without growing.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Ensure there is space for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("RcSliceBuilder: capacity overflow");
+        if needed <= self.cap {
+            return
+        }
+        let cap = cmp::max(cmp::max(needed, 2 * self.cap), 4);
+        self.ptr = if self.cap == 0 {
+            inner::allocate::<Header, T>(Header::new(cap), cap)
+        } else {
+            unsafe { inner::resize::<Header, T>(self.ptr, self.cap, cap) }
+        };
+        self.cap = cap;
+    }
+
+    /// Add `value` to the end.
+    pub fn push(&mut self, value: T) {
+        if self.len == self.cap {
+            self.reserve(1);
+        }
+        unsafe { ptr::write(inner::elems::<Header, T>(self.ptr).add(self.len), value) }
+        self.len += 1;
+    }
+
+    /// Construct the `RcSlice` containing the elements added, shrinking
+    /// the allocation to fit them.
+    pub fn build(self) -> RcSlice<T> {
+        let this = mem::ManuallyDrop::new(self);
+        let (len, cap) = (this.len, this.cap);
+        if len == 0 {
+            if cap > 0 {
+                unsafe { inner::free::<Header, T>(this.ptr, cap) }
+            }
+            return RcSlice::default()
+        }
+        unsafe {
+            let ptr = if cap == len {
+                this.ptr
+            } else {
+                inner::resize::<Header, T>(this.ptr, cap, len)
+            };
+            // the header records the length, for freeing
+            ptr::write(ptr.as_ptr(), Header::new(len));
+            RcSlice::from_header(ptr, len)
+        }
+    }
+}
+
+impl<T: Clone> RcSliceBuilder<T> {
+    /// Add clones of the elements of `slice` to the end.
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        self.reserve(slice.len());
+        for x in slice {
+            self.push(x.clone());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RcSliceBuilder<u8> {
+    /// Read everything from `reader` onto the end, straight into the
+    /// allocation, growing it as needed.
+    fn read_to_end_from<R: io::Read>(&mut self, mut reader: R) -> io::Result<()> {
+        // the spare capacity up to here has been zeroed, so it can be
+        // passed to `read`
+        let mut zeroed = self.len;
+        loop {
+            if self.len == self.cap {
+                // probe before growing, so that space reserved for
+                // exactly the right length isn't doubled just to find
+                // the end
+                let mut probe = [0; 32];
+                match reader.read(&mut probe) {
+                    Ok(0) => return Ok(()),
+                    Ok(n) => self.extend_from_slice(&probe[..n]),
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+                continue
+            }
+            let elems = inner::elems::<Header, u8>(self.ptr);
+            zeroed = cmp::max(zeroed, self.len);
+            unsafe { ptr::write_bytes(elems.add(zeroed), 0, self.cap - zeroed) }
+            zeroed = self.cap;
+
+            let spare = unsafe { slice::from_raw_parts_mut(elems.add(self.len), self.cap - self.len) };
+            match reader.read(spare) {
+                Ok(0) => return Ok(()),
+                Ok(n) => {
+                    assert!(n <= self.cap - self.len, "RcSliceBuilder: reader returned too many bytes");
+                    self.len += n;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<T> Default for RcSliceBuilder<T> {
+    fn default() -> RcSliceBuilder<T> {
+        RcSliceBuilder::new()
+    }
+}
+
+impl<T> Extend<T> for RcSliceBuilder<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for x in iter {
+            self.push(x);
+        }
+    }
+}
+
+impl<T> ops::Deref for RcSliceBuilder<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        // `ptr` is dangling until something is allocated
+        if self.cap == 0 {
+            return &[]
+        }
+        unsafe { slice::from_raw_parts(inner::elems::<Header, T>(self.ptr), self.len) }
+    }
+}
+
+impl<T> ops::DerefMut for RcSliceBuilder<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if self.cap == 0 {
+            return &mut []
+        }
+        unsafe { slice::from_raw_parts_mut(inner::elems::<Header, T>(self.ptr), self.len) }
+    }
+}
+
+impl<T> Drop for RcSliceBuilder<T> {
+    fn drop(&mut self) {
+        if self.cap > 0 {
+            unsafe {
+                inner::drop_elems::<Header, T>(self.ptr, self.len);
+                inner::free::<Header, T>(self.ptr, self.cap);
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcSliceBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A `RcSlice` that is initialized the first time it is needed,
+/// such as a precomputed table in a `thread_local!`.
+///
+/// This is the single-threaded version of `arc::OnceSlice`: once
+/// initialized, each `get_or_init` just returns a new handle to the
+/// same slice.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::rc::{OnceSlice, RcSlice};
+///
+/// thread_local! {
+///     static SQUARES: OnceSlice<u32> = OnceSlice::new();
+/// }
+///
+/// fn squares() -> RcSlice<u32> {
+///     SQUARES.with(|s| s.get_or_init(|| RcSlice::from_fn(256, |i| (i * i) as u32)))
+/// }
+///
+/// assert_eq!(squares()[12], 144);
+/// assert_eq!(squares().as_ptr(), squares().as_ptr());
+/// ```
+pub struct OnceSlice<T> {
+    value: UnsafeCell<Option<RcSlice<T>>>,
+}
+
+impl<T> OnceSlice<T> {
+    /// Construct a new uninitialized `OnceSlice`.
+    pub const fn new() -> OnceSlice<T> {
+        OnceSlice { value: UnsafeCell::new(None) }
+    }
+
+    /// Get a new handle to the slice in `self`, or `None` if it
+    /// hasn't been initialized yet.
+    pub fn get(&self) -> Option<RcSlice<T>> {
+        // no reference into `value` outlives a method call
+        unsafe { (*self.value.get()).clone() }
+    }
+
+    /// Get a new handle to the slice in `self`, initializing it with
+    /// `f` first if it hasn't been already.
+    ///
+    /// If `f` panics, the panic is propagated and `self` is left
+    /// uninitialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` initializes `self` itself.
+    pub fn get_or_init<F: FnOnce() -> RcSlice<T>>(&self, f: F) -> RcSlice<T> {
+        if let Some(slice) = self.get() {
+            return slice
+        }
+        let slice = f();
+        let value = unsafe { &mut *self.value.get() };
+        assert!(value.is_none(), "OnceSlice: reentrant initialization");
+        *value = Some(slice.clone());
+        slice
+    }
+
+    /// Convert `self` into the slice it contains, or `None` if it was
+    /// never initialized.
+    pub fn into_inner(self) -> Option<RcSlice<T>> {
+        self.value.into_inner()
+    }
+}
+
+impl<T> Default for OnceSlice<T> {
+    fn default() -> OnceSlice<T> {
+        OnceSlice::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.get() {
+            Some(slice) => f.debug_tuple("OnceSlice").field(&slice).finish(),
+            None => f.write_str("OnceSlice(<uninit>)"),
+        }
+    }
+}
+
+/// A reference-counted string slice.
+///
+/// This is to `str` as `RcSlice<u8>` is to `[u8]`: a view into a
+/// shared allocation of UTF-8 text, which can be cloned and sliced
+/// without copying the text, and has no lifetime.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::rc::RcStr;
+///
+/// let source = RcStr::from("let x = 1;");
+/// let name = source.clone().slice(4..5);
+/// drop(source);
+/// assert_eq!(name, "x");
+/// ```
+#[derive(Clone, Default)]
+pub struct RcStr {
+    bytes: RcSlice<u8>,
+}
+
+impl RcStr {
+    /// Construct a new `RcStr` containing the text of `s`.
+    pub fn new(s: Box<str>) -> RcStr {
+        RcStr { bytes: RcSlice::new(s.into_boxed_bytes()) }
+    }
+
+    /// Convert `bytes` into a `RcStr`, without copying them, or
+    /// return an error if they aren't valid UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::{RcSlice, RcStr};
+    ///
+    /// let bytes = RcSlice::from(*b"caf\xc3\xa9");
+    /// assert_eq!(RcStr::from_utf8(bytes.clone()).unwrap(), "café");
+    /// assert!(RcStr::from_utf8(bytes.slice_to(4)).is_err());
+    /// ```
+    pub fn from_utf8(bytes: RcSlice<u8>) -> Result<RcStr, str::Utf8Error> {
+        str::from_utf8(&bytes)?;
+        Ok(RcStr { bytes })
+    }
+
+    /// Convert `bytes` into a `RcStr`, without copying them or
+    /// checking that they are valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid UTF-8.
+    pub unsafe fn from_utf8_unchecked(bytes: RcSlice<u8>) -> RcStr {
+        RcStr { bytes }
+    }
+
+    /// Construct a new `RcStr` containing the text of each of `parts`
+    /// in turn, with `sep` between each, like `<[&str]>::join`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let words = [RcStr::from("shared"), RcStr::from("slice")];
+    /// assert_eq!(RcStr::join(&words, "_"), "shared_slice");
+    /// ```
+    pub fn join(parts: &[RcStr], sep: &str) -> RcStr {
+        let mut pieces = Vec::with_capacity(2 * parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                pieces.push(sep.as_bytes());
+            }
+            pieces.push(part.as_bytes());
+        }
+        // joining valid UTF-8 gives valid UTF-8
+        RcStr { bytes: RcSlice::concat(pieces) }
+    }
+
+    /// Construct a new `RcStr` containing the text encoded as UTF-16
+    /// in `units`, or return an error if it isn't valid UTF-16, like
+    /// `String::from_utf16`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let units = [0x66, 0x6f, 0xd834, 0xdd1e];
+    /// assert_eq!(RcStr::from_utf16(&units).unwrap(), "fo𝄞");
+    /// assert!(RcStr::from_utf16(&units[..3]).is_err());
+    /// ```
+    pub fn from_utf16(units: &[u16]) -> Result<RcStr, FromUtf16Error> {
+        String::from_utf16(units).map(RcStr::from)
+    }
+
+    /// Construct a new `RcStr` containing the text encoded as UTF-16
+    /// in `units`, replacing invalid data with U+FFFD, like
+    /// `String::from_utf16_lossy`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let units = [0x66, 0xd834, 0x6f];
+    /// assert_eq!(RcStr::from_utf16_lossy(&units), "f\u{fffd}o");
+    /// ```
+    pub fn from_utf16_lossy(units: &[u16]) -> RcStr {
+        RcStr::from(String::from_utf16_lossy(units))
+    }
+
+    /// The text of `self`.
+    pub fn as_str(&self) -> &str {
+        // the bytes are always valid UTF-8
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+
+    /// Construct a new `RcStr` that only points to the text in
+    /// `range`, such as `2..`, `..=5` or `..`.
+    ///
+    /// This consumes `self` to avoid unnecessary reference-count
+    /// modifications. Use `.clone()` if it is necessary to refer to
+    /// `self` after calling this.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, if either
+    /// are out of bounds, or if either are not on a `char` boundary,
+    /// like indexing a `str`.
+    pub fn slice<R: ops::RangeBounds<usize>>(mut self, range: R) -> RcStr {
+        let (lo, hi) = {
+            let s = &self[range::bounds(&range)];
+            let lo = s.as_ptr() as usize - self.as_ptr() as usize;
+            (lo, lo + s.len())
+        };
+        self.bytes = self.bytes.slice(lo, hi);
+        self
+    }
+
+    /// The bytes of the text of `self`, sharing its allocation.
+    pub fn as_bytes_owned(&self) -> &RcSlice<u8> {
+        &self.bytes
+    }
+
+    /// Convert `self` into its bytes, without copying them.
+    pub fn into_bytes(self) -> RcSlice<u8> {
+        self.bytes
+    }
+
+    /// Construct a new `RcSlice` containing the text of `self` encoded
+    /// as UTF-16, like `str::encode_utf16`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("fo𝄞");
+    /// assert_eq!(&*s.encode_utf16_owned(), [0x66, 0x6f, 0xd834, 0xdd1e]);
+    /// ```
+    pub fn encode_utf16_owned(&self) -> RcSlice<u16> {
+        let mut units = self.encode_utf16();
+        let len = self.encode_utf16().count();
+        RcSlice::from_fn(len, |_| units.next().unwrap())
+    }
+
+    /// Divide `self` into `RcStr`s pointing to the parts of the text
+    /// separated by `sep`, like `str::split`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sep` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("a, b,, c");
+    /// let parts: Vec<_> = s.split_owned(", ").collect();
+    /// assert_eq!(parts, ["a", "b,", "c"]);
+    /// ```
+    pub fn split_owned(self, sep: &str) -> StrSplitOwned<'_> {
+        assert!(!sep.is_empty(), "separator must be non-empty");
+        StrSplitOwned { rest: Some(self), sep }
+    }
+
+    /// Divide `self` into `RcStr`s pointing to its lines, like
+    /// `str::lines`. Lines end with `\n` or `\r\n`, which is not
+    /// included, and a final line ending doesn't start an empty line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("one\r\ntwo\n\nthree\n");
+    /// let lines: Vec<_> = s.lines_owned().collect();
+    /// assert_eq!(lines, ["one", "two", "", "three"]);
+    /// ```
+    pub fn lines_owned(self) -> LinesOwned {
+        LinesOwned { rest: self }
+    }
+
+    /// Divide `self` into `RcStr`s pointing to the parts of the text
+    /// separated by whitespace, like `str::split_whitespace`. None of
+    /// the parts are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from(" let  x =\t1; ");
+    /// let tokens: Vec<_> = s.split_whitespace_owned().collect();
+    /// assert_eq!(tokens, ["let", "x", "=", "1;"]);
+    /// ```
+    pub fn split_whitespace_owned(self) -> SplitWhitespaceOwned {
+        SplitWhitespaceOwned { rest: self }
+    }
+
+    /// Construct a new `RcStr` containing the text of `self` in
+    /// lowercase, like `str::to_lowercase`, or a clone of `self` if it
+    /// is already lowercase.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("Ünïcode");
+    /// assert_eq!(s.to_lowercase_shared(), "ünïcode");
+    ///
+    /// let lower = RcStr::from("already lower");
+    /// assert_eq!(lower.to_lowercase_shared().as_ptr(), lower.as_ptr());
+    /// ```
+    pub fn to_lowercase_shared(&self) -> RcStr {
+        if self.contains('\u{3a3}') {
+            // the lowercase of a capital sigma depends on whether it
+            // ends a word, so let `str` handle it
+            return RcStr::from(self.to_lowercase())
+        }
+        self.map_chars(char::to_lowercase)
+    }
+
+    /// Construct a new `RcStr` containing the text of `self` in
+    /// uppercase, like `str::to_uppercase`, or a clone of `self` if it
+    /// is already uppercase.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("straße");
+    /// assert_eq!(s.to_uppercase_shared(), "STRASSE");
+    /// ```
+    pub fn to_uppercase_shared(&self) -> RcStr {
+        self.map_chars(char::to_uppercase)
+    }
+
+    /// Construct a new `RcStr` containing the text of `self` with
+    /// ASCII letters in lowercase, like `str::to_ascii_lowercase`, or
+    /// a clone of `self` if there are no ASCII uppercase letters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("Content-Type");
+    /// assert_eq!(s.to_ascii_lowercase_shared(), "content-type");
+    /// ```
+    pub fn to_ascii_lowercase_shared(&self) -> RcStr {
+        if !self.bytes().any(|b| b.is_ascii_uppercase()) {
+            return self.clone()
+        }
+        let bytes = self.as_bytes();
+        // changing the case of ASCII letters keeps the text valid UTF-8
+        RcStr { bytes: RcSlice::from_fn(bytes.len(), |i| bytes[i].to_ascii_lowercase()) }
+    }
+
+    /// Construct a new `RcStr` containing the text of `self` with each
+    /// `char` `c` replaced by the `char`s of `f(c)`, written straight
+    /// into one allocation, or a clone of `self` if that changes
+    /// nothing.
+    fn map_chars<I, F>(&self, f: F) -> RcStr
+        where I: Iterator<Item = char>, F: Fn(char) -> I
+    {
+        let mut len = 0;
+        let mut changed = false;
+        for c in self.chars() {
+            for (i, m) in f(c).enumerate() {
+                len += m.len_utf8();
+                changed |= i > 0 || m != c;
+            }
+        }
+        if !changed {
+            return self.clone()
+        }
+        let mut builder = RcSliceBuilder::with_capacity(len);
+        let mut buf = [0; 4];
+        for m in self.chars().flat_map(f) {
+            builder.extend_from_slice(m.encode_utf8(&mut buf).as_bytes());
+        }
+        RcStr { bytes: builder.build() }
+    }
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl RcStr {
+    /// Divide `self` into `RcStr`s pointing to its grapheme clusters,
+    /// as defined by Unicode Standard Annex #29, like
+    /// `UnicodeSegmentation::graphemes`. If `extended` is true,
+    /// extended grapheme clusters are used, rather than legacy ones.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("ne\u{301}e 🇳🇿");
+    /// let graphemes: Vec<_> = s.graphemes_owned(true).collect();
+    /// assert_eq!(graphemes, ["n", "e\u{301}", "e", " ", "🇳🇿"]);
+    /// ```
+    pub fn graphemes_owned(self, extended: bool) -> SegmentsOwned {
+        let iter = Segments::Graphemes(unsafe { self.as_static_str() }.graphemes(extended));
+        SegmentsOwned { iter, text: self }
+    }
+
+    /// Divide `self` into `RcStr`s pointing to its words, as defined
+    /// by Unicode Standard Annex #29, like
+    /// `UnicodeSegmentation::unicode_words`. Only the parts between
+    /// word boundaries that contain a letter or number are included,
+    /// so whitespace and punctuation are skipped.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("The quick (\"brown\") fox can't jump 32.3 feet.");
+    /// let words: Vec<_> = s.unicode_words_owned().collect();
+    /// assert_eq!(words, ["The", "quick", "brown", "fox", "can't", "jump", "32.3", "feet"]);
+    /// ```
+    pub fn unicode_words_owned(self) -> SegmentsOwned {
+        let iter = Segments::Words(unsafe { self.as_static_str() }.unicode_words());
+        SegmentsOwned { iter, text: self }
+    }
+
+    /// Divide `self` into `RcStr`s pointing to the parts between
+    /// each of its word boundaries, as defined by Unicode Standard
+    /// Annex #29, like `UnicodeSegmentation::split_word_bounds`.
+    /// Concatenating the parts gives back the whole text.
+    ///
+    /// This requires the `unicode-segmentation` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shared_slice::rc::RcStr;
+    ///
+    /// let s = RcStr::from("Hello, world!");
+    /// let parts: Vec<_> = s.split_word_bounds_owned().collect();
+    /// assert_eq!(parts, ["Hello", ",", " ", "world", "!"]);
+    /// ```
+    pub fn split_word_bounds_owned(self) -> SegmentsOwned {
+        let iter = Segments::WordBounds(unsafe { self.as_static_str() }.split_word_bounds());
+        SegmentsOwned { iter, text: self }
+    }
+
+    /// The text of `self`, with its lifetime extended.
+    ///
+    /// The text lives in the heap (or static data), so it stays put
+    /// when `self` moves. The result must not be used after `self`
+    /// is dropped.
+    unsafe fn as_static_str(&self) -> &'static str {
+        &*(self.as_str() as *const str)
+    }
+}
+
+impl From<String> for RcStr {
+    fn from(s: String) -> RcStr {
+        RcStr::new(s.into_boxed_str())
+    }
+}
+
+impl From<Box<str>> for RcStr {
+    fn from(s: Box<str>) -> RcStr {
+        RcStr::new(s)
+    }
+}
+
+impl<'a> From<&'a str> for RcStr {
+    fn from(s: &'a str) -> RcStr {
+        RcStr { bytes: RcSlice::from_slice(s.as_bytes()) }
+    }
+}
+
+impl ops::Deref for RcStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for RcStr {
+    fn as_ref(&self) -> &str { self }
+}
+
+impl AsRef<[u8]> for RcStr {
+    fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl Borrow<str> for RcStr {
+    fn borrow(&self) -> &str { self }
+}
+
+impl PartialEq for RcStr {
+    fn eq(&self, other: &RcStr) -> bool { **self == **other }
+}
+impl Eq for RcStr {}
+
+impl PartialEq<str> for RcStr {
+    fn eq(&self, other: &str) -> bool { **self == *other }
+}
+impl<'a> PartialEq<&'a str> for RcStr {
+    fn eq(&self, other: &&'a str) -> bool { **self == **other }
+}
+impl PartialEq<RcStr> for str {
+    fn eq(&self, other: &RcStr) -> bool { *self == **other }
+}
+impl PartialEq<RcStr> for &str {
+    fn eq(&self, other: &RcStr) -> bool { **self == **other }
+}
+
+impl PartialOrd for RcStr {
+    fn partial_cmp(&self, other: &RcStr) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for RcStr {
+    fn cmp(&self, other: &RcStr) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl Hash for RcStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl fmt::Debug for RcStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for RcStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// An iterator over `RcStr`s pointing to the parts of a `RcStr`
+/// separated by a string.
+///
+/// This is created by `RcStr::split_owned`.
+pub struct StrSplitOwned<'a> {
+    rest: Option<RcStr>,
+    sep: &'a str,
+}
+
+impl<'a> Iterator for StrSplitOwned<'a> {
+    type Item = RcStr;
+    fn next(&mut self) -> Option<RcStr> {
+        let i = match self.rest.as_ref()?.find(self.sep) {
+            Some(i) => i,
+            None => return self.rest.take(),
+        };
+        let rest = self.rest.take()?;
+        self.rest = Some(rest.clone().slice(i + self.sep.len()..));
+        Some(rest.slice(..i))
+    }
+}
+
+impl<'a> DoubleEndedIterator for StrSplitOwned<'a> {
+    fn next_back(&mut self) -> Option<RcStr> {
+        let i = match self.rest.as_ref()?.rfind(self.sep) {
+            Some(i) => i,
+            None => return self.rest.take(),
+        };
+        let rest = self.rest.take()?;
+        self.rest = Some(rest.clone().slice(..i));
+        Some(rest.slice(i + self.sep.len()..))
+    }
+}
+
+impl<'a> FusedIterator for StrSplitOwned<'a> {}
+
+/// An iterator over `RcStr`s pointing to the lines of a `RcStr`.
+///
+/// This is created by `RcStr::lines_owned`.
+pub struct LinesOwned {
+    rest: RcStr,
+}
+
+/// The end of the line ended by the `\n` at `i` in `s`, excluding
+/// the `\r` before it, if any.
+fn line_end(s: &str, i: usize) -> usize {
+    if s[..i].ends_with('\r') { i - 1 } else { i }
+}
+
+impl Iterator for LinesOwned {
+    type Item = RcStr;
+    fn next(&mut self) -> Option<RcStr> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = mem::take(&mut self.rest);
+        let line = match rest.find('\n') {
+            Some(i) => {
+                self.rest = rest.clone().slice(i + 1..);
+                let end = line_end(&rest, i);
+                rest.slice(..end)
+            }
+            None => rest,
+        };
+        Some(line)
+    }
+}
+
+impl DoubleEndedIterator for LinesOwned {
+    fn next_back(&mut self) -> Option<RcStr> {
+        if self.rest.is_empty() {
+            return None
+        }
+        let rest = mem::take(&mut self.rest);
+        // a final line ending doesn't start another line
+        let end = if rest.ends_with('\n') { line_end(&rest, rest.len() - 1) } else { rest.len() };
+        let line = match rest[..end].rfind('\n') {
+            Some(i) => {
+                self.rest = rest.clone().slice(..i + 1);
+                rest.slice(i + 1..end)
+            }
+            None => rest.slice(..end),
+        };
+        Some(line)
+    }
+}
+
+impl FusedIterator for LinesOwned {}
+
+/// An iterator over `RcStr`s pointing to the parts of a `RcStr`
+/// separated by whitespace.
+///
+/// This is created by `RcStr::split_whitespace_owned`.
+pub struct SplitWhitespaceOwned {
+    rest: RcStr,
+}
+
+impl Iterator for SplitWhitespaceOwned {
+    type Item = RcStr;
+    fn next(&mut self) -> Option<RcStr> {
+        let start = self.rest.len() - self.rest.trim_start().len();
+        if start == self.rest.len() {
+            self.rest = RcStr::default();
+            return None
+        }
+        let end = self.rest[start..].find(char::is_whitespace)
+            .map_or(self.rest.len(), |i| start + i);
+        let rest = mem::take(&mut self.rest);
+        self.rest = rest.clone().slice(end..);
+        Some(rest.slice(start..end))
+    }
+}
+
+impl DoubleEndedIterator for SplitWhitespaceOwned {
+    fn next_back(&mut self) -> Option<RcStr> {
+        let end = self.rest.trim_end().len();
+        if end == 0 {
+            self.rest = RcStr::default();
+            return None
+        }
+        let start = match self.rest[..end].char_indices().rev().find(|&(_, c)| c.is_whitespace()) {
+            Some((i, c)) => i + c.len_utf8(),
+            None => 0,
+        };
+        let rest = mem::take(&mut self.rest);
+        self.rest = rest.clone().slice(..start);
+        Some(rest.slice(start..end))
+    }
+}
+
+impl FusedIterator for SplitWhitespaceOwned {}
+
+/// An iterator over `RcStr`s pointing to the grapheme clusters or
+/// words of a `RcStr`.
+///
+/// This is created by `RcStr::graphemes_owned`,
+/// `RcStr::unicode_words_owned` and `RcStr::split_word_bounds_owned`,
+/// and requires the `unicode-segmentation` feature.
+#[cfg(feature = "unicode-segmentation")]
+pub struct SegmentsOwned {
+    // borrows from `text`, so is declared (and dropped) first
+    iter: Segments,
+    text: RcStr,
+}
+
+#[cfg(feature = "unicode-segmentation")]
+enum Segments {
+    Graphemes(Graphemes<'static>),
+    Words(UnicodeWords<'static>),
+    WordBounds(UWordBounds<'static>),
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl SegmentsOwned {
+    /// A `RcStr` pointing to `s`, a part of `self.text`.
+    fn owned(&self, s: &str) -> RcStr {
+        let lo = s.as_ptr() as usize - self.text.as_ptr() as usize;
+        self.text.clone().slice(lo..lo + s.len())
+    }
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl Iterator for SegmentsOwned {
+    type Item = RcStr;
+    fn next(&mut self) -> Option<RcStr> {
+        let s = match self.iter {
+            Segments::Graphemes(ref mut it) => it.next(),
+            Segments::Words(ref mut it) => it.next(),
+            Segments::WordBounds(ref mut it) => it.next(),
+        };
+        s.map(|s| self.owned(s))
+    }
+}
+
+#[cfg(feature = "unicode-segmentation")]
+impl DoubleEndedIterator for SegmentsOwned {
+    fn next_back(&mut self) -> Option<RcStr> {
+        let s = match self.iter {
+            Segments::Graphemes(ref mut it) => it.next_back(),
+            Segments::Words(ref mut it) => it.next_back(),
+            Segments::WordBounds(ref mut it) => it.next_back(),
+        };
+        s.map(|s| self.owned(s))
+    }
+}
+
+// each of the underlying iterators keeps returning `None` once it is
+// exhausted
+#[cfg(feature = "unicode-segmentation")]
+impl FusedIterator for SegmentsOwned {}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use super::{FrameCursor, MergeSorted, OnceSlice, RcSlice, RcSliceBuilder, RcRef, RcStr,
+                CompactRcSlice, WeakSlice};
+    use std::str;
+    use std::collections::HashSet;
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::ops;
+    
+    #[test]
+    fn test_once_slice() {
+        let once = OnceSlice::new();
+        assert!(once.get().is_none());
+        assert_eq!(format!("{:?}", once), "OnceSlice(<uninit>)");
+        let x = once.get_or_init(|| RcSlice::from([1, 2, 3]));
+        let y = once.get_or_init(|| panic!());
+        assert_eq!(x.as_ptr(), y.as_ptr());
+        assert_eq!(format!("{:?}", once), "OnceSlice([1, 2, 3])");
+        assert_eq!(once.into_inner(), Some(x));
+        assert_eq!(OnceSlice::<i32>::default().into_inner(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_once_slice_reentrant() {
+        let once = OnceSlice::new();
+        once.get_or_init(|| {
+            once.get_or_init(|| RcSlice::from([1]));
+            RcSlice::from([2])
+        });
+    }
+
+    #[test]
+    fn test_builder() {
+        let rc = Rc::new(0);
+        let mut b = RcSliceBuilder::new();
+        assert_eq!(b.capacity(), 0);
+        for _ in 0..10 {
+            b.push(rc.clone());
+        }
+        assert!(b.capacity() >= 10);
+        b.extend_from_slice(&[rc.clone(), rc.clone()]);
+        b[0] = Rc::new(1);
+        assert_eq!(b.len(), 12);
+        let x = b.build();
+        assert_eq!(x.len(), 12);
+        assert_eq!(*x[0], 1);
+        assert_eq!(Rc::strong_count(&rc), 12);
+        let y = x.clone().slice_from(5);
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 12);
+        drop(y);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // dropped without building
+        let mut b = RcSliceBuilder::with_capacity(3);
+        b.extend(vec![rc.clone(); 5]);
+        drop(b);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        assert!(RcSliceBuilder::<u8>::with_capacity(8).build().is_empty());
+        assert!(RcSliceBuilder::<u8>::new().build().is_empty());
+        let mut b = RcSliceBuilder::<u64>::new();
+        assert_eq!(&*b, &[0u64; 0]);
+        assert!(b.iter_mut().next().is_none());
+
+        let mut b = RcSliceBuilder::with_capacity(4);
+        b.extend(1..5);
+        assert_eq!(b.capacity(), 4);
+        assert_eq!(&*b.build(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn clone() {
+        let x = RcSlice::new(Box::new([Cell::new(false)]));
+        let y = x.clone();
+
+        assert_eq!(x[0].get(), false);
+        assert_eq!(y[0].get(), false);
+
+        x[0].set(true);
+        assert_eq!(x[0].get(), true);
+        assert_eq!(y[0].get(), true);
+    }
+
+    #[test]
+    fn test_upgrade_downgrade() {
+        let x = RcSlice::new(Box::new([1]));
+        let y: WeakSlice<_> = x.downgrade();
+
+        assert_eq!(y.upgrade(), Some(x.clone()));
+
+        drop(x);
+
+        assert!(y.upgrade().is_none())
+    }
+
+    #[test]
+    fn test_total_cmp() {
         let x = RcSlice::new(Box::new([1, 2, 3]));
         let y = RcSlice::new(Box::new([1, 2, 3]));
         let z = RcSlice::new(Box::new([1, 2, 4]));
@@ -226,84 +3801,1214 @@ mod tests {
         assert!(x != z);
         assert!(y != z);
 
-        assert!(x < z);
-        assert!(x <= z);
-        assert!(!(x > z));
-        assert!(!(x >= z));
+        assert!(x < z);
+        assert!(x <= z);
+        assert!(!(x > z));
+        assert!(!(x >= z));
+
+        assert!(!(z < x));
+        assert!(!(z <= x));
+        assert!(z > x);
+        assert!(z >= x);
+
+        assert_eq!(x.partial_cmp(&x), Some(Ordering::Equal));
+        assert_eq!(x.partial_cmp(&y), Some(Ordering::Equal));
+        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
+        assert_eq!(z.partial_cmp(&y), Some(Ordering::Greater));
+
+        assert_eq!(x.cmp(&x), Ordering::Equal);
+        assert_eq!(x.cmp(&y), Ordering::Equal);
+        assert_eq!(x.cmp(&z), Ordering::Less);
+        assert_eq!(z.cmp(&y), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_partial_cmp() {
+        use std::f64;
+        let x = RcSlice::new(Box::new([1.0, f64::NAN]));
+        let y = RcSlice::new(Box::new([1.0, f64::NAN]));
+        let z = RcSlice::new(Box::new([2.0, f64::NAN]));
+        let w = RcSlice::new(Box::new([f64::NAN, 1.0]));
+        assert!(!(x == y));
+        assert!(x != y);
+
+        assert!(!(x < y));
+        assert!(!(x <= y));
+        assert!(!(x > y));
+        assert!(!(x >= y));
+
+        assert!(x < z);
+        assert!(x <= z);
+        assert!(!(x > z));
+        assert!(!(x >= z));
+
+        assert!(!(z < w));
+        assert!(!(z <= w));
+        assert!(!(z > w));
+        assert!(!(z >= w));
+
+        assert_eq!(x.partial_cmp(&x), None);
+        assert_eq!(x.partial_cmp(&y), None);
+        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
+        assert_eq!(z.partial_cmp(&x), Some(Ordering::Greater));
+
+        assert_eq!(x.partial_cmp(&w), None);
+        assert_eq!(y.partial_cmp(&w), None);
+        assert_eq!(z.partial_cmp(&w), None);
+        assert_eq!(w.partial_cmp(&w), None);
+    }
+
+    #[test]
+    fn test_identity_fast_path() {
+        use std::cell::Cell;
+        thread_local!(static CALLS: Cell<usize> = const { Cell::new(0) });
+
+        #[derive(PartialEq, Eq)]
+        struct Counted(u8);
+        impl PartialOrd for Counted {
+            fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Counted {
+            fn cmp(&self, other: &Counted) -> Ordering {
+                CALLS.with(|c| c.set(c.get() + 1));
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let x = RcSlice::new(Box::new([Counted(1), Counted(2)]));
+        let y = x.clone();
+        assert_eq!(x.cmp(&y), Ordering::Equal);
+        assert_eq!(CALLS.with(|c| c.get()), 0);
+
+        // different views of the same memory still need comparing
+        assert_eq!(x.clone().slice(0, 1).cmp(&y), Ordering::Less);
+        assert!(CALLS.with(|c| c.get()) > 0);
+    }
+
+    #[cfg(feature = "unstable")]
+    #[test]
+    fn test_identity_fast_path_eq() {
+        use std::cell::Cell;
+        thread_local!(static CALLS: Cell<usize> = const { Cell::new(0) });
+
+        #[derive(Eq)]
+        struct Counted(u8);
+        impl PartialEq for Counted {
+            fn eq(&self, other: &Counted) -> bool {
+                CALLS.with(|c| c.set(c.get() + 1));
+                self.0 == other.0
+            }
+        }
+
+        let x = RcSlice::new(Box::new([Counted(1), Counted(2)]));
+        assert!(x == x.clone());
+        assert_eq!(CALLS.with(|c| c.get()), 0);
+
+        assert!(x.clone().slice(1, 2) != x.clone().slice(0, 1));
+        assert!(CALLS.with(|c| c.get()) > 0);
+    }
+
+    #[test]
+    fn test_hash_bytes_one_shot() {
+        use std::hash::{Hash, Hasher};
+        // records the number of bytes passed to each call to `write`
+        struct Writes(Vec<usize>);
+        impl Hasher for Writes {
+            fn finish(&self) -> u64 { 0 }
+            fn write(&mut self, bytes: &[u8]) { self.0.push(bytes.len()) }
+        }
+
+        let x = RcSlice::new(vec![0u8; 1000].into_boxed_slice()).slice(1, 1000);
+        let mut h = Writes(vec![]);
+        x.hash(&mut h);
+        // the length, then all the data at once
+        assert_eq!(h.0.len(), 2);
+        assert_eq!(h.0[1], 999);
+    }
+
+    #[test]
+    fn test_show() {
+        let x = RcSlice::new(Box::new([1, 2]));
+        assert_eq!(format!("{:?}", x), "[1, 2]");
+
+        let y: RcSlice<i32> = RcSlice::new(Box::new([]));
+        assert_eq!(format!("{:?}", y), "[]");
+    }
+
+    #[test]
+    fn test_slice() {
+        let x = RcSlice::new(Box::new([1, 2, 3]));
+        let real = [1, 2, 3];
+        for i in 0..3 + 1 {
+            for j in i..3 + 1 {
+                let slice: RcSlice<_> = x.clone().slice(i, j);
+                assert_eq!(&*slice, &real[i..j]);
+            }
+            assert_eq!(&*x.clone().slice_to(i), &real[..i]);
+            assert_eq!(&*x.clone().slice_from(i), &real[i..]);
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_shuffled() {
+        let mut rng = ::rand::thread_rng();
+        let x = RcSlice::new((0..100).collect::<Vec<i32>>().into_boxed_slice());
+
+        // shared, so a copy is made
+        let y = x.clone().slice(10, 90).shuffled(&mut rng);
+        assert_eq!(&*x, &*(0..100).collect::<Vec<_>>());
+        let mut sorted = y.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (10..90).collect::<Vec<_>>());
+
+        // unique, so it is shuffled in place
+        let ptr = x.as_ptr();
+        let z = x.slice(10, 90).shuffled(&mut rng);
+        assert_eq!(z.as_ptr(), unsafe { ptr.offset(10) });
+        let mut sorted = z.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (10..90).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_choose_owned() {
+        let mut rng = ::rand::thread_rng();
+        let x = RcSlice::new((0..10).collect::<Vec<i32>>().into_boxed_slice());
+
+        let y = x.choose_owned(&mut rng, 4);
+        assert_eq!(y.len(), 4);
+        for (i, a) in y.iter().enumerate() {
+            assert!(x.contains(a));
+            assert!(!y[i + 1..].contains(a));
+        }
+
+        assert_eq!(x.choose_owned(&mut rng, 20).len(), 10);
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn test_hash_with() {
+        use sha2::{Digest, Sha256};
+        let x = RcSlice::new(Box::new(*b"hello world"));
+
+        assert_eq!(x.hash_with::<Sha256>(), Sha256::digest(b"hello world"));
+        assert_eq!(x.slice(6, 11).hash_with::<Sha256>(), Sha256::digest(b"world"));
+    }
+
+    #[cfg(feature = "xxhash-rust")]
+    #[test]
+    fn test_fingerprint64() {
+        let x = RcSlice::new(Box::new(*b"hello world"));
+        // the XXH3 test vector for the empty input
+        assert_eq!(x.clone().slice(0, 0).fingerprint64(), 0x2d06_8005_38d3_94c2);
+        assert_eq!(x.clone().slice_from(6).fingerprint64(),
+                   RcSlice::new(Box::new(*b"world")).fingerprint64());
+        assert!(x.fingerprint64() != x.clone().slice_to(5).fingerprint64());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        use regex::bytes::Regex;
+        let x = RcSlice::new(Box::new(*b"a1 b22 c333 d"));
+
+        let re = Regex::new("[0-9]+").unwrap();
+        assert_eq!(&*x.find_owned(&re).unwrap(), b"1");
+        let all = x.find_iter_owned(&re).collect::<Vec<_>>();
+        let mut iter = x.find_iter_owned(&re).fuse();
+        assert_eq!(iter.by_ref().count(), 3);
+        assert!(iter.next().is_none());
+        assert_eq!(all, [&b"1"[..], b"22", b"333"].iter()
+                   .map(|s| RcSlice::new(s.to_vec().into_boxed_slice()))
+                   .collect::<Vec<_>>());
+
+        let empty = Regex::new("[0-9]*").unwrap();
+        let starts = x.find_iter_owned(&empty)
+            .map(|m| m.as_ptr() as usize - x.as_ptr() as usize)
+            .collect::<Vec<_>>();
+        let expected = empty.find_iter(&x).map(|m| m.start()).collect::<Vec<_>>();
+        assert_eq!(starts, expected);
+
+        let re = Regex::new("([a-z])([0-9]+)?").unwrap();
+        let caps = x.clone().slice_from(12).captures_owned(&re).unwrap();
+        assert_eq!(caps.len(), 3);
+        assert_eq!(&**caps[1].as_ref().unwrap(), b"d");
+        assert!(caps[2].is_none());
+        assert!(x.clone().slice_to(0).captures_owned(&re).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_file() {
+        use std::{env, fs};
+        let path = env::temp_dir().join("shared_slice_rc_from_file");
+        fs::write(&path, b"hello").unwrap();
+
+        let x = RcSlice::from_file(&path).unwrap();
+        assert_eq!(&*x, b"hello");
+
+        fs::remove_file(&path).unwrap();
+        assert!(RcSlice::from_file(&path).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_as_io_slice() {
+        use std::io::Write;
+        let x = RcSlice::new(Box::new(*b"hello world"));
+        let (hello, world) = (x.clone().slice_to(6), x.slice_from(6));
+        let bufs = [hello.as_io_slice(), world.as_io_slice()];
+        let mut out = vec![];
+        let n = out.write_vectored(&bufs).unwrap();
+        assert_eq!(n, 11);
+        assert_eq!(out, b"hello world");
+    }
+
+    #[test]
+    fn test_unchecked() {
+        let x = RcSlice::new(Box::new([1, 2, 3, 4]));
+        let y = unsafe { x.clone().slice_unchecked(1, 3) };
+        assert_eq!(&*y, [2, 3]);
+        assert_eq!(y.as_ptr(), x[1..].as_ptr());
+        assert!(unsafe { y.clone().slice_unchecked(2, 2) }.is_empty());
+        assert_eq!(*unsafe { y.get_ref_unchecked(1) }, 3);
+    }
+
+    #[test]
+    fn test_get_ref() {
+        let x = RcSlice::new(Box::new([1, 2, 3]));
+        let y = x.clone().slice_from(1);
+
+        let a: RcRef<_> = y.get_ref(0).unwrap();
+        let b = y.get_ref(1).unwrap();
+        assert!(y.get_ref(2).is_none());
+        drop((x, y));
+
+        assert_eq!(*a, 2);
+        assert_eq!(*b, 3);
+        assert!(a < b);
+        assert_eq!(a.clone(), a);
+        assert_eq!(format!("{:?} {}", a, b), "2 3");
+    }
+
+    #[test]
+    fn test_compact() {
+        use std::mem::size_of;
+        assert!(size_of::<CompactRcSlice<u8>>() < size_of::<RcSlice<u8>>());
+
+        let x = RcSlice::new(Box::new([1, 2, 3, 4, 5]));
+        let y = CompactRcSlice::new(x.clone().slice(1, 4)).unwrap();
+        assert_eq!(&*y, [2, 3, 4]);
+        assert_eq!(&*y.clone().slice(1, 3), [3, 4]);
+        assert_eq!(y.clone().slice(3, 3).len(), 0);
+        assert_eq!(y, y.clone());
+
+        let z: RcSlice<_> = y.slice(1, 2).into();
+        assert_eq!(z, x.slice(2, 3));
+
+        let zsts = RcSlice::new(vec![(); 10].into_boxed_slice()).slice(2, 5);
+        assert_eq!(CompactRcSlice::new(zsts).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let whole = RcSlice::new(Box::new([1, 4, 7, 2, 5, 3, 6, 8, 9]));
+        let runs = vec![whole.clone().slice(0, 3),
+                        whole.clone().slice(3, 5),
+                        whole.clone().slice(5, 5),
+                        whole.clone().slice(5, 9)];
+        let merged = RcSlice::merge_sorted(runs.clone());
+        assert_eq!(&*merged, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let mut iter = MergeSorted::new(runs);
+        assert_eq!(iter.len(), 9);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.len(), 8);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), [2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.fuse().next(), None);
+
+        assert!(RcSlice::<u8>::merge_sorted(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_merge_sorted_stable() {
+        // compares only by the first field
+        #[derive(Clone, Debug, PartialEq)]
+        struct Key(u8, char);
+        impl Eq for Key {}
+        impl PartialOrd for Key {
+            fn partial_cmp(&self, other: &Key) -> Option<::std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Key {
+            fn cmp(&self, other: &Key) -> ::std::cmp::Ordering { self.0.cmp(&other.0) }
+        }
+
+        let a = RcSlice::new(Box::new([Key(1, 'a'), Key(2, 'a')]));
+        let b = RcSlice::new(Box::new([Key(1, 'b'), Key(2, 'b')]));
+        let merged = RcSlice::merge_sorted(vec![a, b]);
+        assert_eq!(merged.iter().map(|k| k.1).collect::<String>(), "abab");
+    }
+
+    #[test]
+    fn test_search() {
+        let x = RcSlice::new(Box::new(*b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"));
+        assert_eq!(x.find_subslice(b"\r\n\r\n"), Some(23));
+        assert_eq!(x.find_subslice(b""), Some(0));
+        assert_eq!(x.find_subslice(b"POST"), None);
+        assert!(x.contains_subslice(b"Host"));
+
+        let lines: Vec<_> = x.clone().split_byte_owned(b'\n').collect();
+        assert_eq!(lines.iter().map(|l| &**l).collect::<Vec<_>>(),
+                   [&b"GET / HTTP/1.1\r"[..], b"Host: a\r", b"\r", b""]);
+        assert_eq!(lines[1].as_ptr(), x[16..].as_ptr());
+        let mut back: Vec<_> = x.clone().split_byte_owned(b'\n').rev().collect();
+        back.reverse();
+        assert_eq!(back, lines);
+        assert_eq!(back[1].as_ptr(), lines[1].as_ptr());
+        let mut parts = RcSlice::new(Box::new(*b"a,b,c")).split_byte_owned(b',');
+        assert_eq!(&*parts.next_back().unwrap(), b"c");
+        assert_eq!(&*parts.next().unwrap(), b"a");
+        assert_eq!(&*parts.next_back().unwrap(), b"b");
+        assert!(parts.next().is_none() && parts.next_back().is_none());
+        assert_eq!(RcSlice::new(Box::new([])).split_byte_owned(b',').count(), 1);
+    }
+
+    #[test]
+    fn test_frame_cursor() {
+        let x = RcSlice::new(Box::new(*b"\x00\x03abcline\nrest"));
+        let mut cursor = FrameCursor::new(x.clone());
+        let header = cursor.take_bytes(2).unwrap();
+        assert_eq!(&*header, [0, 3]);
+        assert_eq!(&*cursor.take_bytes(3).unwrap(), b"abc");
+        assert_eq!(cursor.peek(100), None);
+        assert!(cursor.take_bytes(100).is_none());
+
+        let line = cursor.take_until(b'\n').unwrap();
+        assert_eq!(&*line, b"line");
+        assert_eq!(line.as_ptr(), x[5..].as_ptr());
+        assert!(cursor.take_until(b'\n').is_none());
+        assert_eq!(cursor.remaining(), b"rest");
+        assert_eq!(&*cursor.remaining_owned(), b"rest");
+        assert!(!cursor.is_empty());
+        assert_eq!(&*cursor.take_bytes(4).unwrap(), b"rest");
+        assert!(cursor.is_empty());
+        assert_eq!(cursor.peek(0), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_cdc_chunks() {
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let x = RcSlice::new(data.clone().into_boxed_slice());
+        let chunks: Vec<_> = x.clone().cdc_chunks(32, 128, 512).collect();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.iter().flat_map(|c| c.iter().cloned()).collect::<Vec<_>>(), data);
+        assert_eq!(chunks[1].as_ptr(), x[chunks[0].len()..].as_ptr());
+        assert!(chunks.iter().all(|c| c.len() <= 512));
+        let mut iter = x.clone().cdc_chunks(32, 128, 512).fuse();
+        assert_eq!(iter.by_ref().count(), chunks.len());
+        assert!(iter.next().is_none());
+
+        assert_eq!(RcSlice::new(Box::new([])).cdc_chunks(1, 1, 1).count(), 0);
+    }
+
+    #[cfg(all(feature = "flate2", feature = "std"))]
+    #[test]
+    fn test_decompress_gzip() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let data = b"hello hello hello hello".repeat(100);
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let x = RcSlice::decompress_gzip(&compressed).unwrap();
+        assert_eq!(&*x, &*data);
+        let twice = [&compressed[..], &compressed[..]].concat();
+        assert_eq!(&*RcSlice::decompress_gzip(&twice).unwrap(), &*data.repeat(2));
+        assert!(RcSlice::decompress_gzip(&compressed[..10]).is_err());
+    }
+
+    #[cfg(feature = "encoding_rs")]
+    #[test]
+    fn test_decode() {
+        use encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+        let (text, had_errors) = RcSlice::from(*b"\x93\xfa\x96\x7b").decode(SHIFT_JIS);
+        assert_eq!((&*text, had_errors), ("日本", false));
+        let (text, had_errors) = RcSlice::from(*b"a\xffb").decode(UTF_8);
+        assert_eq!((&*text, had_errors), ("a\u{fffd}b", true));
+
+        let x = RcSlice::from(*b"\xef\xbb\xbfhello");
+        let (text, had_errors) = x.decode(UTF_8);
+        assert_eq!((&*text, had_errors), ("hello", false));
+        assert_eq!(text.as_ptr(), x[3..].as_ptr());
+        assert_eq!(&*RcSlice::from(*b"\x80").decode(WINDOWS_1252).0, "€");
+        assert!(RcSlice::<u8>::default().decode(UTF_8).0.is_empty());
+    }
+
+    #[cfg(all(feature = "zstd", feature = "std"))]
+    #[test]
+    fn test_decompress_zstd() {
+        let data = b"hello hello hello hello".repeat(100);
+        let compressed = ::zstd::encode_all(&data[..], 0).unwrap();
 
-        assert!(!(z < x));
-        assert!(!(z <= x));
-        assert!(z > x);
-        assert!(z >= x);
+        let x = RcSlice::decompress_zstd(&compressed).unwrap();
+        assert_eq!(&*x, &*data);
+        let twice = [&compressed[..], &compressed[..]].concat();
+        assert_eq!(&*RcSlice::decompress_zstd(&twice).unwrap(), &*data.repeat(2));
+        assert!(RcSlice::decompress_zstd(b"not zstd").is_err());
+    }
 
-        assert_eq!(x.partial_cmp(&x), Some(Ordering::Equal));
-        assert_eq!(x.partial_cmp(&y), Some(Ordering::Equal));
-        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
-        assert_eq!(z.partial_cmp(&y), Some(Ordering::Greater));
+    #[test]
+    fn test_try_unwrap() {
+        let drops = Rc::new(Cell::new(0));
+        #[derive(Debug)]
+        struct Foo(u8, Rc<Cell<i32>>);
+        impl Drop for Foo {
+            fn drop(&mut self) { self.1.set(self.1.get() + 1) }
+        }
 
-        assert_eq!(x.cmp(&x), Ordering::Equal);
-        assert_eq!(x.cmp(&y), Ordering::Equal);
-        assert_eq!(x.cmp(&z), Ordering::Less);
-        assert_eq!(z.cmp(&y), Ordering::Greater);
+        let x = RcSlice::new((0..4).map(|i| Foo(i, drops.clone())).collect::<Vec<_>>()
+                                   .into_boxed_slice());
+        let w = x.downgrade();
+        let y = x.clone().slice(1, 3);
+        let x = x.try_unwrap().unwrap_err();
+        drop(x);
+
+        let elems = y.try_unwrap().ok().unwrap();
+        assert_eq!(elems.iter().map(|f| f.0).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(drops.get(), 2);
+        assert!(w.upgrade().is_none());
+        drop(elems);
+        assert_eq!(drops.get(), 4);
     }
 
     #[test]
-    fn test_partial_cmp() {
-        use std::f64;
-        let x = RcSlice::new(Box::new([1.0, f64::NAN]));
-        let y = RcSlice::new(Box::new([1.0, f64::NAN]));
-        let z = RcSlice::new(Box::new([2.0, f64::NAN]));
-        let w = RcSlice::new(Box::new([f64::NAN, 1.0]));
-        assert!(!(x == y));
-        assert!(x != y);
+    fn test_get_mut() {
+        let mut x = RcSlice::new(Box::new([1, 2, 3]));
+        assert!(x.is_unique());
+        x.get_mut().unwrap()[1] = 20;
+        assert_eq!(&*x, [1, 20, 3]);
 
-        assert!(!(x < y));
-        assert!(!(x <= y));
-        assert!(!(x > y));
-        assert!(!(x >= y));
+        let w = x.downgrade();
+        assert!(!x.is_unique());
+        assert!(x.get_mut().is_none());
+        drop(w);
+        let y = x.clone().slice_to(1);
+        assert!(x.get_mut().is_none());
+        drop(y);
+        assert_eq!(x.get_mut(), Some(&mut [1, 20, 3][..]));
+    }
 
-        assert!(x < z);
-        assert!(x <= z);
-        assert!(!(x > z));
-        assert!(!(x >= z));
+    #[test]
+    fn test_make_mut() {
+        let mut x = RcSlice::new(Box::new([1, 2, 3, 4])).slice(1, 3);
+        let w = x.downgrade();
+        x.make_mut()[0] = 20;
+        assert_eq!(&*x, [20, 3]);
+        assert_eq!(x.full_slice().len(), 2);
+        assert!(w.upgrade().is_none());
 
-        assert!(!(z < w));
-        assert!(!(z <= w));
-        assert!(!(z > w));
-        assert!(!(z >= w));
+        let p = x.as_ptr();
+        x.make_mut()[1] = 30;
+        assert_eq!(x.as_ptr(), p);
+        assert_eq!(&*x, [20, 30]);
+    }
 
-        assert_eq!(x.partial_cmp(&x), None);
-        assert_eq!(x.partial_cmp(&y), None);
-        assert_eq!(x.partial_cmp(&z), Some(Ordering::Less));
-        assert_eq!(z.partial_cmp(&x), Some(Ordering::Greater));
+    #[test]
+    fn test_slice_range() {
+        let x = RcSlice::from([1, 2, 3, 4, 5]);
+        assert_eq!(&*x.clone().slice_range(..), [1, 2, 3, 4, 5]);
+        assert_eq!(&*x.clone().slice_range(..2), [1, 2]);
+        assert_eq!(&*x.clone().slice_range(..=2), [1, 2, 3]);
+        assert_eq!(&*x.clone().slice_range(3..), [4, 5]);
+        assert_eq!(&*x.clone().slice_range(1..4).slice_range(1..), [3, 4]);
+        assert!(x.clone().slice_range(5..).is_empty());
+        let bounds = (ops::Bound::Excluded(0), ops::Bound::Included(1));
+        assert_eq!(&*x.slice_range(bounds), [2]);
+    }
 
-        assert_eq!(x.partial_cmp(&w), None);
-        assert_eq!(y.partial_cmp(&w), None);
-        assert_eq!(z.partial_cmp(&w), None);
-        assert_eq!(w.partial_cmp(&w), None);
+    #[test]
+    fn test_split_at() {
+        let x = RcSlice::from([1, 2, 3, 4]);
+        let (a, b) = x.clone().split_at(1);
+        assert_eq!(&*a, [1]);
+        assert_eq!(&*b, [2, 3, 4]);
+        assert_eq!(b.offset(), 1);
+
+        let (a, b) = x.clone().split_at(4);
+        assert_eq!((&*a, &*b), (&[1, 2, 3, 4][..], &[][..]));
+        let (a, b) = x.split_at(0);
+        assert_eq!((&*a, &*b), (&[][..], &[1, 2, 3, 4][..]));
     }
 
     #[test]
-    fn test_show() {
-        let x = RcSlice::new(Box::new([1, 2]));
-        assert_eq!(format!("{:?}", x), "[1, 2]");
+    fn test_split_off() {
+        let mut x = RcSlice::from([1, 2, 3, 4, 5]);
+        let y = x.split_off(3);
+        assert_eq!(&*x, [1, 2, 3]);
+        assert_eq!(&*y, [4, 5]);
+        assert_eq!(y.offset(), 3);
 
-        let y: RcSlice<i32> = RcSlice::new(Box::new([]));
-        assert_eq!(format!("{:?}", y), "[]");
+        let z = x.split_off(3);
+        assert!(z.is_empty());
+        assert_eq!(&*x, [1, 2, 3]);
+        assert_eq!(&*x.split_off(0), [1, 2, 3]);
+        assert!(x.is_empty());
     }
 
     #[test]
-    fn test_slice() {
-        let x = RcSlice::new(Box::new([1, 2, 3]));
-        let real = [1, 2, 3];
-        for i in (0..3 + 1) {
-            for j in (i..3 + 1) {
-                let slice: RcSlice<_> = x.clone().slice(i, j);
-                assert_eq!(&*slice, &real[i..j]);
-            }
-            assert_eq!(&*x.clone().slice_to(i), &real[..i]);
-            assert_eq!(&*x.clone().slice_from(i), &real[i..]);
+    fn test_weak_new() {
+        struct Node {
+            parent: WeakSlice<u8>,
+        }
+        const ROOT: Node = Node { parent: WeakSlice::new() };
+        let root = ROOT;
+        assert!(root.parent.upgrade().is_none());
+
+        let x = RcSlice::from([1, 2]);
+        let child = Node { parent: x.downgrade() };
+        assert_eq!(x.weak_count(), 1);
+        drop(root);
+        assert_eq!(&*child.parent.upgrade().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_counts() {
+        let x = RcSlice::from([1, 2, 3]);
+        assert_eq!((x.strong_count(), x.weak_count()), (1, 0));
+        let y = x.clone().slice_to(1);
+        let w = x.downgrade();
+        let v = y.downgrade();
+        assert_eq!((y.strong_count(), y.weak_count()), (2, 2));
+        drop((w, v));
+        drop(x);
+        assert_eq!((y.strong_count(), y.weak_count()), (1, 0));
+
+        let empty = RcSlice::<i32>::default();
+        assert_eq!((empty.strong_count(), empty.weak_count()), (1, 0));
+    }
+
+    #[test]
+    fn test_ptr_eq() {
+        let x = RcSlice::from([1, 2, 3, 4]);
+        let y = x.clone().slice(1, 3);
+        assert!(x.ptr_eq(&x.clone()));
+        assert!(!x.ptr_eq(&y));
+        assert!(x.same_allocation(&y));
+        assert!(y.ptr_eq(&x.clone().slice(1, 3)));
+
+        let z = RcSlice::from([1, 2, 3, 4]);
+        assert!(!x.ptr_eq(&z) && !x.same_allocation(&z));
+        let empty = RcSlice::<i32>::default();
+        assert!(empty.ptr_eq(&RcSlice::default()));
+        assert!(!empty.same_allocation(&x));
+        assert!(!x.same_allocation(&empty));
+        assert!(!RcSlice::from([(); 2]).ptr_eq(&RcSlice::from([(); 2])));
+    }
+
+    #[test]
+    fn test_split_to() {
+        let mut x = RcSlice::from([1, 2, 3, 4, 5]);
+        let y = x.split_to(2);
+        assert_eq!(&*y, [1, 2]);
+        assert_eq!(&*x, [3, 4, 5]);
+        assert_eq!(x.offset(), 2);
+        assert_eq!(y.offset(), 0);
+
+        assert!(x.split_to(0).is_empty());
+        assert_eq!(&*x.split_to(3), [3, 4, 5]);
+        assert!(x.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_to_out_of_bounds() {
+        RcSlice::from([1, 2]).split_to(3);
+    }
+
+    #[test]
+    fn test_advance_truncate() {
+        let rc = Rc::new(0);
+        let mut x = RcSlice::from(vec![rc.clone(), rc.clone(), rc.clone(), rc.clone()]);
+        x.advance(1);
+        assert_eq!(x.len(), 3);
+        assert_eq!(x.offset(), 1);
+        x.truncate(2);
+        assert_eq!(x.len(), 2);
+        x.truncate(5);
+        assert_eq!(x.len(), 2);
+        assert_eq!(Rc::strong_count(&rc), 5);
+        x.advance(2);
+        assert!(x.is_empty());
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_advance_out_of_bounds() {
+        RcSlice::from([1, 2]).advance(3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_at_out_of_bounds() {
+        RcSlice::from([1, 2]).split_at(3);
+    }
+
+    #[test]
+    fn test_chunks_owned() {
+        let x = RcSlice::from([1, 2, 3, 4, 5, 6, 7]);
+        let chunks = x.clone().chunks_owned(3);
+        assert_eq!(chunks.len(), 3);
+        let v: Vec<_> = chunks.collect();
+        assert_eq!(v, [RcSlice::from([1, 2, 3]), RcSlice::from([4, 5, 6]), RcSlice::from([7])]);
+        assert_eq!(v[2].offset(), 6);
+
+        let v: Vec<_> = x.clone().chunks_owned(3).rev().collect();
+        assert_eq!(v, [RcSlice::from([7]), RcSlice::from([4, 5, 6]), RcSlice::from([1, 2, 3])]);
+        let v: Vec<_> = x.slice_to(6).chunks_owned(2).rev().collect();
+        assert_eq!(v, [RcSlice::from([5, 6]), RcSlice::from([3, 4]), RcSlice::from([1, 2])]);
+
+        let mut it = RcSlice::from([1, 2, 3]).chunks_owned(2);
+        assert_eq!(it.next_back().map(|c| c.to_vec()), Some(vec![3]));
+        assert_eq!(it.next().map(|c| c.to_vec()), Some(vec![1, 2]));
+        assert!(it.next().is_none());
+        assert!(it.next_back().is_none());
+
+        assert_eq!(RcSlice::<u8>::default().chunks_owned(4).count(), 0);
+    }
+
+    #[test]
+    fn test_chunks_exact_owned() {
+        let x = RcSlice::from([1, 2, 3, 4, 5, 6, 7]);
+        let chunks = x.clone().chunks_exact_owned(3);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(&**chunks.remainder(), [7]);
+        assert_eq!(chunks.remainder().offset(), 6);
+        let v: Vec<_> = chunks.rev().collect();
+        assert_eq!(v, [RcSlice::from([4, 5, 6]), RcSlice::from([1, 2, 3])]);
+
+        let chunks = x.clone().chunks_exact_owned(8);
+        assert!(chunks.remainder()[..] == x[..]);
+        assert_eq!(chunks.count(), 0);
+        assert!(x.slice_to(6).chunks_exact_owned(3).remainder().is_empty());
+    }
+
+    #[test]
+    fn test_windows_owned() {
+        let x = RcSlice::from([1, 2, 3, 4, 5]);
+        let windows = x.clone().windows_owned(2);
+        assert_eq!(windows.len(), 4);
+        let v: Vec<_> = windows.map(|w| w.to_vec()).collect();
+        assert_eq!(v, [[1, 2], [2, 3], [3, 4], [4, 5]]);
+
+        let mut windows = x.clone().windows_owned(3);
+        assert_eq!(&*windows.next_back().unwrap(), [3, 4, 5]);
+        assert_eq!(&*windows.next().unwrap(), [1, 2, 3]);
+        assert_eq!(windows.len(), 1);
+        let last = windows.next_back().unwrap();
+        assert_eq!((&*last, last.offset()), (&[2, 3, 4][..], 1));
+        assert!(windows.next().is_none());
+
+        assert_eq!(x.clone().windows_owned(5).count(), 1);
+        assert_eq!(x.windows_owned(6).len(), 0);
+    }
+
+    #[test]
+    fn test_split_owned() {
+        let x = RcSlice::from(*b",a,,bc,");
+        let parts: Vec<_> = x.clone().split_owned(|&b| b == b',').collect();
+        assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(),
+                   [&b""[..], b"a", b"", b"bc", b""]);
+        assert_eq!(parts[3].offset(), 4);
+
+        let parts: Vec<_> = x.clone().split_owned(|&b| b == b',').rev().collect();
+        assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(),
+                   [&b""[..], b"bc", b"", b"a", b""]);
+
+        let parts: Vec<_> = x.clone().splitn_owned(3, |&b| b == b',').collect();
+        assert_eq!(parts.iter().map(|p| &**p).collect::<Vec<_>>(),
+                   [&b""[..], b"a", b",bc,"]);
+        assert_eq!(x.clone().splitn_owned(0, |_| true).count(), 0);
+        assert_eq!(&*x.clone().splitn_owned(1, |_| true).next().unwrap(), &*x);
+
+        let mut it = RcSlice::<u8>::default().split_owned(|_| true);
+        assert!(it.next().unwrap().is_empty());
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_rchunks_owned() {
+        let x = RcSlice::from([1, 2, 3, 4, 5, 6, 7]);
+        let chunks = x.clone().rchunks_owned(3);
+        assert_eq!(chunks.len(), 3);
+        let v: Vec<_> = chunks.collect();
+        assert_eq!(v, [RcSlice::from([5, 6, 7]), RcSlice::from([2, 3, 4]), RcSlice::from([1])]);
+        assert_eq!(v[0].offset(), 4);
+
+        let v: Vec<_> = x.clone().rchunks_owned(3).rev().collect();
+        assert_eq!(v, [RcSlice::from([1]), RcSlice::from([2, 3, 4]), RcSlice::from([5, 6, 7])]);
+        let v: Vec<_> = x.slice_from(1).rchunks_owned(3).rev().collect();
+        assert_eq!(v, [RcSlice::from([2, 3, 4]), RcSlice::from([5, 6, 7])]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_chunks_owned_zero() {
+        RcSlice::from([1, 2]).chunks_owned(0);
+    }
+
+    #[test]
+    fn test_rc_str() {
+        let s = RcStr::from(String::from("héllo wörld"));
+        let world = s.clone().slice(7..);
+        assert_eq!(world, "wörld");
+        assert_eq!("wörld", world);
+        assert_eq!(world.as_bytes_owned().offset(), 7);
+        assert_eq!(&*s.clone().slice(..=2), "hé");
+        assert_eq!(world.to_string(), "wörld");
+        assert_eq!(format!("{:?}", world), "\"wörld\"");
+
+        let mut set = HashSet::new();
+        set.insert(world.clone());
+        assert!(set.contains("wörld"));
+
+        let (a, b) = (RcStr::from("a"), RcStr::from("b"));
+        assert!(a < b);
+        assert_eq!(RcStr::default(), "");
+        assert_eq!(&*world.into_bytes(), "wörld".as_bytes());
+    }
+
+    #[test]
+    fn test_rc_str_split() {
+        let s = RcStr::from("a--b----c");
+        let parts: Vec<_> = s.clone().split_owned("--").collect();
+        assert_eq!(parts, ["a", "b", "", "c"]);
+        let parts: Vec<_> = s.split_owned("--").rev().collect();
+        assert_eq!(parts, ["c", "", "b", "a"]);
+        assert_eq!(RcStr::default().split_owned(",").collect::<Vec<_>>(), [""]);
+
+        let s = RcStr::from("x\r\n\ny\nz\r\n");
+        let lines: Vec<_> = s.clone().lines_owned().collect();
+        assert_eq!(lines, ["x", "", "y", "z"]);
+        let lines: Vec<_> = s.lines_owned().rev().collect();
+        assert_eq!(lines, ["z", "y", "", "x"]);
+        assert_eq!(RcStr::from("\n").lines_owned().collect::<Vec<_>>(), [""]);
+        assert_eq!(RcStr::from("\n").lines_owned().rev().collect::<Vec<_>>(), [""]);
+        assert_eq!(RcStr::from("\n").lines_owned().rev().collect::<Vec<_>>(), [""]);
+        // only a `\r` right before a `\n` is part of the line ending
+        let s = RcStr::from("a\rb\r\r\nc\r");
+        assert_eq!(s.clone().lines_owned().collect::<Vec<_>>(), ["a\rb\r", "c\r"]);
+        assert_eq!(s.lines_owned().rev().collect::<Vec<_>>(), ["c\r", "a\rb\r"]);
+
+        let s = RcStr::from("\tfoo bär\u{3000}baz  ");
+        let words: Vec<_> = s.clone().split_whitespace_owned().collect();
+        assert_eq!(words, ["foo", "bär", "baz"]);
+        assert_eq!(words[1].as_bytes_owned().offset(), 5);
+        let mut it = s.split_whitespace_owned();
+        assert_eq!(it.next_back().unwrap(), "baz");
+        assert_eq!(it.next().unwrap(), "foo");
+        assert_eq!(it.next_back().unwrap(), "bär");
+        assert!(it.next().is_none());
+        assert!(it.next_back().is_none());
+    }
+
+    #[test]
+    fn test_rc_str_from_utf8() {
+        let bytes = RcSlice::from(*b"\xffok\xe2\x9c\x93");
+        let s = RcStr::from_utf8(bytes.clone().slice_from(1)).unwrap();
+        assert_eq!(s, "ok\u{2713}");
+        assert_eq!(s.as_bytes_owned().offset(), 1);
+
+        let err = RcStr::from_utf8(bytes.clone().slice_to(5)).unwrap_err();
+        assert_eq!(err.valid_up_to(), 0);
+        let err = RcStr::from_utf8(bytes.slice(1, 5)).unwrap_err();
+        assert_eq!(err.valid_up_to(), 2);
+    }
+
+    #[test]
+    fn test_rc_str_utf16() {
+        let s = RcStr::from("a\u{e9}\u{1f600}");
+        let units = s.encode_utf16_owned();
+        assert_eq!(&*units, [0x61, 0xe9, 0xd83d, 0xde00]);
+        assert_eq!(RcStr::from_utf16(&units).unwrap(), s);
+        assert!(RcStr::from_utf16(&units[..3]).is_err());
+        assert_eq!(RcStr::from_utf16_lossy(&units[..3]), "a\u{e9}\u{fffd}");
+        assert!(RcStr::from_utf16(&[]).unwrap().is_empty());
+        assert!(RcStr::default().encode_utf16_owned().is_empty());
+    }
+
+    #[test]
+    fn test_rc_str_case() {
+        let s = RcStr::from("Hello, ΑΒΓ Wörld ΣΑΣ!");
+        assert_eq!(*s.to_lowercase_shared(), s.to_lowercase());
+        assert_eq!(*s.to_uppercase_shared(), s.to_uppercase());
+        assert_eq!(*s.to_ascii_lowercase_shared(), s.to_ascii_lowercase());
+
+        let s = RcStr::from("İx");
+        assert_eq!(s.to_lowercase_shared(), "i\u{307}x");
+        assert_eq!(s.to_ascii_lowercase_shared(), "İx");
+        assert_eq!(s.to_ascii_lowercase_shared().as_ptr(), s.as_ptr());
+
+        let s = RcStr::from("ὈΔΥΣΣΕΎΣ ǅ").slice(3..);
+        assert_eq!(s.to_lowercase_shared(), "δυσσεύς ǆ");
+        assert_eq!(s.to_uppercase_shared(), "ΔΥΣΣΕΎΣ Ǆ");
+        let upper = RcStr::from("ABC 123").slice(1..);
+        assert_eq!(upper.to_uppercase_shared().as_ptr(), upper.as_ptr());
+        assert!(RcStr::default().to_lowercase_shared().is_empty());
+    }
+
+    #[test]
+    fn test_rc_str_join() {
+        let s = RcStr::from("α,β");
+        let parts: Vec<_> = s.split_owned(",").collect();
+        assert_eq!(RcStr::join(&parts, " + "), "α + β");
+        assert_eq!(RcStr::join(&[], "-"), "");
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn test_rc_str_segments() {
+        let s = RcStr::from("  a\u{308}b, c  ").slice(1..);
+        let graphemes: Vec<_> = s.clone().graphemes_owned(true).collect();
+        assert_eq!(graphemes, [" ", "a\u{308}", "b", ",", " ", "c", " ", " "]);
+        assert_eq!(graphemes[1].as_ptr(), s[1..].as_ptr());
+
+        let mut back: Vec<_> = s.clone().graphemes_owned(true).rev().collect();
+        back.reverse();
+        assert_eq!(back, graphemes);
+        let mut words = s.clone().unicode_words_owned();
+        assert_eq!(words.next_back().unwrap(), "c");
+        assert_eq!(words.next().unwrap(), "a\u{308}b");
+        assert!(words.next().is_none());
+        assert!(words.fuse().next_back().is_none());
+
+        let parts: Vec<_> = s.clone().split_word_bounds_owned().collect();
+        assert_eq!(parts.concat(), *s);
+        assert_eq!(parts[parts.len() - 1], "  ");
+        assert_eq!(parts[parts.len() - 1].as_ptr(), s[s.len() - 2..].as_ptr());
+
+        let iter = RcStr::from(String::from("x y")).unicode_words_owned();
+        assert_eq!(iter.collect::<Vec<_>>(), ["x", "y"]);
+        assert_eq!(RcStr::default().graphemes_owned(false).count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rc_str_char_boundary() {
+        RcStr::from("héllo").slice(2..);
+    }
+
+    #[test]
+    fn test_map_ref() {
+        let x = RcSlice::from([Rc::new(1), Rc::new(2)]);
+        let one = x[0].clone();
+        let view = x.clone().map_ref(|x| &*x[1]);
+        drop(x);
+        assert_eq!(*view, 2);
+        assert_eq!(Rc::strong_count(&one), 2);
+
+        let s = RcSlice::from(*b"key: value").map_ref(|b| str::from_utf8(b).unwrap());
+        let value = s.clone().map(|s| &s[5..]);
+        drop(s);
+        assert_eq!(&*value, "value");
+        assert_eq!(format!("{:?}", value), "\"value\"");
+
+        drop(view);
+        assert_eq!(Rc::strong_count(&one), 1);
+    }
+
+    #[test]
+    fn test_into_ref() {
+        let x = RcSlice::from([1, 2, 3]).slice_from(1);
+        let r = x.clone().into_ref(1).unwrap();
+        assert_eq!(*r, 3);
+        assert!(r == x.get_ref(1).unwrap());
+        assert!(x.into_ref(2).is_none());
+        assert_eq!(*r, 3);
+    }
+
+    #[test]
+    fn test_split_first_last_owned() {
+        let x = RcSlice::from([1, 2, 3]);
+        let (first, rest) = x.clone().split_first_owned().unwrap();
+        assert_eq!((*first, &*rest), (1, &[2, 3][..]));
+        let (last, rest) = x.split_last_owned().unwrap();
+        drop(rest);
+        assert_eq!(*last, 3);
+
+        let (only, rest) = RcSlice::from([4]).split_last_owned().unwrap();
+        assert_eq!(*only, 4);
+        assert!(rest.is_empty());
+        assert!(RcSlice::<i32>::default().split_first_owned().is_none());
+        assert!(RcSlice::<i32>::default().split_last_owned().is_none());
+    }
+
+    #[test]
+    fn test_iter_cloned() {
+        let x = RcSlice::from([1, 2, 3, 4]).slice_from(1);
+        let mut it = x.iter_cloned();
+        drop(x);
+        assert_eq!(it.len(), 3);
+        assert_eq!(it.next_back(), Some(4));
+        assert_eq!(it.collect::<Vec<_>>(), [2, 3]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let x = RcSlice::from([Rc::new(1), Rc::new(2), Rc::new(3)]);
+        let y = x.clone().slice_from(1);
+        let first = x[0].clone();
+
+        // shared, so cloned
+        let mut it = y.clone().into_iter();
+        assert_eq!(it.len(), 2);
+        let three = it.next_back().unwrap();
+        assert_eq!(Rc::strong_count(&three), 2);
+        assert_eq!(it.collect::<Vec<_>>(), [Rc::new(2)]);
+
+        // unique, so moved
+        drop(x);
+        let v: Vec<_> = y.into_iter().rev().collect();
+        assert_eq!(v, [Rc::new(3), Rc::new(2)]);
+        assert_eq!(Rc::strong_count(&v[1]), 1);
+        assert_eq!(Rc::strong_count(&first), 1);
+
+        assert_eq!(RcSlice::<i32>::default().into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_try_slice() {
+        let x = RcSlice::from([1, 2, 3, 4]);
+        assert_eq!(&*x.clone().try_slice(1..3).unwrap(), [2, 3]);
+        assert_eq!(&*x.get_owned(..=1).unwrap(), [1, 2]);
+        assert!(x.get_owned(5..).is_none());
+        let (hi, lo) = (2, 3);
+        assert!(x.get_owned(lo..hi).is_none());
+
+        let err = x.try_slice(2..9).unwrap_err();
+        assert_eq!(err.to_string(), "range 2..9 out of bounds for slice of length 4");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_range_out_of_bounds() {
+        RcSlice::from([1, 2, 3]).slice_range(2..=3);
+    }
+
+    #[test]
+    fn test_full_slice() {
+        let x = RcSlice::new(Box::new([1, 2, 3, 4, 5]));
+        let y = x.clone().slice(1, 4).slice_from(2);
+        drop(x);
+        assert_eq!(&*y, [4]);
+        assert_eq!(y.offset(), 3);
+        let z = y.full_slice();
+        assert_eq!(&*z, [1, 2, 3, 4, 5]);
+        assert_eq!(z.offset(), 0);
+        assert_eq!(z.slice_to(0).full_slice().len(), 5);
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let mut v = Vec::with_capacity(10);
+        v.extend_from_slice(&[1, 2, 3]);
+        let x: RcSlice<i32> = v.into();
+        assert_eq!(&*x, [1, 2, 3]);
+        assert!(RcSlice::from(Vec::<u8>::new()).is_empty());
+    }
+
+    #[test]
+    fn test_repeat() {
+        let rc = Rc::new(0);
+        let x = RcSlice::repeat(rc.clone(), 3);
+        assert_eq!(x.len(), 3);
+        assert_eq!(Rc::strong_count(&rc), 4);
+        drop(x);
+        assert!(RcSlice::repeat(rc.clone(), 0).is_empty());
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        assert_eq!(&*RcSlice::repeat(7u8, 5), [7; 5]);
+    }
+
+    #[test]
+    fn test_from_fn() {
+        let x = RcSlice::from_fn(4, |i| vec![i; i]);
+        assert_eq!(x.iter().map(|v| v.len()).sum::<usize>(), 6);
+        assert_eq!(&*x[3], [3, 3, 3]);
+
+        let mut calls = 0;
+        assert!(RcSlice::<u8>::from_fn(0, |_| { calls += 1; 0 }).is_empty());
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn test_new_uninit() {
+        let mut x = RcSlice::<Rc<i32>>::new_uninit(2);
+        let rc = Rc::new(1);
+        for elem in x.get_mut().unwrap() {
+            elem.write(rc.clone());
         }
+        let x = unsafe { x.assume_init() };
+        assert_eq!(Rc::strong_count(&rc), 3);
+        drop(x);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let z = unsafe { RcSlice::<u64>::new_zeroed(3).assume_init() };
+        assert_eq!(&*z, [0, 0, 0]);
+        assert!(unsafe { RcSlice::<u64>::new_zeroed(0).assume_init() }.is_empty());
+    }
+
+    #[test]
+    fn test_concat() {
+        let x = RcSlice::from([1, 2, 3]);
+        let y = RcSlice::concat(vec![x.clone().slice_from(1), RcSlice::default(), x.clone()]);
+        assert_eq!(&*y, [2, 3, 1, 2, 3]);
+        assert_eq!(y.offset(), 0);
+        assert_eq!(&*RcSlice::concat([&[4][..], &[5, 6]]), [4, 5, 6]);
+        assert!(RcSlice::<u8>::concat(Vec::<Vec<u8>>::new()).is_empty());
+    }
+
+    #[test]
+    fn test_join() {
+        let x = RcSlice::from([1, 2, 3]);
+        let parts = [x.clone().slice_to(1), RcSlice::default(), x.slice_from(1)];
+        assert_eq!(&*RcSlice::join(&parts, &[0, 0]), [1, 0, 0, 0, 0, 2, 3]);
+        assert_eq!(&*RcSlice::join(&parts[..1], &[0]), [1]);
+        assert!(RcSlice::join(&[], &[0]).is_empty());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let x = RcSlice::from([1, 2, 3]);
+        let nested = RcSlice::from([x.clone().slice_from(2), RcSlice::default(), x.clone()]);
+        assert_eq!(&*RcSlice::flatten(nested), [3, 1, 2, 3]);
+        assert!(RcSlice::flatten(RcSlice::<RcSlice<u8>>::default()).is_empty());
+    }
+
+    #[test]
+    fn test_from_array() {
+        let x = RcSlice::from([1, 2, 3]);
+        assert_eq!(&*x, [1, 2, 3]);
+        let y: RcSlice<String> = ["a".to_string()].into();
+        assert_eq!(&*y, ["a"]);
+        assert!(RcSlice::<String>::from([]).is_empty());
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let x = RcSlice::from_slice(&[1u8, 2, 3][..]);
+        assert_eq!(&*x, [1, 2, 3]);
+        let y: RcSlice<String> = RcSlice::from(&["a".to_string(), "b".to_string()][..]);
+        assert_eq!(&*y, ["a", "b"]);
+        assert!(RcSlice::<String>::from_slice(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_default() {
+        let x = RcSlice::<String>::default();
+        assert!(x.is_empty());
+        assert_eq!(x.offset(), 0);
+        assert!(x.full_slice().is_empty());
+        let y = x.clone();
+        let w = y.downgrade();
+        drop((x, y));
+        assert!(w.upgrade().unwrap().is_empty());
+        assert!(CompactRcSlice::new(RcSlice::<u8>::new(Box::new([]))).unwrap().is_empty());
+
+        assert!(WeakSlice::<String>::default().upgrade().is_none());
+    }
+
+    #[test]
+    fn test_from_static() {
+        static DATA: [u32; 3] = [1, 2, 3];
+        let x = RcSlice::from_static(&DATA);
+        let mut y = x.clone().slice_from(1);
+        assert_eq!(&*y, [2, 3]);
+        assert_eq!(y.offset(), 0);
+        assert!(y.get_mut().is_none());
+        let w = x.downgrade();
+        drop(x);
+        assert_eq!(&*w.upgrade().unwrap(), [1, 2, 3]);
+        assert!(CompactRcSlice::new(y.clone()).is_err());
+
+        y.make_mut()[0] = 20;
+        assert_eq!(&*y, [20, 3]);
+        assert_eq!(DATA, [1, 2, 3]);
+        assert!(y.try_unwrap().is_ok());
+        assert!(RcSlice::from_static(&DATA).try_unwrap().is_err());
+
+        let mut empty = RcSlice::from_static(&DATA).slice_to(0);
+        assert!(!empty.is_unique());
+        assert_eq!(empty.get_mut().map(|e| e.len()), Some(0));
+        assert!(empty.try_unwrap().unwrap().is_empty());
+        let mut empty = RcSlice::<String>::empty();
+        assert_eq!(empty.get_mut().map(|e| e.len()), Some(0));
+        assert!(empty.try_unwrap().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_const() {
+        static DATA: [u32; 3] = [1, 2, 3];
+        const X: &RcSlice<u32> = &RcSlice::from_static(&DATA);
+        const LEN: usize = X.len();
+        const EMPTY: &RcSlice<u32> = &RcSlice::empty();
+        const IS_EMPTY: bool = EMPTY.is_empty();
+        assert_eq!((LEN, IS_EMPTY), (3, true));
+        assert_eq!(RcSlice::<u32>::empty(), RcSlice::default());
+    }
+
+    #[test]
+    fn test_layout() {
+        // elements more aligned than the header, and zero-sized ones
+        #[repr(align(64))]
+        #[derive(Debug, PartialEq)]
+        struct Big(u8);
+        let x = RcSlice::new(Box::new([Big(1), Big(2)]));
+        assert_eq!(x.as_ptr() as usize % 64, 0);
+        assert_eq!(x.clone().slice_from(1)[0], Big(2));
+
+        let y = RcSlice::new(vec![(); 5].into_boxed_slice());
+        let w = y.downgrade();
+        assert_eq!(y.slice(1, 4).len(), 3);
+        assert!(w.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_take() {
+        let mut x = RcSlice::new(Box::new([1, 2, 3])).slice_from(1);
+        let y = x.downgrade();
+
+        let z = x.take();
+        assert_eq!(&*z, [2, 3]);
+        assert!(x.is_empty());
+        assert_eq!(x.take().len(), 0);
+
+        drop(z);
+        assert!(y.upgrade().is_none());
+    }
+
+    #[cfg(all(feature = "checked", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "outside its allocation")]
+    fn test_checked() {
+        use std::marker::PhantomData;
+        let x = RcSlice::new(Box::new([1, 2, 3]));
+        let y = RcSlice::new(Box::new([4, 5]));
+        // simulate misuse of unsafe code mixing up allocations
+        let bad = RcSlice { data: x.data, header: y.clone().into_parts().1, _marker: PhantomData };
+        let _ = bad.first();
     }
 
     #[test]
@@ -0,0 +1,224 @@
+//! Recycling of same-sized thread-safe shared buffers.
+//!
+//! This is re-exported from the `arc` module, and requires the `std`
+//! feature.
+
+use std::fmt;
+use std::sync::Mutex;
+
+use alloc::vec::Vec;
+
+use arc::{ArcSlice, ArcSliceMut};
+
+/// A pool of `ArcSliceMut<T>` buffers of the same length, for reusing
+/// their allocations once they have been shared and are done with.
+///
+/// This avoids allocating and freeing a buffer for every item when
+/// processing a high rate of them, such as received packets: `get`
+/// hands out a buffer to fill in, which can then be frozen into an
+/// `ArcSlice` and shared. Once the other handles to it have been
+/// dropped, the last one can be given back with `recycle`, which
+/// reclaims the allocation with `ArcSlice::try_into_mut`, so that a
+/// later `get` reuses it without the allocator being involved.
+///
+/// The pool holds no reference to the buffers it hands out, so they
+/// behave like any other: `strong_count`, `WeakSlice::upgrade`,
+/// `is_unique`, `get_mut` and `try_unwrap` see only the handles that
+/// actually exist, and a buffer that is never recycled is just freed
+/// when its last handle is dropped. The pool keeps the recycled
+/// buffers until `get` hands them out again, `shrink` is called or it
+/// is dropped. Reused buffers still contain the elements written into
+/// them last time.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::SlicePool;
+///
+/// let pool = SlicePool::new(1500, 0u8);
+///
+/// let mut buf = pool.get();
+/// buf[..5].copy_from_slice(b"hello");
+/// let packet = buf.freeze();
+/// let payload = packet.clone().slice_to(5);
+/// assert_eq!(&*payload, b"hello");
+///
+/// // still shared with `payload`, so it can't be reused yet
+/// let packet = pool.recycle(packet).unwrap_err();
+/// drop(payload);
+/// let first = packet.as_ptr();
+/// pool.recycle(packet).unwrap();
+///
+/// assert_eq!(pool.len(), 1);
+/// assert_eq!(pool.get().as_ptr(), first);
+/// ```
+pub struct SlicePool<T> {
+    value: T,
+    len: usize,
+    // buffers that have been recycled and not yet handed out again
+    free: Mutex<Vec<ArcSliceMut<T>>>,
+}
+
+impl<T: Clone> SlicePool<T> {
+    /// Construct a new empty `SlicePool` of buffers of `len`
+    /// elements, which are clones of `value` when first allocated.
+    pub fn new(len: usize, value: T) -> SlicePool<T> {
+        SlicePool {
+            value,
+            len,
+            free: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get a buffer to fill in, reusing a recycled one if there are
+    /// any, or allocating a new one otherwise.
+    pub fn get(&self) -> ArcSliceMut<T> {
+        let free = self.free.lock().unwrap().pop();
+        match free {
+            Some(buffer) => buffer,
+            None => ArcSliceMut::repeat(self.value.clone(), self.len),
+        }
+    }
+}
+
+impl<T> SlicePool<T> {
+    /// Give `slice` back to be reused by a later `get`, if it is the
+    /// only handle to a buffer of the right length, and points to all
+    /// of it, like `ArcSlice::try_into_mut`. Otherwise, it is returned
+    /// unchanged.
+    ///
+    /// A buffer from `get` that was never frozen can be given back with
+    /// `recycle(buffer.freeze())`. Buffers that weren't allocated by
+    /// `self` are accepted too, if they are the right length.
+    pub fn recycle(&self, slice: ArcSlice<T>) -> Result<(), ArcSlice<T>> {
+        if slice.len() != self.len {
+            return Err(slice)
+        }
+        if self.len == 0 {
+            // empty buffers don't allocate, so there's nothing to reuse
+            return Ok(())
+        }
+        let buffer = slice.try_into_mut()?;
+        self.free.lock().unwrap().push(buffer);
+        Ok(())
+    }
+
+    /// The length of the buffers in `self`.
+    pub fn buffer_len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of recycled buffers in `self`, waiting to be reused.
+    pub fn len(&self) -> usize {
+        self.free.lock().unwrap().len()
+    }
+
+    /// Whether `self` has no recycled buffers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Free the recycled buffers in `self`, returning the number freed.
+    pub fn shrink(&self) -> usize {
+        let mut free = self.free.lock().unwrap();
+        let freed = free.len();
+        free.clear();
+        freed
+    }
+}
+
+impl<T> fmt::Debug for SlicePool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SlicePool")
+            .field("buffer_len", &self.len)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlicePool;
+    use arc::ArcSlice;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_reuse() {
+        let pool = SlicePool::new(4, 0);
+        let mut a = pool.get();
+        a.copy_from_slice(&[1, 2, 3, 4]);
+        let b = pool.get();
+        assert_eq!(&*b, [0; 4]);
+        assert!(pool.is_empty());
+
+        let a = a.freeze();
+        assert_eq!(a.strong_count(), 1);
+        let weak = a.downgrade();
+        let ptr = a.as_ptr();
+        let a = pool.recycle(a).unwrap_err();
+        drop(weak);
+        let part = a.clone().slice_from(1);
+        let a = pool.recycle(a).unwrap_err();
+        assert!(pool.recycle(part).is_err());
+        pool.recycle(a).unwrap();
+        assert_eq!(pool.len(), 1);
+
+        let mut c = pool.get();
+        assert_eq!(c.as_ptr(), ptr);
+        assert_eq!(&*c, [1, 2, 3, 4]);
+        c[0] = 5;
+        assert!(pool.is_empty());
+
+        pool.recycle(b.freeze()).unwrap();
+        pool.recycle(c.freeze()).unwrap();
+        assert!(pool.recycle(ArcSlice::from_fn(3, |i| i as i32)).is_err());
+        pool.recycle(ArcSlice::from_fn(4, |i| i as i32)).unwrap();
+        assert_eq!(pool.len(), 3);
+        assert_eq!(format!("{:?}", pool), "SlicePool { buffer_len: 4, len: 3 }");
+        assert_eq!(pool.shrink(), 3);
+        assert!(pool.is_empty());
+
+        let empty = SlicePool::new(0, 0);
+        assert!(empty.get().is_empty());
+        empty.recycle(ArcSlice::default()).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_no_hidden_reference() {
+        let pool = SlicePool::new(2, 0u8);
+        let x = pool.get().freeze();
+        let weak = x.downgrade();
+        drop(x);
+        assert!(weak.upgrade().is_none());
+
+        let mut x = pool.get().freeze();
+        assert!(x.is_unique());
+        x.get_mut().unwrap()[0] = 1;
+        assert_eq!(&*x.try_unwrap().unwrap(), [1, 0]);
+    }
+
+    #[test]
+    fn test_threads() {
+        let pool = Arc::new(SlicePool::new(8, 0usize));
+        let threads: Vec<_> = (0..4).map(|t| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                for i in 0..100 {
+                    let mut buf = pool.get();
+                    buf.iter_mut().for_each(|x| *x = t * i);
+                    let buf = buf.freeze();
+                    let shared = buf.clone();
+                    assert!(shared.iter().all(|&x| x == t * i));
+                    drop(shared);
+                    pool.recycle(buf).unwrap();
+                }
+            })
+        }).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert!(pool.len() <= 4);
+    }
+}
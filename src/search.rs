@@ -0,0 +1,40 @@
+//! Byte searching, using the `memchr` crate when it is enabled.
+
+#[cfg(feature = "memchr")]
+use memchr::{memchr, memmem, memrchr};
+
+/// The index of the first occurrence of `byte` in `haystack`.
+#[cfg(feature = "memchr")]
+pub fn find_byte(byte: u8, haystack: &[u8]) -> Option<usize> {
+    memchr(byte, haystack)
+}
+/// The index of the first occurrence of `byte` in `haystack`.
+#[cfg(not(feature = "memchr"))]
+pub fn find_byte(byte: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b == byte)
+}
+
+/// The index of the last occurrence of `byte` in `haystack`.
+#[cfg(feature = "memchr")]
+pub fn rfind_byte(byte: u8, haystack: &[u8]) -> Option<usize> {
+    memrchr(byte, haystack)
+}
+/// The index of the last occurrence of `byte` in `haystack`.
+#[cfg(not(feature = "memchr"))]
+pub fn rfind_byte(byte: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| b == byte)
+}
+
+/// The index of the first occurrence of `needle` in `haystack`.
+#[cfg(feature = "memchr")]
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    memmem::find(haystack, needle)
+}
+/// The index of the first occurrence of `needle` in `haystack`.
+#[cfg(not(feature = "memchr"))]
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0)
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
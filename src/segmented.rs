@@ -0,0 +1,591 @@
+//! A sequence of thread-safe shared slices that behaves like their
+//! concatenation.
+
+use core::{fmt, ops};
+use core::iter::{FromIterator, FusedIterator};
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::io::IoSlice;
+
+use arc::ArcSlice;
+use range::{self, SliceError};
+
+/// Segments shorter than this are merged with short neighbours after
+/// `insert` and `remove`, and by `rebalance`.
+const MIN_SEGMENT_LEN: usize = 64;
+
+/// A sequence of elements stored as a list of `ArcSlice<T>` segments,
+/// also known as a rope.
+///
+/// This behaves like the concatenation of the segments, but doesn't
+/// copy them: appending a segment and taking a subrange only clone
+/// the handles of the segments involved. The elements can be copied
+/// into a single `ArcSlice` with `to_contiguous` when needed.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::ArcSlice;
+/// use shared_slice::segmented::SegmentedSlice;
+///
+/// let mut message = SegmentedSlice::new();
+/// message.push(ArcSlice::from(*b"GET / HT"));
+/// message.push(ArcSlice::from(*b"TP/1.1\r\n"));
+/// assert_eq!(message.len(), 16);
+/// assert_eq!(message[9], b'P');
+///
+/// let version = message.slice(6..14);
+/// assert_eq!(version.segments().len(), 2);
+/// assert_eq!(&*version.to_contiguous(), b"HTTP/1.1");
+/// ```
+///
+/// Elements can also be inserted and removed anywhere, which makes
+/// this suitable for the text of a document in an editor: each clone
+/// is a cheap snapshot, which later edits don't affect.
+///
+/// ```rust
+/// use shared_slice::arc::ArcSlice;
+/// use shared_slice::segmented::SegmentedSlice;
+///
+/// let mut doc = SegmentedSlice::from(ArcSlice::from(*b"hello world"));
+/// let before = doc.clone();
+/// doc.insert(5, ArcSlice::from(*b","));
+/// doc.remove(7..12);
+/// doc.insert(7, ArcSlice::from(*b"rope"));
+/// assert_eq!(&*doc.to_contiguous(), b"hello, rope");
+/// assert_eq!(&*before.to_contiguous(), b"hello world");
+/// ```
+pub struct SegmentedSlice<T> {
+    // none of the segments are empty
+    segments: Vec<ArcSlice<T>>,
+    // the index just past the end of each segment
+    ends: Vec<usize>,
+}
+
+impl<T> SegmentedSlice<T> {
+    /// Construct a new empty `SegmentedSlice`.
+    pub fn new() -> SegmentedSlice<T> {
+        SegmentedSlice {
+            segments: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// Append the elements of `segment` to `self`, without copying
+    /// them.
+    pub fn push(&mut self, segment: ArcSlice<T>) {
+        if !segment.is_empty() {
+            self.ends.push(self.len() + segment.len());
+            self.segments.push(segment);
+        }
+    }
+
+    /// The number of elements in `self`.
+    pub fn len(&self) -> usize {
+        self.ends.last().cloned().unwrap_or(0)
+    }
+
+    /// Whether `self` contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The segments of `self`, none of which are empty.
+    pub fn segments(&self) -> &[ArcSlice<T>] {
+        &self.segments
+    }
+
+    /// The index of the segment containing element `i`, and the index
+    /// of its first element.
+    fn locate(&self, i: usize) -> (usize, usize) {
+        let seg = self.ends.partition_point(|&end| end <= i);
+        (seg, if seg == 0 { 0 } else { self.ends[seg - 1] })
+    }
+
+    /// Get the element at index `i`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len() {
+            return None
+        }
+        let (seg, start) = self.locate(i);
+        Some(&self.segments[seg][i - start])
+    }
+
+    /// Iterate over the elements of `self`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            segments: &self.segments,
+            front: [].iter(),
+            back: [].iter(),
+            len: self.len(),
+        }
+    }
+
+    /// Construct a new `SegmentedSlice` that only contains the
+    /// elements of `self` in `range`, such as `2..`, `..=5` or `..`,
+    /// reusing the segments that overlap it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, or if either
+    /// are out of bounds, like indexing a slice.
+    pub fn slice<R: ops::RangeBounds<usize>>(&self, range: R) -> SegmentedSlice<T> {
+        match self.try_slice(range) {
+            Ok(slice) => slice,
+            Err(e) => panic!("SegmentedSlice::slice: {}", e),
+        }
+    }
+
+    /// Construct a new `SegmentedSlice` that only contains the
+    /// elements of `self` in `range`, or return an error if it is out
+    /// of bounds.
+    pub fn try_slice<R: ops::RangeBounds<usize>>(&self, range: R)
+                                                  -> Result<SegmentedSlice<T>, SliceError> {
+        let (lo, hi) = range::check(&range, self.len())?;
+        let mut slice = SegmentedSlice::new();
+        if lo == hi {
+            return Ok(slice)
+        }
+        let (first, mut start) = self.locate(lo);
+        for segment in &self.segments[first..] {
+            if start >= hi {
+                break
+            }
+            let seg_lo = lo.saturating_sub(start);
+            let seg_hi = (hi - start).min(segment.len());
+            slice.push(segment.clone().slice(seg_lo, seg_hi));
+            start += segment.len();
+        }
+        Ok(slice)
+    }
+}
+
+impl<T: Clone> SegmentedSlice<T> {
+    /// Construct a new `ArcSlice` containing the elements of `self`.
+    ///
+    /// This only copies the elements if there is more than one
+    /// segment.
+    pub fn to_contiguous(&self) -> ArcSlice<T> {
+        match self.segments.len() {
+            0 => ArcSlice::default(),
+            1 => self.segments[0].clone(),
+            _ => ArcSlice::concat(&self.segments),
+        }
+    }
+
+    /// Insert the elements of `segment` at index `at`, after the
+    /// elements before it, without copying them.
+    ///
+    /// If this leaves short segments (of fewer than 64 elements) next
+    /// to each other around `at`, they are merged into one, copying
+    /// their elements, so that many small edits don't leave many tiny
+    /// segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    pub fn insert(&mut self, at: usize, segment: ArcSlice<T>) {
+        assert!(at <= self.len(), "SegmentedSlice::insert: {} out of bounds for length {}",
+                at, self.len());
+        if segment.is_empty() {
+            return
+        }
+        let (i, start) = self.locate(at);
+        let edited = if at == start {
+            self.segments.insert(i, segment);
+            i..i + 1
+        } else {
+            // split the segment containing `at` around `segment`
+            let (before, after) = self.segments[i].clone().split_at(at - start);
+            self.segments.splice(i..i + 1, [before, segment, after]);
+            i..i + 3
+        };
+        self.merge_short(edited);
+    }
+
+    /// Remove the elements of `self` in `range`, such as `2..5` or
+    /// `..=3`, reusing the segments around it.
+    ///
+    /// Like `insert`, short segments left next to each other are
+    /// merged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is after its end, or if either
+    /// are out of bounds, like indexing a slice.
+    pub fn remove<R: ops::RangeBounds<usize>>(&mut self, range: R) {
+        let (lo, hi) = match range::check(&range, self.len()) {
+            Ok(bounds) => bounds,
+            Err(e) => panic!("SegmentedSlice::remove: {}", e),
+        };
+        if lo == hi {
+            return
+        }
+        let (first, first_start) = self.locate(lo);
+        let (last, last_start) = self.locate(hi - 1);
+        let mut kept = Vec::with_capacity(2);
+        if lo > first_start {
+            kept.push(self.segments[first].clone().slice_to(lo - first_start));
+        }
+        if hi - last_start < self.segments[last].len() {
+            kept.push(self.segments[last].clone().slice_from(hi - last_start));
+        }
+        let n = kept.len();
+        self.segments.splice(first..last + 1, kept);
+        self.merge_short(first..first + n);
+    }
+
+    /// Merge each run of adjacent short segments (of fewer than 64
+    /// elements) into a single segment, copying their elements.
+    ///
+    /// This is done around each edit by `insert` and `remove`
+    /// automatically, but can be used to tidy up after many short
+    /// segments are added with `push`.
+    pub fn rebalance(&mut self) {
+        let n = self.segments.len();
+        self.merge_short(0..n);
+    }
+
+    /// Merge the runs of adjacent short segments that overlap the
+    /// segments in `edited` (or are next to where it would be, when it
+    /// is empty), and update `ends` to match.
+    fn merge_short(&mut self, edited: ops::Range<usize>) {
+        let short = |s: &ArcSlice<T>| s.len() < MIN_SEGMENT_LEN;
+        let mut lo = edited.start;
+        while lo > 0 && short(&self.segments[lo - 1]) {
+            lo -= 1;
+        }
+        let mut hi = edited.end;
+        while hi < self.segments.len() && short(&self.segments[hi]) {
+            hi += 1;
+        }
+
+        let mut i = lo;
+        while i < hi {
+            let run = self.segments[i..hi].iter().take_while(|s| short(s)).count();
+            if run > 1 {
+                let merged = ArcSlice::concat(&self.segments[i..i + run]);
+                self.segments.splice(i..i + run, Some(merged));
+                hi -= run - 1;
+            }
+            i += 1;
+        }
+
+        self.ends.clear();
+        let mut end = 0;
+        for segment in &self.segments {
+            end += segment.len();
+            self.ends.push(end);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl SegmentedSlice<u8> {
+    /// The segments of `self` as `IoSlice`s, to write them all at once
+    /// with `Write::write_vectored`, without copying them.
+    ///
+    /// This requires the `std` feature.
+    pub fn as_io_slices(&self) -> Vec<IoSlice<'_>> {
+        self.segments.iter().map(|s| IoSlice::new(s)).collect()
+    }
+}
+
+impl<T> Clone for SegmentedSlice<T> {
+    fn clone(&self) -> SegmentedSlice<T> {
+        SegmentedSlice {
+            segments: self.segments.clone(),
+            ends: self.ends.clone(),
+        }
+    }
+}
+
+impl<T> Default for SegmentedSlice<T> {
+    fn default() -> SegmentedSlice<T> {
+        SegmentedSlice::new()
+    }
+}
+
+impl<T> From<ArcSlice<T>> for SegmentedSlice<T> {
+    fn from(segment: ArcSlice<T>) -> SegmentedSlice<T> {
+        let mut slice = SegmentedSlice::new();
+        slice.push(segment);
+        slice
+    }
+}
+
+impl<T> FromIterator<ArcSlice<T>> for SegmentedSlice<T> {
+    fn from_iter<I: IntoIterator<Item = ArcSlice<T>>>(segments: I) -> SegmentedSlice<T> {
+        let mut slice = SegmentedSlice::new();
+        slice.extend(segments);
+        slice
+    }
+}
+
+impl<T> Extend<ArcSlice<T>> for SegmentedSlice<T> {
+    fn extend<I: IntoIterator<Item = ArcSlice<T>>>(&mut self, segments: I) {
+        for segment in segments {
+            self.push(segment);
+        }
+    }
+}
+
+impl<T> ops::Index<usize> for SegmentedSlice<T> {
+    type Output = T;
+    fn index(&self, i: usize) -> &T {
+        match self.get(i) {
+            Some(x) => x,
+            None => panic!("SegmentedSlice: index {} out of bounds for length {}", i, self.len()),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SegmentedSlice<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SegmentedSlice<T> {
+    fn eq(&self, other: &SegmentedSlice<T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<T: Eq> Eq for SegmentedSlice<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for SegmentedSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the elements of a `SegmentedSlice`.
+///
+/// This is created by `SegmentedSlice::iter`.
+pub struct Iter<'a, T: 'a> {
+    // the segments not yet started from either end
+    segments: &'a [ArcSlice<T>],
+    front: ::core::slice::Iter<'a, T>,
+    back: ::core::slice::Iter<'a, T>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(x) = self.front.next() {
+                self.len -= 1;
+                return Some(x)
+            }
+            match self.segments.split_first() {
+                Some((first, rest)) => {
+                    self.front = first.iter();
+                    self.segments = rest;
+                }
+                None => {
+                    let x = self.back.next()?;
+                    self.len -= 1;
+                    return Some(x)
+                }
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(x) = self.back.next_back() {
+                self.len -= 1;
+                return Some(x)
+            }
+            match self.segments.split_last() {
+                Some((last, rest)) => {
+                    self.back = last.iter();
+                    self.segments = rest;
+                }
+                None => {
+                    let x = self.front.next_back()?;
+                    self.len -= 1;
+                    return Some(x)
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::SegmentedSlice;
+    use arc::ArcSlice;
+
+    fn example() -> SegmentedSlice<i32> {
+        vec![ArcSlice::from([1, 2, 3]), ArcSlice::default(), ArcSlice::from([4]),
+             ArcSlice::from([5, 6])].into_iter().collect()
+    }
+
+    #[test]
+    fn test_index() {
+        let x = example();
+        assert_eq!(x.len(), 6);
+        assert_eq!(x.segments().len(), 3);
+        assert_eq!((0..6).map(|i| x[i]).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(x.get(6), None);
+        assert!(SegmentedSlice::<i32>::new().is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let x = example();
+        assert_eq!(x.iter().cloned().collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(x.iter().rev().cloned().collect::<Vec<_>>(), [6, 5, 4, 3, 2, 1]);
+
+        let mut it = x.iter();
+        assert_eq!(it.next_back(), Some(&6));
+        assert_eq!(it.next(), Some(&1));
+        assert_eq!(it.len(), 4);
+        assert_eq!(it.next_back(), Some(&5));
+        assert_eq!(it.next_back(), Some(&4));
+        assert_eq!(it.next_back(), Some(&3));
+        assert_eq!(it.next(), Some(&2));
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next_back(), None);
+    }
+
+    #[test]
+    fn test_slice() {
+        let x = example();
+        let y = x.slice(2..5);
+        assert_eq!(y.iter().cloned().collect::<Vec<_>>(), [3, 4, 5]);
+        assert_eq!(y.segments().len(), 3);
+        assert_eq!(y.segments()[0].offset(), 2);
+
+        assert_eq!(x.slice(3..4).segments().len(), 1);
+        assert!(x.slice(6..).is_empty());
+        assert!(x.slice(..) == x);
+        assert!(x.try_slice(4..7).is_err());
+        let (hi, lo) = (3, 4);
+        assert!(x.try_slice(lo..hi).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_out_of_bounds() {
+        example().slice(..7);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut x = example();
+        x.insert(1, ArcSlice::from([10, 11]));
+        x.insert(0, ArcSlice::from([12]));
+        x.insert(9, ArcSlice::from([13]));
+        x.insert(3, ArcSlice::default());
+        assert_eq!(x.iter().cloned().collect::<Vec<_>>(), [12, 1, 10, 11, 2, 3, 4, 5, 6, 13]);
+        // all the segments are short, so are merged
+        assert_eq!(x.segments().len(), 1);
+        assert_eq!(x.len(), 10);
+
+        let long = ArcSlice::from_fn(200, |i| i as i32);
+        let mut x = SegmentedSlice::from(long.clone());
+        x.insert(100, ArcSlice::from([-1]));
+        assert_eq!(x.segments().len(), 3);
+        assert_eq!(x.segments()[2].as_ptr(), long[100..].as_ptr());
+        x.insert(201, long.clone().slice(10, 20));
+        x.insert(202, ArcSlice::from([-2]));
+        assert_eq!(x.segments().len(), 4);
+        assert_eq!(&*x.segments()[3], &*ArcSlice::concat([&[10][..], &[-2], &long[11..20]]));
+        assert_eq!(x.len(), 212);
+        assert_eq!(x[100], -1);
+        assert_eq!(x[211], 19);
+
+        // the halves of a short segment are merged back together
+        let mut x = SegmentedSlice::from(long.slice_to(50));
+        x.insert(25, ArcSlice::from([-1]));
+        assert_eq!(x.segments().len(), 1);
+        assert_eq!(x[25], -1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let long = ArcSlice::from_fn(200, |i| i as i32);
+        let mut x = SegmentedSlice::from(long.clone());
+        x.remove(100..110);
+        assert_eq!(x.segments().len(), 2);
+        assert_eq!(x.len(), 190);
+        assert_eq!(x[100], 110);
+        x.remove(..=98);
+        x.remove(2..);
+        assert_eq!(x.iter().cloned().collect::<Vec<_>>(), [99, 110]);
+        assert_eq!(x.segments().len(), 1);
+        x.remove(1..1);
+        x.remove(..);
+        assert!(x.is_empty());
+        assert!(x.segments().is_empty());
+
+        let mut x = example();
+        x.remove(1..4);
+        assert_eq!(x.iter().cloned().collect::<Vec<_>>(), [1, 5, 6]);
+        assert_eq!(x.len(), 3);
+        assert_eq!(x.segments().len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_remove_out_of_bounds() {
+        example().remove(5..7);
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let long = ArcSlice::from_fn(100, |i| i as i32);
+        let mut x: SegmentedSlice<i32> = vec![
+            ArcSlice::from([1]), ArcSlice::from([2]), long.clone(), ArcSlice::from([3]),
+            long.clone(), ArcSlice::from([4]), ArcSlice::from([5]), ArcSlice::from([6]),
+        ].into_iter().collect();
+        let before = x.clone();
+        x.rebalance();
+        assert!(x == before);
+        let lens: Vec<_> = x.segments().iter().map(|s| s.len()).collect();
+        assert_eq!(lens, [2, 100, 1, 100, 3]);
+        assert_eq!(x.get(205), Some(&6));
+        assert_eq!(x.get(206), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_as_io_slices() {
+        use std::io::Write;
+        let bytes: SegmentedSlice<u8> = vec![ArcSlice::from(*b"ab"), ArcSlice::default(),
+                                             ArcSlice::from(*b"cde")].into_iter().collect();
+        let slices = bytes.as_io_slices();
+        assert_eq!(slices.len(), 2);
+        let mut out = Vec::new();
+        assert_eq!(out.write_vectored(&slices).unwrap(), 5);
+        assert_eq!(out, b"abcde");
+    }
+
+    #[test]
+    fn test_to_contiguous() {
+        let x = example();
+        assert_eq!(&*x.to_contiguous(), [1, 2, 3, 4, 5, 6]);
+
+        let one = x.slice(4..);
+        assert_eq!(one.to_contiguous().offset(), 0);
+        assert_eq!(one.to_contiguous().as_ptr(), one.segments()[0].as_ptr());
+        assert!(SegmentedSlice::<i32>::new().to_contiguous().is_empty());
+        assert_eq!(format!("{:?}", one), "[5, 6]");
+    }
+}
@@ -0,0 +1,305 @@
+//! Sharing-aware serialization of shared slices.
+//!
+//! Serializing an `ArcSlice` or `RcSlice` normally writes out its
+//! elements, so a slice that is referenced from many places is
+//! written many times, and deserializing creates a separate
+//! allocation for each. The `arc` and `rc` submodules here can be
+//! used with `#[serde(with = "...")]` to instead write out each
+//! allocation only once per call to `scope`, with later occurrences
+//! (including other subslices of the same allocation) stored as
+//! references to it. Deserializing inside `scope` reconstructs the
+//! sharing, so the resulting slices point into a single allocation
+//! again.
+//!
+//! Outside of `scope`, only the elements of each slice are written
+//! out, not the rest of its allocation, and deserializing fails on
+//! references.
+//!
+//! This requires the `serde` and `std` features.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate shared_slice; extern crate serde_json;
+//! # #[macro_use] extern crate serde_derive;
+//! use shared_slice::arc::ArcSlice;
+//! use shared_slice::sharing;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Node {
+//!     #[serde(with = "sharing::arc")]
+//!     text: ArcSlice<u8>,
+//! }
+//!
+//! # fn main() {
+//! let text = ArcSlice::new(Box::new(*b"a long document"));
+//! let nodes = vec![Node { text: text.clone().slice(0, 6) },
+//!                  Node { text: text.clone().slice(7, 15) }];
+//!
+//! let json = sharing::scope(|| serde_json::to_string(&nodes)).unwrap();
+//! let nodes: Vec<Node> = sharing::scope(|| serde_json::from_str(&json)).unwrap();
+//!
+//! assert_eq!(&*nodes[1].text, b"document");
+//! # }
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::de::Error;
+
+struct State {
+    // serialization: the allocations seen so far, by address and
+    // length (since different views of static data are separate
+    // "allocations" at the same address), and clones of them to
+    // ensure their addresses aren't reused while in scope.
+    ids: HashMap<(usize, usize), u64>,
+    keep_alive: Vec<Box<dyn Any>>,
+    // deserialization: the whole allocations seen so far.
+    slices: HashMap<u64, Box<dyn Any>>,
+}
+
+thread_local!(static STATE: RefCell<Option<State>> = const { RefCell::new(None) });
+
+/// Run `f`, sharing allocations between everything serialized or
+/// deserialized by the `arc` and `rc` submodules on this thread
+/// while it runs.
+///
+/// Data serialized within one call to `scope` must be deserialized
+/// within a single call to `scope`, too. Calls can be nested, and the
+/// inner call does not share with the outer one.
+pub fn scope<R, F: FnOnce() -> R>(f: F) -> R {
+    struct Restore(Option<State>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let old = self.0.take();
+            STATE.with(|s| *s.borrow_mut() = old);
+        }
+    }
+
+    let state = State {
+        ids: HashMap::new(),
+        keep_alive: Vec::new(),
+        slices: HashMap::new(),
+    };
+    let _restore = Restore(STATE.with(|s| s.replace(Some(state))));
+    f()
+}
+
+/// Get the ID for the allocation at `address` with `len` elements,
+/// and whether this is the first time it has been seen, or `None`
+/// outside of `scope`.
+fn id_for<F>(address: usize, len: usize, keep_alive: F) -> Option<(u64, bool)>
+    where F: FnOnce() -> Box<dyn Any>
+{
+    STATE.with(|s| {
+        match *s.borrow_mut() {
+            Some(ref mut state) => {
+                let next = state.ids.len() as u64;
+                let id = *state.ids.entry((address, len)).or_insert(next);
+                if id == next {
+                    state.keep_alive.push(keep_alive());
+                }
+                Some((id, id == next))
+            }
+            None => None,
+        }
+    })
+}
+
+/// Resolve the allocation with ID `id`, either constructing it from
+/// `data` (and recording it), or looking up a previous one.
+fn resolve<T, S, E, F>(id: u64, data: Option<Vec<T>>, new: F) -> Result<S, E>
+    where S: Clone + Any, E: Error, F: FnOnce(Vec<T>) -> S
+{
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        match data {
+            Some(data) => {
+                let whole = new(data);
+                if let Some(ref mut state) = *state {
+                    state.slices.insert(id, Box::new(whole.clone()));
+                }
+                Ok(whole)
+            }
+            None => {
+                match state.as_ref().and_then(|state| state.slices.get(&id)) {
+                    Some(whole) => whole.downcast_ref::<S>().cloned().ok_or_else(|| {
+                        E::custom(format_args!("shared slice {} has a different type", id))
+                    }),
+                    None => Err(E::custom(format_args!("reference to unknown shared slice {}", id))),
+                }
+            }
+        }
+    })
+}
+
+#[cfg(feature = "arc")]
+/// Sharing-aware serialization for `ArcSlice`, for use with
+/// `#[serde(with = "shared_slice::sharing::arc")]`.
+pub mod arc {
+    use std::any::Any;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::Error;
+
+    use arc::ArcSlice;
+    use super::{id_for, resolve};
+
+    /// Serialize `slice`, writing out its allocation only if it has
+    /// not been seen already in this `scope`, or just its elements
+    /// outside of one.
+    pub fn serialize<T, S>(slice: &ArcSlice<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Serialize + Any, S: Serializer
+    {
+        let (whole, address) = slice.allocation();
+        match id_for(address, whole.len(), || Box::new(slice.clone())) {
+            Some((id, first)) => {
+                let data = if first { Some(whole) } else { None };
+                (id, slice.offset(), slice.len(), data).serialize(serializer)
+            }
+            None => (0, 0, slice.len(), Some(&**slice)).serialize(serializer),
+        }
+    }
+
+    /// Deserialize a slice written by `serialize`, sharing
+    /// allocations with previous slices deserialized in this `scope`.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<ArcSlice<T>, D::Error>
+        where T: Deserialize<'de> + Any, D: Deserializer<'de>
+    {
+        let (id, offset, len, data): (u64, usize, usize, Option<Vec<T>>) =
+            Deserialize::deserialize(deserializer)?;
+        let whole: ArcSlice<T> = resolve(id, data, |v| ArcSlice::new(v.into_boxed_slice()))?;
+        match offset.checked_add(len) {
+            Some(hi) if hi <= whole.len() => Ok(whole.slice(offset, hi)),
+            _ => Err(D::Error::custom(format_args!(
+                "range {}+{} out of bounds for shared slice {} of length {}",
+                offset, len, id, whole.len()))),
+        }
+    }
+}
+
+#[cfg(feature = "rc")]
+/// Sharing-aware serialization for `RcSlice`, for use with
+/// `#[serde(with = "shared_slice::sharing::rc")]`.
+pub mod rc {
+    use std::any::Any;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::Error;
+
+    use rc::RcSlice;
+    use super::{id_for, resolve};
+
+    /// Serialize `slice`, writing out its allocation only if it has
+    /// not been seen already in this `scope`, or just its elements
+    /// outside of one.
+    pub fn serialize<T, S>(slice: &RcSlice<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Serialize + Any, S: Serializer
+    {
+        let (whole, address) = slice.allocation();
+        match id_for(address, whole.len(), || Box::new(slice.clone())) {
+            Some((id, first)) => {
+                let data = if first { Some(whole) } else { None };
+                (id, slice.offset(), slice.len(), data).serialize(serializer)
+            }
+            None => (0, 0, slice.len(), Some(&**slice)).serialize(serializer),
+        }
+    }
+
+    /// Deserialize a slice written by `serialize`, sharing
+    /// allocations with previous slices deserialized in this `scope`.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<RcSlice<T>, D::Error>
+        where T: Deserialize<'de> + Any, D: Deserializer<'de>
+    {
+        let (id, offset, len, data): (u64, usize, usize, Option<Vec<T>>) =
+            Deserialize::deserialize(deserializer)?;
+        let whole: RcSlice<T> = resolve(id, data, |v| RcSlice::new(v.into_boxed_slice()))?;
+        match offset.checked_add(len) {
+            Some(hi) if hi <= whole.len() => Ok(whole.slice(offset, hi)),
+            _ => Err(D::Error::custom(format_args!(
+                "range {}+{} out of bounds for shared slice {} of length {}",
+                offset, len, id, whole.len()))),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rc", feature = "arc"))]
+mod tests {
+    use arc::ArcSlice;
+    use rc::RcSlice;
+    use serde_json;
+    use super::scope;
+
+    #[derive(Serialize, Deserialize)]
+    struct Doc {
+        #[serde(with = "super::arc")]
+        a: ArcSlice<u32>,
+        #[serde(with = "super::arc")]
+        b: ArcSlice<u32>,
+        #[serde(with = "super::rc")]
+        c: RcSlice<u32>,
+    }
+
+    fn doc() -> Doc {
+        let a = ArcSlice::new((0..100).collect::<Vec<_>>().into_boxed_slice());
+        Doc {
+            b: a.clone().slice(10, 20),
+            a,
+            c: RcSlice::new(Box::new([7, 8, 9])),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = scope(|| serde_json::to_string(&doc())).unwrap();
+        let plain = serde_json::to_string(&doc()).unwrap();
+        assert!(json.len() < plain.len());
+
+        let d: Doc = scope(|| serde_json::from_str(&json)).unwrap();
+        assert_eq!(&*d.a, &*(0..100).collect::<Vec<_>>());
+        assert_eq!(&*d.b, &*(10..20).collect::<Vec<_>>());
+        assert_eq!(d.b.as_ptr(), d.a[10..].as_ptr());
+        assert_eq!(&*d.c, [7, 8, 9]);
+
+        // without sharing, each slice is written out, but only its own
+        // elements
+        assert!(plain.contains(r#""b":[0,0,10,[10,11,12,13,14,15,16,17,18,19]]"#));
+        let d: Doc = serde_json::from_str(&plain).unwrap();
+        assert_eq!(&*d.b, &*(10..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_static() {
+        static DATA: [u32; 4] = [1, 2, 3, 4];
+        let whole = ArcSlice::from_static(&DATA);
+        let d = Doc { a: whole.clone().slice_to(2), b: whole, c: RcSlice::default() };
+        let json = scope(|| serde_json::to_string(&d)).unwrap();
+
+        let d: Doc = scope(|| serde_json::from_str(&json)).unwrap();
+        assert_eq!(&*d.a, [1, 2]);
+        assert_eq!(&*d.b, [1, 2, 3, 4]);
+        assert!(d.c.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let json = scope(|| serde_json::to_string(&doc())).unwrap();
+        assert!(serde_json::from_str::<Doc>(&json).is_err());
+
+        let bad = r#"{"a": [0, 0, 1, [1]], "b": [0, 1, 1, null], "c": [1, 0, 0, []]}"#;
+        assert!(scope(|| serde_json::from_str::<Doc>(bad)).is_err());
+        let wrong_type = r#"{"a": [0, 0, 1, [1]], "b": [0, 0, 1, null], "c": [0, 0, 0, null]}"#;
+        assert!(scope(|| serde_json::from_str::<Doc>(wrong_type)).is_err());
+    }
+
+    #[test]
+    fn test_plain() {
+        let x = ArcSlice::new(Box::new([1, 2, 3])).slice_from(1);
+        let json = serde_json::to_string(&x).unwrap();
+        assert_eq!(json, "[2,3]");
+        let y: RcSlice<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(&*y, [2, 3]);
+    }
+}
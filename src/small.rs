@@ -0,0 +1,243 @@
+//! Thread-safe shared slices that store a few elements inline.
+//!
+//! This is re-exported from the `arc` module.
+
+use core::{cmp, fmt, mem, ops, ptr, slice};
+use core::hash::{Hash, Hasher};
+use core::mem::MaybeUninit;
+
+use alloc::vec::Vec;
+
+use arc::ArcSlice;
+
+/// A slice that stores up to `N` elements inline, and is an
+/// `ArcSlice` beyond that.
+///
+/// Allocating and reference counting dominate the cost of very short
+/// slices, so a `SmallArcSlice` avoids them when there are at most `N`
+/// elements, by keeping the elements in the handle itself. Cloning an
+/// inline slice clones its elements, rather than sharing them, so
+/// this suits elements that are cheap to clone, and small `N`.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::arc::SmallArcSlice;
+///
+/// let short = SmallArcSlice::<u32, 4>::from_slice(&[1, 2]);
+/// assert!(short.is_inline());
+///
+/// let long = SmallArcSlice::<u32, 4>::from(vec![1, 2, 3, 4, 5]);
+/// assert!(!long.is_inline());
+/// assert_eq!(long.clone().as_ptr(), long.as_ptr());
+/// assert_eq!(long[..2], *short);
+/// ```
+pub struct SmallArcSlice<T, const N: usize> {
+    repr: Repr<T, N>,
+}
+
+enum Repr<T, const N: usize> {
+    // the first `len` elements are initialized
+    Inline { len: usize, elems: [MaybeUninit<T>; N] },
+    Heap(ArcSlice<T>),
+}
+
+impl<T, const N: usize> SmallArcSlice<T, N> {
+    /// Construct a new empty `SmallArcSlice`.
+    pub fn new() -> SmallArcSlice<T, N> {
+        SmallArcSlice {
+            // an array of `MaybeUninit`s needs no initialization
+            repr: Repr::Inline { len: 0, elems: unsafe { MaybeUninit::uninit().assume_init() } },
+        }
+    }
+
+    /// Add `value` to the end of the inline elements, which must have
+    /// space for it.
+    fn push_inline(&mut self, value: T) {
+        match self.repr {
+            Repr::Inline { ref mut len, ref mut elems } => {
+                elems[*len] = MaybeUninit::new(value);
+                *len += 1;
+            }
+            Repr::Heap(_) => unreachable!(),
+        }
+    }
+
+    /// Whether the elements of `self` are stored inline, rather than
+    /// in a shared allocation.
+    pub fn is_inline(&self) -> bool {
+        match self.repr {
+            Repr::Inline { .. } => true,
+            Repr::Heap(_) => false,
+        }
+    }
+
+    /// Convert `self` into an `ArcSlice`, moving the elements into a
+    /// new allocation if they are inline.
+    pub fn into_arc_slice(self) -> ArcSlice<T> {
+        let mut this = mem::ManuallyDrop::new(self);
+        match this.repr {
+            Repr::Inline { len, ref mut elems } => {
+                // `this` isn't dropped, so each element is moved out once
+                let elems = elems.as_ptr() as *const T;
+                ArcSlice::from_fn(len, |i| unsafe { ptr::read(elems.add(i)) })
+            }
+            Repr::Heap(ref mut slice) => unsafe { ptr::read(slice) },
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> SmallArcSlice<T, N> {
+    /// Construct a new `SmallArcSlice` containing clones of the
+    /// elements of `slice`, inline if there are at most `N`.
+    pub fn from_slice(slice: &[T]) -> SmallArcSlice<T, N> {
+        if slice.len() > N {
+            return SmallArcSlice { repr: Repr::Heap(ArcSlice::from_slice(slice)) }
+        }
+        let mut small = SmallArcSlice::new();
+        for x in slice {
+            small.push_inline(x.clone());
+        }
+        small
+    }
+}
+
+impl<T, const N: usize> Drop for SmallArcSlice<T, N> {
+    fn drop(&mut self) {
+        if let Repr::Inline { len, ref mut elems } = self.repr {
+            unsafe { ptr::drop_in_place(&mut elems[..len] as *mut [MaybeUninit<T>] as *mut [T]) }
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SmallArcSlice<T, N> {
+    fn clone(&self) -> SmallArcSlice<T, N> {
+        match self.repr {
+            Repr::Inline { .. } => SmallArcSlice::from_slice(self),
+            Repr::Heap(ref slice) => SmallArcSlice { repr: Repr::Heap(slice.clone()) },
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SmallArcSlice<T, N> {
+    fn default() -> SmallArcSlice<T, N> {
+        SmallArcSlice::new()
+    }
+}
+
+impl<T, const N: usize> ops::Deref for SmallArcSlice<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        match self.repr {
+            Repr::Inline { len, ref elems } => unsafe {
+                slice::from_raw_parts(elems.as_ptr() as *const T, len)
+            },
+            Repr::Heap(ref slice) => slice,
+        }
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for SmallArcSlice<T, N> {
+    fn as_ref(&self) -> &[T] { self }
+}
+
+impl<T, const N: usize> From<Vec<T>> for SmallArcSlice<T, N> {
+    fn from(v: Vec<T>) -> SmallArcSlice<T, N> {
+        if v.len() > N {
+            return SmallArcSlice { repr: Repr::Heap(ArcSlice::from(v)) }
+        }
+        let mut small = SmallArcSlice::new();
+        for x in v {
+            small.push_inline(x);
+        }
+        small
+    }
+}
+
+impl<'a, T: Clone, const N: usize> From<&'a [T]> for SmallArcSlice<T, N> {
+    fn from(slice: &'a [T]) -> SmallArcSlice<T, N> {
+        SmallArcSlice::from_slice(slice)
+    }
+}
+
+/// This keeps the elements in the shared allocation, even if there
+/// are few enough to store inline.
+impl<T, const N: usize> From<ArcSlice<T>> for SmallArcSlice<T, N> {
+    fn from(slice: ArcSlice<T>) -> SmallArcSlice<T, N> {
+        SmallArcSlice { repr: Repr::Heap(slice) }
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for SmallArcSlice<T, N> {
+    fn eq(&self, other: &SmallArcSlice<T, N>) -> bool { **self == **other }
+}
+impl<T: Eq, const N: usize> Eq for SmallArcSlice<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for SmallArcSlice<T, N> {
+    fn partial_cmp(&self, other: &SmallArcSlice<T, N>) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+impl<T: Ord, const N: usize> Ord for SmallArcSlice<T, N> {
+    fn cmp(&self, other: &SmallArcSlice<T, N>) -> cmp::Ordering { (**self).cmp(&**other) }
+}
+
+impl<T: Hash, const N: usize> Hash for SmallArcSlice<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SmallArcSlice<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallArcSlice;
+    use arc::ArcSlice;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_inline() {
+        let rc = Rc::new(0);
+        let x = SmallArcSlice::<_, 3>::from_slice(&[rc.clone(), rc.clone()]);
+        assert!(x.is_inline());
+        assert_eq!(x.len(), 2);
+        let y = x.clone();
+        assert!(y.as_ptr() != x.as_ptr());
+        assert_eq!(Rc::strong_count(&rc), 5);
+        drop((x, y));
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let x = SmallArcSlice::<_, 3>::from(vec![rc.clone(); 3]);
+        assert!(x.is_inline());
+        let arc = x.into_arc_slice();
+        assert_eq!(arc.len(), 3);
+        assert_eq!(Rc::strong_count(&rc), 4);
+        drop(arc);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let empty = SmallArcSlice::<i32, 0>::default();
+        assert!(empty.is_inline() && empty.is_empty());
+    }
+
+    #[test]
+    fn test_heap() {
+        let x = SmallArcSlice::<i32, 2>::from_slice(&[1, 2, 3]);
+        assert!(!x.is_inline());
+        let y = x.clone();
+        assert_eq!(x.as_ptr(), y.as_ptr());
+        assert_eq!(y.into_arc_slice().as_ptr(), x.as_ptr());
+
+        let arc = ArcSlice::from([1]);
+        let z = SmallArcSlice::<i32, 2>::from(arc.clone());
+        assert!(!z.is_inline());
+        assert_eq!(z.as_ptr(), arc.as_ptr());
+        assert!(z < x);
+        assert_eq!(z, SmallArcSlice::from_slice(&[1]));
+        assert_eq!(format!("{:?}", x), "[1, 2, 3]");
+    }
+}
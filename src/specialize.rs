@@ -0,0 +1,88 @@
+//! Implementations that rely on specialization, enabled by the
+//! `unstable` feature.
+
+use core::ptr;
+
+use identity::same_view;
+
+/// Compare `a` and `b` for equality, immediately returning true for
+/// identical views when `T: Eq`.
+pub fn slice_eq<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+    <T as SliceEq>::slice_eq(a, b)
+}
+
+trait SliceEq: Sized {
+    fn slice_eq(a: &[Self], b: &[Self]) -> bool;
+}
+
+impl<T: PartialEq> SliceEq for T {
+    default fn slice_eq(a: &[T], b: &[T]) -> bool {
+        a == b
+    }
+}
+
+impl<T: Eq> SliceEq for T {
+    fn slice_eq(a: &[T], b: &[T]) -> bool {
+        same_view(a, b) || a == b
+    }
+}
+
+/// Clone the elements of `src` into the uninitialized `dst`, counting
+/// them in `init` as they are written, with a single copy when
+/// `T: Copy`.
+#[cfg(any(feature = "rc", feature = "arc"))]
+pub unsafe fn clone_into<T: Clone>(src: &[T], dst: *mut T, init: &mut usize) {
+    <T as CloneInto>::clone_into(src, dst, init)
+}
+
+trait CloneInto: Sized {
+    unsafe fn clone_into(src: &[Self], dst: *mut Self, init: &mut usize);
+}
+
+impl<T: Clone> CloneInto for T {
+    default unsafe fn clone_into(src: &[T], dst: *mut T, init: &mut usize) {
+        for x in src {
+            ptr::write(dst.add(*init), x.clone());
+            *init += 1;
+        }
+    }
+}
+
+impl<T: Copy> CloneInto for T {
+    unsafe fn clone_into(src: &[T], dst: *mut T, init: &mut usize) {
+        ptr::copy_nonoverlapping(src.as_ptr(), dst.add(*init), src.len());
+        *init += src.len();
+    }
+}
+
+/// Write clones of `value` into the uninitialized `dst` until `len`
+/// elements are initialized, counting them in `init`, with a single
+/// `memset` for bytes.
+#[cfg(any(feature = "rc", feature = "arc"))]
+pub unsafe fn fill<T: Clone>(value: T, dst: *mut T, len: usize, init: &mut usize) {
+    <T as Fill>::fill(value, dst, len, init)
+}
+
+trait Fill: Sized {
+    unsafe fn fill(value: Self, dst: *mut Self, len: usize, init: &mut usize);
+}
+
+impl<T: Clone> Fill for T {
+    default unsafe fn fill(value: T, dst: *mut T, len: usize, init: &mut usize) {
+        while *init + 1 < len {
+            ptr::write(dst.add(*init), value.clone());
+            *init += 1;
+        }
+        if *init < len {
+            ptr::write(dst.add(*init), value);
+            *init += 1;
+        }
+    }
+}
+
+impl Fill for u8 {
+    unsafe fn fill(value: u8, dst: *mut u8, len: usize, init: &mut usize) {
+        ptr::write_bytes(dst.add(*init), value, len - *init);
+        *init = len;
+    }
+}
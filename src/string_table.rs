@@ -0,0 +1,272 @@
+//! A table of many strings stored in one thread-safe shared
+//! allocation.
+
+use core::{fmt, ops, str};
+use core::iter::FromIterator;
+use core::hash::{Hash, Hasher};
+
+use alloc::vec::Vec;
+
+use arc::ArcSlice;
+use csr::SharedCsr;
+
+/// A table of strings packed into a single shared allocation.
+///
+/// Storing many small strings individually costs an allocation (and
+/// a reference count) each; a `SharedStringTable` stores the bytes of
+/// all of them contiguously, along with the offsets of each string.
+/// Individual entries can be extracted as `TableStr`s, which keep the
+/// whole table alive.
+///
+/// # Examples
+///
+/// ```rust
+/// use shared_slice::string_table::SharedStringTableBuilder;
+///
+/// let mut builder = SharedStringTableBuilder::new();
+/// let foo = builder.push("foo");
+/// let bar = builder.push("bar");
+/// let table = builder.build();
+///
+/// assert_eq!(table.get(foo), Some("foo"));
+///
+/// let entry = table.get_owned(bar).unwrap();
+/// drop(table);
+/// assert_eq!(&*entry, "bar");
+/// ```
+pub struct SharedStringTable {
+    // invariant: every row is valid UTF-8
+    strings: SharedCsr<u8>,
+}
+
+/// A builder for a `SharedStringTable`.
+pub struct SharedStringTableBuilder {
+    offsets: Vec<usize>,
+    bytes: Vec<u8>,
+}
+
+/// A string stored in a `SharedStringTable`.
+///
+/// This dereferences to a `str`, and keeps the table it came from
+/// alive.
+pub struct TableStr {
+    // invariant: valid UTF-8
+    bytes: ArcSlice<u8>,
+}
+
+impl SharedStringTableBuilder {
+    /// Construct a new, empty builder.
+    pub fn new() -> SharedStringTableBuilder {
+        SharedStringTableBuilder {
+            offsets: vec![0],
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Add `s` to the end of the table, returning its index.
+    pub fn push(&mut self, s: &str) -> usize {
+        self.bytes.extend_from_slice(s.as_bytes());
+        self.offsets.push(self.bytes.len());
+        self.offsets.len() - 2
+    }
+
+    /// The number of strings added so far.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Whether no strings have been added so far.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Construct the table from the strings added so far.
+    pub fn build(self) -> SharedStringTable {
+        let offsets = ArcSlice::new(self.offsets.into_boxed_slice());
+        let bytes = ArcSlice::new(self.bytes.into_boxed_slice());
+        SharedStringTable {
+            strings: SharedCsr::new(offsets, bytes),
+        }
+    }
+}
+
+impl Default for SharedStringTableBuilder {
+    fn default() -> SharedStringTableBuilder {
+        SharedStringTableBuilder::new()
+    }
+}
+
+impl<'a> Extend<&'a str> for SharedStringTableBuilder {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            self.push(s);
+        }
+    }
+}
+
+impl SharedStringTable {
+    /// The number of strings in `self`.
+    pub fn len(&self) -> usize {
+        self.strings.rows()
+    }
+
+    /// Whether `self` contains no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the string at index `i`, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<&str> {
+        if i < self.len() {
+            Some(unsafe { str::from_utf8_unchecked(self.strings.row(i)) })
+        } else {
+            None
+        }
+    }
+
+    /// Construct a new `TableStr` pointing to the string at index
+    /// `i`, or `None` if `i` is out of bounds.
+    pub fn get_owned(&self, i: usize) -> Option<TableStr> {
+        if i < self.len() {
+            Some(TableStr { bytes: self.strings.row_owned(i) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the strings in `self`.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { table: self, pos: 0 }
+    }
+}
+
+impl<'a> FromIterator<&'a str> for SharedStringTable {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> SharedStringTable {
+        let mut builder = SharedStringTableBuilder::new();
+        builder.extend(iter);
+        builder.build()
+    }
+}
+
+impl Clone for SharedStringTable {
+    fn clone(&self) -> SharedStringTable {
+        SharedStringTable { strings: self.strings.clone() }
+    }
+}
+
+impl fmt::Debug for SharedStringTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the strings of a `SharedStringTable`.
+pub struct Iter<'a> {
+    table: &'a SharedStringTable,
+    pos: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<&'a str> {
+        let s = self.table.get(self.pos);
+        if s.is_some() {
+            self.pos += 1;
+        }
+        s
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.table.len() - self.pos;
+        (n, Some(n))
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl TableStr {
+    /// Get the bytes of `self` as an `ArcSlice`, sharing the same
+    /// allocation.
+    pub fn into_bytes(self) -> ArcSlice<u8> {
+        self.bytes
+    }
+}
+
+impl Clone for TableStr {
+    fn clone(&self) -> TableStr {
+        TableStr { bytes: self.bytes.clone() }
+    }
+}
+
+impl ops::Deref for TableStr {
+    type Target = str;
+    fn deref(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl AsRef<str> for TableStr {
+    fn as_ref(&self) -> &str { self }
+}
+
+impl PartialEq for TableStr {
+    fn eq(&self, other: &TableStr) -> bool { **self == **other }
+}
+impl Eq for TableStr {}
+
+impl<'a> PartialEq<&'a str> for TableStr {
+    fn eq(&self, other: &&'a str) -> bool { &**self == *other }
+}
+
+impl Hash for TableStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl fmt::Debug for TableStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for TableStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SharedStringTable, SharedStringTableBuilder};
+
+    #[test]
+    fn test_build() {
+        let mut builder = SharedStringTableBuilder::new();
+        assert!(builder.is_empty());
+        assert_eq!(builder.push("héllo"), 0);
+        assert_eq!(builder.push(""), 1);
+        assert_eq!(builder.push("wörld"), 2);
+        assert_eq!(builder.len(), 3);
+
+        let table = builder.build();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get(0), Some("héllo"));
+        assert_eq!(table.get(1), Some(""));
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.iter().collect::<Vec<_>>(), ["héllo", "", "wörld"]);
+        assert_eq!(format!("{:?}", table), r#"["héllo", "", "wörld"]"#);
+    }
+
+    #[test]
+    fn test_get_owned() {
+        let table: SharedStringTable = "a bb ccc".split(' ').collect();
+        let c = table.get_owned(2).unwrap();
+        let a = table.get_owned(0).unwrap();
+        assert!(table.get_owned(3).is_none());
+        drop(table);
+
+        assert_eq!(c, "ccc");
+        assert_eq!(format!("{} {:?}", a, c), "a \"ccc\"");
+        assert_eq!(&*c.clone().into_bytes(), b"ccc");
+    }
+}
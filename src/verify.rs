@@ -0,0 +1,123 @@
+//! Model-checking harnesses for the drop and reference counting
+//! logic, for use with Kani.
+//!
+//! These are only compiled when verifying, via `cargo kani`, and
+//! prove that the elements of an allocation are dropped exactly once,
+//! when the last strong handle goes away (whichever handle that is,
+//! and however it was sliced), and never while a strong handle still
+//! exists.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use alloc::boxed::Box;
+
+#[cfg(feature = "arc")]
+use arc::ArcSlice;
+#[cfg(feature = "rc")]
+use rc::RcSlice;
+
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+struct Counted;
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn drops() -> usize {
+    DROPS.load(Ordering::SeqCst)
+}
+
+/// Arbitrary bounds `lo <= hi <= len`.
+fn any_range(len: usize) -> (usize, usize) {
+    let lo: usize = kani::any();
+    let hi: usize = kani::any();
+    kani::assume(lo <= hi && hi <= len);
+    (lo, hi)
+}
+
+#[cfg(feature = "rc")]
+#[kani::proof]
+#[kani::unwind(4)]
+fn rc_last_owner_frees_once() {
+    let whole = RcSlice::new(Box::new([Counted, Counted, Counted]));
+    let (lo, hi) = any_range(3);
+    let part = whole.clone().slice(lo, hi);
+    let weak = whole.downgrade();
+
+    let (first, second) = if kani::any() { (whole, part) } else { (part, whole) };
+    drop(first);
+    assert!(drops() == 0);
+    assert!(weak.upgrade().is_some());
+
+    drop(second);
+    assert!(drops() == 3);
+    assert!(weak.upgrade().is_none());
+
+    drop(weak);
+    assert!(drops() == 3);
+}
+
+#[cfg(feature = "rc")]
+#[kani::proof]
+#[kani::unwind(4)]
+fn rc_take_frees_once() {
+    let mut x = RcSlice::new(Box::new([Counted, Counted]));
+    let y = x.take();
+    assert!(x.is_empty());
+
+    if kani::any() {
+        drop(x);
+        assert!(drops() == 0);
+        drop(y);
+    } else {
+        drop(y);
+        assert!(drops() == 2);
+        drop(x);
+    }
+    assert!(drops() == 2);
+}
+
+#[cfg(feature = "arc")]
+#[kani::proof]
+#[kani::unwind(4)]
+fn arc_last_owner_frees_once() {
+    let whole = ArcSlice::new(Box::new([Counted, Counted, Counted]));
+    let (lo, hi) = any_range(3);
+    let part = whole.clone().slice(lo, hi);
+    let weak = whole.downgrade();
+
+    let (first, second) = if kani::any() { (whole, part) } else { (part, whole) };
+    drop(first);
+    assert!(drops() == 0);
+    assert!(weak.upgrade().is_some());
+
+    drop(second);
+    assert!(drops() == 3);
+    assert!(weak.upgrade().is_none());
+
+    drop(weak);
+    assert!(drops() == 3);
+}
+
+#[cfg(feature = "arc")]
+#[kani::proof]
+#[kani::unwind(4)]
+fn arc_take_frees_once() {
+    let mut x = ArcSlice::new(Box::new([Counted, Counted]));
+    let y = x.take();
+    assert!(x.is_empty());
+
+    if kani::any() {
+        drop(x);
+        assert!(drops() == 0);
+        drop(y);
+    } else {
+        drop(y);
+        assert!(drops() == 2);
+        drop(x);
+    }
+    assert!(drops() == 2);
+}